@@ -0,0 +1,6 @@
+// Standalone command line for the Koharu pipeline
+// Subcommands, settings, and models are shared with `koharu --headless`; see src-tauri/src/headless.rs.
+
+fn main() -> anyhow::Result<()> {
+    koharu_lib::run_cli()
+}
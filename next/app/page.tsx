@@ -12,7 +12,11 @@ import TranslationPanel from '@/components/translation-panel'
 import InpaintPanel from '@/components/inpaint-panel'
 import InpaintSettings from '@/components/inpaint-settings'
 import RenderPanel from '@/components/render-panel'
+import CrashReportDialog from '@/components/crash-report-dialog'
 import { useEditorStore } from '@/lib/state'
+import { syncUiLanguage } from '@/lib/errors'
+import { persistSession, restoreSession } from '@/lib/session'
+import { redo, trackHistory, undo } from '@/lib/history'
 
 const SIDEBAR_MIN_WIDTH = 200
 const SIDEBAR_COLLAPSED_WIDTH = 48
@@ -42,6 +46,50 @@ function App() {
     }
   }, [theme])
 
+  // Backend error messages follow the webview's language
+  useEffect(() => {
+    syncUiLanguage()
+  }, [])
+
+  // Resume where the last session left off, then keep recording it
+  useEffect(() => {
+    let cancelled = false
+    let stop: (() => void) | null = null
+    restoreSession()
+      .catch(console.error)
+      .finally(() => {
+        if (!cancelled) stop = persistSession()
+      })
+    return () => {
+      cancelled = true
+      stop?.()
+    }
+  }, [])
+
+  // Block edits go through the backend's per-page undo history
+  useEffect(() => trackHistory(), [])
+
+  useEffect(() => {
+    const handleUndoKeys = (event: KeyboardEvent) => {
+      if (!(event.ctrlKey || event.metaKey)) return
+      // Text fields keep their own undo
+      const target = event.target as HTMLElement | null
+      if (target && (target.isContentEditable || ['INPUT', 'TEXTAREA'].includes(target.tagName))) return
+
+      const key = event.key.toLowerCase()
+      if (key === 'z' && !event.shiftKey) {
+        event.preventDefault()
+        undo().catch(console.error)
+      } else if (key === 'y' || (key === 'z' && event.shiftKey)) {
+        event.preventDefault()
+        redo().catch(console.error)
+      }
+    }
+
+    window.addEventListener('keydown', handleUndoKeys)
+    return () => window.removeEventListener('keydown', handleUndoKeys)
+  }, [])
+
   // Detect touch-capable devices to widen the resize handle for accessibility
   useEffect(() => {
     if (typeof window === 'undefined') return
@@ -180,6 +228,7 @@ function App() {
   return (
     <main className='flex h-screen max-h-screen w-screen max-w-screen flex-col bg-gray-200 dark:bg-gray-900'>
       <Topbar />
+      <CrashReportDialog />
       <div className='flex flex-1 overflow-hidden bg-gray-200 dark:bg-gray-900'>
         <div className='flex h-full w-20 items-start p-3'>
           <Tools />
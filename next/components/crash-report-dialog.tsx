@@ -0,0 +1,72 @@
+'use client'
+
+import { useState, useEffect } from 'react'
+import { Dialog, Button, Text, TextArea } from '@radix-ui/themes'
+import { invoke } from '@tauri-apps/api/core'
+
+interface CrashReport {
+  createdAt: number
+  appVersion: string
+  thread: string
+  message: string
+  location: string | null
+}
+
+// Shown on launch when the previous session panicked and crash reports are enabled
+export default function CrashReportDialog() {
+  const [report, setReport] = useState<CrashReport | null>(null)
+  const [copied, setCopied] = useState(false)
+
+  useEffect(() => {
+    invoke<CrashReport | null>('get_pending_crash_report')
+      .then(setReport)
+      .catch((err) => console.error('Failed to read crash report:', err))
+  }, [])
+
+  const handleCopy = async () => {
+    await navigator.clipboard.writeText(JSON.stringify(report, null, 2))
+    setCopied(true)
+  }
+
+  const handleClose = async () => {
+    setReport(null)
+    try {
+      await invoke('dismiss_crash_reports')
+    } catch (err) {
+      console.error('Failed to dismiss crash reports:', err)
+    }
+  }
+
+  if (!report) return null
+
+  return (
+    <Dialog.Root open onOpenChange={(open) => !open && handleClose()}>
+      <Dialog.Content maxWidth='560px'>
+        <Dialog.Title>Koharu crashed last time</Dialog.Title>
+        <Dialog.Description size='2' mb='3'>
+          A crash report was saved on {new Date(report.createdAt * 1000).toLocaleString()}. Copy it into a bug report
+          to help fix the problem.
+        </Dialog.Description>
+
+        <TextArea
+          readOnly
+          rows={4}
+          value={`${report.message}\n at ${report.location ?? 'unknown location'} (thread '${report.thread}', v${report.appVersion})`}
+          className='font-mono text-xs'
+        />
+        <Text as='p' size='1' color='gray' mt='2'>
+          The full report includes a backtrace, GPU status, model revisions, and recent log lines.
+        </Text>
+
+        <div className='mt-4 flex justify-end gap-3'>
+          <Button variant='soft' onClick={handleCopy}>
+            {copied ? 'Copied!' : 'Copy report'}
+          </Button>
+          <Button variant='soft' color='gray' onClick={handleClose}>
+            Dismiss
+          </Button>
+        </div>
+      </Dialog.Content>
+    </Dialog.Root>
+  )
+}
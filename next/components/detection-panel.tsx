@@ -37,6 +37,7 @@ const ensureTextBlockIds = (blocks: TextBlock[]): TextBlock[] =>
 export default function DetectionPanel() {
   const {
     image,
+    pageId,
     textBlocks,
     setTextBlocks,
     setSegmentationMask,
@@ -69,6 +70,7 @@ export default function DetectionPanel() {
         image: sourceImage.buffer,
         confidenceThreshold: confidenceThreshold,
         nmsThreshold: nmsThreshold,
+        pageId,
       })
 
     console.log('Detection result:', result)
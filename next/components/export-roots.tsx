@@ -0,0 +1,71 @@
+'use client'
+
+import { useState, useEffect } from 'react'
+import { Button, IconButton, Text } from '@radix-ui/themes'
+import { FolderPlus, X } from 'lucide-react'
+import { invoke } from '@tauri-apps/api/core'
+import { errorMessage } from '@/lib/errors'
+
+// Folders the backend may write exports into; adding one always goes through a native dialog
+export default function ExportRoots() {
+  const [roots, setRoots] = useState<string[]>([])
+  const [message, setMessage] = useState<string | null>(null)
+
+  useEffect(() => {
+    invoke<string[]>('list_export_roots').then(setRoots).catch(console.error)
+  }, [])
+
+  const handleAdd = async () => {
+    try {
+      const updated = await invoke<string[] | null>('add_export_root', { suggested: null })
+      if (updated) setRoots(updated)
+      setMessage(null)
+    } catch (err) {
+      setMessage(errorMessage(err))
+    }
+  }
+
+  const handleRemove = async (path: string) => {
+    try {
+      setRoots(await invoke<string[]>('remove_export_root', { path }))
+    } catch (err) {
+      setMessage(errorMessage(err))
+    }
+  }
+
+  return (
+    <div className='space-y-2'>
+      <Text as='div' size='2' weight='bold'>
+        Export folders
+      </Text>
+      <Text as='p' size='1' color='gray'>
+        Folder processing, PDF export, settings export and project files can only be written inside these
+        folders.
+      </Text>
+      {roots.length === 0 && (
+        <Text as='p' size='1' color='gray'>
+          No folders trusted yet.
+        </Text>
+      )}
+      {roots.map((root) => (
+        <div key={root} className='flex items-center gap-2'>
+          <Text size='1' className='flex-1 truncate font-mono'>
+            {root}
+          </Text>
+          <IconButton variant='ghost' size='1' color='gray' onClick={() => handleRemove(root)}>
+            <X className='h-3 w-3' />
+          </IconButton>
+        </div>
+      ))}
+      <Button variant='soft' size='1' onClick={handleAdd}>
+        <FolderPlus className='h-3 w-3' />
+        Add folder
+      </Button>
+      {message && (
+        <Text as='p' size='1' color='gray'>
+          {message}
+        </Text>
+      )}
+    </div>
+  )
+}
@@ -4,6 +4,7 @@ import { Badge, Button, Callout, Text, Code } from '@radix-ui/themes'
 import { CheckCircle, XCircle, AlertTriangle, Zap, RefreshCw } from 'lucide-react'
 import { invoke } from '@tauri-apps/api/core'
 import { useState, useEffect } from 'react'
+import { errorMessage } from '@/lib/errors'
 
 interface GpuInitResult {
   requested_provider: string
@@ -39,7 +40,7 @@ export default function GpuStatusPanel() {
       const result = await invoke<GpuInitResult>('get_current_gpu_status')
       setStatus(result)
     } catch (err) {
-      setError(err instanceof Error ? err.message : 'Failed to load GPU status')
+      setError(errorMessage(err, 'Failed to load GPU status'))
     }
   }
 
@@ -53,7 +54,7 @@ export default function GpuStatusPanel() {
       })
       setStressTest(result)
     } catch (err) {
-      setError(err instanceof Error ? err.message : 'Stress test failed')
+      setError(errorMessage(err, 'Stress test failed'))
     } finally {
       setLoading(false)
     }
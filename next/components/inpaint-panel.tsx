@@ -8,6 +8,7 @@ import { useEditorStore } from '@/lib/state'
 import { imageBitmapToArrayBuffer, maskToArrayBuffer, maskToUint8Array } from '@/utils/image'
 import { createImageFromBuffer } from '@/lib/image'
 import { compositeMaskedRegion } from '@/utils/alpha-compositing'
+import { errorMessage } from '@/lib/errors'
 
 interface InpaintedRegion {
   image: number[]
@@ -27,7 +28,7 @@ interface InpaintedRegion {
 }
 
 export default function InpaintPanel() {
-  const { image, segmentationMask, segmentationMaskWidth, segmentationMaskHeight, textBlocks, setInpaintedImage, renderMethod, setPipelineStage, setCurrentStage, inpaintingConfig } = useEditorStore()
+  const { image, pageId, segmentationMask, segmentationMaskWidth, segmentationMaskHeight, textBlocks, setInpaintedImage, renderMethod, setPipelineStage, setCurrentStage, inpaintingConfig } = useEditorStore()
   const [loading, setLoading] = useState(false)
   const [error, setError] = useState<string | null>(null)
   const [success, setSuccess] = useState(false)
@@ -36,7 +37,7 @@ export default function InpaintPanel() {
   const [cancelled, setCancelled] = useState(false)
 
   const runInpaint = async () => {
-    if (!image || !segmentationMask || textBlocks.length === 0) {
+    if (!image || !pageId || !segmentationMask || textBlocks.length === 0) {
       setError('Missing requirements. Run Detection first.')
       return
     }
@@ -78,8 +79,6 @@ export default function InpaintPanel() {
     setProgress(0)
     setCancelled(false)
 
-    let cachePrimed = false
-
     try {
       const imageWidth = image!.bitmap.width
       const imageHeight = image!.bitmap.height
@@ -90,10 +89,10 @@ export default function InpaintPanel() {
       const maskPng = await maskToArrayBuffer(maskData, maskWidth, maskHeight)
 
       await invoke('cache_inpainting_data', {
+        pageId,
         imagePng: Array.from(new Uint8Array(imagePng)),
         maskPng: Array.from(new Uint8Array(maskPng)),
       })
-      cachePrimed = true
 
       const canvas = new OffscreenCanvas(imageWidth, imageHeight)
       const ctx = canvas.getContext('2d', { willReadFrequently: true })
@@ -113,6 +112,7 @@ export default function InpaintPanel() {
         if (blockWidth < 20 || blockHeight < 20) continue
 
         const result = await invoke<InpaintedRegion>('inpaint_region_cached', {
+          pageId,
           bbox: {
             xmin: block.xmin,
             ymin: block.ymin,
@@ -171,15 +171,8 @@ export default function InpaintPanel() {
       setSuccess(true)
     } catch (err) {
       console.error('Localized LaMa invoke failed', err)
-      setError(err instanceof Error ? err.message : 'Localized inpainting failed')
+      setError(errorMessage(err, 'Localized inpainting failed'))
     } finally {
-      if (cachePrimed) {
-        try {
-          await invoke('clear_inpainting_cache')
-        } catch (cacheError) {
-          console.warn('Failed to clear inpainting cache', cacheError)
-        }
-      }
       setLoading(false)
       setCurrentBlock(0)
       setCancelled(false)
@@ -193,8 +186,6 @@ export default function InpaintPanel() {
     setProgress(0)
     setCancelled(false)
 
-    let cachePrimed = false
-
     try {
       const imageWidth = image!.bitmap.width
       const imageHeight = image!.bitmap.height
@@ -205,10 +196,10 @@ export default function InpaintPanel() {
       const maskPng = await maskToArrayBuffer(maskData, maskWidth, maskHeight)
 
       await invoke('cache_inpainting_data', {
+        pageId,
         imagePng: Array.from(new Uint8Array(imagePng)),
         maskPng: Array.from(new Uint8Array(maskPng)),
       })
-      cachePrimed = true
 
       const canvas = new OffscreenCanvas(imageWidth, imageHeight)
       const ctx = canvas.getContext('2d', { willReadFrequently: true })
@@ -228,6 +219,7 @@ export default function InpaintPanel() {
         if (blockWidth < 20 || blockHeight < 20) continue
 
         const result = await invoke<InpaintedRegion>('inpaint_region_cached', {
+          pageId,
           bbox: {
             xmin: block.xmin,
             ymin: block.ymin,
@@ -333,15 +325,8 @@ export default function InpaintPanel() {
       setSuccess(true)
     } catch (err) {
       console.error('NewLaMa invoke failed', err)
-      setError(err instanceof Error ? err.message : 'NewLaMa inpainting failed')
+      setError(errorMessage(err, 'NewLaMa inpainting failed'))
     } finally {
-      if (cachePrimed) {
-        try {
-          await invoke('clear_inpainting_cache')
-        } catch (cacheError) {
-          console.warn('Failed to clear inpainting cache', cacheError)
-        }
-      }
       setLoading(false)
       setCurrentBlock(0)
       setCancelled(false)
@@ -0,0 +1,150 @@
+'use client'
+
+import { useState, useEffect } from 'react'
+import { Button, Select, Text, TextArea, TextField } from '@radix-ui/themes'
+import { Copy, RefreshCw } from 'lucide-react'
+import { invoke } from '@tauri-apps/api/core'
+
+const LOG_LEVELS = ['error', 'warn', 'info', 'debug', 'trace']
+const RECENT_LINES = 300
+
+export default function LogsPanel() {
+  const [level, setLevel] = useState('info')
+  const [logDir, setLogDir] = useState<string | null>(null)
+  const [lines, setLines] = useState<string[]>([])
+  const [copied, setCopied] = useState(false)
+  const [crashReports, setCrashReports] = useState(false)
+  const [backup, setBackup] = useState({ enabled: true, keep: 10 })
+
+  const loadLogs = async () => {
+    try {
+      setLines(await invoke<string[]>('get_recent_logs', { n: RECENT_LINES }))
+    } catch (err) {
+      console.error('Failed to load logs:', err)
+    }
+  }
+
+  useEffect(() => {
+    invoke<string>('get_log_level').then(setLevel).catch(console.error)
+    invoke<string | null>('get_log_dir').then(setLogDir).catch(console.error)
+    invoke<{ diagnostics: { crashReports: boolean }; backup: { enabled: boolean; keep: number } }>('get_settings')
+      .then((settings) => {
+        setCrashReports(settings.diagnostics.crashReports)
+        setBackup(settings.backup)
+      })
+      .catch(console.error)
+    loadLogs()
+  }, [])
+
+  const handleCrashReportsChange = async (enabled: boolean) => {
+    setCrashReports(enabled)
+    try {
+      const settings = await invoke<Record<string, unknown>>('get_settings')
+      await invoke('update_settings', { settings: { ...settings, diagnostics: { crashReports: enabled } } })
+    } catch (err) {
+      console.error('Failed to save crash report setting:', err)
+    }
+  }
+
+  const handleBackupChange = async (next: { enabled: boolean; keep: number }) => {
+    setBackup(next)
+    try {
+      const settings = await invoke<Record<string, unknown>>('get_settings')
+      await invoke('update_settings', { settings: { ...settings, backup: next } })
+    } catch (err) {
+      console.error('Failed to save backup setting:', err)
+    }
+  }
+
+  const handleLevelChange = async (value: string) => {
+    try {
+      setLevel(await invoke<string>('set_log_level', { level: value }))
+      await loadLogs()
+    } catch (err) {
+      console.error('Failed to set log level:', err)
+    }
+  }
+
+  const handleCopy = async () => {
+    await navigator.clipboard.writeText(lines.join('\n'))
+    setCopied(true)
+    setTimeout(() => setCopied(false), 2000)
+  }
+
+  return (
+    <div className='mt-4 space-y-3'>
+      <label>
+        <Text as='div' size='2' mb='1' weight='bold'>
+          Log Level
+        </Text>
+        <Select.Root value={LOG_LEVELS.includes(level) ? level : undefined} onValueChange={handleLevelChange}>
+          <Select.Trigger className='w-full' placeholder={level} />
+          <Select.Content>
+            {LOG_LEVELS.map((value) => (
+              <Select.Item key={value} value={value}>
+                {value}
+              </Select.Item>
+            ))}
+          </Select.Content>
+        </Select.Root>
+      </label>
+
+      <TextArea readOnly rows={10} value={lines.join('\n')} className='font-mono text-xs' />
+
+      <div className='flex gap-2'>
+        <Button variant='soft' size='1' onClick={loadLogs}>
+          <RefreshCw className='h-3 w-3' />
+          Refresh
+        </Button>
+        <Button variant='soft' size='1' onClick={handleCopy} disabled={lines.length === 0}>
+          <Copy className='h-3 w-3' />
+          {copied ? 'Copied!' : 'Copy for bug report'}
+        </Button>
+      </div>
+
+      <div className='flex items-center justify-between'>
+        <label className='text-sm font-medium dark:text-gray-300'>Save crash reports</label>
+        <input
+          type='checkbox'
+          checked={crashReports}
+          onChange={(e) => handleCrashReportsChange(e.target.checked)}
+          className='rounded'
+        />
+      </div>
+      <Text as='p' size='1' color='gray'>
+        Reports stay on this computer; you&apos;ll be offered to copy one after a crash.
+      </Text>
+
+      <div className='flex items-center justify-between'>
+        <label className='text-sm font-medium dark:text-gray-300'>Back up originals before overwriting</label>
+        <input
+          type='checkbox'
+          checked={backup.enabled}
+          onChange={(e) => handleBackupChange({ ...backup, enabled: e.target.checked })}
+          className='rounded'
+        />
+      </div>
+      <div className='flex items-center justify-between'>
+        <label className='text-sm dark:text-gray-300'>Backup runs to keep per folder (0 = all)</label>
+        <TextField.Root
+          type='number'
+          min={0}
+          size='1'
+          className='w-20'
+          value={backup.keep}
+          disabled={!backup.enabled}
+          onChange={(e) => handleBackupChange({ ...backup, keep: Math.max(0, parseInt(e.target.value) || 0) })}
+        />
+      </div>
+      <Text as='p' size='1' color='gray'>
+        Files an export would replace inside the source folder are copied to .koharu-backups first.
+      </Text>
+
+      {logDir && (
+        <Text as='p' size='1' color='gray'>
+          Full logs (kept for 7 days): {logDir}
+        </Text>
+      )}
+    </div>
+  )
+}
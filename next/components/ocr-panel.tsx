@@ -10,6 +10,7 @@ import { invoke } from '@tauri-apps/api/core'
 export default function OCRPanel() {
   const {
     image,
+    pageId,
     textBlocks,
     setTextBlocks,
     setSelectedBlockIndex,
@@ -57,12 +58,11 @@ export default function OCRPanel() {
   }, [editingBlock, editValue, persistManualEdit])
 
   const run = useCallback(async () => {
-    if (!image || !textBlocks.length) return
+    if (!image || !pageId || !textBlocks.length) return
 
     finishEditing()
 
     setLoading(true)
-    let cachePrimed = false
     let cacheDuration = 0
 
     try {
@@ -72,9 +72,9 @@ export default function OCRPanel() {
       const cacheStart = performance.now()
       const imageBuffer = await imageBitmapToArrayBuffer(image.bitmap)
       await invoke('cache_ocr_image', {
+        pageId,
         imagePng: Array.from(new Uint8Array(imageBuffer)),
       })
-      cachePrimed = true
       cacheDuration = performance.now() - cacheStart
       console.info(
         `[ocr] run=${runId} cachePrime=${cacheDuration.toFixed(1)}ms payload=${(imageBuffer.byteLength / 1024).toFixed(1)}KB`
@@ -100,7 +100,7 @@ export default function OCRPanel() {
         const prepareDuration = performance.now() - prepareStart
 
         const invokeStart = performance.now()
-        const ocrResults = await invoke<string[]>('ocr_cached_block', { bbox })
+        const ocrResults = await invoke<string[]>('ocr_cached_block', { pageId, bbox })
         const invokeDuration = performance.now() - invokeStart
 
         const updateStart = performance.now()
@@ -140,16 +140,9 @@ export default function OCRPanel() {
     } catch (error) {
       console.error('Error during OCR:', error)
     } finally {
-      if (cachePrimed) {
-        try {
-          await invoke('clear_ocr_cache')
-        } catch (cacheError) {
-          console.warn('Failed to clear OCR cache', cacheError)
-        }
-      }
       setLoading(false)
     }
-  }, [finishEditing, image, textBlocks, setTextBlocks])
+  }, [finishEditing, image, pageId, textBlocks, setTextBlocks])
 
   // Auto-trigger OCR when boxes become stale (with debounce)
   useEffect(() => {
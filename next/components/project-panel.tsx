@@ -0,0 +1,207 @@
+'use client'
+
+import { useState, useEffect } from 'react'
+import { Button, Text, TextArea, TextField } from '@radix-ui/themes'
+import { FolderOpen, Save, X } from 'lucide-react'
+import { invoke } from '@tauri-apps/api/core'
+import { open, save } from '@tauri-apps/plugin-dialog'
+import { useEditorStore } from '@/lib/state'
+import { TranslationProvider } from '@/utils/translation'
+import { errorMessage } from '@/lib/errors'
+import { writeWithTrust } from '@/lib/export-roots'
+
+const PROJECT_FILE = 'koharu-project.json'
+const FILE_FILTERS = [{ name: 'Koharu Project', extensions: ['json'] }]
+
+interface GlossaryEntry {
+  source: string
+  target: string
+  note?: string | null
+}
+
+interface ProjectMetadata {
+  title: string | null
+  chapter: string | null
+  translator: string | null
+}
+
+interface Project {
+  version: number
+  name: string | null
+  glossary: GlossaryEntry[]
+  tone: string | null
+  translationProvider: string | null
+  defaultFont: string | null
+  renderMethod: string | null
+  inpaint: Record<string, unknown> | null
+  metadata: ProjectMetadata | null
+}
+
+interface OpenProject {
+  path: string
+  project: Project
+}
+
+// One "source = target" pair per line, optionally followed by "# note"
+function formatGlossary(glossary: GlossaryEntry[]): string {
+  return glossary.map((e) => `${e.source} = ${e.target}${e.note ? ` # ${e.note}` : ''}`).join('\n')
+}
+
+function parseGlossary(text: string): GlossaryEntry[] {
+  return text
+    .split('\n')
+    .map((line) => {
+      const [pair, note] = line.split('#', 2)
+      const [source, target] = pair.split('=', 2).map((s) => s?.trim())
+      return source && target ? { source, target, note: note?.trim() || null } : null
+    })
+    .filter((entry): entry is GlossaryEntry => entry !== null)
+}
+
+// Series-level glossary and style stored in a project file next to the chapters
+export default function ProjectPanel() {
+  const { setDefaultFont, setTranslationProvider } = useEditorStore()
+  const [current, setCurrent] = useState<OpenProject | null>(null)
+  const [glossary, setGlossary] = useState('')
+  const [tone, setTone] = useState('')
+  const [title, setTitle] = useState('')
+  const [chapter, setChapter] = useState('')
+  const [translator, setTranslator] = useState('')
+  const [message, setMessage] = useState<string | null>(null)
+
+  const applyOpened = (opened: OpenProject | null) => {
+    setCurrent(opened)
+    setGlossary(formatGlossary(opened?.project.glossary ?? []))
+    setTone(opened?.project.tone ?? '')
+    setTitle(opened?.project.metadata?.title ?? '')
+    setChapter(opened?.project.metadata?.chapter ?? '')
+    setTranslator(opened?.project.metadata?.translator ?? '')
+    if (opened?.project.defaultFont) setDefaultFont(opened.project.defaultFont)
+    if (opened?.project.translationProvider) {
+      setTranslationProvider(opened.project.translationProvider as TranslationProvider)
+    }
+  }
+
+  useEffect(() => {
+    invoke<OpenProject | null>('get_project').then(applyOpened).catch(console.error)
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, [])
+
+  const handleOpen = async () => {
+    const path = await open({ multiple: false, directory: false, filters: FILE_FILTERS })
+    if (!path) return
+    try {
+      applyOpened(await invoke<OpenProject>('open_project', { path }))
+      setMessage(null)
+    } catch (err) {
+      setMessage(`Open failed: ${errorMessage(err)}`)
+    }
+  }
+
+  const handleSave = async () => {
+    const path = current?.path ?? (await save({ defaultPath: PROJECT_FILE, filters: FILE_FILTERS }))
+    if (!path) return
+    try {
+      // A new project snapshots the current settings; an open one keeps its other fields
+      const args = current
+        ? {
+            path,
+            project: {
+              ...current.project,
+              glossary: parseGlossary(glossary),
+              tone: tone.trim() || null,
+              metadata: {
+                title: title.trim() || null,
+                chapter: chapter.trim() || null,
+                translator: translator.trim() || null,
+              },
+            },
+          }
+        : { path }
+      const saved = await writeWithTrust(path, () => invoke<OpenProject>('save_project', args))
+      applyOpened(saved)
+      setMessage(`Project saved to ${saved.path}`)
+    } catch (err) {
+      setMessage(`Save failed: ${errorMessage(err)}`)
+    }
+  }
+
+  const handleClose = async () => {
+    await invoke('close_project').catch(console.error)
+    applyOpened(null)
+    setMessage(null)
+  }
+
+  return (
+    <div className='mt-4 space-y-3'>
+      <Text as='p' size='1' color='gray'>
+        {current
+          ? `${current.project.name ?? 'Project'}: ${current.path}`
+          : 'No project open. Saving creates one from the current settings.'}
+      </Text>
+
+      {current && (
+        <>
+          <label>
+            <Text as='div' size='2' mb='1' weight='bold'>
+              Glossary
+            </Text>
+            <TextArea
+              rows={6}
+              value={glossary}
+              onChange={(e) => setGlossary(e.target.value)}
+              placeholder={'炭治郎 = Tanjiro # main character'}
+              className='font-mono text-xs'
+            />
+          </label>
+          <label>
+            <Text as='div' size='2' mb='1' weight='bold'>
+              Tone
+            </Text>
+            <TextField.Root
+              value={tone}
+              onChange={(e) => setTone(e.target.value)}
+              placeholder='e.g. casual, keep honorifics'
+            />
+          </label>
+          <Text as='div' size='2' weight='bold'>
+            Export tags
+          </Text>
+          <Text as='p' size='1' color='gray'>
+            Embedded in exported pages in place of the Render panel values; leave empty to use those.
+          </Text>
+          <TextField.Root value={title} onChange={(e) => setTitle(e.target.value)} placeholder='Title' />
+          <TextField.Root value={chapter} onChange={(e) => setChapter(e.target.value)} placeholder='Chapter' />
+          <TextField.Root
+            value={translator}
+            onChange={(e) => setTranslator(e.target.value)}
+            placeholder='Translator credit'
+          />
+        </>
+      )}
+
+      <div className='flex gap-2'>
+        <Button variant='soft' size='1' onClick={handleOpen}>
+          <FolderOpen className='h-3 w-3' />
+          Open project
+        </Button>
+        <Button variant='soft' size='1' onClick={handleSave}>
+          <Save className='h-3 w-3' />
+          {current ? 'Save project' : 'Create project'}
+        </Button>
+        {current && (
+          <Button variant='soft' size='1' color='gray' onClick={handleClose}>
+            <X className='h-3 w-3' />
+            Close
+          </Button>
+        )}
+      </div>
+
+      {message && (
+        <Text as='p' size='1' color='gray'>
+          {message}
+        </Text>
+      )}
+    </div>
+  )
+}
@@ -14,6 +14,10 @@ import { createImageFromBuffer } from '@/lib/image'
 import { invoke } from '@tauri-apps/api/core'
 import { fileSave } from 'browser-fs-access'
 import RenderCustomization from './render-customization'
+import { errorMessage } from '@/lib/errors'
+import { open } from '@tauri-apps/plugin-dialog'
+import { writeWithTrust } from '@/lib/export-roots'
+import { flushHistory } from '@/lib/history'
 
 // Utility function for creating canvas with OffscreenCanvas fallback
 function createCanvas(width: number, height: number): { canvas: HTMLCanvasElement | OffscreenCanvas, ctx: CanvasRenderingContext2D | OffscreenCanvasRenderingContext2D } {
@@ -51,6 +55,51 @@ async function canvasToBlob(canvas: HTMLCanvasElement | OffscreenCanvas, options
   throw new Error('Unsupported canvas type')
 }
 
+type ExportFormat = 'png' | 'jpeg' | 'webp' | 'avif'
+
+interface ExportOptions {
+  format: ExportFormat
+  quality: number | null // JPEG and AVIF only; the backend rejects it for lossless formats
+  pngCompression: 'fast' | 'default' | 'best'
+  maxDimension: number | null
+  metadata: ExportMetadata
+  filenameTemplate: string | null
+}
+
+interface ExportMetadata {
+  embedTags: boolean
+  title: string | null
+  chapter: string | null
+  translator: string | null
+  preserveExif: boolean
+}
+
+const DEFAULT_QUALITY = 90
+const takesQuality = (format: ExportFormat) => format === 'jpeg' || format === 'avif'
+
+const EXPORT_FORMATS: Record<ExportFormat, { extension: string, mime: string, label: string }> = {
+  png: { extension: '.png', mime: 'image/png', label: 'PNG Image' },
+  jpeg: { extension: '.jpg', mime: 'image/jpeg', label: 'JPEG Image' },
+  webp: { extension: '.webp', mime: 'image/webp', label: 'WebP Image' },
+  avif: { extension: '.avif', mime: 'image/avif', label: 'AVIF Image' },
+}
+
+type ComparisonMode = 'sideBySide' | 'animatedGif' | 'animatedWebp'
+
+const COMPARISON_FILES: Record<Exclude<ComparisonMode, 'sideBySide'>, { extension: string, mime: string, label: string }> = {
+  animatedGif: { extension: '.gif', mime: 'image/gif', label: 'Animated GIF' },
+  animatedWebp: { extension: '.webp', mime: 'image/webp', label: 'Animated WebP' },
+}
+
+const DEFAULT_EXPORT_OPTIONS: ExportOptions = {
+  format: 'png',
+  quality: null,
+  pngCompression: 'default',
+  maxDimension: null,
+  metadata: { embedTags: false, title: null, chapter: null, translator: null, preserveExif: false },
+  filenameTemplate: null,
+}
+
 interface GpuStatus {
   requested_provider: string
   active_provider: string
@@ -61,17 +110,46 @@ interface GpuStatus {
 }
 
 export default function RenderPanel() {
-  const { image, textBlocks, setTextBlocks, renderMethod, setRenderMethod, inpaintedImage, setPipelineStage, setCurrentStage, defaultFont, setTool, pipelineStages } = useEditorStore()
+  const { image, pageId, textBlocks, setTextBlocks, renderMethod, setRenderMethod, inpaintedImage, setPipelineStage, setCurrentStage, defaultFont, setTool, pipelineStages } = useEditorStore()
   const [processing, setProcessing] = useState(false)
   const [progress, setProgress] = useState(0)
   const [error, setError] = useState<string | null>(null)
   const [selectedBlock, setSelectedBlock] = useState<number | null>(null)
   const [gpuStatus, setGpuStatus] = useState<GpuStatus | null>(null)
+  const [exportOptions, setExportOptions] = useState<ExportOptions>(DEFAULT_EXPORT_OPTIONS)
+  const [lastExport, setLastExport] = useState<number[] | null>(null)
+  const [comparisonMode, setComparisonMode] = useState<ComparisonMode>('sideBySide')
+  const [annotationFormat, setAnnotationFormat] = useState<'coco' | 'labelme'>('coco')
 
   useEffect(() => {
     loadGpuStatus()
+    loadExportOptions()
   }, [])
 
+  const loadExportOptions = async () => {
+    try {
+      const settings = await invoke<{ export: ExportOptions }>('get_settings')
+      setExportOptions(settings.export)
+    } catch (err) {
+      console.error('Failed to load export settings:', err)
+    }
+  }
+
+  // Export options are saved with the other backend settings so batch exports use them too
+  const updateExportOptions = async (changes: Partial<ExportOptions>) => {
+    const next = { ...exportOptions, ...changes }
+    setExportOptions(next)
+    try {
+      const settings = await invoke<Record<string, unknown>>('get_settings')
+      await invoke('update_settings', { settings: { ...settings, export: next } })
+    } catch (err) {
+      console.error('Failed to save export settings:', err)
+    }
+  }
+
+  const updateExportMetadata = (changes: Partial<ExportMetadata>) =>
+    updateExportOptions({ metadata: { ...exportOptions.metadata, ...changes } })
+
   const loadGpuStatus = async () => {
     try {
       const status = await invoke<GpuStatus>('get_current_gpu_status')
@@ -232,7 +310,29 @@ export default function RenderPanel() {
       setProgress(1)
       setTextBlocks(updated)
 
-      // Generate final composition and save as pipeline stage
+      // Boxes, mask, corrected text, and translations as COCO/labelme training data
+  const exportAnnotations = async () => {
+    if (!pageId) return
+    try {
+      setError(null)
+      const outputDir = await open({ directory: true, title: 'Export annotations to' })
+      if (typeof outputDir !== 'string') return
+      const written = await writeWithTrust(outputDir, () =>
+        invoke<string[]>('export_annotations', {
+          pageId,
+          blocks: textBlocks,
+          outputDir,
+          format: annotationFormat,
+        })
+      )
+      console.log('[ANNOTATIONS] Wrote', written)
+    } catch (err) {
+      console.error('[ANNOTATIONS] Error:', err)
+      setError(errorMessage(err, 'Failed to export annotations'))
+    }
+  }
+
+  // Generate final composition and save as pipeline stage
       await generateFinalComposition()
 
       // Switch to render tool and 'final' stage to show live preview with rendered text
@@ -242,7 +342,7 @@ export default function RenderPanel() {
       console.log('Color processing complete! Switched to render preview.')
     } catch (err) {
       console.error('Color processing error:', err)
-      setError(err instanceof Error ? err.message : 'Failed to process colors')
+      setError(errorMessage(err, 'Failed to process colors'))
     } finally {
       setProcessing(false)
     }
@@ -342,32 +442,61 @@ export default function RenderPanel() {
       // DEBUG: Log Rust function call
       // console.log('[EXPORT] Calling Rust render_and_export_image...')
 
-      // Step 4: Call Rust backend
-      const pngBuffer: number[] = await invoke('render_and_export_image', {
+      // Step 4: Call Rust backend; it renders the page's edit history, so record the latest edit first
+      await flushHistory()
+      const exportBuffer: number[] = await invoke('render_and_export_image', {
         request: {
           baseImageBuffer,
           textBlocks: textBlocksForRust,
           renderMethod,
           defaultFont,
+          export: exportOptions,
+          pageId,
         },
       })
 
       // DEBUG: Log completion and buffer size
-      // console.log('[EXPORT] Rust rendering complete, buffer size:', pngBuffer.length)
+      // console.log('[EXPORT] Rust rendering complete, buffer size:', exportBuffer.length)
+
+      setLastExport(exportBuffer)
 
       // Step 5: Convert buffer to Blob and save
-      const exportBlob = new Blob([new Uint8Array(pngBuffer)], { type: 'image/png' })
+      const format = EXPORT_FORMATS[exportOptions.format]
+      const exportBlob = new Blob([new Uint8Array(exportBuffer)], { type: format.mime })
 
       await fileSave(exportBlob, {
-        fileName: `translated-manga-${Date.now()}.png`,
-        extensions: ['.png'],
-        description: 'PNG Image',
+        fileName: `translated-manga-${Date.now()}${format.extension}`,
+        extensions: [format.extension],
+        description: format.label,
       })
 
       console.log('[EXPORT] Image exported successfully!')
     } catch (err) {
       console.error('[EXPORT] Error:', err)
-      setError(err instanceof Error ? err.message : 'Failed to export image')
+      setError(errorMessage(err, 'Failed to export image'))
+    }
+  }
+
+  // Before/after of the original page and the last export, composed in the backend
+  const exportComparison = async () => {
+    if (!image || !lastExport) return
+    try {
+      setError(null)
+      const comparisonBuffer: number[] = await invoke('create_comparison', {
+        original: Array.from(new Uint8Array(image.buffer)),
+        translated: lastExport,
+        options: { mode: comparisonMode, format: exportOptions.format },
+      })
+
+      const file = comparisonMode === 'sideBySide' ? EXPORT_FORMATS[exportOptions.format] : COMPARISON_FILES[comparisonMode]
+      await fileSave(new Blob([new Uint8Array(comparisonBuffer)], { type: file.mime }), {
+        fileName: `comparison-${Date.now()}${file.extension}`,
+        extensions: [file.extension],
+        description: file.label,
+      })
+    } catch (err) {
+      console.error('[COMPARISON] Error:', err)
+      setError(errorMessage(err, 'Failed to create comparison'))
     }
   }
 
@@ -588,6 +717,37 @@ export default function RenderPanel() {
         </Button>
       </div>
 
+      {/* Before/after comparison of the last export */}
+      <div className='flex items-center gap-2 px-3 pb-3'>
+        <Select.Root value={comparisonMode} onValueChange={(value: ComparisonMode) => setComparisonMode(value)}>
+          <Select.Trigger className='flex-1' />
+          <Select.Content>
+            <Select.Item value='sideBySide'>Side by side</Select.Item>
+            <Select.Item value='animatedGif'>Before/after GIF</Select.Item>
+            <Select.Item value='animatedWebp'>Before/after WebP</Select.Item>
+          </Select.Content>
+        </Select.Root>
+        <Button onClick={exportComparison} variant='soft' disabled={!lastExport}>
+          <Download className='h-4 w-4' />
+          Comparison
+        </Button>
+      </div>
+
+      {/* Training data export of the current page */}
+      <div className='flex items-center gap-2 px-3 pb-3'>
+        <Select.Root value={annotationFormat} onValueChange={(value: 'coco' | 'labelme') => setAnnotationFormat(value)}>
+          <Select.Trigger className='flex-1' />
+          <Select.Content>
+            <Select.Item value='coco'>COCO</Select.Item>
+            <Select.Item value='labelme'>labelme</Select.Item>
+          </Select.Content>
+        </Select.Root>
+        <Button onClick={exportAnnotations} variant='soft' disabled={!pageId || textBlocks.length === 0}>
+          <Download className='h-4 w-4' />
+          Annotations
+        </Button>
+      </div>
+
       {/* GPU Status */}
       {gpuStatus && (
         <div className='border-t border-gray-200 p-3 dark:border-gray-700'>
@@ -644,6 +804,114 @@ export default function RenderPanel() {
         </Select.Root>
       </div>
 
+      {/* Export Encoding */}
+      <div className='flex flex-col gap-2 border-t border-gray-200 p-3 dark:border-gray-700'>
+        <label className='text-xs font-semibold text-gray-600 dark:text-gray-400'>
+          Export Format
+        </label>
+        <Select.Root
+          value={exportOptions.format}
+          onValueChange={(value: ExportFormat) =>
+            updateExportOptions({ format: value, quality: takesQuality(value) ? exportOptions.quality : null })
+          }
+        >
+          <Select.Trigger className='w-full' />
+          <Select.Content>
+            <Select.Item value='png'>PNG (lossless)</Select.Item>
+            <Select.Item value='jpeg'>JPEG</Select.Item>
+            <Select.Item value='webp'>WebP (lossless)</Select.Item>
+            <Select.Item value='avif'>AVIF</Select.Item>
+          </Select.Content>
+        </Select.Root>
+        {exportOptions.format === 'png' && (
+          <Select.Root
+            value={exportOptions.pngCompression}
+            onValueChange={(value: ExportOptions['pngCompression']) => updateExportOptions({ pngCompression: value })}
+          >
+            <Select.Trigger className='w-full' />
+            <Select.Content>
+              <Select.Item value='fast'>Fast compression</Select.Item>
+              <Select.Item value='default'>Default compression</Select.Item>
+              <Select.Item value='best'>Best compression (slow)</Select.Item>
+            </Select.Content>
+          </Select.Root>
+        )}
+        {takesQuality(exportOptions.format) && (
+          <div className='flex items-center justify-between gap-2 text-sm'>
+            <span className='dark:text-gray-300'>Quality</span>
+            <input
+              type='number'
+              min={1}
+              max={100}
+              value={exportOptions.quality ?? DEFAULT_QUALITY}
+              onChange={(e) => updateExportOptions({ quality: Math.min(100, Math.max(1, Number(e.target.value) || 1)) })}
+              className='w-20 rounded border border-gray-300 px-2 py-1 dark:border-gray-600 dark:bg-gray-700 dark:text-white'
+            />
+          </div>
+        )}
+        <div className='flex items-center justify-between gap-2 text-sm'>
+          <span className='dark:text-gray-300'>Max dimension (px)</span>
+          <input
+            type='number'
+            min={0}
+            placeholder='Original'
+            value={exportOptions.maxDimension ?? ''}
+            onChange={(e) => {
+              const value = Number(e.target.value)
+              updateExportOptions({ maxDimension: value > 0 ? value : null })
+            }}
+            className='w-24 rounded border border-gray-300 px-2 py-1 dark:border-gray-600 dark:bg-gray-700 dark:text-white'
+          />
+        </div>
+        <div className='flex items-center justify-between gap-2 text-sm'>
+          <span className='dark:text-gray-300'>Embed title/credit tags</span>
+          <input
+            type='checkbox'
+            checked={exportOptions.metadata.embedTags}
+            onChange={(e) => updateExportMetadata({ embedTags: e.target.checked })}
+            className='rounded'
+          />
+        </div>
+        {exportOptions.metadata.embedTags &&
+          (['title', 'chapter', 'translator'] as const).map((field) => (
+            <input
+              key={field}
+              type='text'
+              placeholder={field[0].toUpperCase() + field.slice(1)}
+              value={exportOptions.metadata[field] ?? ''}
+              onChange={(e) => updateExportMetadata({ [field]: e.target.value || null })}
+              className='w-full rounded border border-gray-300 px-2 py-1 text-sm dark:border-gray-600 dark:bg-gray-700 dark:text-white'
+            />
+          ))}
+        <div className='flex items-center justify-between gap-2 text-sm'>
+          <span className='dark:text-gray-300'>Keep source EXIF (credits, dates)</span>
+          <input
+            type='checkbox'
+            checked={exportOptions.metadata.preserveExif}
+            onChange={(e) => updateExportMetadata({ preserveExif: e.target.checked })}
+            className='rounded'
+          />
+        </div>
+        <div className='flex flex-col gap-1 text-sm'>
+          <span className='dark:text-gray-300'>Batch filename template</span>
+          <input
+            type='text'
+            placeholder='{title}/{chapter}/{page:03}_{lang}'
+            value={exportOptions.filenameTemplate ?? ''}
+            onChange={(e) => updateExportOptions({ filenameTemplate: e.target.value || null })}
+            className='w-full rounded border border-gray-300 px-2 py-1 dark:border-gray-600 dark:bg-gray-700 dark:text-white'
+          />
+          <Text size='1' color='gray'>
+            Fields: title, chapter, page, lang, name. Empty keeps the input file names.
+          </Text>
+        </div>
+        {exportOptions.metadata.embedTags && (exportOptions.format === 'webp' || exportOptions.format === 'avif') && (
+          <Text size='1' color='gray'>
+            Metadata is only embedded in PNG and JPEG exports
+          </Text>
+        )}
+      </div>
+
       {/* Body */}
       <div className='flex flex-col gap-2 p-3'>
         {/* Progress */}
@@ -7,6 +7,11 @@ import { useEditorStore } from '@/lib/state'
 import { testApiKey, TranslationProvider } from '@/utils/translation'
 import { invoke } from '@tauri-apps/api/core'
 import GpuStatusPanel from './gpu-status-panel'
+import LogsPanel from './logs-panel'
+import ProjectPanel from './project-panel'
+import SettingsTransfer from './settings-transfer'
+import ExportRoots from './export-roots'
+import { errorMessage } from '@/lib/errors'
 
 export default function SettingsDialog() {
   const {
@@ -64,7 +69,7 @@ export default function SettingsDialog() {
       }
     } catch (err) {
       setTestResult('error')
-      setTestMessage(err instanceof Error ? err.message : 'Connection failed')
+      setTestMessage(errorMessage(err, 'Connection failed'))
     } finally {
       setTesting(false)
     }
@@ -159,6 +164,8 @@ export default function SettingsDialog() {
             <Tabs.Trigger value='render'>Render/Text</Tabs.Trigger>
             <Tabs.Trigger value='ocr'>OCR Engine</Tabs.Trigger>
             <Tabs.Trigger value='gpu'>GPU & Performance</Tabs.Trigger>
+            <Tabs.Trigger value='project'>Project</Tabs.Trigger>
+            <Tabs.Trigger value='logs'>Logs & Backup</Tabs.Trigger>
           </Tabs.List>
 
           {/* Translation Tab */}
@@ -565,6 +572,22 @@ export default function SettingsDialog() {
               </details>
             </div>
           </Tabs.Content>
+
+          {/* Project Tab */}
+          <Tabs.Content value='project'>
+            <ProjectPanel />
+            <div className='mt-4 border-t border-gray-200 pt-3 dark:border-gray-700'>
+              <ExportRoots />
+            </div>
+          </Tabs.Content>
+
+          {/* Logs & Backup Tab */}
+          <Tabs.Content value='logs'>
+            <LogsPanel />
+            <div className='mt-4 border-t border-gray-200 pt-3 dark:border-gray-700'>
+              <SettingsTransfer />
+            </div>
+          </Tabs.Content>
         </Tabs.Root>
 
         <div className='mt-6 flex justify-end gap-3'>
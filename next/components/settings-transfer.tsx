@@ -0,0 +1,84 @@
+'use client'
+
+import { useState } from 'react'
+import { Button, Text } from '@radix-ui/themes'
+import { Download, Upload } from 'lucide-react'
+import { invoke } from '@tauri-apps/api/core'
+import { open, save } from '@tauri-apps/plugin-dialog'
+import { errorMessage } from '@/lib/errors'
+import { writeWithTrust } from '@/lib/export-roots'
+
+// localStorage keys holding API keys; only exported when explicitly requested
+const SECRET_KEYS = ['google_translate_api_key', 'deepl_translate_api_key']
+const FILE_FILTERS = [{ name: 'Koharu Settings', extensions: ['json'] }]
+
+function collectFrontendPreferences(includeSecrets: boolean): Record<string, string> {
+  const preferences: Record<string, string> = {}
+  for (let i = 0; i < localStorage.length; i++) {
+    const key = localStorage.key(i)
+    if (!key || (!includeSecrets && SECRET_KEYS.includes(key))) continue
+    preferences[key] = localStorage.getItem(key) ?? ''
+  }
+  return preferences
+}
+
+// Export/import of backend settings plus UI preferences, for moving machines or sharing a team setup
+export default function SettingsTransfer() {
+  const [includeSecrets, setIncludeSecrets] = useState(false)
+  const [message, setMessage] = useState<string | null>(null)
+
+  const handleExport = async () => {
+    const path = await save({ defaultPath: 'koharu-settings.json', filters: FILE_FILTERS })
+    if (!path) return
+    try {
+      await writeWithTrust(path, () =>
+        invoke('export_settings', { path, includeSecrets, frontend: collectFrontendPreferences(includeSecrets) })
+      )
+      setMessage(`Settings exported to ${path}`)
+    } catch (err) {
+      setMessage(`Export failed: ${errorMessage(err)}`)
+    }
+  }
+
+  const handleImport = async () => {
+    const path = await open({ multiple: false, directory: false, filters: FILE_FILTERS })
+    if (!path) return
+    try {
+      const imported = await invoke<{ frontend: Record<string, string> | null }>('import_settings', { path })
+      Object.entries(imported.frontend ?? {}).forEach(([key, value]) => localStorage.setItem(key, value))
+      // UI preferences are read from localStorage on startup
+      window.location.reload()
+    } catch (err) {
+      setMessage(`Import failed: ${errorMessage(err)}`)
+    }
+  }
+
+  return (
+    <div className='space-y-2'>
+      <div className='flex items-center gap-2'>
+        <Button variant='soft' size='1' onClick={handleExport}>
+          <Download className='h-3 w-3' />
+          Export settings
+        </Button>
+        <Button variant='soft' size='1' onClick={handleImport}>
+          <Upload className='h-3 w-3' />
+          Import settings
+        </Button>
+        <label className='ml-auto flex items-center gap-1 text-xs text-gray-600 dark:text-gray-400'>
+          <input
+            type='checkbox'
+            checked={includeSecrets}
+            onChange={(e) => setIncludeSecrets(e.target.checked)}
+            className='rounded'
+          />
+          Include API keys
+        </label>
+      </div>
+      {message && (
+        <Text as='p' size='1' color='gray'>
+          {message}
+        </Text>
+      )}
+    </div>
+  )
+}
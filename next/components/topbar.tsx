@@ -7,6 +7,7 @@ import { useEditorStore } from '@/lib/state'
 import { createImageFromBlob } from '@/lib/image'
 import SettingsDialog from './settings-dialog'
 import DetectionControls from './detection-controls'
+import { errorMessage } from '@/lib/errors'
 
 function Topbar() {
   const { setImage, theme, setTheme, tool, currentStage, setCurrentStage, pipelineStages, renderMethod, textBlocks, fontSizeStep, setFontSizeStep, setTextBlocks } = useEditorStore()
@@ -16,6 +17,7 @@ function Topbar() {
       const blob = await fileOpen({
         multiple: false,
         mimeTypes: ['image/*'],
+        extensions: ['.png', '.jpg', '.jpeg', '.webp', '.bmp', '.avif', '.jxl'],
       })
 
       if (!blob) return
@@ -23,7 +25,7 @@ function Topbar() {
       const image = await createImageFromBlob(blob)
       setImage(image)
     } catch (err) {
-      alert(`Error opening image: ${err}`)
+      alert(`Error opening image: ${errorMessage(err)}`)
     }
   }
 
@@ -50,7 +52,7 @@ function Topbar() {
       const image = await createImageFromBlob(blob)
       setImage(image)
     } catch (err) {
-      alert(`Error pasting image: ${err}`)
+      alert(`Error pasting image: ${errorMessage(err)}`)
     }
   }
 
@@ -5,6 +5,7 @@ import { Play, AlertCircle } from 'lucide-react'
 import { useState, useEffect, useRef } from 'react'
 import { useEditorStore } from '@/lib/state'
 import { translate, TranslationAPIError } from '@/utils/translation'
+import { errorMessage } from '@/lib/errors'
 
 function TranslationPanel() {
   const {
@@ -101,7 +102,7 @@ function TranslationPanel() {
       setProgress('Translation complete!')
     } catch (err) {
       console.error('Translation error:', err)
-      setError(err instanceof Error ? err.message : 'Translation failed')
+      setError(errorMessage(err, 'Translation failed'))
     } finally {
       setLoading(false)
     }
@@ -0,0 +1,26 @@
+import { invoke } from '@tauri-apps/api/core'
+
+// Shape of a rejected invoke() from a backend command (see src-tauri/src/error.rs)
+export interface BackendError {
+  code: string // Stable identifier, e.g. 'quota-exceeded'
+  message: string // Localized in the language passed to set_ui_language
+  detail: string // Original English error chain, for logs and bug reports
+}
+
+export function isBackendError(err: unknown): err is BackendError {
+  return typeof err === 'object' && err !== null && 'code' in err && 'message' in err && 'detail' in err
+}
+
+// Text to show the user for anything caught from invoke() or frontend code
+export function errorMessage(err: unknown, fallback = 'Unknown error'): string {
+  if (isBackendError(err)) return err.message
+  if (err instanceof Error) return err.message
+  if (typeof err === 'string') return err
+  return fallback
+}
+
+// Tell the backend which message catalog to use; it falls back to English
+export async function syncUiLanguage(): Promise<void> {
+  if (typeof navigator === 'undefined') return
+  await invoke<string>('set_ui_language', { language: navigator.language }).catch(console.error)
+}
@@ -0,0 +1,20 @@
+import { invoke } from '@tauri-apps/api/core'
+import { isBackendError } from '@/lib/errors'
+
+function parentDir(path: string): string {
+  const index = Math.max(path.lastIndexOf('/'), path.lastIndexOf('\\'))
+  return index > 0 ? path.slice(0, index) : path
+}
+
+// Run a backend write; if `path` is outside the trusted export folders, let the user trust
+// one in the backend's folder dialog (opened at the target's folder) and retry once
+export async function writeWithTrust<T>(path: string, write: () => Promise<T>): Promise<T> {
+  try {
+    return await write()
+  } catch (err) {
+    if (!isBackendError(err) || err.code !== 'outside-export-roots') throw err
+    const roots = await invoke<string[] | null>('add_export_root', { suggested: parentDir(path) })
+    if (!roots) throw err
+    return write()
+  }
+}
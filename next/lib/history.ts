@@ -0,0 +1,75 @@
+import { invoke } from '@tauri-apps/api/core'
+import { useEditorStore, TextBlock } from '@/lib/state'
+import { isBackendError } from '@/lib/errors'
+
+// Mirrors EditorSnapshot in src-tauri/src/history.rs
+interface EditorSnapshot {
+  blocks: TextBlock[]
+  canUndo: boolean
+  canRedo: boolean
+  undoLabel: string | null
+  redoLabel: string | null
+}
+
+const RECORD_DELAY_MS = 400 // A drag or a burst of typing becomes one undo step
+
+let timer: ReturnType<typeof setTimeout> | null = null
+let pending: { pageId: string; blocks: TextBlock[] } | null = null
+let recorded: TextBlock[] | null = null // Blocks the backend history already holds
+
+// Send the latest block edit now instead of waiting for the editor to go quiet
+export async function flushHistory(): Promise<void> {
+  if (timer) clearTimeout(timer)
+  timer = null
+  const edit = pending
+  pending = null
+  if (edit) await invoke('update_blocks', edit)
+}
+
+// Record block edits in the backend's per-page history; returns an unsubscribe
+export function trackHistory(): () => void {
+  let { pageId, textBlocks } = useEditorStore.getState()
+  recorded = textBlocks
+  if (pageId) invoke('load_editor_state', { pageId, blocks: textBlocks }).catch(console.error)
+
+  const unsubscribe = useEditorStore.subscribe((state) => {
+    if (state.pageId !== pageId) {
+      // Finish the previous page's edit before starting the new page's history
+      flushHistory().catch(console.error)
+      pageId = state.pageId
+      recorded = state.textBlocks
+      if (pageId) invoke('load_editor_state', { pageId, blocks: state.textBlocks }).catch(console.error)
+      return
+    }
+    if (!pageId || state.textBlocks === recorded) return
+
+    recorded = state.textBlocks
+    pending = { pageId, blocks: state.textBlocks }
+    if (timer) clearTimeout(timer)
+    timer = setTimeout(() => {
+      flushHistory().catch(console.error)
+    }, RECORD_DELAY_MS)
+  })
+
+  return () => {
+    if (timer) clearTimeout(timer)
+    unsubscribe()
+  }
+}
+
+async function step(command: 'undo' | 'redo'): Promise<void> {
+  const { pageId } = useEditorStore.getState()
+  if (!pageId) return
+  await flushHistory()
+  try {
+    const snapshot = await invoke<EditorSnapshot>(command, { pageId })
+    recorded = snapshot.blocks
+    useEditorStore.getState().setTextBlocks(snapshot.blocks)
+  } catch (err) {
+    if (isBackendError(err) && err.code === 'nothing-to-undo') return
+    throw err
+  }
+}
+
+export const undo = () => step('undo')
+export const redo = () => step('redo')
@@ -1,17 +1,32 @@
+import { invoke } from '@tauri-apps/api/core'
+
 export type Image = {
   buffer: ArrayBuffer
   bitmap: ImageBitmap
 }
 
+// Keep the source's pixel values instead of converting to the display color space,
+// so canvas edits stay in the space of the ICC profile the backend re-embeds on export
+const BITMAP_OPTIONS: ImageBitmapOptions = { colorSpaceConversion: 'none' }
+
 export async function createImageFromBlob(blob: Blob): Promise<Image> {
-  const bitmap = await createImageBitmap(blob)
-  return { buffer: await blob.arrayBuffer(), bitmap }
+  try {
+    const bitmap = await createImageBitmap(blob, BITMAP_OPTIONS)
+    return { buffer: await blob.arrayBuffer(), bitmap }
+  } catch (err) {
+    // The webview can't decode every format (e.g. JPEG XL); let the backend convert it to PNG
+    console.warn('createImageBitmap failed, decoding in backend', err)
+    const png = await invoke<number[]>('decode_image', {
+      image: Array.from(new Uint8Array(await blob.arrayBuffer())),
+    })
+    return createImageFromBuffer(new Uint8Array(png).buffer)
+  }
 }
 
 export async function createImageFromBuffer(
   buffer: ArrayBuffer
 ): Promise<Image> {
   const blob = new Blob([buffer])
-  const bitmap = await createImageBitmap(blob)
+  const bitmap = await createImageBitmap(blob, BITMAP_OPTIONS)
   return { buffer, bitmap }
 }
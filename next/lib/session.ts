@@ -0,0 +1,74 @@
+import { invoke } from '@tauri-apps/api/core'
+import { useEditorStore } from '@/lib/state'
+import { createImageFromBuffer } from '@/lib/image'
+
+// Mirrors src-tauri/src/session.rs
+interface Session {
+  project: string | null
+  workspace: string | null
+  pageIndex: number | null
+  zoom: number | null
+  ocrEngine: string | null
+  translationProvider: string | null
+  renderMethod: string | null
+}
+
+interface RestoredSession {
+  session: Session
+  project: { path: string } | null
+  page: { index: number; count: number; path: string; image: number[] } | null
+}
+
+const SAVE_DELAY_MS = 1000
+
+type EditorState = ReturnType<typeof useEditorStore.getState>
+
+// Reopen the last project, engines, page and zoom; the backend skips anything that's gone
+export async function restoreSession(): Promise<void> {
+  const restored = await invoke<RestoredSession>('restore_session')
+  const { session, page } = restored
+  const store = useEditorStore.getState()
+
+  if (session.ocrEngine) store.setOcrEngine(session.ocrEngine as EditorState['ocrEngine'])
+  if (session.translationProvider) {
+    store.setTranslationProvider(session.translationProvider as EditorState['translationProvider'])
+  }
+  if (session.renderMethod) store.setRenderMethod(session.renderMethod as EditorState['renderMethod'])
+
+  if (page) {
+    store.setImage(await createImageFromBuffer(new Uint8Array(page.image).buffer))
+    // Loading an image fits it to the viewport; apply the saved zoom after that has run
+    await new Promise((resolve) => requestAnimationFrame(() => requestAnimationFrame(resolve)))
+  }
+  if (session.zoom) useEditorStore.getState().setScale(session.zoom)
+}
+
+function sessionOf(state: EditorState): Partial<Session> {
+  return {
+    zoom: state.scale,
+    ocrEngine: state.ocrEngine,
+    translationProvider: state.translationProvider,
+    renderMethod: state.renderMethod,
+  }
+}
+
+// Save view state to the backend a moment after it stops changing; returns an unsubscribe
+export function persistSession(): () => void {
+  let timer: ReturnType<typeof setTimeout> | null = null
+  let last = JSON.stringify(sessionOf(useEditorStore.getState()))
+
+  const unsubscribe = useEditorStore.subscribe((state) => {
+    const current = JSON.stringify(sessionOf(state))
+    if (current === last) return
+    last = current
+    if (timer) clearTimeout(timer)
+    timer = setTimeout(() => {
+      invoke('save_session', { session: JSON.parse(current) }).catch(console.error)
+    }, SAVE_DELAY_MS)
+  })
+
+  return () => {
+    if (timer) clearTimeout(timer)
+    unsubscribe()
+  }
+}
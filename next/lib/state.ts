@@ -270,6 +270,7 @@ export const useEditorStore = create(
   combine(
     {
       image: null,
+      pageId: null,
       tool: 'detection',
       scale: 1,
   textBlocks: [],
@@ -311,6 +312,7 @@ export const useEditorStore = create(
       addTextAreaHandler: null,
     } as {
       image: Image | null
+      pageId: string | null // Backend page store key for the current image
       tool: string
       scale: number
       textBlocks: TextBlock[]
@@ -363,6 +365,7 @@ export const useEditorStore = create(
 
         return {
           image,
+          pageId: image ? crypto.randomUUID() : null,
           currentStage: 'original',
           pipelineStages: {
             original: null,
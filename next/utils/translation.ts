@@ -4,6 +4,8 @@
  * Uses REST API (no SDK needed) - works in browser/Tauri context
  */
 
+import { isBackendError } from '@/lib/errors'
+
 export type TranslationProvider = 'google' | 'deepl-free' | 'deepl-pro' | 'ollama'
 
 export interface TranslationError {
@@ -134,30 +136,17 @@ export async function translateWithDeepL(
 
     return result
   } catch (error) {
-    // Tauri invoke errors come as strings
-    if (typeof error === 'string') {
-      // Parse known error patterns
-      if (error.includes('Invalid API key') || error.includes('403') || error.includes('401')) {
-        throw new TranslationAPIError({
-          code: 403,
-          message: 'Invalid API key or insufficient permissions',
-          status: 'Forbidden',
-        })
-      } else if (error.includes('Rate limit') || error.includes('429')) {
-        throw new TranslationAPIError({
-          code: 429,
-          message: 'Rate limit exceeded. Please wait and try again.',
-          status: 'Too Many Requests',
-        })
-      } else if (error.includes('Quota exceeded') || error.includes('456')) {
-        throw new TranslationAPIError({
-          code: 456,
-          message: 'Quota exceeded. For DeepL Free, you\'ve used your 500,000 character/month limit.',
-          status: 'Quota Exceeded',
-        })
+    // Map backend error codes onto the HTTP-style codes the translation panel handles
+    if (isBackendError(error)) {
+      const mapped: Record<string, Omit<TranslationError, 'message'>> = {
+        'api-key-invalid': { code: 403, status: 'Forbidden' },
+        'rate-limited': { code: 429, status: 'Too Many Requests' },
+        'quota-exceeded': { code: 456, status: 'Quota Exceeded' },
       }
-
-      throw new Error(`DeepL translation failed: ${error}`)
+      if (mapped[error.code]) {
+        throw new TranslationAPIError({ ...mapped[error.code], message: error.message })
+      }
+      throw new Error(`DeepL translation failed: ${error.message}`)
     }
 
     if (error instanceof Error) {
@@ -197,11 +186,9 @@ export async function translateWithOllama(
 
     return result
   } catch (error) {
-    if (typeof error === 'string') {
-      if (error.includes('Failed to connect to Ollama')) {
-        throw new Error('Could not connect to Ollama. Make sure Ollama is running on http://localhost:11434')
-      }
-      throw new Error(`Ollama translation failed: ${error}`)
+    if (isBackendError(error)) {
+      // provider-unavailable already tells the user to start Ollama
+      throw new Error(error.message)
     }
 
     if (error instanceof Error) {
@@ -0,0 +1,20 @@
+# Backend error messages shown in the UI. `$detail` is the original English error.
+error-unknown = { $detail }
+error-cuda-unavailable = CUDA is not available. Check the NVIDIA driver and CUDA installation, or switch to CPU in Settings → GPU.
+error-directml-unavailable = DirectML is only available on Windows 10 or later.
+error-ocr-engine-unavailable = The selected OCR engine is not installed. Pick another engine in Settings → OCR.
+error-api-key-missing = No API key is set for the translation provider.
+error-api-key-invalid = The translation API key was rejected. Check the key and its permissions.
+error-quota-exceeded = The translation quota is used up. Wait for it to reset or upgrade your plan.
+error-rate-limited = Too many translation requests. Wait a moment and try again.
+error-network = Could not reach the server. Check your internet connection.
+error-provider-unavailable = The translation service is not running. Start it and try again.
+error-plugin-failed = A plugin failed: { $detail }
+error-file-not-found = The file could not be found.
+error-permission-denied = Koharu does not have permission to access this file or folder.
+error-unsupported-format = This file format is not supported.
+error-nothing-to-undo = There is nothing to undo or redo.
+error-outside-export-roots = Koharu can only write to folders you have trusted. Add this folder under Settings → Project → Export folders.
+error-model-missing = A model could not be downloaded or loaded. Check your internet connection and free disk space, then restart Koharu.
+error-invalid-input = The input could not be read. Check that it is a valid image or JSON file.
+error-inference-failed = A model failed while processing this page: { $detail }
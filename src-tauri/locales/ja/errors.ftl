@@ -0,0 +1,20 @@
+# UI に表示するバックエンドのエラーメッセージ。`$detail` は元の英語のエラーです。
+error-unknown = { $detail }
+error-cuda-unavailable = CUDA を利用できません。NVIDIA ドライバーと CUDA のインストールを確認するか、設定 → GPU で CPU に切り替えてください。
+error-directml-unavailable = DirectML は Windows 10 以降でのみ利用できます。
+error-ocr-engine-unavailable = 選択した OCR エンジンがインストールされていません。設定 → OCR で別のエンジンを選んでください。
+error-api-key-missing = 翻訳プロバイダーの API キーが設定されていません。
+error-api-key-invalid = 翻訳 API キーが拒否されました。キーと権限を確認してください。
+error-quota-exceeded = 翻訳の利用上限に達しました。リセットを待つか、プランをアップグレードしてください。
+error-rate-limited = 翻訳リクエストが多すぎます。しばらく待ってから再試行してください。
+error-network = サーバーに接続できません。インターネット接続を確認してください。
+error-provider-unavailable = 翻訳サービスが起動していません。起動してから再試行してください。
+error-plugin-failed = プラグインでエラーが発生しました: { $detail }
+error-file-not-found = ファイルが見つかりません。
+error-permission-denied = このファイルまたはフォルダーへのアクセス権がありません。
+error-unsupported-format = このファイル形式には対応していません。
+error-nothing-to-undo = 元に戻す・やり直す操作がありません。
+error-outside-export-roots = 信頼済みのフォルダーにのみ書き込めます。設定 → プロジェクト → 書き出し先フォルダーでこのフォルダーを追加してください。
+error-model-missing = モデルをダウンロードまたは読み込みできませんでした。インターネット接続とディスクの空き容量を確認してから Koharu を再起動してください。
+error-invalid-input = 入力を読み込めませんでした。有効な画像または JSON ファイルか確認してください。
+error-inference-failed = ページの処理中にモデルでエラーが発生しました: { $detail }
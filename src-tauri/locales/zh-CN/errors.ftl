@@ -0,0 +1,20 @@
+# 界面中显示的后端错误信息。`$detail` 是原始英文错误。
+error-unknown = { $detail }
+error-cuda-unavailable = CUDA 不可用。请检查 NVIDIA 驱动和 CUDA 安装，或在 设置 → GPU 中切换到 CPU。
+error-directml-unavailable = DirectML 仅在 Windows 10 及以上版本可用。
+error-ocr-engine-unavailable = 所选的 OCR 引擎未安装。请在 设置 → OCR 中选择其他引擎。
+error-api-key-missing = 尚未为翻译服务设置 API 密钥。
+error-api-key-invalid = 翻译 API 密钥被拒绝。请检查密钥及其权限。
+error-quota-exceeded = 翻译额度已用完。请等待额度重置或升级套餐。
+error-rate-limited = 翻译请求过多。请稍后再试。
+error-network = 无法连接服务器。请检查网络连接。
+error-provider-unavailable = 翻译服务未运行。请启动后重试。
+error-plugin-failed = 插件出错：{ $detail }
+error-file-not-found = 找不到该文件。
+error-permission-denied = 没有访问此文件或文件夹的权限。
+error-unsupported-format = 不支持此文件格式。
+error-nothing-to-undo = 没有可撤销或重做的操作。
+error-outside-export-roots = Koharu 只能写入已信任的文件夹。请在 设置 → 项目 → 导出文件夹 中添加此文件夹。
+error-model-missing = 无法下载或加载模型。请检查网络连接和磁盘空间，然后重新启动 Koharu。
+error-invalid-input = 无法读取输入。请确认它是有效的图片或 JSON 文件。
+error-inference-failed = 模型处理此页面时出错：{ $detail }
@@ -1,3 +1,9 @@
+use anyhow::{Context, anyhow};
+use image::{DynamicImage, GrayImage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
 use unicode_segmentation::UnicodeSegmentation;
 
 /// Character Error Rate (CER) and Word Error Rate (WER) calculation
@@ -79,7 +85,8 @@ impl AccuracyMetrics {
 }
 
 /// Batch accuracy calculation for multiple samples
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct BatchAccuracy {
     pub samples: Vec<SampleAccuracy>,
     pub average_cer: f64,
@@ -88,25 +95,66 @@ pub struct BatchAccuracy {
     pub max_wer: f64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct SampleAccuracy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>, // e.g. the sample's file name
     pub ground_truth: String,
     pub predicted: String,
     pub cer: f64,
     pub wer: f64,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReportFormat {
+    Csv,
+    #[default]
+    Json,
+}
+
+impl ReportFormat {
+    /// Format for a report path: `.csv` is CSV, anything else JSON
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => ReportFormat::Csv,
+            _ => ReportFormat::Json,
+        }
+    }
+}
+
+/// Quote a CSV field when it holds a separator, quote, or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
 impl BatchAccuracy {
     pub fn calculate(samples: Vec<(String, String)>) -> Self {
+        Self::calculate_labeled(
+            samples
+                .into_iter()
+                .map(|(ground_truth, predicted)| (None, ground_truth, predicted))
+                .collect(),
+        )
+    }
+
+    /// Like `calculate`, keeping a label (e.g. file name) with each sample
+    pub fn calculate_labeled(samples: Vec<(Option<String>, String, String)>) -> Self {
         let mut sample_accuracies = Vec::new();
         let mut total_cer = 0.0;
         let mut total_wer = 0.0;
         let mut max_cer = 0.0f64;
         let mut max_wer = 0.0f64;
 
-        for (ground_truth, predicted) in samples {
+        for (label, ground_truth, predicted) in samples {
             let metrics = AccuracyMetrics::calculate(&ground_truth, &predicted);
             let sample = SampleAccuracy {
+                label,
                 ground_truth,
                 predicted,
                 cer: metrics.cer,
@@ -138,6 +186,404 @@ impl BatchAccuracy {
     pub fn meets_thresholds(&self, max_cer: f64, max_wer: f64) -> bool {
         self.average_cer <= max_cer && self.average_wer <= max_wer
     }
+
+    /// One row per sample, then `average` and `max` rows
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("sample,ground_truth,predicted,cer,wer\n");
+        for (index, sample) in self.samples.iter().enumerate() {
+            let label = sample
+                .label
+                .clone()
+                .unwrap_or_else(|| (index + 1).to_string());
+            csv.push_str(&format!(
+                "{},{},{},{:.6},{:.6}\n",
+                csv_field(&label),
+                csv_field(&sample.ground_truth),
+                csv_field(&sample.predicted),
+                sample.cer,
+                sample.wer
+            ));
+        }
+        csv.push_str(&format!(
+            "average,,,{:.6},{:.6}\n",
+            self.average_cer, self.average_wer
+        ));
+        csv.push_str(&format!("max,,,{:.6},{:.6}\n", self.max_cer, self.max_wer));
+        csv
+    }
+
+    pub fn write_report(&self, path: &Path, format: ReportFormat) -> anyhow::Result<()> {
+        let contents = match format {
+            ReportFormat::Csv => self.to_csv(),
+            ReportFormat::Json => serde_json::to_string_pretty(self)? + "\n",
+        };
+        fs::write(path, contents).with_context(|| format!("Failed to write {:?}", path))
+    }
+}
+
+const BLEU_ORDER: usize = 4;
+const CHRF_ORDER: usize = 6;
+const CHRF_BETA: f64 = 2.0; // Recall weighted twice as much as precision
+
+/// Whitespace-separated words, with CJK characters and punctuation as tokens of their own
+/// so unsegmented Japanese or Chinese still gets word-level n-grams
+fn bleu_tokens(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for word in text.split_whitespace() {
+        let mut current = String::new();
+        for c in word.chars() {
+            let standalone = c.is_ascii_punctuation()
+                || matches!(c as u32, 0x3000..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xFF00..=0xFFEF);
+            if standalone {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                tokens.push(c.to_string());
+            } else {
+                current.push(c);
+            }
+        }
+        if !current.is_empty() {
+            tokens.push(current);
+        }
+    }
+    tokens
+}
+
+fn ngram_counts<T: Eq + std::hash::Hash>(items: &[T], n: usize) -> HashMap<&[T], usize> {
+    let mut counts = HashMap::new();
+    if n > 0 && items.len() >= n {
+        for gram in items.windows(n) {
+            *counts.entry(gram).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// (clipped matches, hypothesis n-grams) for each order, plus hypothesis and reference lengths
+fn bleu_stats(hypothesis: &str, reference: &str) -> ([(usize, usize); BLEU_ORDER], usize, usize) {
+    let hyp = bleu_tokens(hypothesis);
+    let reference = bleu_tokens(reference);
+    let mut stats = [(0, 0); BLEU_ORDER];
+    for (i, stat) in stats.iter_mut().enumerate() {
+        let ref_counts = ngram_counts(&reference, i + 1);
+        let hyp_counts = ngram_counts(&hyp, i + 1);
+        stat.0 = hyp_counts
+            .iter()
+            .map(|(gram, count)| (*count).min(*ref_counts.get(gram).unwrap_or(&0)))
+            .sum();
+        stat.1 = hyp_counts.values().sum();
+    }
+    (stats, hyp.len(), reference.len())
+}
+
+/// BLEU (0-100) from summed statistics; `smooth` adds one to higher orders (sentence level)
+fn bleu_score(
+    stats: &[(usize, usize); BLEU_ORDER],
+    hyp_len: usize,
+    ref_len: usize,
+    smooth: bool,
+) -> f64 {
+    if hyp_len == 0 {
+        return 0.0;
+    }
+    let mut log_precision = 0.0;
+    for (i, &(matches, total)) in stats.iter().enumerate() {
+        let (matches, total) = if smooth && i > 0 {
+            (matches as f64 + 1.0, total as f64 + 1.0)
+        } else {
+            (matches as f64, total as f64)
+        };
+        if matches == 0.0 || total == 0.0 {
+            return 0.0;
+        }
+        log_precision += (matches / total).ln() / BLEU_ORDER as f64;
+    }
+    let brevity_penalty = if hyp_len < ref_len {
+        (1.0 - ref_len as f64 / hyp_len as f64).exp()
+    } else {
+        1.0
+    };
+    100.0 * brevity_penalty * log_precision.exp()
+}
+
+/// Corpus BLEU-4 (0-100) of `hypotheses` against one reference each
+pub fn bleu(hypotheses: &[String], references: &[String]) -> f64 {
+    let mut stats = [(0, 0); BLEU_ORDER];
+    let (mut hyp_len, mut ref_len) = (0, 0);
+    for (hypothesis, reference) in hypotheses.iter().zip(references) {
+        let (sample, h, r) = bleu_stats(hypothesis, reference);
+        for (total, sample) in stats.iter_mut().zip(sample) {
+            total.0 += sample.0;
+            total.1 += sample.1;
+        }
+        hyp_len += h;
+        ref_len += r;
+    }
+    bleu_score(&stats, hyp_len, ref_len, false)
+}
+
+/// Per order (matches, hypothesis n-grams, reference n-grams) over characters, spaces removed
+fn chrf_stats(hypothesis: &str, reference: &str) -> [(usize, usize, usize); CHRF_ORDER] {
+    let hyp: Vec<char> = hypothesis.chars().filter(|c| !c.is_whitespace()).collect();
+    let reference: Vec<char> = reference.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut stats = [(0, 0, 0); CHRF_ORDER];
+    for (i, stat) in stats.iter_mut().enumerate() {
+        let hyp_counts = ngram_counts(&hyp, i + 1);
+        let ref_counts = ngram_counts(&reference, i + 1);
+        stat.0 = hyp_counts
+            .iter()
+            .map(|(gram, count)| (*count).min(*ref_counts.get(gram).unwrap_or(&0)))
+            .sum();
+        stat.1 = hyp_counts.values().sum();
+        stat.2 = ref_counts.values().sum();
+    }
+    stats
+}
+
+/// chrF (0-100): character n-gram F-score averaged over orders 1-6, β = 2
+fn chrf_score(stats: &[(usize, usize, usize); CHRF_ORDER]) -> f64 {
+    let orders: Vec<(f64, f64)> = stats
+        .iter()
+        .filter(|(_, hyp, reference)| *hyp > 0 && *reference > 0)
+        .map(|&(matches, hyp, reference)| {
+            (
+                matches as f64 / hyp as f64,
+                matches as f64 / reference as f64,
+            )
+        })
+        .collect();
+    if orders.is_empty() {
+        return 0.0;
+    }
+    let count = orders.len() as f64;
+    let precision = orders.iter().map(|(p, _)| p).sum::<f64>() / count;
+    let recall = orders.iter().map(|(_, r)| r).sum::<f64>() / count;
+    let beta2 = CHRF_BETA * CHRF_BETA;
+    if precision + recall == 0.0 {
+        return 0.0;
+    }
+    100.0 * (1.0 + beta2) * precision * recall / (beta2 * precision + recall)
+}
+
+/// Sentence-level chrF (0-100) of one hypothesis against its reference
+pub fn chrf(hypothesis: &str, reference: &str) -> f64 {
+    chrf_score(&chrf_stats(hypothesis, reference))
+}
+
+/// Corpus chrF (0-100), with n-gram statistics summed over every pair before scoring
+pub fn corpus_chrf(hypotheses: &[String], references: &[String]) -> f64 {
+    let mut stats = [(0, 0, 0); CHRF_ORDER];
+    for (hypothesis, reference) in hypotheses.iter().zip(references) {
+        for (total, sample) in stats.iter_mut().zip(chrf_stats(hypothesis, reference)) {
+            total.0 += sample.0;
+            total.1 += sample.1;
+            total.2 += sample.2;
+        }
+    }
+    chrf_score(&stats)
+}
+
+/// One machine translation scored against its reference
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SampleTranslationScore {
+    pub source: String,
+    pub reference: String,
+    pub translation: String,
+    pub bleu: f64, // Sentence BLEU, smoothed so short lines do not all score zero
+    pub chrf: f64,
+}
+
+/// Corpus BLEU and chrF (both 0-100) for one provider's translations
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranslationScore {
+    pub samples: Vec<SampleTranslationScore>,
+    pub bleu: f64,
+    pub chrf: f64,
+}
+
+impl TranslationScore {
+    /// Score (source, reference, translation) triples
+    pub fn calculate(samples: Vec<(String, String, String)>) -> Self {
+        let (hypotheses, references): (Vec<String>, Vec<String>) = samples
+            .iter()
+            .map(|(_, reference, translation)| (translation.clone(), reference.clone()))
+            .unzip();
+        let bleu = bleu(&hypotheses, &references);
+        let chrf = corpus_chrf(&hypotheses, &references);
+        let samples = samples
+            .into_iter()
+            .map(|(source, reference, translation)| {
+                let (stats, hyp_len, ref_len) = bleu_stats(&translation, &reference);
+                SampleTranslationScore {
+                    bleu: bleu_score(&stats, hyp_len, ref_len, true),
+                    chrf: self::chrf(&translation, &reference),
+                    source,
+                    reference,
+                    translation,
+                }
+            })
+            .collect();
+        Self {
+            samples,
+            bleu,
+            chrf,
+        }
+    }
+}
+
+const SSIM_WINDOW: u32 = 8;
+const SSIM_STRIDE: u32 = 4;
+const SSIM_C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const SSIM_C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+const GMSD_C: f64 = 170.0; // Stability constant for 0-255 luma, from the GMSD paper
+const MAX_PSNR: f64 = 100.0; // Reported for identical images instead of infinity
+
+/// Reconstruction quality of an inpainted image against the clean original
+///
+/// With a mask, only the masked area counts (SSIM windows touching it), so a large untouched
+/// page does not hide a bad fill.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InpaintMetrics {
+    pub ssim: f64, // Structural similarity, 1 = identical
+    pub gmsd: f64, // Gradient magnitude similarity deviation, 0 = identical edges
+    pub psnr: f64, // dB, capped at 100
+}
+
+impl InpaintMetrics {
+    pub fn calculate(
+        original: &DynamicImage,
+        inpainted: &DynamicImage,
+        mask: Option<&GrayImage>,
+    ) -> anyhow::Result<Self> {
+        let a = original.to_luma8();
+        let b = inpainted.to_luma8();
+        if a.dimensions() != b.dimensions() {
+            return Err(anyhow!(
+                "Size changed from {:?} to {:?}",
+                a.dimensions(),
+                b.dimensions()
+            ));
+        }
+        if mask.is_some_and(|mask| mask.dimensions() != a.dimensions()) {
+            return Err(anyhow!(
+                "Mask size differs from the image {:?}",
+                a.dimensions()
+            ));
+        }
+        let mask = mask.filter(|mask| mask.pixels().any(|p| p[0] > 0));
+        Ok(Self {
+            ssim: ssim(&a, &b, mask),
+            gmsd: gmsd(&a, &b, mask),
+            psnr: psnr(&a, &b, mask),
+        })
+    }
+}
+
+fn masked(mask: Option<&GrayImage>, x: u32, y: u32) -> bool {
+    mask.is_none_or(|mask| mask.get_pixel(x, y)[0] > 0)
+}
+
+/// Mean SSIM over 8x8 windows (stride 4) of two same-sized luma images
+pub fn ssim(a: &GrayImage, b: &GrayImage, mask: Option<&GrayImage>) -> f64 {
+    let (width, height) = a.dimensions();
+    let window_w = SSIM_WINDOW.min(width);
+    let window_h = SSIM_WINDOW.min(height);
+    let mut total = 0.0;
+    let mut windows = 0usize;
+    let mut y = 0;
+    while y + window_h <= height {
+        let mut x = 0;
+        while x + window_w <= width {
+            let pixels = (y..y + window_h).flat_map(|py| (x..x + window_w).map(move |px| (px, py)));
+            if mask.is_none() || pixels.clone().any(|(px, py)| masked(mask, px, py)) {
+                let count = (window_w * window_h) as f64;
+                let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) =
+                    (0.0, 0.0, 0.0, 0.0, 0.0);
+                for (px, py) in pixels {
+                    let va = a.get_pixel(px, py)[0] as f64;
+                    let vb = b.get_pixel(px, py)[0] as f64;
+                    sum_a += va;
+                    sum_b += vb;
+                    sum_aa += va * va;
+                    sum_bb += vb * vb;
+                    sum_ab += va * vb;
+                }
+                let (mean_a, mean_b) = (sum_a / count, sum_b / count);
+                let var_a = sum_aa / count - mean_a * mean_a;
+                let var_b = sum_bb / count - mean_b * mean_b;
+                let covariance = sum_ab / count - mean_a * mean_b;
+                total += ((2.0 * mean_a * mean_b + SSIM_C1) * (2.0 * covariance + SSIM_C2))
+                    / ((mean_a * mean_a + mean_b * mean_b + SSIM_C1) * (var_a + var_b + SSIM_C2));
+                windows += 1;
+            }
+            x += SSIM_STRIDE;
+        }
+        y += SSIM_STRIDE;
+    }
+    if windows == 0 {
+        1.0
+    } else {
+        total / windows as f64
+    }
+}
+
+/// Prewitt gradient magnitude at an interior pixel
+fn gradient(image: &GrayImage, x: u32, y: u32) -> f64 {
+    let p = |dx: i32, dy: i32| {
+        image.get_pixel((x as i32 + dx) as u32, (y as i32 + dy) as u32)[0] as f64
+    };
+    let gx = (p(1, -1) + p(1, 0) + p(1, 1) - p(-1, -1) - p(-1, 0) - p(-1, 1)) / 3.0;
+    let gy = (p(-1, 1) + p(0, 1) + p(1, 1) - p(-1, -1) - p(0, -1) - p(1, -1)) / 3.0;
+    (gx * gx + gy * gy).sqrt()
+}
+
+/// Gradient magnitude similarity deviation (Xue et al., 2014), a cheap perceptual metric
+///
+/// Standard deviation of the per-pixel gradient similarity; blur, smears, and ghost edges
+/// raise it, flat color shifts do not.
+pub fn gmsd(a: &GrayImage, b: &GrayImage, mask: Option<&GrayImage>) -> f64 {
+    let (width, height) = a.dimensions();
+    let mut similarities = Vec::new();
+    for y in 1..height.saturating_sub(1) {
+        for x in 1..width.saturating_sub(1) {
+            if masked(mask, x, y) {
+                let (ga, gb) = (gradient(a, x, y), gradient(b, x, y));
+                similarities.push((2.0 * ga * gb + GMSD_C) / (ga * ga + gb * gb + GMSD_C));
+            }
+        }
+    }
+    if similarities.is_empty() {
+        return 0.0;
+    }
+    let count = similarities.len() as f64;
+    let mean = similarities.iter().sum::<f64>() / count;
+    (similarities
+        .iter()
+        .map(|s| (s - mean) * (s - mean))
+        .sum::<f64>()
+        / count)
+        .sqrt()
+}
+
+/// Peak signal-to-noise ratio in dB
+pub fn psnr(a: &GrayImage, b: &GrayImage, mask: Option<&GrayImage>) -> f64 {
+    let (mut squared_error, mut count) = (0.0, 0usize);
+    for ((x, y, pa), pb) in a.enumerate_pixels().zip(b.pixels()) {
+        if masked(mask, x, y) {
+            let diff = pa[0] as f64 - pb[0] as f64;
+            squared_error += diff * diff;
+            count += 1;
+        }
+    }
+    if count == 0 || squared_error == 0.0 {
+        return MAX_PSNR;
+    }
+    let mse = squared_error / count as f64;
+    (10.0 * (255.0 * 255.0 / mse).log10()).min(MAX_PSNR)
 }
 
 #[cfg(test)]
@@ -173,10 +619,131 @@ mod tests {
         assert_eq!(metrics.wer, 1.0);
     }
 
+    #[test]
+    fn test_csv_report_quotes_fields_and_adds_aggregates() {
+        let batch = BatchAccuracy::calculate_labeled(vec![
+            (Some("a.png".into()), "hello".into(), "hello".into()),
+            (None, "say \"hi\", ok".into(), "say hi ok".into()),
+        ]);
+        let csv = batch.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "sample,ground_truth,predicted,cer,wer");
+        assert_eq!(lines[1], "a.png,hello,hello,0.000000,0.000000");
+        assert!(lines[2].starts_with("2,\"say \"\"hi\"\", ok\",say hi ok,"));
+        assert!(lines[3].starts_with("average,,,"));
+        assert!(lines[4].starts_with("max,,,"));
+
+        let json = serde_json::to_value(&batch).unwrap();
+        assert_eq!(json["samples"][0]["label"], "a.png");
+        assert!(json["samples"][1].get("label").is_none());
+        assert_eq!(json["averageCer"], batch.average_cer);
+    }
+
+    #[test]
+    fn test_report_format_from_extension() {
+        assert_eq!(
+            ReportFormat::for_path(Path::new("run.CSV")),
+            ReportFormat::Csv
+        );
+        assert_eq!(
+            ReportFormat::for_path(Path::new("run.json")),
+            ReportFormat::Json
+        );
+    }
+
     #[test]
     fn test_empty_prediction() {
         let metrics = AccuracyMetrics::calculate("hello", "");
         assert_eq!(metrics.cer, 1.0);
         assert_eq!(metrics.wer, 1.0);
     }
+
+    #[test]
+    fn test_bleu_identical_and_disjoint() {
+        let refs = vec!["the cat sat on the mat".to_string()];
+        assert!((bleu(&refs, &refs) - 100.0).abs() < 1e-9);
+        assert_eq!(bleu(&["a dog ran off quickly".to_string()], &refs), 0.0);
+        assert_eq!(bleu(&[String::new()], &refs), 0.0);
+    }
+
+    #[test]
+    fn test_bleu_brevity_penalty_and_cjk_tokens() {
+        let refs = vec!["the cat sat on the mat today".to_string()];
+        let short = bleu(&["the cat sat on the".to_string()], &refs);
+        assert!(short > 0.0 && short < 100.0);
+        assert_eq!(
+            bleu_tokens("今日は、いい天気!"),
+            vec!["今", "日", "は", "、", "い", "い", "天", "気", "!"]
+        );
+        assert_eq!(
+            bleu_tokens("Hello, world."),
+            vec!["Hello", ",", "world", "."]
+        );
+    }
+
+    #[test]
+    fn test_chrf_rewards_partial_matches() {
+        assert!((chrf("hello world", "hello world") - 100.0).abs() < 1e-9);
+        assert_eq!(chrf("", "hello"), 0.0);
+        let close = chrf("helo world", "hello world");
+        let far = chrf("goodbye", "hello world");
+        assert!(close > far && close < 100.0);
+    }
+
+    #[test]
+    fn test_translation_score_keeps_samples() {
+        let score = TranslationScore::calculate(vec![
+            (
+                "こんにちは".into(),
+                "Hello there".into(),
+                "Hello there".into(),
+            ),
+            (
+                "元気?".into(),
+                "How are you?".into(),
+                "Are you well?".into(),
+            ),
+        ]);
+        assert_eq!(score.samples.len(), 2);
+        assert!((score.samples[0].chrf - 100.0).abs() < 1e-9);
+        assert!(score.samples[1].bleu > 0.0 && score.samples[1].bleu < 100.0);
+        assert!(score.chrf > 0.0 && score.chrf < 100.0);
+        let json = serde_json::to_value(&score).unwrap();
+        assert_eq!(json["samples"][1]["translation"], "Are you well?");
+    }
+
+    fn gradient_image(width: u32, height: u32) -> GrayImage {
+        GrayImage::from_fn(width, height, |x, y| {
+            image::Luma([((x * 7 + y * 3) % 256) as u8])
+        })
+    }
+
+    #[test]
+    fn test_inpaint_metrics_identical_and_damaged() {
+        let original = DynamicImage::ImageLuma8(gradient_image(64, 64));
+        let same = InpaintMetrics::calculate(&original, &original, None).unwrap();
+        assert!((same.ssim - 1.0).abs() < 1e-9);
+        assert_eq!(same.gmsd, 0.0);
+        assert_eq!(same.psnr, MAX_PSNR);
+
+        // Flatten a 16x16 hole, as a blurry fill would
+        let mut damaged = gradient_image(64, 64);
+        let mut mask = GrayImage::new(64, 64);
+        for y in 24..40 {
+            for x in 24..40 {
+                damaged.put_pixel(x, y, image::Luma([128]));
+                mask.put_pixel(x, y, image::Luma([255]));
+            }
+        }
+        let damaged = DynamicImage::ImageLuma8(damaged);
+        let page = InpaintMetrics::calculate(&original, &damaged, None).unwrap();
+        let hole = InpaintMetrics::calculate(&original, &damaged, Some(&mask)).unwrap();
+        assert!(page.ssim < 1.0 && page.gmsd > 0.0);
+        // The untouched page dilutes the damage; the masked score does not
+        assert!(hole.ssim < page.ssim);
+        assert!(hole.psnr < page.psnr);
+
+        let small = DynamicImage::ImageLuma8(gradient_image(32, 64));
+        assert!(InpaintMetrics::calculate(&original, &small, None).is_err());
+    }
 }
@@ -0,0 +1,282 @@
+// Dataset annotation export: detection boxes, masks, corrected OCR text, and translations
+// Written per page as COCO or labelme JSON next to a copy of the page image, so corrected
+// pages can be used as training data for the detector and OCR models.
+
+use anyhow::Context;
+use base64::Engine;
+use image::{DynamicImage, GrayImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+const LABELME_VERSION: &str = "5.4.1";
+
+/// Detector classes: 0 is dark text, 1 is light text
+pub(crate) const CATEGORIES: [&str; 2] = ["text-dark", "text-light"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AnnotationFormat {
+    #[default]
+    Coco,
+    Labelme,
+}
+
+/// A text block as the editor holds it
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AnnotatedBlock {
+    pub xmin: f32,
+    pub ymin: f32,
+    pub xmax: f32,
+    pub ymax: f32,
+    pub confidence: f32,
+    pub class: usize,
+    pub text: Option<String>,
+    pub translated_text: Option<String>,
+    pub manually_edited_text: bool, // Text was corrected by the user, not just OCR output
+}
+
+impl AnnotatedBlock {
+    /// Box clamped to the image, as (x, y, width, height) in whole pixels
+    fn rect(&self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let clamp = |v: f32, max: u32| (v.max(0.0).round() as u32).min(max);
+        let x0 = clamp(self.xmin, width);
+        let y0 = clamp(self.ymin, height);
+        let x1 = clamp(self.xmax, width).max(x0);
+        let y1 = clamp(self.ymax, height).max(y0);
+        (x0, y0, x1 - x0, y1 - y0)
+    }
+
+    fn label(&self) -> &'static str {
+        CATEGORIES[self.class.min(CATEGORIES.len() - 1)]
+    }
+}
+
+/// Uncompressed COCO RLE of the mask pixels inside `rect`, over the whole image
+///
+/// Counts alternate background/foreground runs in column-major order, starting with background.
+fn coco_rle(mask: &GrayImage, rect: (u32, u32, u32, u32)) -> Value {
+    let (width, height) = mask.dimensions();
+    let (x0, y0, w, h) = rect;
+    let mut counts = Vec::new();
+    let mut current = false;
+    let mut run = 0u32;
+    for x in 0..width {
+        for y in 0..height {
+            let inside = x >= x0 && x < x0 + w && y >= y0 && y < y0 + h;
+            let on = inside && mask.get_pixel(x, y)[0] > 127;
+            if on != current {
+                counts.push(run);
+                run = 0;
+                current = on;
+            }
+            run += 1;
+        }
+    }
+    counts.push(run);
+    json!({ "size": [height, width], "counts": counts })
+}
+
+fn rect_polygon((x, y, w, h): (u32, u32, u32, u32)) -> Value {
+    json!([[x, y, x + w, y, x + w, y + h, x, y + h]])
+}
+
+fn attributes(block: &AnnotatedBlock) -> Value {
+    json!({
+        "text": block.text.as_deref().unwrap_or_default(),
+        "translation": block.translated_text,
+        "verified": block.manually_edited_text,
+    })
+}
+
+pub fn coco(
+    image_name: &str,
+    width: u32,
+    height: u32,
+    blocks: &[AnnotatedBlock],
+    mask: Option<&GrayImage>,
+) -> Value {
+    let annotations: Vec<Value> = blocks
+        .iter()
+        .enumerate()
+        .map(|(i, block)| {
+            let rect = block.rect(width, height);
+            let (x, y, w, h) = rect;
+            let segmentation = match mask {
+                Some(mask) => coco_rle(mask, rect),
+                None => rect_polygon(rect),
+            };
+            json!({
+                "id": i + 1,
+                "image_id": 1,
+                "category_id": block.class.min(CATEGORIES.len() - 1) + 1,
+                "bbox": [x, y, w, h],
+                "area": w * h,
+                "iscrowd": 0,
+                "score": block.confidence,
+                "segmentation": segmentation,
+                "attributes": attributes(block),
+            })
+        })
+        .collect();
+
+    let categories: Vec<Value> = CATEGORIES
+        .iter()
+        .enumerate()
+        .map(|(i, name)| json!({ "id": i + 1, "name": name, "supercategory": "text" }))
+        .collect();
+
+    json!({
+        "info": { "description": "Exported from Koharu" },
+        "images": [{ "id": 1, "file_name": image_name, "width": width, "height": height }],
+        "annotations": annotations,
+        "categories": categories,
+    })
+}
+
+/// Base64 PNG of the mask cropped to `rect`, as labelme's "mask" shapes expect
+fn mask_crop_png(mask: &GrayImage, (x, y, w, h): (u32, u32, u32, u32)) -> anyhow::Result<String> {
+    let crop = image::imageops::crop_imm(mask, x, y, w, h).to_image();
+    let mut png = Vec::new();
+    DynamicImage::ImageLuma8(crop).write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(png))
+}
+
+pub fn labelme(
+    image_name: &str,
+    width: u32,
+    height: u32,
+    blocks: &[AnnotatedBlock],
+    mask: Option<&GrayImage>,
+) -> anyhow::Result<Value> {
+    let mut shapes = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        let rect = block.rect(width, height);
+        let (x, y, w, h) = rect;
+        let mut shape = json!({
+            "label": block.label(),
+            "points": [[x, y], [x + w, y + h]],
+            "group_id": null,
+            "description": block.text.as_deref().unwrap_or_default(),
+            "shape_type": "rectangle",
+            "flags": {},
+            "mask": null,
+            "attributes": attributes(block),
+        });
+        if let Some(mask) = mask.filter(|_| w > 0 && h > 0) {
+            shape["shape_type"] = json!("mask");
+            shape["mask"] = json!(mask_crop_png(mask, rect)?);
+        }
+        shapes.push(shape);
+    }
+
+    Ok(json!({
+        "version": LABELME_VERSION,
+        "flags": {},
+        "shapes": shapes,
+        "imagePath": image_name,
+        "imageData": null,
+        "imageHeight": height,
+        "imageWidth": width,
+    }))
+}
+
+/// Write `<stem>.png` and its annotation file into `dir`; returns the written paths
+pub fn export(
+    dir: &Path,
+    stem: &str,
+    format: AnnotationFormat,
+    image: &DynamicImage,
+    blocks: &[AnnotatedBlock],
+    mask: Option<&GrayImage>,
+) -> anyhow::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    // Masks from another resolution can't be matched to the boxes
+    let mask = mask.filter(|m| m.dimensions() == image.dimensions());
+    let (width, height) = (image.width(), image.height());
+
+    let image_name = format!("{}.png", stem);
+    let image_path = dir.join(&image_name);
+    image
+        .save_with_format(&image_path, ImageFormat::Png)
+        .with_context(|| format!("Failed to write {:?}", image_path))?;
+
+    let (annotation_path, annotation) = match format {
+        AnnotationFormat::Coco => (
+            dir.join(format!("{}.coco.json", stem)),
+            coco(&image_name, width, height, blocks, mask),
+        ),
+        AnnotationFormat::Labelme => (
+            dir.join(format!("{}.json", stem)),
+            labelme(&image_name, width, height, blocks, mask)?,
+        ),
+    };
+    fs::write(&annotation_path, serde_json::to_string_pretty(&annotation)?)
+        .with_context(|| format!("Failed to write {:?}", annotation_path))?;
+
+    Ok(vec![image_path, annotation_path])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block() -> AnnotatedBlock {
+        AnnotatedBlock {
+            xmin: 1.0,
+            ymin: 1.0,
+            xmax: 3.0,
+            ymax: 3.0,
+            confidence: 0.9,
+            class: 1,
+            text: Some("テスト".to_string()),
+            translated_text: Some("Test".to_string()),
+            manually_edited_text: true,
+        }
+    }
+
+    #[test]
+    fn test_coco_rle_is_column_major_and_limited_to_box() {
+        let mut mask = GrayImage::new(4, 4);
+        for (x, y) in [(1, 1), (1, 2), (2, 1), (0, 0)] {
+            mask.put_pixel(x, y, image::Luma([255]));
+        }
+        // (0, 0) is outside the box; column 1 has rows 1-2, column 2 has row 1
+        let rle = coco_rle(&mask, (1, 1, 2, 2));
+        assert_eq!(rle["size"], json!([4, 4]));
+        assert_eq!(rle["counts"], json!([5, 2, 2, 1, 6]));
+    }
+
+    #[test]
+    fn test_coco_annotation() {
+        let doc = coco("p1.png", 4, 4, &[block()], None);
+        let annotation = &doc["annotations"][0];
+        assert_eq!(annotation["bbox"], json!([1, 1, 2, 2]));
+        assert_eq!(annotation["category_id"], 2);
+        assert_eq!(
+            annotation["segmentation"],
+            json!([[1, 1, 3, 1, 3, 3, 1, 3]])
+        );
+        assert_eq!(annotation["attributes"]["text"], "テスト");
+        assert_eq!(annotation["attributes"]["verified"], true);
+        assert_eq!(doc["images"][0]["file_name"], "p1.png");
+    }
+
+    #[test]
+    fn test_labelme_shapes() {
+        let mask = GrayImage::from_pixel(4, 4, image::Luma([255]));
+        let doc = labelme("p1.png", 4, 4, &[block()], Some(&mask)).unwrap();
+        let shape = &doc["shapes"][0];
+        assert_eq!(shape["label"], "text-light");
+        assert_eq!(shape["shape_type"], "mask");
+        assert_eq!(shape["points"], json!([[1, 1], [3, 3]]));
+        assert_eq!(shape["attributes"]["translation"], "Test");
+        assert!(shape["mask"].is_string());
+
+        let doc = labelme("p1.png", 4, 4, &[block()], None).unwrap();
+        assert_eq!(doc["shapes"][0]["shape_type"], "rectangle");
+    }
+}
@@ -0,0 +1,251 @@
+// Source text appearance from the original crop of a block: the pixels are clustered into
+// (up to) three colors, the one covering the crop border is the bubble background, the one
+// most enclosed by the others is the text, and a remaining cluster lining the text strokes is
+// an outline. Outline width is its area over the length of the text edge it wraps.
+
+use image::{DynamicImage, Rgb, RgbImage};
+
+use crate::text_renderer::{AppearanceData, RgbColor};
+
+const CLUSTERS: usize = 3;
+const ITERATIONS: usize = 10;
+const BORDER: u32 = 2; // Pixels from the crop edge sampled as background
+const MIN_OUTLINE_SHARE: f32 = 0.15; // Outline pixels per text pixel below this are anti-aliasing
+const MIN_OUTLINE_CONTRAST: f32 = 60.0; // RGB distance between outline and text
+const MIN_BLEND_DISTANCE: f32 = 40.0; // RGB distance off the text-to-background line
+const MIN_OUTLINE_COVERAGE: f32 = 0.5; // Share of the text edge the outline must line
+const MAX_OUTLINE_WIDTH: f32 = 12.0;
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f32>()
+        .sqrt()
+}
+
+fn to_rgb(color: [f32; 3]) -> RgbColor {
+    let [r, g, b] = color.map(|c| c.round().clamp(0.0, 255.0) as u8);
+    RgbColor { r, g, b }
+}
+
+/// Seeds spread by farthest-point: the mean border color, then each next seed the pixel
+/// farthest from those already picked, so results are deterministic
+fn seeds(image: &RgbImage, pixels: &[[f32; 3]]) -> Vec<[f32; 3]> {
+    let (width, height) = image.dimensions();
+    let border: Vec<[f32; 3]> = image
+        .enumerate_pixels()
+        .filter(|(x, y, _)| {
+            *x < BORDER || *y < BORDER || x + BORDER >= width || y + BORDER >= height
+        })
+        .map(|(_, _, Rgb(p))| p.map(f32::from))
+        .collect();
+    let mut mean = [0.0f32; 3];
+    for pixel in &border {
+        for (sum, value) in mean.iter_mut().zip(pixel) {
+            *sum += value / border.len() as f32;
+        }
+    }
+
+    let mut seeds = vec![mean];
+    while seeds.len() < CLUSTERS {
+        let nearest = |p: &[f32; 3]| {
+            seeds
+                .iter()
+                .map(|s| distance(*p, *s))
+                .fold(f32::MAX, f32::min)
+        };
+        let farthest = pixels
+            .iter()
+            .max_by(|a, b| nearest(a).total_cmp(&nearest(b)))
+            .copied()
+            .unwrap_or(mean);
+        seeds.push(farthest);
+    }
+    seeds
+}
+
+/// k-means over the crop's colors; returns the centers and each pixel's cluster, row-major
+fn cluster(image: &RgbImage) -> (Vec<[f32; 3]>, Vec<usize>) {
+    let pixels: Vec<[f32; 3]> = image.pixels().map(|Rgb(p)| p.map(f32::from)).collect();
+    let mut centers = seeds(image, &pixels);
+    let mut labels = vec![0; pixels.len()];
+    for _ in 0..ITERATIONS {
+        for (label, pixel) in labels.iter_mut().zip(&pixels) {
+            *label = (0..CLUSTERS)
+                .min_by(|&a, &b| {
+                    distance(*pixel, centers[a]).total_cmp(&distance(*pixel, centers[b]))
+                })
+                .unwrap_or(0);
+        }
+        let mut sums = [[0.0f32; 3]; CLUSTERS];
+        let mut counts = [0usize; CLUSTERS];
+        for (&label, pixel) in labels.iter().zip(&pixels) {
+            counts[label] += 1;
+            for (sum, value) in sums[label].iter_mut().zip(pixel) {
+                *sum += value;
+            }
+        }
+        for (index, center) in centers.iter_mut().enumerate() {
+            if counts[index] > 0 {
+                *center = sums[index].map(|sum| sum / counts[index] as f32);
+            }
+        }
+    }
+    (centers, labels)
+}
+
+/// How far `color` sits from the line between `a` and `b`; small for a blend of the two
+fn blend_distance(color: [f32; 3], a: [f32; 3], b: [f32; 3]) -> f32 {
+    let span = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let length = span.iter().map(|d| d * d).sum::<f32>();
+    if length == 0.0 {
+        return distance(color, a);
+    }
+    let t = ((0..3).map(|i| (color[i] - a[i]) * span[i]).sum::<f32>() / length).clamp(0.0, 1.0);
+    distance(color, [0, 1, 2].map(|i| a[i] + t * span[i]))
+}
+
+/// Estimate text, outline, and background colors of a source text crop
+///
+/// With two non-background colors, the one touching the background more is the outline
+/// candidate. It is kept when it differs from both the text and a text/background blend
+/// (anti-aliasing), and lines most of the text edge.
+pub fn analyze(crop: &DynamicImage) -> AppearanceData {
+    let mut appearance = AppearanceData::default();
+    let image = crop.to_rgb8();
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        return appearance;
+    }
+
+    let (centers, labels) = cluster(&image);
+    let label_at = |x: u32, y: u32| labels[(y * width + x) as usize];
+    let neighbours = |x: u32, y: u32| {
+        [(-1i64, 0i64), (1, 0), (0, -1), (0, 1)]
+            .into_iter()
+            .map(move |(dx, dy)| (x as i64 + dx, y as i64 + dy))
+            .filter(|&(nx, ny)| nx >= 0 && ny >= 0 && nx < width as i64 && ny < height as i64)
+            .map(move |(nx, ny)| label_at(nx as u32, ny as u32))
+    };
+
+    let mut counts = [0usize; CLUSTERS];
+    let mut border_counts = [0usize; CLUSTERS];
+    for y in 0..height {
+        for x in 0..width {
+            let label = label_at(x, y);
+            counts[label] += 1;
+            if x < BORDER || y < BORDER || x + BORDER >= width || y + BORDER >= height {
+                border_counts[label] += 1;
+            }
+        }
+    }
+    let background = (0..CLUSTERS).max_by_key(|&c| border_counts[c]).unwrap_or(0);
+    appearance.source_background_color = Some(to_rgb(centers[background]));
+
+    let mut foreground: Vec<usize> = (0..CLUSTERS)
+        .filter(|&c| c != background && counts[c] > 0)
+        .collect();
+    if foreground.is_empty() {
+        return appearance; // A flat crop: nothing but background
+    }
+
+    // Share of each foreground cluster's pixels that touch the background
+    let mut touching = [0usize; CLUSTERS];
+    for y in 0..height {
+        for x in 0..width {
+            let label = label_at(x, y);
+            if label != background && neighbours(x, y).any(|n| n == background) {
+                touching[label] += 1;
+            }
+        }
+    }
+    let exposure = |c: usize| touching[c] as f32 / counts[c] as f32;
+    foreground.sort_by(|&a, &b| exposure(a).total_cmp(&exposure(b)));
+    let text = foreground[0];
+    appearance.source_text_color = Some(to_rgb(centers[text]));
+
+    let Some(&outline) = foreground.get(1) else {
+        return appearance;
+    };
+    let distinct = counts[outline] as f32 >= counts[text] as f32 * MIN_OUTLINE_SHARE
+        && distance(centers[outline], centers[text]) >= MIN_OUTLINE_CONTRAST
+        && blend_distance(centers[outline], centers[text], centers[background])
+            >= MIN_BLEND_DISTANCE;
+    if !distinct {
+        return appearance;
+    }
+
+    let mut text_edge = 0usize;
+    let mut lined_edge = 0usize;
+    for y in 0..height {
+        for x in 0..width {
+            if label_at(x, y) == text && neighbours(x, y).any(|n| n != text) {
+                text_edge += 1;
+                if neighbours(x, y).any(|n| n == outline) {
+                    lined_edge += 1;
+                }
+            }
+        }
+    }
+    if text_edge == 0 || (lined_edge as f32) < text_edge as f32 * MIN_OUTLINE_COVERAGE {
+        return appearance;
+    }
+
+    let outline_width = (counts[outline] as f32 / text_edge as f32).clamp(1.0, MAX_OUTLINE_WIDTH);
+    appearance.source_outline_color = Some(to_rgb(centers[outline]));
+    appearance.outline_width_px = Some((outline_width * 2.0).round() / 2.0);
+    appearance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// White bubble with a black bar, optionally wrapped in a red band `outline` px wide
+    fn sample(outline: u32) -> DynamicImage {
+        let mut image = RgbImage::from_pixel(60, 60, Rgb([250, 250, 250]));
+        for y in 10..50 {
+            for x in 20..40 {
+                let inside = (20 + outline..40 - outline).contains(&x)
+                    && (10 + outline..50 - outline).contains(&y);
+                let color = if inside { [10, 10, 10] } else { [220, 30, 30] };
+                image.put_pixel(x, y, Rgb(color));
+            }
+        }
+        DynamicImage::ImageRgb8(image)
+    }
+
+    fn rgb(color: &Option<RgbColor>) -> Option<(u8, u8, u8)> {
+        color.as_ref().map(|c| (c.r, c.g, c.b))
+    }
+
+    #[test]
+    fn test_plain_text_has_no_outline() {
+        let appearance = analyze(&sample(0));
+        assert_eq!(rgb(&appearance.source_text_color), Some((10, 10, 10)));
+        assert_eq!(
+            rgb(&appearance.source_background_color),
+            Some((250, 250, 250))
+        );
+        assert!(appearance.source_outline_color.is_none());
+        assert!(appearance.outline_width_px.is_none());
+
+        let flat = analyze(&DynamicImage::ImageRgb8(RgbImage::from_pixel(
+            8,
+            8,
+            Rgb([0, 0, 0]),
+        )));
+        assert!(flat.source_text_color.is_none());
+        assert!(flat.source_background_color.is_some());
+    }
+
+    #[test]
+    fn test_outline_color_and_width() {
+        let appearance = analyze(&sample(3));
+        assert_eq!(rgb(&appearance.source_text_color), Some((10, 10, 10)));
+        assert_eq!(rgb(&appearance.source_outline_color), Some((220, 30, 30)));
+        let width = appearance.outline_width_px.unwrap();
+        assert!((2.0..=4.0).contains(&width), "width {}", width);
+    }
+}
@@ -0,0 +1,328 @@
+// Comic archive import: CBZ/ZIP and CBR/RAR chapters are extracted into
+// app_data_dir/workspace/<name>/ so pages can be opened like any other image.
+// The archive type is sniffed from its header, since .cbr files are often zips.
+
+use anyhow::{Context, anyhow};
+use std::cmp::Ordering;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+use crate::image_io;
+
+pub const WORKSPACE_DIR: &str = "workspace";
+
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const RAR_MAGIC: &[u8] = b"Rar!\x1a\x07";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveKind {
+    Zip,
+    Rar,
+}
+
+#[derive(Debug, Clone)]
+pub struct Extracted {
+    pub dir: PathBuf,
+    pub pages: Vec<PathBuf>, // Naturally sorted by path inside the archive
+}
+
+fn detect(path: &Path) -> anyhow::Result<ArchiveKind> {
+    let mut header = [0u8; 8];
+    let read = File::open(path)
+        .and_then(|mut file| file.read(&mut header))
+        .with_context(|| format!("Failed to read {:?}", path))?;
+    let header = &header[..read];
+
+    if header.starts_with(ZIP_MAGIC) {
+        Ok(ArchiveKind::Zip)
+    } else if header.starts_with(RAR_MAGIC) {
+        Ok(ArchiveKind::Rar)
+    } else {
+        Err(anyhow!(
+            "{:?} is not a supported archive (expected CBZ/ZIP or CBR/RAR)",
+            path
+        ))
+    }
+}
+
+/// Compare strings with digit runs ordered by value, so "page2" sorts before "page10"
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let take_number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(c) = chars.peek().copied().filter(char::is_ascii_digit) {
+                        digits.push(c);
+                        chars.next();
+                    }
+                    digits
+                };
+                let (x, y) = (take_number(&mut a), take_number(&mut b));
+                let (x_trimmed, y_trimmed) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                let ordering = x_trimmed
+                    .len()
+                    .cmp(&y_trimmed.len())
+                    .then_with(|| x_trimmed.cmp(y_trimmed))
+                    .then_with(|| x.len().cmp(&y.len()));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+/// Where an entry should be extracted, or None for entries that aren't pages
+///
+/// Rejects absolute paths and `..`, and skips hidden files and macOS resource forks.
+fn page_path(name: &Path) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => {
+                let part = part.to_string_lossy();
+                if part.starts_with('.') || part == "__MACOSX" {
+                    return None;
+                }
+                path.push(part.as_ref());
+            }
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    image_io::is_supported_input(&path).then_some(path)
+}
+
+/// Workspace directory for an archive, stable across re-imports of the same file
+fn workspace_name(archive: &Path) -> anyhow::Result<String> {
+    let metadata =
+        fs::metadata(archive).with_context(|| format!("Failed to read {:?}", archive))?;
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(archive.to_string_lossy().as_bytes());
+    hasher.update(&metadata.len().to_le_bytes());
+    if let Ok(modified) = metadata.modified() {
+        hasher.update(format!("{:?}", modified).as_bytes());
+    }
+
+    let stem: String = archive
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "archive".to_string())
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    Ok(format!("{}-{:08x}", stem.trim(), hasher.finalize()))
+}
+
+fn extract_zip(archive: &Path, dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let file = File::open(archive).with_context(|| format!("Failed to open {:?}", archive))?;
+    let mut zip = zip::ZipArchive::new(file).context("Invalid ZIP archive")?;
+
+    let mut pages = Vec::new();
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index).context("Failed to read ZIP entry")?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(relative) = page_path(Path::new(entry.name())) else {
+            continue;
+        };
+        let output = dir.join(&relative);
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file =
+            File::create(&output).with_context(|| format!("Failed to create {:?}", output))?;
+        io::copy(&mut entry, &mut file)
+            .with_context(|| format!("Failed to extract {}", entry.name()))?;
+        pages.push(relative);
+    }
+    Ok(pages)
+}
+
+fn extract_rar(archive: &Path, dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut rar = unrar::Archive::new(archive)
+        .open_for_processing()
+        .context("Invalid RAR archive")?;
+
+    let mut pages = Vec::new();
+    while let Some(header) = rar.read_header().context("Failed to read RAR entry")? {
+        let entry = header.entry();
+        let relative = if entry.is_file() {
+            page_path(&entry.filename)
+        } else {
+            None
+        };
+        rar = match relative {
+            Some(relative) => {
+                let output = dir.join(&relative);
+                if let Some(parent) = output.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let next = header
+                    .extract_to(&output)
+                    .with_context(|| format!("Failed to extract {:?}", relative))?;
+                pages.push(relative);
+                next
+            }
+            None => header.skip().context("Failed to skip RAR entry")?,
+        };
+    }
+    Ok(pages)
+}
+
+/// Extract the pages of `archive` into a fresh directory under `workspace_root`
+pub fn extract(archive: &Path, workspace_root: &Path) -> anyhow::Result<Extracted> {
+    let kind = detect(archive)?;
+    let dir = workspace_root.join(workspace_name(archive)?);
+    if dir.exists() {
+        // Re-import replaces the previous extraction rather than mixing old pages in
+        fs::remove_dir_all(&dir).with_context(|| format!("Failed to clear {:?}", dir))?;
+    }
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {:?}", dir))?;
+
+    let mut pages = match kind {
+        ArchiveKind::Zip => extract_zip(archive, &dir)?,
+        ArchiveKind::Rar => extract_rar(archive, &dir)?,
+    };
+    if pages.is_empty() {
+        return Err(anyhow!("No supported images found in {:?}", archive));
+    }
+
+    pages.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+    pages.dedup();
+    tracing::info!(
+        "[archive] extracted {} page(s) from {:?} ({:?}) -> {:?}",
+        pages.len(),
+        archive,
+        kind,
+        dir
+    );
+
+    Ok(Extracted {
+        pages: pages.into_iter().map(|page| dir.join(page)).collect(),
+        dir,
+    })
+}
+
+/// Pages previously extracted into `dir`, in the same order `extract` returned them
+pub fn workspace_pages(dir: &Path) -> Vec<PathBuf> {
+    fn walk(dir: &Path, base: &Path, pages: &mut Vec<PathBuf>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for path in entries.filter_map(|entry| entry.ok().map(|e| e.path())) {
+            if path.is_dir() {
+                walk(&path, base, pages);
+            } else if let Ok(relative) = path.strip_prefix(base) {
+                if image_io::is_supported_input(relative) {
+                    pages.push(relative.to_path_buf());
+                }
+            }
+        }
+    }
+
+    let mut pages = Vec::new();
+    walk(dir, dir, &mut pages);
+    pages.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+    pages.into_iter().map(|page| dir.join(page)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::{FileOptions, ZipWriter};
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec![
+            "page10.png",
+            "Page2.png",
+            "page1.png",
+            "page02.png",
+            "cover.jpg",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            vec![
+                "cover.jpg",
+                "page1.png",
+                "Page2.png",
+                "page02.png",
+                "page10.png"
+            ]
+        );
+        assert_eq!(natural_cmp("ch1/10.png", "ch2/1.png"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_page_path_rejects_unsafe_and_non_page_entries() {
+        assert_eq!(
+            page_path(Path::new("ch01/001.jpg")),
+            Some(PathBuf::from("ch01/001.jpg"))
+        );
+        assert_eq!(page_path(Path::new("../evil.png")), None);
+        assert_eq!(page_path(Path::new("/abs.png")), None);
+        assert_eq!(page_path(Path::new("__MACOSX/._001.jpg")), None);
+        assert_eq!(page_path(Path::new(".thumb.png")), None);
+        assert_eq!(page_path(Path::new("ComicInfo.xml")), None);
+    }
+
+    #[test]
+    fn test_extract_zip_sorts_pages_naturally() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("chapter.cbz");
+        {
+            let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+            let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+            for name in ["10.png", "2.png", "1.png", "ComicInfo.xml"] {
+                zip.start_file(name, options).unwrap();
+                zip.write_all(name.as_bytes()).unwrap();
+            }
+            zip.finish().unwrap();
+        }
+
+        let workspace = dir.path().join(WORKSPACE_DIR);
+        let extracted = extract(&archive, &workspace).unwrap();
+        let names: Vec<String> = extracted
+            .pages
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["1.png", "2.png", "10.png"]);
+        assert!(extracted.dir.starts_with(&workspace));
+        assert_eq!(fs::read(&extracted.pages[2]).unwrap(), b"10.png");
+
+        // Re-importing reuses the same directory
+        assert_eq!(extract(&archive, &workspace).unwrap().dir, extracted.dir);
+        assert_eq!(workspace_pages(&extracted.dir), extracted.pages);
+    }
+
+    #[test]
+    fn test_detect_rejects_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.cbz");
+        fs::write(&path, b"not an archive").unwrap();
+        assert!(extract(&path, dir.path()).is_err());
+    }
+}
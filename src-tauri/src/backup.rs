@@ -0,0 +1,194 @@
+// Safety net for exports that overwrite files in the source folder
+// Before an existing file inside the source folder is replaced, it is copied to
+// <source>/.koharu-backups/<timestamp>/ (one directory per run, oldest pruned past `keep`).
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::metadata;
+
+pub const BACKUP_DIR: &str = ".koharu-backups";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BackupSettings {
+    pub enabled: bool,
+    pub keep: usize, // Backup runs kept per source folder; 0 keeps all
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        BackupSettings {
+            enabled: true,
+            keep: 10,
+        }
+    }
+}
+
+/// Backups for one export run; every file it protects lands in the same timestamped directory
+#[derive(Debug, Clone)]
+pub struct Backup {
+    settings: BackupSettings,
+    stamp: String,
+}
+
+impl Backup {
+    /// `started` names the run's directory, so a resumed job keeps using its original one
+    pub fn new(settings: BackupSettings, started: SystemTime) -> Self {
+        // Colons aren't allowed in Windows file names
+        let stamp = metadata::utc_timestamp(started).replace(':', "-");
+        Backup { settings, stamp }
+    }
+
+    /// Copy `target` aside if it exists and lives inside `source_dir`
+    ///
+    /// Returns the backup path, or None when nothing needed protecting. A file already
+    /// backed up in this run is left alone so the copy stays the original.
+    pub fn protect(&self, target: &Path, source_dir: &Path) -> anyhow::Result<Option<PathBuf>> {
+        if !self.settings.enabled || !target.is_file() {
+            return Ok(None);
+        }
+        // `Path::parent` of a bare file name is empty
+        let source_dir = if source_dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            source_dir
+        };
+        let (Ok(target), Ok(source_dir)) = (target.canonicalize(), source_dir.canonicalize())
+        else {
+            return Ok(None);
+        };
+        let Ok(relative) = target.strip_prefix(&source_dir) else {
+            return Ok(None);
+        };
+
+        let root = source_dir.join(BACKUP_DIR);
+        if relative.starts_with(BACKUP_DIR) {
+            return Ok(None);
+        }
+        let run_dir = root.join(&self.stamp);
+        let backup = run_dir.join(relative);
+        if backup.exists() {
+            return Ok(None);
+        }
+
+        let new_run = !run_dir.exists();
+        if let Some(parent) = backup.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create backup directory {:?}", parent))?;
+        }
+        fs::copy(&target, &backup)
+            .with_context(|| format!("Failed to back up {:?} to {:?}", target, backup))?;
+        tracing::info!("[backup] {:?} -> {:?}", target, backup);
+
+        if new_run {
+            prune(&root, self.settings.keep);
+        }
+        Ok(Some(backup))
+    }
+}
+
+/// Remove the oldest run directories beyond `keep`
+fn prune(root: &Path, keep: usize) {
+    if keep == 0 {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    let mut runs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    // Timestamps sort chronologically as strings
+    runs.sort();
+    let excess = runs.len().saturating_sub(keep);
+    for old in runs.into_iter().take(excess) {
+        if let Err(err) = fs::remove_dir_all(&old) {
+            tracing::warn!("[backup] failed to prune {:?}: {}", old, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn backup_at(secs: u64, keep: usize) -> Backup {
+        let settings = BackupSettings {
+            enabled: true,
+            keep,
+        };
+        Backup::new(settings, UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    #[test]
+    fn test_protect_copies_only_files_in_source_folder() {
+        let source = tempfile::tempdir().unwrap();
+        let elsewhere = tempfile::tempdir().unwrap();
+        let page = source.path().join("001.png");
+        fs::write(&page, b"original").unwrap();
+        fs::write(elsewhere.path().join("001.png"), b"other").unwrap();
+
+        let backup = backup_at(0, 10);
+        let copied = backup.protect(&page, source.path()).unwrap().unwrap();
+        assert_eq!(
+            copied,
+            source
+                .path()
+                .canonicalize()
+                .unwrap()
+                .join(BACKUP_DIR)
+                .join("1970-01-01T00-00-00Z")
+                .join("001.png")
+        );
+
+        // Second write in the same run keeps the first copy
+        fs::write(&page, b"translated").unwrap();
+        assert!(backup.protect(&page, source.path()).unwrap().is_none());
+        assert_eq!(fs::read(&copied).unwrap(), b"original");
+
+        let outside = elsewhere.path().join("001.png");
+        assert!(backup.protect(&outside, source.path()).unwrap().is_none());
+        let missing = source.path().join("002.png");
+        assert!(backup.protect(&missing, source.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_old_runs_are_pruned() {
+        let source = tempfile::tempdir().unwrap();
+        let page = source.path().join("001.png");
+        fs::write(&page, b"original").unwrap();
+
+        for secs in 0..5 {
+            backup_at(secs, 3).protect(&page, source.path()).unwrap();
+        }
+        let runs: Vec<String> = fs::read_dir(source.path().join(BACKUP_DIR))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(runs.len(), 3);
+        assert!(!runs.contains(&"1970-01-01T00-00-00Z".to_string()));
+    }
+
+    #[test]
+    fn test_disabled_does_nothing() {
+        let source = tempfile::tempdir().unwrap();
+        let page = source.path().join("001.png");
+        fs::write(&page, b"original").unwrap();
+
+        let backup = Backup::new(
+            BackupSettings {
+                enabled: false,
+                keep: 10,
+            },
+            SystemTime::now(),
+        );
+        assert!(backup.protect(&page, source.path()).unwrap().is_none());
+        assert!(!source.path().join(BACKUP_DIR).exists());
+    }
+}
@@ -0,0 +1,314 @@
+// Folder batch processing: runs the page pipeline over every image in a directory
+
+use anyhow::{Context, anyhow};
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+use crate::backup::Backup;
+use crate::image_io;
+use crate::naming::{self, NameFields};
+use crate::pipeline::{self, ProcessOptions, StageProgress};
+use crate::state::AppState;
+
+/// `{lang}` for providers without an explicit target language (e.g. Ollama prompts)
+const DEFAULT_LANG: &str = "en";
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PageStatus {
+    Started,
+    Completed,
+    Failed,
+}
+
+impl PageStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PageStatus::Started => "started",
+            PageStatus::Completed => "completed",
+            PageStatus::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderPageEvent {
+    pub index: usize,
+    pub total: usize,
+    pub file: String,
+    pub status: PageStatus,
+    pub output: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedPage {
+    pub file: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderSummary {
+    pub total: usize,
+    pub succeeded: usize,
+    pub outputs: Vec<String>,
+    pub failed: Vec<FailedPage>,
+    pub elapsed_ms: u64,
+}
+
+/// List supported image files in a directory, sorted by file name
+pub fn list_images(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read directory {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && image_io::is_supported_input(path))
+        .collect();
+
+    files.sort();
+    Ok(files)
+}
+
+/// Output path for the page at `index` in the input listing
+///
+/// Uses the export filename template when one is set, otherwise the input stem with
+/// the output format's extension.
+fn output_path_for(
+    input: &Path,
+    index: usize,
+    output_dir: &Path,
+    options: &ProcessOptions,
+) -> anyhow::Result<PathBuf> {
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "page".to_string());
+    let extension = options.export.format.extension();
+
+    let Some(template) = &options.export.filename_template else {
+        return Ok(output_dir.join(format!("{}.{}", stem, extension)));
+    };
+    let metadata = &options.export.metadata;
+    let fields = NameFields {
+        title: metadata.title.as_deref(),
+        chapter: metadata.chapter.as_deref(),
+        page: index + 1,
+        lang: options
+            .translator
+            .as_ref()
+            .and_then(|t| t.target_lang())
+            .unwrap_or(DEFAULT_LANG),
+        name: &stem,
+    };
+    Ok(output_dir.join(naming::render(template, &fields, extension)?))
+}
+
+pub async fn process_file(
+    state: &AppState,
+    input: &Path,
+    index: usize,
+    output_dir: &Path,
+    options: &ProcessOptions,
+    backup: &Backup,
+    debug_dir: Option<&Path>,
+) -> anyhow::Result<PathBuf> {
+    let source = image_io::open_with_metadata(input)?;
+
+    let noop = |_: StageProgress| {};
+    let result = pipeline::run_page(state, &source.image, options, debug_dir, &noop).await?;
+
+    let output = output_path_for(input, index, output_dir, options)?;
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create output directory {:?}", parent))?;
+    }
+    if let Some(source_dir) = input.parent() {
+        backup.protect(&output, source_dir)?;
+    }
+    image_io::save(&result.rendered, &output, &options.export, &source.metadata)?;
+
+    Ok(output)
+}
+
+/// Run the full pipeline on every image in input_dir, writing results to output_dir
+///
+/// Pages run on a bounded pool of `workers` concurrent tasks. A failing page is
+/// reported through `on_event` and the summary without aborting the others.
+pub async fn process_folder(
+    state: &AppState,
+    input_dir: &Path,
+    output_dir: &Path,
+    options: &ProcessOptions,
+    workers: usize,
+    debug_dir: Option<&Path>,
+    on_event: &(dyn Fn(FolderPageEvent) + Send + Sync),
+) -> anyhow::Result<FolderSummary> {
+    if !input_dir.is_dir() {
+        return Err(anyhow!("Input directory does not exist: {:?}", input_dir));
+    }
+    if let Some(template) = &options.export.filename_template {
+        naming::validate(template)?;
+    }
+    fs::create_dir_all(output_dir)
+        .with_context(|| format!("Failed to create output directory {:?}", output_dir))?;
+
+    let files = list_images(input_dir)?;
+    let total = files.len();
+    let backup = Backup::new(state.settings.get().await.backup, SystemTime::now());
+    let backup = &backup;
+    let workers = workers.max(1);
+    let start = Instant::now();
+
+    tracing::info!(
+        "[batch] processing {} page(s) from {:?} -> {:?} with {} worker(s)",
+        total,
+        input_dir,
+        output_dir,
+        workers
+    );
+
+    let results: Vec<(String, anyhow::Result<PathBuf>)> = stream::iter(files.iter().enumerate())
+        .map(|(index, input)| async move {
+            let file = input
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            on_event(FolderPageEvent {
+                index,
+                total,
+                file: file.clone(),
+                status: PageStatus::Started,
+                output: None,
+                error: None,
+            });
+
+            let page_start = Instant::now();
+            let result =
+                process_file(state, input, index, output_dir, options, backup, debug_dir).await;
+
+            match &result {
+                Ok(output) => {
+                    tracing::info!(
+                        "[batch] page {}/{} '{}' done in {}ms",
+                        index + 1,
+                        total,
+                        file,
+                        page_start.elapsed().as_millis()
+                    );
+                    on_event(FolderPageEvent {
+                        index,
+                        total,
+                        file: file.clone(),
+                        status: PageStatus::Completed,
+                        output: Some(output.to_string_lossy().to_string()),
+                        error: None,
+                    });
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        "[batch] page {}/{} '{}' failed: {:#}",
+                        index + 1,
+                        total,
+                        file,
+                        err
+                    );
+                    on_event(FolderPageEvent {
+                        index,
+                        total,
+                        file: file.clone(),
+                        status: PageStatus::Failed,
+                        output: None,
+                        error: Some(format!("{:#}", err)),
+                    });
+                }
+            }
+
+            (file, result)
+        })
+        .buffer_unordered(workers)
+        .collect()
+        .await;
+
+    let mut outputs = Vec::new();
+    let mut failed = Vec::new();
+    for (file, result) in results {
+        match result {
+            Ok(output) => outputs.push(output.to_string_lossy().to_string()),
+            Err(err) => failed.push(FailedPage {
+                file,
+                error: format!("{:#}", err),
+            }),
+        }
+    }
+    outputs.sort();
+
+    let summary = FolderSummary {
+        total,
+        succeeded: outputs.len(),
+        outputs,
+        failed,
+        elapsed_ms: start.elapsed().as_millis() as u64,
+    };
+
+    tracing::info!(
+        "[batch] finished: {}/{} succeeded, {} failed in {}ms",
+        summary.succeeded,
+        summary.total,
+        summary.failed.len(),
+        summary.elapsed_ms
+    );
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_images_filters_and_sorts() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["b.PNG", "a.jpg", "notes.txt", "c.webp", "d.jxl"] {
+            fs::write(dir.path().join(name), b"").unwrap();
+        }
+        fs::create_dir(dir.path().join("nested.png")).unwrap();
+
+        let files = list_images(dir.path()).unwrap();
+        let names: Vec<_> = files
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+
+        assert_eq!(names, vec!["a.jpg", "b.PNG", "c.webp", "d.jxl"]);
+    }
+
+    #[test]
+    fn test_output_path_uses_format_extension() {
+        let input = Path::new("/in/page_01.jpg");
+        let mut options = ProcessOptions::default();
+        let output = output_path_for(input, 0, Path::new("/out"), &options).unwrap();
+        assert_eq!(output, PathBuf::from("/out/page_01.png"));
+
+        options.export.format = image_io::OutputFormat::Webp;
+        let output = output_path_for(input, 0, Path::new("/out"), &options).unwrap();
+        assert_eq!(output, PathBuf::from("/out/page_01.webp"));
+    }
+
+    #[test]
+    fn test_output_path_applies_filename_template() {
+        let mut options = ProcessOptions::default();
+        options.export.filename_template = Some("{title}/c{chapter}/{page:03}_{lang}".to_string());
+        options.export.metadata.title = Some("Sample".to_string());
+        options.export.metadata.chapter = Some("4".to_string());
+
+        let input = Path::new("/in/scan.jpg");
+        let output = output_path_for(input, 11, Path::new("/out"), &options).unwrap();
+        assert_eq!(output, PathBuf::from("/out/Sample/c4/012_en.png"));
+    }
+}
@@ -0,0 +1,266 @@
+// Benchmark suite: times detection, OCR, inpainting, and rendering on built-in reference pages
+// The pages are drawn from the bundled fonts rather than shipped as images, so every install
+// benchmarks the same input and results can be compared across machines and providers.
+
+use image::{DynamicImage, Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_ellipse_mut, draw_hollow_ellipse_mut, draw_hollow_rect_mut};
+use imageproc::rect::Rect;
+use serde::Serialize;
+use std::time::Instant;
+
+use crate::pipeline::{self, PageBlock, StageProgress};
+use crate::settings::Settings;
+use crate::state::AppState;
+use crate::text_renderer::{RgbColor, render_text_on_image};
+
+pub const DEFAULT_ITERATIONS: usize = 3;
+const STAGES: [&str; 4] = ["detection", "ocr", "inpainting", "rendering"];
+
+/// (width, height, bubbles as (center x, center y, radius x, radius y, text))
+type ReferencePage = (u32, u32, &'static [(i32, i32, i32, i32, &'static str)]);
+
+const REFERENCE_PAGES: [ReferencePage; 2] = [
+    (
+        1000,
+        1400,
+        &[
+            (760, 220, 150, 120, "こんにちは\n元気?"),
+            (260, 300, 170, 130, "今日はいい天気\nですね"),
+            (700, 850, 160, 150, "どこへ\n行くの?"),
+            (300, 1100, 180, 140, "ちょっと\n待って!"),
+        ],
+    ),
+    (
+        800,
+        1200,
+        &[
+            (560, 200, 170, 120, "本当に?\nすごい!"),
+            (240, 640, 150, 170, "ありがとう\nございます"),
+            (560, 1000, 160, 110, "また明日"),
+        ],
+    ),
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageTiming {
+    pub stage: String,
+    pub avg_ms: f64, // Per page
+    pub min_ms: u64,
+    pub max_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkResult {
+    pub provider: String,
+    pub device_name: Option<String>,
+    pub ocr_engine: String,
+    pub pages: usize,
+    pub iterations: usize,
+    pub stages: Vec<StageTiming>,
+    pub page_ms: f64, // All stages, per page
+    pub images_per_minute: f64,
+}
+
+fn white() -> RgbColor {
+    RgbColor {
+        r: 255,
+        g: 255,
+        b: 255,
+    }
+}
+
+/// A manga-like page: panel borders and speech bubbles with Japanese text
+///
+/// Returns the page and the text boxes, used in place of detections if the detector finds none.
+fn reference_page(
+    (width, height, bubbles): &ReferencePage,
+) -> anyhow::Result<(DynamicImage, Vec<PageBlock>)> {
+    let black = Rgba([0, 0, 0, 255]);
+    let mut page = RgbaImage::from_pixel(*width, *height, Rgba([255, 255, 255, 255]));
+    let panel_height = height / 2 - 30;
+    for top in [20, height / 2 + 10] {
+        let panel = Rect::at(20, top as i32).of_size(width - 40, panel_height);
+        draw_hollow_rect_mut(&mut page, panel, black);
+    }
+
+    let mut blocks = Vec::with_capacity(bubbles.len());
+    for &(x, y, rx, ry, text) in bubbles.iter() {
+        draw_filled_ellipse_mut(&mut page, (x, y), rx, ry, Rgba([255, 255, 255, 255]));
+        draw_hollow_ellipse_mut(&mut page, (x, y), rx, ry, black);
+        let mut block = PageBlock::from_detection(&comic_text_detector::ClassifiedBbox {
+            xmin: (x - rx * 2 / 3) as f32,
+            ymin: (y - ry * 2 / 3) as f32,
+            xmax: (x + rx * 2 / 3) as f32,
+            ymax: (y + ry * 2 / 3) as f32,
+            confidence: 1.0,
+            class: 0,
+        });
+        block.translated_text = Some(text.to_string());
+        block.text_color = Some(RgbColor { r: 0, g: 0, b: 0 });
+        block.background_color = Some(white());
+        blocks.push(block);
+    }
+
+    let text_blocks = blocks.iter().map(PageBlock::to_text_block).collect();
+    let page = render_text_on_image(
+        DynamicImage::ImageRgba8(page),
+        text_blocks,
+        "rectangle",
+        "Noto Sans",
+    )?;
+    for block in &mut blocks {
+        block.translated_text = None;
+    }
+    Ok((page, blocks))
+}
+
+/// Times (ms) for each stage on one page
+async fn run_page(
+    state: &AppState,
+    settings: &Settings,
+    page: &DynamicImage,
+    reference_blocks: &[PageBlock],
+) -> anyhow::Result<[u64; 4]> {
+    let quiet = |_: StageProgress| {};
+    let detect = settings.process_options();
+
+    let start = Instant::now();
+    let (mut blocks, mask) = pipeline::detect_blocks(
+        state,
+        page,
+        detect.confidence_threshold,
+        detect.nms_threshold,
+    )
+    .await?;
+    let detection = start.elapsed().as_millis() as u64;
+    if blocks.is_empty() {
+        blocks = reference_blocks.to_vec();
+    }
+
+    let start = Instant::now();
+    pipeline::recognize_blocks(state, page, &mut blocks, &quiet).await?;
+    let ocr = start.elapsed().as_millis() as u64;
+
+    let start = Instant::now();
+    let textless =
+        pipeline::inpaint_blocks(state, page, &mask, &blocks, &settings.inpaint, None, &quiet)
+            .await?;
+    let inpainting = start.elapsed().as_millis() as u64;
+
+    // No translation; render the recognized text back so rendering does real layout work
+    for block in &mut blocks {
+        block.translated_text = Some(if block.text.is_empty() {
+            "Benchmark".to_string()
+        } else {
+            block.text.clone()
+        });
+    }
+    let start = Instant::now();
+    pipeline::typeset_and_render(textless, &mut blocks, "lama", &settings.render.default_font)?;
+    let rendering = start.elapsed().as_millis() as u64;
+
+    Ok([detection, ocr, inpainting, rendering])
+}
+
+fn summarize(runs: &[[u64; 4]]) -> (Vec<StageTiming>, f64) {
+    let count = runs.len().max(1) as f64;
+    let stages = STAGES
+        .iter()
+        .enumerate()
+        .map(|(i, stage)| {
+            let times = runs.iter().map(|run| run[i]);
+            StageTiming {
+                stage: stage.to_string(),
+                avg_ms: times.clone().sum::<u64>() as f64 / count,
+                min_ms: times.clone().min().unwrap_or(0),
+                max_ms: times.max().unwrap_or(0),
+            }
+        })
+        .collect::<Vec<_>>();
+    let page_ms = stages.iter().map(|s| s.avg_ms).sum();
+    (stages, page_ms)
+}
+
+/// Run every reference page `iterations` times after one untimed warm-up page
+///
+/// `on_progress` gets (completed, total) page runs.
+pub async fn run(
+    state: &AppState,
+    settings: &Settings,
+    iterations: usize,
+    on_progress: &(dyn Fn(usize, usize) + Send + Sync),
+) -> anyhow::Result<BenchmarkResult> {
+    let iterations = iterations.max(1);
+    let pages = REFERENCE_PAGES
+        .iter()
+        .map(reference_page)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let total = pages.len() * iterations;
+
+    // First inference pays for session setup and GPU kernel compilation
+    run_page(state, settings, &pages[0].0, &pages[0].1).await?;
+
+    let mut runs = Vec::with_capacity(total);
+    for _ in 0..iterations {
+        for (page, blocks) in &pages {
+            runs.push(run_page(state, settings, page, blocks).await?);
+            on_progress(runs.len(), total);
+        }
+    }
+
+    let (stages, page_ms) = summarize(&runs);
+    let gpu = state.gpu_init_result.lock().await.clone();
+    let result = BenchmarkResult {
+        provider: gpu.active_provider,
+        device_name: gpu.device_name,
+        ocr_engine: state.active_ocr.read().await.clone(),
+        pages: pages.len(),
+        iterations,
+        stages,
+        page_ms,
+        images_per_minute: if page_ms > 0.0 {
+            60_000.0 / page_ms
+        } else {
+            0.0
+        },
+    };
+    tracing::info!(
+        "[benchmark] {} on {}: {:.0}ms per page ({:.1} images/min)",
+        result.ocr_engine,
+        result.provider,
+        result.page_ms,
+        result.images_per_minute
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_averages_each_stage() {
+        let (stages, page_ms) = summarize(&[[100, 40, 300, 60], [120, 20, 340, 80]]);
+        assert_eq!(stages.len(), 4);
+        assert_eq!(stages[0].stage, "detection");
+        assert_eq!(stages[0].avg_ms, 110.0);
+        assert_eq!((stages[2].min_ms, stages[2].max_ms), (300, 340));
+        assert_eq!(page_ms, 110.0 + 30.0 + 320.0 + 70.0);
+    }
+
+    #[test]
+    fn test_reference_pages_have_text_boxes_inside_the_page() {
+        for reference in &REFERENCE_PAGES {
+            let (page, blocks) = reference_page(reference).unwrap();
+            assert_eq!((page.width(), page.height()), (reference.0, reference.1));
+            assert_eq!(blocks.len(), reference.2.len());
+            for block in blocks {
+                assert!(block.xmin >= 0.0 && block.ymin >= 0.0);
+                assert!(block.xmax <= page.width() as f32 && block.ymax <= page.height() as f32);
+                assert!(block.translated_text.is_none());
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+// What lies behind a text block, to pick how its text is removed: a flat bubble is repainted
+// with its own color (rectangle fill), while screentone and artwork need LaMa. Text pixels from
+// the detection mask, grown by a small margin for anti-aliasing, are left out of the sample.
+
+use image::{DynamicImage, GenericImageView, GrayImage, Luma, Rgba, RgbaImage};
+use imageproc::distance_transform::Norm;
+use imageproc::morphology::dilate;
+use serde::Serialize;
+
+use crate::stages::BBox;
+use crate::text_renderer::RgbColor;
+
+const TEXT_MARGIN: u8 = 3; // px grown around the text mask
+const MIN_SAMPLES: usize = 32; // Background pixels needed to judge a block
+const MAX_PLAIN_DEVIATION: f32 = 10.0; // Luma std dev of a flat fill with scan and JPEG noise
+const TILE: u32 = 16;
+const MIN_TILE_COVERAGE: f32 = 0.9; // Share of a tile that must be background to be sampled
+const MAX_TONE_DEVIATION: f32 = 12.0; // Std dev of tile means over an even screentone
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Background {
+    Plain,
+    Screentone,
+    Art,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BubbleAnalysis {
+    pub background: Background,
+    pub render_method: String, // "rectangle" for plain backgrounds, otherwise "lama"
+    pub fill_color: RgbColor,  // Mean background color
+}
+
+/// Page-space pixel bounds of `bbox` as (x0, y0, x1, y1), clamped to the page
+fn bounds(bbox: &BBox, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+    let x0 = bbox.xmin.floor().clamp(0.0, width as f32) as u32;
+    let y0 = bbox.ymin.floor().clamp(0.0, height as f32) as u32;
+    let x1 = bbox.xmax.ceil().clamp(0.0, width as f32) as u32;
+    let y1 = bbox.ymax.ceil().clamp(0.0, height as f32) as u32;
+    (x1 > x0 && y1 > y0).then_some((x0, y0, x1, y1))
+}
+
+/// Text pixels within `bounds`, sampled from the detection mask at page resolution and grown
+/// by `TEXT_MARGIN`
+fn text_pixels(mask: &GrayImage, bounds: (u32, u32, u32, u32), page: (u32, u32)) -> GrayImage {
+    let (x0, y0, x1, y1) = bounds;
+    let scale_x = mask.width() as f32 / page.0 as f32;
+    let scale_y = mask.height() as f32 / page.1 as f32;
+    let text = GrayImage::from_fn(x1 - x0, y1 - y0, |x, y| {
+        let mx = (((x0 + x) as f32 + 0.5) * scale_x) as u32;
+        let my = (((y0 + y) as f32 + 0.5) * scale_y) as u32;
+        let value = mask.get_pixel(mx.min(mask.width() - 1), my.min(mask.height() - 1))[0];
+        Luma([if value > 0 { 255 } else { 0 }])
+    });
+    dilate(&text, Norm::LInf, TEXT_MARGIN)
+}
+
+fn deviation(values: &[f32]) -> f32 {
+    let mean = values.iter().sum::<f32>() / values.len() as f32;
+    (values.iter().map(|v| (v - mean) * (v - mean)).sum::<f32>() / values.len() as f32).sqrt()
+}
+
+/// Classify the background around the text in `bbox`; `None` when the block is all text
+///
+/// A background with little luma variation is plain. Otherwise it is cut into tiles: an even
+/// texture whose tiles all average out alike is screentone, anything else is art.
+pub fn analyze(image: &DynamicImage, mask: &GrayImage, bbox: &BBox) -> Option<BubbleAnalysis> {
+    let (width, height) = image.dimensions();
+    let (x0, y0, x1, y1) = bounds(bbox, width, height)?;
+    let text = text_pixels(mask, (x0, y0, x1, y1), (width, height));
+    let crop = image.crop_imm(x0, y0, x1 - x0, y1 - y0).to_rgb8();
+
+    let is_background = |x: u32, y: u32| text.get_pixel(x, y)[0] == 0;
+    let luma = |x: u32, y: u32| {
+        let [r, g, b] = crop.get_pixel(x, y).0.map(f32::from);
+        0.299 * r + 0.587 * g + 0.114 * b
+    };
+
+    let mut sums = [0.0f32; 3];
+    let mut lumas = Vec::new();
+    for (x, y, pixel) in crop.enumerate_pixels() {
+        if is_background(x, y) {
+            for (sum, value) in sums.iter_mut().zip(pixel.0) {
+                *sum += value as f32;
+            }
+            lumas.push(luma(x, y));
+        }
+    }
+    if lumas.len() < MIN_SAMPLES {
+        return None;
+    }
+    let [r, g, b] = sums.map(|sum| (sum / lumas.len() as f32).round() as u8);
+    let fill_color = RgbColor { r, g, b };
+
+    let background = if deviation(&lumas) <= MAX_PLAIN_DEVIATION {
+        Background::Plain
+    } else {
+        let mut tiles = Vec::new();
+        for ty in 0..crop.height() / TILE {
+            for tx in 0..crop.width() / TILE {
+                let samples: Vec<f32> = (0..TILE * TILE)
+                    .map(|i| (tx * TILE + i % TILE, ty * TILE + i / TILE))
+                    .filter(|&(x, y)| is_background(x, y))
+                    .map(|(x, y)| luma(x, y))
+                    .collect();
+                if samples.len() as f32 >= (TILE * TILE) as f32 * MIN_TILE_COVERAGE {
+                    tiles.push(samples.iter().sum::<f32>() / samples.len() as f32);
+                }
+            }
+        }
+        if tiles.len() >= 2 && deviation(&tiles) <= MAX_TONE_DEVIATION {
+            Background::Screentone
+        } else {
+            Background::Art
+        }
+    };
+
+    let render_method = match background {
+        Background::Plain => "rectangle",
+        Background::Screentone | Background::Art => "lama",
+    };
+    Some(BubbleAnalysis {
+        background,
+        render_method: render_method.to_string(),
+        fill_color,
+    })
+}
+
+/// Paint the text pixels of `bbox` (with their margin) in `color`, leaving the rest of the
+/// block, such as a bubble border inside the box, untouched
+pub fn fill_text(canvas: &mut RgbaImage, mask: &GrayImage, bbox: &BBox, color: &RgbColor) {
+    let (width, height) = canvas.dimensions();
+    let Some((x0, y0, x1, y1)) = bounds(bbox, width, height) else {
+        return;
+    };
+    let text = text_pixels(mask, (x0, y0, x1, y1), (width, height));
+    for (x, y, pixel) in text.enumerate_pixels() {
+        if pixel[0] > 0 {
+            canvas.put_pixel(x0 + x, y0 + y, Rgba([color.r, color.g, color.b, 255]));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    const BLOCK: BBox = BBox {
+        xmin: 0.0,
+        ymin: 0.0,
+        xmax: 96.0,
+        ymax: 96.0,
+    };
+
+    fn page(color: impl Fn(u32, u32) -> u8) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(96, 96, |x, y| {
+            let value = color(x, y);
+            Rgb([value, value, value])
+        }))
+    }
+
+    #[test]
+    fn test_plain_bubble_is_filled_and_text_is_painted_over() {
+        // Black text bar on white, with the mask at half the page resolution
+        let text = |x: u32, y: u32| (40..56).contains(&x) && (16..80).contains(&y);
+        let image = page(|x, y| if text(x, y) { 0 } else { 255 });
+        let mask = GrayImage::from_fn(48, 48, |x, y| {
+            Luma([if text(x * 2, y * 2) { 255 } else { 0 }])
+        });
+
+        let analysis = analyze(&image, &mask, &BLOCK).unwrap();
+        assert_eq!(analysis.background, Background::Plain);
+        assert_eq!(analysis.render_method, "rectangle");
+        assert_eq!(
+            (
+                analysis.fill_color.r,
+                analysis.fill_color.g,
+                analysis.fill_color.b
+            ),
+            (255, 255, 255)
+        );
+
+        let mut canvas = image.to_rgba8();
+        fill_text(&mut canvas, &mask, &BLOCK, &analysis.fill_color);
+        assert!(canvas.pixels().all(|p| p.0 == [255, 255, 255, 255]));
+
+        let all_text = GrayImage::from_pixel(48, 48, Luma([255]));
+        assert!(analyze(&image, &all_text, &BLOCK).is_none());
+    }
+
+    #[test]
+    fn test_screentone_and_art_go_to_lama() {
+        let mask = GrayImage::new(96, 96);
+        let tone = page(|x, y| if x % 4 < 2 && y % 4 < 2 { 40 } else { 230 });
+        let analysis = analyze(&tone, &mask, &BLOCK).unwrap();
+        assert_eq!(analysis.background, Background::Screentone);
+        assert_eq!(analysis.render_method, "lama");
+
+        let gradient = page(|x, y| ((x + y) * 255 / 190) as u8);
+        assert_eq!(
+            analyze(&gradient, &mask, &BLOCK).unwrap().background,
+            Background::Art
+        );
+    }
+}
@@ -0,0 +1,180 @@
+// TOML config for the CLI: `--config pipeline.toml` instead of repeating long command lines
+// Applied on top of saved settings and the project; flags given on the command line still win.
+
+use anyhow::Context;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::image_io::{OutputFormat, PngCompression};
+use crate::settings::Settings;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CliConfig {
+    pub detect: DetectConfig,
+    pub inpaint: InpaintOverrides,
+    pub translation: TranslationOverrides,
+    pub render: RenderOverrides,
+    pub export: ExportOverrides,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DetectConfig {
+    pub confidence_threshold: Option<f32>,
+    pub nms_threshold: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InpaintOverrides {
+    pub padding: Option<i32>,
+    pub target_size: Option<u32>,
+    pub mask_threshold: Option<u8>,
+    pub mask_erosion: Option<u32>,
+    pub mask_dilation: Option<u32>,
+    pub feather_radius: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TranslationOverrides {
+    pub provider: Option<String>,
+    pub api_key: Option<String>, // DeepL
+    pub model: Option<String>,   // Ollama
+    pub system_prompt: Option<String>,
+    pub source_lang: Option<String>,
+    pub target_lang: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RenderOverrides {
+    pub method: Option<String>,
+    pub font: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExportOverrides {
+    pub format: Option<String>,
+    pub quality: Option<u8>,
+    pub png_compression: Option<PngCompression>,
+    pub max_dimension: Option<u32>,
+    pub name_template: Option<String>,
+}
+
+fn set<T: Clone>(target: &mut T, value: &Option<T>) {
+    if let Some(value) = value {
+        *target = value.clone();
+    }
+}
+
+impl CliConfig {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents =
+            fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+        toml::from_str(&contents).with_context(|| format!("Invalid config in {:?}", path))
+    }
+
+    /// Overwrite the settings this file sets
+    pub fn apply(&self, settings: &mut Settings) -> anyhow::Result<()> {
+        let inpaint = &mut settings.inpaint;
+        set(&mut inpaint.padding, &self.inpaint.padding);
+        set(&mut inpaint.target_size, &self.inpaint.target_size);
+        set(&mut inpaint.mask_threshold, &self.inpaint.mask_threshold);
+        set(&mut inpaint.mask_erosion, &self.inpaint.mask_erosion);
+        set(&mut inpaint.mask_dilation, &self.inpaint.mask_dilation);
+        set(&mut inpaint.feather_radius, &self.inpaint.feather_radius);
+
+        let translation = &mut settings.translation;
+        set(&mut translation.provider, &self.translation.provider);
+        set(&mut translation.ollama_model, &self.translation.model);
+        if self.translation.api_key.is_some() {
+            translation.deepl_api_key = self.translation.api_key.clone();
+        }
+        if self.translation.system_prompt.is_some() {
+            translation.ollama_system_prompt = self.translation.system_prompt.clone();
+        }
+        if self.translation.source_lang.is_some() {
+            translation.source_lang = self.translation.source_lang.clone();
+        }
+        if self.translation.target_lang.is_some() {
+            translation.target_lang = self.translation.target_lang.clone();
+        }
+
+        set(&mut settings.render.method, &self.render.method);
+        set(&mut settings.render.default_font, &self.render.font);
+
+        let export = &mut settings.export;
+        if let Some(format) = &self.export.format {
+            export
+                .set_format(OutputFormat::from_extension(format).with_context(|| {
+                    format!("Unsupported output format '{}' in config", format)
+                })?);
+        }
+        if self.export.quality.is_some() {
+            export.quality = self.export.quality;
+        }
+        set(&mut export.png_compression, &self.export.png_compression);
+        if self.export.max_dimension.is_some() {
+            export.max_dimension = self.export.max_dimension;
+        }
+        if self.export.name_template.is_some() {
+            export.filename_template = self.export.name_template.clone();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::RenderSettings;
+
+    #[test]
+    fn test_config_overrides_only_what_it_sets() {
+        let config: CliConfig = toml::from_str(
+            r#"
+            [detect]
+            confidence_threshold = 0.3
+
+            [inpaint]
+            padding = 80
+
+            [translation]
+            provider = "ollama"
+            model = "qwen2.5"
+            target_lang = "en"
+
+            [render]
+            font = "Anime Ace"
+
+            [export]
+            format = "webp"
+            png_compression = "best"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.detect.confidence_threshold, Some(0.3));
+        assert_eq!(config.detect.nms_threshold, None);
+
+        let mut settings = Settings::default();
+        config.apply(&mut settings).unwrap();
+        assert_eq!(settings.inpaint.padding, 80);
+        assert_eq!(settings.inpaint.target_size, 512);
+        assert_eq!(settings.translation.provider, "ollama");
+        assert_eq!(settings.translation.ollama_model, "qwen2.5");
+        assert_eq!(settings.translation.target_lang.as_deref(), Some("en"));
+        assert_eq!(settings.render.default_font, "Anime Ace");
+        assert_eq!(settings.render.method, RenderSettings::default().method);
+        assert_eq!(settings.export.format, OutputFormat::Webp);
+        assert_eq!(settings.export.png_compression, PngCompression::Best);
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        assert!(toml::from_str::<CliConfig>("[render]\nfonts = \"Arial\"").is_err());
+    }
+}
@@ -1,113 +1,76 @@
 use anyhow::{Context, anyhow};
 use font_kit::source::SystemSource;
 use image::{DynamicImage, GenericImageView, GrayImage};
-use reqwest;
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use std::fs;
 use std::io::Cursor;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Instant;
 use tauri::{AppHandle, Manager};
-
-use crate::ocr_pipeline::{MANGA_OCR_KEY, OcrPipeline};
-use crate::text_renderer::{TextBlock, render_text_on_image};
-use crate::{AppState, error::CommandResult};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::AppState;
+use crate::accuracy::ReportFormat;
+use crate::annotations::{self, AnnotatedBlock, AnnotationFormat};
+use crate::appearance;
+use crate::archive;
+use crate::batch::{self, FolderSummary};
+use crate::benchmark::{self, BenchmarkResult};
+use crate::bubble::{self, BubbleAnalysis};
+use crate::comparison::{self, ComparisonOptions};
+use crate::crash::{self, CrashReport};
+use crate::detection_eval::{self, DetectionEvaluation};
+use crate::error::{CommandResult, ErrorCode, coded};
+use crate::golden::{self, GoldenReport, Tolerance};
+use crate::history::{Edit, EditorSnapshot, EditorState, History};
+use crate::i18n;
+use crate::image_io::{self, ExportOptions};
+use crate::inpaint_eval::{self, InpaintEvaluation};
+use crate::jobs::{Job, JobParams, emit_job_update};
+use crate::lettering::{self, LetteringEstimate};
+use crate::logging;
+use crate::mit_json::{self, ImportedPage};
+use crate::ocr_compare::{self, OcrComparison, OcrConfig};
+use crate::ocr_eval::OcrEvaluation;
+use crate::pdf_export::{self, PdfOptions};
+use crate::pipeline::{self, PageBlock, PageOutput, ProcessOptions};
+use crate::plugins::{self, PluginInfo};
+use crate::progress::{TaskHandle, TaskProgress};
+use crate::project::{OpenProject, PROJECT_FILE, Project};
+use crate::scripting::{Hook, ScriptInfo};
+use crate::session::Session;
+use crate::settings::{Settings, SettingsBundle};
+use crate::stages::{
+    BBox, InpaintConfig, InpaintedRegion, crop_to_bbox, evaluate_ocr_dataset, ocr_pipeline_for,
+    run_inpainting_pipeline, run_ocr_with_pipelines,
+};
+use crate::text_renderer::{AppearanceData, TextBlock, render_text_on_image};
+use crate::translation;
+use crate::translation_eval::{self, ProviderScore, ReferencePair};
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DetectionResult {
     pub bboxes: Vec<comic_text_detector::ClassifiedBbox>,
+    pub bubbles: Vec<Option<BubbleAnalysis>>, // Background and suggested render method per bbox
     pub mask_png: Vec<u8>,
     pub mask_width: u32,
     pub mask_height: u32,
 }
 
-#[derive(Serialize)]
-struct OcrRunResult {
-    texts: Vec<String>,
-    engine: String,
-    region_count: usize,
-}
-
-async fn execute_ocr_pipeline(
-    pipeline: Arc<dyn OcrPipeline + Send + Sync>,
-    key: &str,
-    image: &DynamicImage,
-    payload_bytes: usize,
-) -> anyhow::Result<OcrRunResult> {
-    let detect_start = Instant::now();
-    let regions = pipeline.detect_text_regions(image).await?;
-    let detect_elapsed = detect_start.elapsed();
-    tracing::info!(
-        "[ocr:{}] detect_text_regions took {}ms ({} region(s), payload={} bytes)",
-        key,
-        detect_elapsed.as_millis(),
-        regions.len(),
-        payload_bytes
-    );
-
-    let recognize_start = Instant::now();
-    let recognized = pipeline.recognize_text(image, &regions).await?;
-    let recognize_elapsed = recognize_start.elapsed();
+/// Convert an image the webview can't decode (e.g. JPEG XL) to PNG
+#[tauri::command]
+pub async fn decode_image(image: Vec<u8>) -> CommandResult<Vec<u8>> {
+    let decoded = image_io::decode(&image).context("Failed to decode image")?;
     tracing::info!(
-        "[ocr:{}] recognize_text took {}ms",
-        key,
-        recognize_elapsed.as_millis()
+        "[image] converted {} byte upload to PNG ({}x{})",
+        image.len(),
+        decoded.width(),
+        decoded.height()
     );
-
-    Ok(OcrRunResult {
-        texts: recognized,
-        engine: key.to_string(),
-        region_count: regions.len(),
-    })
-}
-
-async fn run_ocr_with_pipelines(
-    state: &AppState,
-    active_key: &str,
-    image: &DynamicImage,
-    payload_bytes: usize,
-) -> anyhow::Result<OcrRunResult> {
-    let pipeline = {
-        let guard = state.ocr_pipelines.read().await;
-        guard.get(active_key).cloned()
-    };
-
-    let pipeline = match pipeline {
-        Some(p) => p,
-        None => {
-            let available: Vec<String> = {
-                let guard = state.ocr_pipelines.read().await;
-                guard.keys().cloned().collect()
-            };
-            return Err(anyhow!(
-                "OCR pipeline '{}' not found. Available engines: {:?}",
-                active_key,
-                available
-            ));
-        }
-    };
-
-    match execute_ocr_pipeline(pipeline, active_key, image, payload_bytes).await {
-        Ok(result) => Ok(result),
-        Err(err) => {
-            tracing::warn!("OCR pipeline '{}' failed: {}", active_key, err);
-
-            if active_key != MANGA_OCR_KEY {
-                if let Some(fallback) = {
-                    let guard = state.ocr_pipelines.read().await;
-                    guard.get(MANGA_OCR_KEY).cloned()
-                } {
-                    tracing::warn!("Falling back to '{}' pipeline", MANGA_OCR_KEY);
-                    execute_ocr_pipeline(fallback, MANGA_OCR_KEY, image, payload_bytes).await
-                } else {
-                    Err(err)
-                }
-            } else {
-                Err(err)
-            }
-        }
-    }
+    Ok(pipeline::encode_png(&decoded)?)
 }
 
 #[tauri::command]
@@ -116,18 +79,26 @@ pub async fn detection(
     image: Vec<u8>,
     confidence_threshold: f32,
     nms_threshold: f32,
+    page_id: Option<String>,
+    task_id: Option<String>,
 ) -> CommandResult<DetectionResult> {
     let state = app.state::<AppState>();
+    let task = TaskHandle::start(&app, "detection", task_id);
 
     let total_start = Instant::now();
+    task.report("decode", 0, 3, None);
     let decode_start = Instant::now();
-    let img = image::load_from_memory(&image).context("Failed to load image")?;
+    let image_io::Decoded {
+        image: img,
+        metadata,
+    } = image_io::decode_with_metadata(&image).context("Failed to load image")?;
     let decode_elapsed = decode_start.elapsed();
     tracing::info!(
         "[detection] image decode took {}ms",
         decode_elapsed.as_millis()
     );
 
+    task.report("inference", 1, 3, None);
     let inference_start = Instant::now();
     let output = state
         .comic_text_detector
@@ -148,9 +119,26 @@ pub async fn detection(
         mask_height,
     } = output;
 
+    task.report("encode", 2, 3, None);
     let encode_start = Instant::now();
     let mask_image = image::GrayImage::from_vec(mask_width, mask_height, segment)
         .context("Failed to reconstruct segmentation mask")?;
+    let bubbles = bboxes
+        .iter()
+        .map(|bbox| bubble::analyze(&img, &mask_image, &PageBlock::from_detection(bbox).bbox()))
+        .collect();
+
+    if let Some(page_id) = &page_id {
+        let detections = bboxes.iter().map(PageBlock::from_detection).collect();
+        let mask = Arc::new(mask_image.clone());
+        let mut pages = state.pages.lock().await;
+        pages.set_image(page_id, img, metadata);
+        pages.update(page_id, |page| {
+            page.mask = Some(mask);
+            page.detections = Some(Arc::new(detections));
+        });
+    }
+
     let mut mask_dynamic = image::DynamicImage::ImageLuma8(mask_image);
     let mut mask_png = Vec::new();
     mask_dynamic
@@ -167,9 +155,11 @@ pub async fn detection(
         "[detection] total command time {}ms",
         total_start.elapsed().as_millis()
     );
+    task.finish();
 
     Ok(DetectionResult {
         bboxes,
+        bubbles,
         mask_png,
         mask_width,
         mask_height,
@@ -177,13 +167,18 @@ pub async fn detection(
 }
 
 #[tauri::command]
-pub async fn ocr(app: AppHandle, image: Vec<u8>) -> CommandResult<Vec<String>> {
+pub async fn ocr(
+    app: AppHandle,
+    image: Vec<u8>,
+    task_id: Option<String>,
+) -> CommandResult<Vec<String>> {
     let state = app.state::<AppState>();
+    let task = TaskHandle::start(&app, "ocr", task_id);
     let command_start = Instant::now();
     let payload_bytes = image.len();
 
     let decode_start = Instant::now();
-    let img = image::load_from_memory(&image).context("Failed to load image")?;
+    let img = image_io::decode(&image).context("Failed to load image")?;
     let decode_elapsed = decode_start.elapsed();
     tracing::info!(
         "[ocr] image decode took {}ms ({} bytes, source=frontend)",
@@ -192,6 +187,7 @@ pub async fn ocr(app: AppHandle, image: Vec<u8>) -> CommandResult<Vec<String>> {
     );
 
     let active_key = state.active_ocr.read().await.clone();
+    task.report("recognize", 0, 1, Some(active_key.clone()));
     let run_result = run_ocr_with_pipelines(&state, &active_key, &img, payload_bytes).await?;
 
     tracing::info!(
@@ -201,27 +197,36 @@ pub async fn ocr(app: AppHandle, image: Vec<u8>) -> CommandResult<Vec<String>> {
         run_result.region_count,
         payload_bytes
     );
+    task.finish();
 
     Ok(run_result.texts)
 }
 
 #[tauri::command]
-pub async fn cache_ocr_image(app: AppHandle, image_png: Vec<u8>) -> CommandResult<()> {
+pub async fn cache_ocr_image(
+    app: AppHandle,
+    page_id: String,
+    image_png: Vec<u8>,
+) -> CommandResult<()> {
     let state = app.state::<AppState>();
 
     let decode_start = Instant::now();
-    let decoded =
-        image::load_from_memory(&image_png).context("Failed to decode cached OCR image")?;
+    let image_io::Decoded {
+        image: decoded,
+        metadata,
+    } = image_io::decode_with_metadata(&image_png).context("Failed to decode cached OCR image")?;
     let decode_elapsed = decode_start.elapsed();
     let (width, height) = decoded.dimensions();
 
-    {
-        let mut cache = state.ocr_image_cache.write().await;
-        *cache = Some(Arc::new(decoded));
-    }
+    state
+        .pages
+        .lock()
+        .await
+        .set_image(&page_id, decoded, metadata);
 
     tracing::info!(
-        "[ocr-cache] primed image cache in {}ms ({} bytes, dimensions={}x{})",
+        "[ocr-cache] primed page '{}' in {}ms ({} bytes, dimensions={}x{})",
+        page_id,
         decode_elapsed.as_millis(),
         image_png.len(),
         width,
@@ -232,87 +237,65 @@ pub async fn cache_ocr_image(app: AppHandle, image_png: Vec<u8>) -> CommandResul
 }
 
 #[tauri::command]
-pub async fn clear_ocr_cache(app: AppHandle) -> CommandResult<()> {
+pub async fn release_page(app: AppHandle, page_id: String) -> CommandResult<()> {
     let state = app.state::<AppState>();
 
-    let mut cache = state.ocr_image_cache.write().await;
-    if cache.is_some() {
-        *cache = None;
-        tracing::info!("[ocr-cache] cleared image cache");
+    let mut pages = state.pages.lock().await;
+    if pages.remove(&page_id) {
+        tracing::info!(
+            "[pages] released page '{}' ({} bytes still cached)",
+            page_id,
+            pages.used_bytes()
+        );
     } else {
-        tracing::debug!("[ocr-cache] clear requested but cache already empty");
+        tracing::debug!("[pages] release requested for unknown page '{}'", page_id);
     }
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn ocr_cached_block(app: AppHandle, bbox: BBox) -> CommandResult<Vec<String>> {
+pub async fn get_page_composite(app: AppHandle, page_id: String) -> CommandResult<Option<Vec<u8>>> {
     let state = app.state::<AppState>();
-    let command_start = Instant::now();
-
-    let image_arc = {
-        let guard = state.ocr_image_cache.read().await;
-        guard
-            .clone()
-            .ok_or_else(|| anyhow!("No cached OCR image. Call cache_ocr_image first."))?
-    };
-
-    let (image_width, image_height) = image_arc.dimensions();
-
-    let crop_start = Instant::now();
-    let xmin_f = bbox.xmin.floor().max(0.0);
-    let ymin_f = bbox.ymin.floor().max(0.0);
-    let xmax_f = bbox.xmax.ceil().min(image_width as f32);
-    let ymax_f = bbox.ymax.ceil().min(image_height as f32);
-
-    if xmax_f <= xmin_f || ymax_f <= ymin_f {
-        return Err(anyhow!(
-            "Invalid bounding box after clamping: [{:.2},{:.2}->{:.2},{:.2}]",
-            xmin_f,
-            ymin_f,
-            xmax_f,
-            ymax_f
-        )
-        .into());
-    }
-
-    let mut width = (xmax_f - xmin_f).ceil().max(1.0) as u32;
-    let mut height = (ymax_f - ymin_f).ceil().max(1.0) as u32;
-
-    let xmin = xmin_f as u32;
-    let ymin = ymin_f as u32;
-
-    if xmin >= image_width || ymin >= image_height {
-        return Err(anyhow!(
-            "Bounding box origin outside image bounds after clamping: ({}, {}) >= ({} ,{})",
-            xmin,
-            ymin,
-            image_width,
-            image_height
-        )
-        .into());
-    }
 
-    let max_width = image_width - xmin;
-    let max_height = image_height - ymin;
+    let composite = state
+        .pages
+        .lock()
+        .await
+        .get(&page_id)
+        .and_then(|page| page.composite.clone());
 
-    if max_width == 0 || max_height == 0 {
-        return Err(anyhow!("Bounding box collapses to zero area after clamping").into());
+    match composite {
+        Some(image) => Ok(Some(pipeline::encode_png(&image)?)),
+        None => Ok(None),
     }
+}
 
-    if width > max_width {
-        width = max_width;
-    }
-    if height > max_height {
-        height = max_height;
-    }
+#[tauri::command]
+pub async fn ocr_cached_block(
+    app: AppHandle,
+    page_id: String,
+    bbox: BBox,
+) -> CommandResult<Vec<String>> {
+    let state = app.state::<AppState>();
+    let command_start = Instant::now();
 
-    if width == 0 || height == 0 {
-        return Err(anyhow!("Computed crop dimensions are zero after clamping").into());
-    }
+    let image_arc = state
+        .pages
+        .lock()
+        .await
+        .get(&page_id)
+        .and_then(|page| page.image.clone())
+        .ok_or_else(|| {
+            anyhow!(
+                "No cached OCR image for page '{}'. Call cache_ocr_image first.",
+                page_id
+            )
+        })?;
 
-    let cropped = image_arc.crop_imm(xmin, ymin, width, height);
+    let crop_start = Instant::now();
+    let cropped = crop_to_bbox(&image_arc, &bbox)?;
+    let (width, height) = cropped.dimensions();
     let crop_elapsed = crop_start.elapsed();
 
     let payload_bytes = (width as usize)
@@ -345,6 +328,73 @@ pub async fn ocr_cached_block(app: AppHandle, bbox: BBox) -> CommandResult<Vec<S
     Ok(run_result.texts)
 }
 
+/// Text, outline, and bubble colors of a block, measured from the cached source page
+#[tauri::command]
+pub async fn analyze_block_appearance(
+    app: AppHandle,
+    page_id: String,
+    bbox: BBox,
+) -> CommandResult<AppearanceData> {
+    let state = app.state::<AppState>();
+    let image_arc = state
+        .pages
+        .lock()
+        .await
+        .get(&page_id)
+        .and_then(|page| page.image.clone())
+        .ok_or_else(|| {
+            anyhow!(
+                "No cached OCR image for page '{}'. Call cache_ocr_image first.",
+                page_id
+            )
+        })?;
+
+    let cropped = crop_to_bbox(&image_arc, &bbox)?;
+    let appearance = appearance::analyze(&cropped);
+    tracing::info!(
+        "[appearance] bbox [{:.1},{:.1}->{:.1},{:.1}]: outline {:?}px",
+        bbox.xmin,
+        bbox.ymin,
+        bbox.xmax,
+        bbox.ymax,
+        appearance.outline_width_px
+    );
+    Ok(appearance)
+}
+
+/// Source glyph size and stroke weight of a block, with the font size and weight matching them
+#[tauri::command]
+pub async fn estimate_block_lettering(
+    app: AppHandle,
+    page_id: String,
+    bbox: BBox,
+) -> CommandResult<Option<LetteringEstimate>> {
+    let state = app.state::<AppState>();
+    let (image, mask) = state
+        .pages
+        .lock()
+        .await
+        .get(&page_id)
+        .and_then(|page| page.image.clone().zip(page.mask.clone()))
+        .ok_or_else(|| {
+            anyhow!(
+                "No detection mask for page '{}'. Run detection with this page id first.",
+                page_id
+            )
+        })?;
+
+    let estimate = lettering::estimate(&mask, &bbox, image.width(), image.height());
+    tracing::info!(
+        "[lettering] bbox [{:.1},{:.1}->{:.1},{:.1}]: {:?}",
+        bbox.xmin,
+        bbox.ymin,
+        bbox.xmax,
+        bbox.ymax,
+        estimate
+    );
+    Ok(estimate)
+}
+
 #[tauri::command]
 pub async fn set_active_ocr(app: AppHandle, model_key: String) -> CommandResult<()> {
     let state = app.state::<AppState>();
@@ -352,10 +402,12 @@ pub async fn set_active_ocr(app: AppHandle, model_key: String) -> CommandResult<
 
     if !pipelines.contains_key(&model_key) {
         let available: Vec<String> = pipelines.keys().cloned().collect();
-        return Err(anyhow!(
-            "OCR model '{}' not found. Available engines: {:?}",
-            model_key,
-            available
+        return Err(coded(
+            ErrorCode::OcrEngineUnavailable,
+            format!(
+                "OCR model '{}' not found. Available engines: {:?}",
+                model_key, available
+            ),
         )
         .into());
     }
@@ -364,9 +416,82 @@ pub async fn set_active_ocr(app: AppHandle, model_key: String) -> CommandResult<
 
     *state.active_ocr.write().await = model_key.clone();
     tracing::info!("Switched active OCR engine to '{}'", model_key);
+
+    state
+        .settings
+        .update(|settings| settings.ocr.engine = Some(model_key))
+        .await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn evaluate_ocr(
+    app: AppHandle,
+    dataset_dir: String,
+    engine: Option<String>,
+) -> CommandResult<OcrEvaluation> {
+    let state = app.state::<AppState>();
+    Ok(evaluate_ocr_dataset(&state, Path::new(&dataset_dir), engine).await?)
+}
+
+/// Run a labelled dataset through two OCR configurations and compare them sample by sample
+///
+/// Subfolders of the dataset are reported as separate classes, each with its own winner.
+#[tauri::command]
+pub async fn compare_ocr_configs(
+    app: AppHandle,
+    dataset_dir: String,
+    a: OcrConfig,
+    b: OcrConfig,
+) -> CommandResult<OcrComparison> {
+    let state = app.state::<AppState>();
+    let (_, pipeline_a) = ocr_pipeline_for(&state, a.engine.clone()).await?;
+    let (_, pipeline_b) = ocr_pipeline_for(&state, b.engine.clone()).await?;
+    Ok(ocr_compare::compare(Path::new(&dataset_dir), (a, pipeline_a), (b, pipeline_b)).await?)
+}
+
+/// Write the last `evaluate_ocr` run's per-sample CER/WER and averages to `path`
+///
+/// The format defaults to the file extension: `.csv` for spreadsheets, otherwise JSON.
+#[tauri::command]
+pub async fn export_accuracy_report(
+    app: AppHandle,
+    path: String,
+    format: Option<ReportFormat>,
+) -> CommandResult<()> {
+    let state = app.state::<AppState>();
+    let path = state.export_scope.check(Path::new(&path)).await?;
+    let evaluation = state.last_ocr_evaluation.lock().await.clone();
+    let Some(evaluation) = evaluation else {
+        return Err(anyhow!("No OCR evaluation to export; run evaluate_ocr first").into());
+    };
+    let format = format.unwrap_or_else(|| ReportFormat::for_path(&path));
+    evaluation.accuracy.write_report(&path, format)?;
+    tracing::info!("[ocr-eval] wrote accuracy report to {:?}", path);
     Ok(())
 }
 
+/// Score the detector on a folder of pages with exported COCO or labelme annotations
+#[tauri::command]
+pub async fn evaluate_detection(
+    app: AppHandle,
+    dataset_dir: String,
+    confidence_threshold: Option<f32>,
+    nms_threshold: Option<f32>,
+    iou_threshold: Option<f32>,
+) -> CommandResult<DetectionEvaluation> {
+    let state = app.state::<AppState>();
+    let defaults = ProcessOptions::default();
+    Ok(detection_eval::evaluate(
+        &state,
+        Path::new(&dataset_dir),
+        confidence_threshold.unwrap_or(defaults.confidence_threshold),
+        nms_threshold.unwrap_or(defaults.nms_threshold),
+        iou_threshold.unwrap_or(detection_eval::DEFAULT_IOU_THRESHOLD),
+    )
+    .await?)
+}
+
 /// DEPRECATED: Full-image inpainting - replaced by inpaint_region with per-block processing
 /// This function produces suboptimal results (white fills) and should not be used.
 /// Use inpaint_region instead for proper cropping, erosion, and mask handling.
@@ -375,8 +500,8 @@ pub async fn set_active_ocr(app: AppHandle, model_key: String) -> CommandResult<
 pub async fn inpaint(app: AppHandle, image: Vec<u8>, mask: Vec<u8>) -> CommandResult<Vec<u8>> {
     let state = app.state::<AppState>();
 
-    let img = image::load_from_memory(&image).context("Failed to load image")?;
-    let mask_img = image::load_from_memory(&mask).context("Failed to load mask")?;
+    let img = image_io::decode(&image).context("Failed to load image")?;
+    let mask_img = image_io::decode(&mask).context("Failed to load mask")?;
 
     let result = state
         .lama
@@ -410,394 +535,34 @@ pub fn get_system_fonts() -> CommandResult<Vec<String>> {
     Ok(fonts)
 }
 
-#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
-pub struct BBox {
-    pub xmin: f32,
-    pub ymin: f32,
-    pub xmax: f32,
-    pub ymax: f32,
-}
-
-#[derive(Debug, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct InpaintConfig {
-    pub padding: i32,        // Context padding (15-100px)
-    pub target_size: u32,    // Inference resolution (256/384/512/768/1024)
-    pub mask_threshold: u8,  // Binary threshold (0-50)
-    pub mask_erosion: u32,   // Erosion radius (0-10px)
-    pub mask_dilation: u32,  // Optional dilation before erosion (0-5px)
-    pub feather_radius: u32, // Alpha compositing feather (used by frontend)
-    pub debug_mode: bool,    // Export triptychs
-}
-
-impl Default for InpaintConfig {
-    fn default() -> Self {
-        InpaintConfig {
-            padding: 50,
-            target_size: 512,
-            mask_threshold: 30,
-            mask_erosion: 3,
-            mask_dilation: 0,
-            feather_radius: 5,
-            debug_mode: false,
-        }
-    }
-}
-
-#[derive(serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct InpaintedRegion {
-    pub image: Vec<u8>,
-    pub x: u32,
-    pub y: u32,
-    pub width: u32,
-    pub height: u32,
-    pub mask: Vec<u8>,
-    pub mask_width: u32,
-    pub mask_height: u32,
-    pub padded_bbox: BBox,
-}
-
-async fn run_inpainting_pipeline(
-    app: &AppHandle,
-    state: &AppState,
-    full_image: &DynamicImage,
-    full_mask: &GrayImage,
-    bbox: &BBox,
-    cfg: &InpaintConfig,
-) -> anyhow::Result<InpaintedRegion> {
-    let (image_width, image_height) = full_image.dimensions();
-    let mask_width = full_mask.width();
-    let mask_height = full_mask.height();
-
-    tracing::info!(
-        "inpaint pipeline start: config={:?}, image={}x{}, mask={}x{}",
-        cfg,
-        image_width,
-        image_height,
-        mask_width,
-        mask_height
-    );
-
-    let padded_min_x = (bbox.xmin - cfg.padding as f32)
-        .floor()
-        .clamp(0.0, image_width.saturating_sub(1) as f32);
-    let padded_min_y = (bbox.ymin - cfg.padding as f32)
-        .floor()
-        .clamp(0.0, image_height.saturating_sub(1) as f32);
-    let padded_max_x = (bbox.xmax + cfg.padding as f32)
-        .ceil()
-        .clamp(0.0, image_width as f32);
-    let padded_max_y = (bbox.ymax + cfg.padding as f32)
-        .ceil()
-        .clamp(0.0, image_height as f32);
-
-    let crop_x = padded_min_x as u32;
-    let crop_y = padded_min_y as u32;
-    let crop_x2 = padded_max_x as u32;
-    let crop_y2 = padded_max_y as u32;
-
-    if !(crop_x2 > crop_x && crop_y2 > crop_y) {
-        anyhow::bail!(
-            "Invalid padded bbox after clamping: [{},{} -> {},{}]",
-            crop_x,
-            crop_y,
-            crop_x2,
-            crop_y2
-        );
-    }
-
-    let crop_width = crop_x2 - crop_x;
-    let crop_height = crop_y2 - crop_y;
-
-    let padded_bbox = BBox {
-        xmin: crop_x as f32,
-        ymin: crop_y as f32,
-        xmax: crop_x2 as f32,
-        ymax: crop_y2 as f32,
-    };
-
-    tracing::debug!(
-        "Padded bbox: [{},{} -> {},{}] = {}x{}px",
-        padded_bbox.xmin,
-        padded_bbox.ymin,
-        padded_bbox.xmax,
-        padded_bbox.ymax,
-        crop_width,
-        crop_height
-    );
-
-    let cropped_image = full_image.crop_imm(crop_x, crop_y, crop_width, crop_height);
-
-    fn extract_and_resize_mask(
-        full_mask: &GrayImage,
-        bbox: &BBox,
-        orig_width: u32,
-        orig_height: u32,
-        target_width: u32,
-        target_height: u32,
-        config: &InpaintConfig,
-    ) -> anyhow::Result<GrayImage> {
-        let mask_width = full_mask.width();
-        let mask_height = full_mask.height();
-        let scale_x = mask_width as f32 / orig_width as f32;
-        let scale_y = mask_height as f32 / orig_height as f32;
-
-        let mask_xmin = (bbox.xmin * scale_x).floor().max(0.0) as u32;
-        let mask_ymin = (bbox.ymin * scale_y).floor().max(0.0) as u32;
-        let mask_xmax = (bbox.xmax * scale_x).ceil().min(mask_width as f32) as u32;
-        let mask_ymax = (bbox.ymax * scale_y).ceil().min(mask_height as f32) as u32;
-
-        let mask_crop_width = mask_xmax.saturating_sub(mask_xmin);
-        let mask_crop_height = mask_ymax.saturating_sub(mask_ymin);
-
-        tracing::debug!(
-            "Mask extraction: scale=({:.3},{:.3}), mask_bbox=[{},{} -> {},{}], crop={}x{}",
-            scale_x,
-            scale_y,
-            mask_xmin,
-            mask_ymin,
-            mask_xmax,
-            mask_ymax,
-            mask_crop_width,
-            mask_crop_height
-        );
-
-        if mask_crop_width == 0 || mask_crop_height == 0 {
-            return Err(anyhow!(
-                "Invalid mask crop dimensions: {}x{}",
-                mask_crop_width,
-                mask_crop_height
-            ));
-        }
-
-        let mut cropped_mask = GrayImage::new(mask_crop_width, mask_crop_height);
-        for y in 0..mask_crop_height {
-            for x in 0..mask_crop_width {
-                let px = (mask_xmin + x).min(mask_width - 1);
-                let py = (mask_ymin + y).min(mask_height - 1);
-                let pixel = full_mask.get_pixel(px, py);
-                cropped_mask.put_pixel(x, y, *pixel);
-            }
-        }
-
-        let mut thresholded = cropped_mask.clone();
-        for pixel in thresholded.pixels_mut() {
-            if pixel[0] < config.mask_threshold {
-                pixel[0] = 0;
-            }
-        }
-
-        let mut morphed = thresholded;
-        if config.mask_dilation > 0 {
-            morphed = dilate_mask(&morphed, config.mask_dilation);
-            tracing::debug!("Applied {}px mask dilation", config.mask_dilation);
-        }
-
-        let mut resized_mask = image::imageops::resize(
-            &morphed,
-            target_width,
-            target_height,
-            image::imageops::FilterType::Nearest,
-        );
-
-        if config.mask_erosion > 0 {
-            resized_mask = erode_mask(&resized_mask, config.mask_erosion);
-            tracing::debug!("Applied {}px mask erosion", config.mask_erosion);
-        }
-
-        tracing::debug!(
-            "Mask resized: {}x{} -> {}x{} (threshold={}, erosion={}px, dilation={}px)",
-            mask_crop_width,
-            mask_crop_height,
-            target_width,
-            target_height,
-            config.mask_threshold,
-            config.mask_erosion,
-            config.mask_dilation
-        );
-
-        Ok(resized_mask)
-    }
-
-    fn dilate_mask(mask: &GrayImage, kernel_size: u32) -> GrayImage {
-        use imageproc::distance_transform::Norm;
-        use imageproc::morphology::dilate;
-
-        dilate(mask, Norm::LInf, kernel_size as u8)
-    }
-
-    fn erode_mask(mask: &GrayImage, kernel_size: u32) -> GrayImage {
-        use imageproc::distance_transform::Norm;
-        use imageproc::morphology::dilate_mut;
-
-        let mut result = mask.clone();
-
-        for pixel in result.pixels_mut() {
-            pixel[0] = 255 - pixel[0];
-        }
-
-        dilate_mut(&mut result, Norm::LInf, kernel_size as u8);
-
-        for pixel in result.pixels_mut() {
-            pixel[0] = 255 - pixel[0];
-        }
-
-        result
-    }
-
-    let cropped_mask = extract_and_resize_mask(
-        full_mask,
-        &padded_bbox,
-        image_width,
-        image_height,
-        crop_width,
-        crop_height,
-        cfg,
-    )?;
-
-    if cfg.debug_mode {
-        save_debug_triptych(app, &cropped_image, &cropped_mask, bbox, &padded_bbox)?;
-    }
-
-    tracing::info!(
-        "Running LaMa inference with target_size={}",
-        cfg.target_size
-    );
-
-    let mask_dynamic = image::DynamicImage::ImageLuma8(cropped_mask.clone());
-
-    let inpainted_crop = state
-        .lama
-        .lock()
-        .await
-        .inference_with_size(&cropped_image, &mask_dynamic, cfg.target_size)
-        .context("Failed to perform inpainting")?;
-
-    tracing::info!("LaMa inference completed successfully");
-
-    if cfg.debug_mode {
-        save_debug_output(app, &cropped_image, &cropped_mask, &inpainted_crop, bbox)?;
-    }
-
-    let mut output_rgba = inpainted_crop.to_rgba8();
-    let actual_width = output_rgba.width();
-    let actual_height = output_rgba.height();
-
-    tracing::debug!(
-        "[inpaint] raw LaMa output dimensions: {}x{} (target {}x{})",
-        actual_width,
-        actual_height,
-        crop_width,
-        crop_height
-    );
-
-    if actual_width != crop_width || actual_height != crop_height {
-        tracing::warn!(
-            "[inpaint] correcting LaMa output from {}x{} to {}x{}",
-            actual_width,
-            actual_height,
-            crop_width,
-            crop_height
-        );
-
-        let resized = image::DynamicImage::ImageRgba8(output_rgba)
-            .resize_exact(
-                crop_width,
-                crop_height,
-                image::imageops::FilterType::CatmullRom,
-            )
-            .to_rgba8();
-
-        tracing::info!(
-            "[inpaint] resampled buffer: {} bytes for {}x{} region",
-            resized.len(),
-            crop_width,
-            crop_height
-        );
-
-        output_rgba = resized;
-    }
-
-    let mut output_pixels = output_rgba.into_raw();
-    let expected_pixel_bytes = (crop_width as usize)
-        .saturating_mul(crop_height as usize)
-        .saturating_mul(4);
-
-    if output_pixels.len() != expected_pixel_bytes {
-        tracing::error!(
-            "[inpaint] pixel buffer mismatch after correction: expected={} actual={} bbox=[{},{} -> {},{}]",
-            expected_pixel_bytes,
-            output_pixels.len(),
-            padded_bbox.xmin,
-            padded_bbox.ymin,
-            padded_bbox.xmax,
-            padded_bbox.ymax
-        );
-
-        if output_pixels.len() < expected_pixel_bytes {
-            tracing::warn!(
-                "[inpaint] padding output buffer from {} to {} bytes",
-                output_pixels.len(),
-                expected_pixel_bytes
-            );
-            output_pixels.resize(expected_pixel_bytes, 0);
-        } else {
-            tracing::warn!(
-                "[inpaint] truncating output buffer from {} to {} bytes",
-                output_pixels.len(),
-                expected_pixel_bytes
-            );
-            output_pixels.truncate(expected_pixel_bytes);
-        }
-    } else {
-        tracing::debug!(
-            "[inpaint] pixel buffer ok: {} bytes for {}x{} region",
-            output_pixels.len(),
-            crop_width,
-            crop_height
-        );
-    }
-    let mask_bytes = cropped_mask.into_raw();
-
-    Ok(InpaintedRegion {
-        image: output_pixels,
-        x: crop_x,
-        y: crop_y,
-        width: crop_width,
-        height: crop_height,
-        mask: mask_bytes,
-        mask_width: crop_width,
-        mask_height: crop_height,
-        padded_bbox,
-    })
-}
-
 #[tauri::command]
 pub async fn cache_inpainting_data(
     app: AppHandle,
+    page_id: String,
     image_png: Vec<u8>,
     mask_png: Vec<u8>,
 ) -> CommandResult<()> {
     let state = app.state::<AppState>();
 
-    let decoded_image =
-        image::load_from_memory(&image_png).context("Failed to decode cached inpaint image")?;
-    let decoded_mask = image::load_from_memory(&mask_png)
+    let decoded_image = image_io::decode_with_metadata(&image_png)
+        .context("Failed to decode cached inpaint image")?;
+    let decoded_mask = image_io::decode(&mask_png)
         .context("Failed to decode cached inpaint mask")?
         .to_luma8();
 
     {
-        let mut image_cache = state.inpaint_image_cache.write().await;
-        *image_cache = Some(Arc::new(decoded_image));
-    }
-
-    {
-        let mut mask_cache = state.inpaint_mask_cache.write().await;
-        *mask_cache = Some(Arc::new(decoded_mask));
+        let mut pages = state.pages.lock().await;
+        pages.set_image(&page_id, decoded_image.image, decoded_image.metadata);
+        pages.update(&page_id, |page| {
+            page.mask = Some(Arc::new(decoded_mask));
+            page.composite = None; // A new inpainting pass starts from the original image
+        });
     }
 
-    tracing::info!("Inpainting cache primed with image and mask data");
+    tracing::info!(
+        "Inpainting cache primed with image and mask for page '{}'",
+        page_id
+    );
 
     Ok(())
 }
@@ -805,12 +570,15 @@ pub async fn cache_inpainting_data(
 #[tauri::command]
 pub async fn inpaint_region_cached(
     app: AppHandle,
+    page_id: String,
     bbox: BBox,
     padding: Option<i32>,
     debug_mode: Option<bool>,
     config: Option<InpaintConfig>,
+    task_id: Option<String>,
 ) -> CommandResult<InpaintedRegion> {
     let state = app.state::<AppState>();
+    let task = TaskHandle::start(&app, "inpainting", task_id);
 
     let mut cfg = config.unwrap_or_default();
     if let Some(padding) = padding {
@@ -820,42 +588,52 @@ pub async fn inpaint_region_cached(
         cfg.debug_mode = debug_mode;
     }
 
-    let image_arc = {
-        let guard = state.inpaint_image_cache.read().await;
-        guard
+    let (image_arc, mask_arc) = {
+        let mut pages = state.pages.lock().await;
+        let page = pages.get(&page_id).ok_or_else(|| {
+            anyhow!(
+                "Page '{}' is not cached. Call cache_inpainting_data first.",
+                page_id
+            )
+        })?;
+        let image = page
+            .image
             .clone()
-            .ok_or_else(|| anyhow!("No cached image. Call cache_inpainting_data first."))?
-    };
-
-    let mask_arc = {
-        let guard = state.inpaint_mask_cache.read().await;
-        guard
+            .ok_or_else(|| anyhow!("No cached image. Call cache_inpainting_data first."))?;
+        let mask = page
+            .mask
             .clone()
-            .ok_or_else(|| anyhow!("No cached mask. Call cache_inpainting_data first."))?
+            .ok_or_else(|| anyhow!("No cached mask. Call cache_inpainting_data first."))?;
+        (image, mask)
     };
 
-    let result = run_inpainting_pipeline(&app, &state, &image_arc, &mask_arc, &bbox, &cfg).await?;
+    let debug_dir = if cfg.debug_mode {
+        Some(inpaint_debug_dir(&app)?)
+    } else {
+        None
+    };
 
-    Ok(result)
-}
-
-#[tauri::command]
-pub async fn clear_inpainting_cache(app: AppHandle) -> CommandResult<()> {
-    let state = app.state::<AppState>();
-
-    {
-        let mut image_cache = state.inpaint_image_cache.write().await;
-        *image_cache = None;
-    }
-
-    {
-        let mut mask_cache = state.inpaint_mask_cache.write().await;
-        *mask_cache = None;
+    task.report("inpaint", 0, 1, None);
+    let result = run_inpainting_pipeline(
+        debug_dir.as_deref(),
+        &state,
+        &image_arc,
+        &mask_arc,
+        &bbox,
+        &cfg,
+    )
+    .await;
+
+    if let Ok(region) = &result {
+        // Accumulate results so the page composite survives switching pages
+        state.pages.lock().await.update(&page_id, |page| {
+            let mut canvas = page.composite.as_deref().unwrap_or(&image_arc).to_rgba8();
+            pipeline::composite_region(&mut canvas, region);
+            page.composite = Some(Arc::new(DynamicImage::ImageRgba8(canvas)));
+        });
     }
 
-    tracing::info!("Inpainting cache cleared");
-
-    Ok(())
+    Ok(task.complete(result)?)
 }
 
 #[tauri::command]
@@ -871,8 +649,10 @@ pub async fn inpaint_region(
     padding: Option<i32>,          // DEPRECATED: Use config.padding instead
     debug_mode: Option<bool>,      // DEPRECATED: Use config.debug_mode instead
     config: Option<InpaintConfig>, // NEW: Full configuration
+    task_id: Option<String>,
 ) -> CommandResult<InpaintedRegion> {
     let state = app.state::<AppState>();
+    let task = TaskHandle::start(&app, "inpainting", task_id);
 
     let mut cfg = config.unwrap_or_default();
     if let Some(padding) = padding {
@@ -926,9 +706,24 @@ pub async fn inpaint_region(
             .ok_or_else(|| anyhow!("Failed to reconstruct mask buffer"))?;
     let full_mask: GrayImage = full_mask_buffer;
 
-    run_inpainting_pipeline(&app, &state, &full_image, &full_mask, &bbox, &cfg)
-        .await
-        .map_err(Into::into)
+    let debug_dir = if cfg.debug_mode {
+        Some(inpaint_debug_dir(&app)?)
+    } else {
+        None
+    };
+
+    task.report("inpaint", 0, 1, None);
+    let result = run_inpainting_pipeline(
+        debug_dir.as_deref(),
+        &state,
+        &full_image,
+        &full_mask,
+        &bbox,
+        &cfg,
+    )
+    .await;
+
+    Ok(task.complete(result)?)
 }
 /// Simple erosion: shrink white regions by kernel_size pixels
 fn erode_mask(mask: &image::GrayImage, kernel_size: u32) -> image::GrayImage {
@@ -951,60 +746,8 @@ fn erode_mask(mask: &image::GrayImage, kernel_size: u32) -> image::GrayImage {
     result
 }
 
-/// Save debug triptych: original crop, mask, and red overlay
-fn save_debug_triptych(
-    app: &AppHandle,
-    crop: &image::DynamicImage,
-    mask: &image::GrayImage,
-    bbox: &BBox,
-    _padded_bbox: &BBox,
-) -> anyhow::Result<()> {
-    let debug_dir = app
-        .path()
-        .app_cache_dir()
-        .context("Failed to get cache dir")?
-        .join("inpaint_debug");
-
-    fs::create_dir_all(&debug_dir)?;
-
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)?
-        .as_secs();
-
-    let bbox_str = format!("{:.0}_{:.0}", bbox.xmin, bbox.ymin);
-
-    // Save crop
-    crop.save(debug_dir.join(format!("{}_{}_crop.png", timestamp, bbox_str)))?;
-
-    // Save mask
-    image::DynamicImage::ImageLuma8(mask.clone())
-        .save(debug_dir.join(format!("{}_{}_mask.png", timestamp, bbox_str)))?;
-
-    // Create red overlay
-    let mut overlay = crop.to_rgb8();
-    for y in 0..mask.height() {
-        for x in 0..mask.width() {
-            if mask.get_pixel(x, y)[0] > 128 {
-                // Red overlay on white mask regions
-                overlay.put_pixel(x, y, image::Rgb([255, 0, 0]));
-            }
-        }
-    }
-    image::DynamicImage::ImageRgb8(overlay)
-        .save(debug_dir.join(format!("{}_{}_overlay.png", timestamp, bbox_str)))?;
-
-    tracing::info!("Saved debug triptych to {:?}", debug_dir);
-    Ok(())
-}
-
-/// Save debug output after inpainting
-fn save_debug_output(
-    app: &AppHandle,
-    crop: &image::DynamicImage,
-    mask: &image::GrayImage,
-    output: &image::DynamicImage,
-    bbox: &BBox,
-) -> anyhow::Result<()> {
+/// Directory where inpainting debug artifacts are written
+pub(crate) fn inpaint_debug_dir(app: &AppHandle) -> anyhow::Result<PathBuf> {
     let debug_dir = app
         .path()
         .app_cache_dir()
@@ -1013,60 +756,17 @@ fn save_debug_output(
 
     fs::create_dir_all(&debug_dir)?;
 
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)?
-        .as_secs();
-
-    let bbox_str = format!("{:.0}_{:.0}", bbox.xmin, bbox.ymin);
-
-    // Create side-by-side triptych
-    let w = crop.width();
-    let h = crop.height();
-    let mut triptych = image::RgbImage::new(w * 3, h);
-
-    // Panel 1: Original crop
-    let crop_rgb = crop.to_rgb8();
-    for y in 0..h {
-        for x in 0..w {
-            triptych.put_pixel(x, y, *crop_rgb.get_pixel(x, y));
-        }
-    }
-
-    // Panel 2: Mask (white = hole to fill)
-    for y in 0..h {
-        for x in 0..w {
-            let v = mask.get_pixel(x, y)[0];
-            triptych.put_pixel(w + x, y, image::Rgb([v, v, v]));
-        }
-    }
-
-    // Panel 3: LaMa output
-    let output_rgb = output.to_rgb8();
-    for y in 0..h {
-        for x in 0..w {
-            triptych.put_pixel(w * 2 + x, y, *output_rgb.get_pixel(x, y));
-        }
-    }
-
-    image::DynamicImage::ImageRgb8(triptych)
-        .save(debug_dir.join(format!("{}_{}_triptych.png", timestamp, bbox_str)))?;
-
-    tracing::info!("Saved inpaint triptych to {:?}", debug_dir);
-    Ok(())
+    Ok(debug_dir)
 }
 
 #[tauri::command]
-pub fn set_gpu_preference(app: AppHandle, preference: String) -> CommandResult<()> {
-    let app_dir = app
-        .path()
-        .app_config_dir()
-        .context("Failed to get app config directory")?;
-
-    fs::create_dir_all(&app_dir).context("Failed to create app config directory")?;
-
-    let config_path = app_dir.join("gpu_preference.txt");
-
-    fs::write(&config_path, preference.trim()).context("Failed to write GPU preference")?;
+pub async fn set_gpu_preference(app: AppHandle, preference: String) -> CommandResult<()> {
+    let state = app.state::<AppState>();
+    state
+        .settings
+        .update(|settings| settings.gpu.preference = preference.trim().to_string())
+        .await
+        .context("Failed to save GPU preference")?;
 
     tracing::info!("GPU preference saved. Restart required to take effect.");
 
@@ -1194,185 +894,116 @@ pub async fn run_gpu_stress_test(
     })
 }
 
-// DeepL Translation API types and command
-#[derive(Debug, Serialize, Deserialize)]
-struct DeepLRequest {
-    text: Vec<String>,
-    target_lang: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    source_lang: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct DeepLTranslation {
-    detected_source_language: Option<String>,
-    text: String,
+/// Time each pipeline stage on the built-in reference pages with the active provider
+#[tauri::command]
+pub async fn run_benchmark_suite(
+    app: AppHandle,
+    iterations: Option<usize>,
+    task_id: Option<String>,
+) -> CommandResult<BenchmarkResult> {
+    let state = app.state::<AppState>();
+    let settings = state.effective_settings().await;
+    let task = TaskHandle::start(&app, "benchmark", task_id);
+    let report = |current: usize, total: usize| task.report("page", current, total, None);
+    let result = benchmark::run(
+        &state,
+        &settings,
+        iterations.unwrap_or(benchmark::DEFAULT_ITERATIONS),
+        &report,
+    )
+    .await?;
+    task.finish();
+    Ok(result)
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct DeepLResponse {
-    translations: Vec<DeepLTranslation>,
+/// Score inpainting on clean pages with the given config (saved settings by default)
+///
+/// Each page gets synthetic text holes, or its `<name>.mask.png`; see `inpaint_eval`.
+#[tauri::command]
+pub async fn evaluate_inpainting(
+    app: AppHandle,
+    dataset_dir: String,
+    config: Option<InpaintConfig>,
+    task_id: Option<String>,
+) -> CommandResult<InpaintEvaluation> {
+    let state = app.state::<AppState>();
+    let config = match config {
+        Some(config) => config,
+        None => state.effective_settings().await.inpaint,
+    };
+    let task = TaskHandle::start(&app, "inpaint-eval", task_id);
+    let report = |current: usize, total: usize| task.report("page", current, total, None);
+    let evaluation =
+        inpaint_eval::evaluate(&state, Path::new(&dataset_dir), &config, &report).await?;
+    task.finish();
+    Ok(evaluation)
 }
 
 #[tauri::command]
 pub async fn translate_with_deepl(
+    app: AppHandle,
     api_key: String,
     text: String,
     use_pro: bool,
     source_lang: Option<String>,
     target_lang: Option<String>,
 ) -> CommandResult<String> {
-    let base_url = if use_pro {
-        "https://api.deepl.com"
-    } else {
-        "https://api-free.deepl.com"
-    };
-
-    let url = format!("{}/v2/translate", base_url);
-
-    // Default to EN-US as recommended by DeepL docs
-    let target = target_lang
-        .unwrap_or_else(|| "EN-US".to_string())
-        .to_uppercase();
-
-    let request_body = DeepLRequest {
-        text: vec![text],
-        target_lang: target,
-        source_lang: source_lang.map(|s| s.to_uppercase()),
-    };
-
-    tracing::debug!(
-        "DeepL request: endpoint={}, use_pro={}, body={:?}",
-        url,
+    let state = app.state::<AppState>();
+    let style = state.effective_settings().await.translation.style;
+    let translated = translation::translate_deepl(
+        &api_key,
+        &style.substitute(&text),
         use_pro,
-        request_body
-    );
-
-    let client = reqwest::Client::new();
-    let response = client
-        .post(&url)
-        .header("Authorization", format!("DeepL-Auth-Key {}", api_key))
-        .header("User-Agent", "Koharu/1.0")
-        .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await
-        .context("Failed to send DeepL API request")?;
-
-    let status = response.status();
-
-    if !status.is_success() {
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-
-        // Handle specific error codes
-        let error_msg = match status.as_u16() {
-            401 | 403 => "Invalid API key or insufficient permissions".to_string(),
-            429 => "Rate limit exceeded. Please wait and try again.".to_string(),
-            456 => {
-                "Quota exceeded. For DeepL Free, you've used your 500,000 character/month limit."
-                    .to_string()
-            }
-            _ => format!("DeepL API error ({}): {}", status.as_u16(), error_text),
-        };
-
-        return Err(anyhow::anyhow!(error_msg).into());
-    }
-
-    let deepl_response: DeepLResponse = response
-        .json()
-        .await
-        .context("Failed to parse DeepL API response")?;
-
-    deepl_response
-        .translations
-        .first()
-        .map(|t| t.text.clone())
-        .ok_or_else(|| anyhow::anyhow!("DeepL returned no translations").into())
-}
-
-// Ollama Translation API types and command
-#[derive(Debug, Serialize, Deserialize)]
-struct OllamaChatMessage {
-    role: String,
-    content: String,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct OllamaChatRequest {
-    model: String,
-    messages: Vec<OllamaChatMessage>,
-    stream: bool,
-}
+        source_lang.as_deref(),
+        target_lang.as_deref(),
+    )
+    .await?;
 
-#[derive(Debug, Serialize, Deserialize)]
-struct OllamaChatResponse {
-    message: OllamaChatMessage,
+    Ok(translated)
 }
 
 #[tauri::command]
 pub async fn translate_with_ollama(
+    app: AppHandle,
     text: String,
     model: String,
     system_prompt: Option<String>,
 ) -> CommandResult<String> {
-    let url = "http://localhost:11434/api/chat";
-
-    // Build messages array
-    let mut messages = Vec::new();
-
-    // Add system prompt if provided
-    if let Some(prompt) = system_prompt {
-        if !prompt.trim().is_empty() {
-            messages.push(OllamaChatMessage {
-                role: "system".to_string(),
-                content: prompt,
-            });
-        }
-    }
-
-    // Add user message with the OCR'd text
-    messages.push(OllamaChatMessage {
-        role: "user".to_string(),
-        content: text,
-    });
-
-    let request_body = OllamaChatRequest {
-        model,
-        messages,
-        stream: false,
-    };
-
-    let client = reqwest::Client::new();
-    let response = client
-        .post(url)
-        .header("Content-Type", "application/json")
-        .json(&request_body)
-        .send()
-        .await
-        .context(
-            "Failed to connect to Ollama. Make sure Ollama is running on http://localhost:11434",
-        )?;
+    let state = app.state::<AppState>();
+    let style = state.effective_settings().await.translation.style;
+    let prompt = style.system_prompt(system_prompt.as_deref());
+    let translated = translation::translate_ollama(&text, &model, prompt.as_deref()).await?;
 
-    let status = response.status();
+    Ok(translated)
+}
 
-    if !status.is_success() {
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        let error_msg = format!("Ollama API error ({}): {}", status.as_u16(), error_text);
-        return Err(anyhow::anyhow!(error_msg).into());
+/// Translate the reference sources with each provider and score them with BLEU and chrF
+///
+/// `providers` are provider keys as in settings ("deepl-free", "ollama", "plugin:<id>");
+/// defaults to the configured provider. Keys, model, and languages come from settings.
+#[tauri::command]
+pub async fn score_translations(
+    app: AppHandle,
+    references: Vec<ReferencePair>,
+    providers: Option<Vec<String>>,
+) -> CommandResult<Vec<ProviderScore>> {
+    if references.is_empty() {
+        return Err(coded(ErrorCode::InvalidInput, "No reference translations given").into());
     }
-
-    let ollama_response: OllamaChatResponse = response
-        .json()
-        .await
-        .context("Failed to parse Ollama API response")?;
-
-    Ok(ollama_response.message.content)
+    let state = app.state::<AppState>();
+    let saved = state.effective_settings().await.translation;
+    let providers = providers.unwrap_or_else(|| vec![saved.provider.clone()]);
+    for provider in &providers {
+        if let Err(err) = translation_eval::translator_for(&saved, provider) {
+            let code = if provider.starts_with("deepl") {
+                ErrorCode::ApiKeyMissing
+            } else {
+                ErrorCode::ProviderUnavailable
+            };
+            return Err(coded(code, format!("{:#}", err)).into());
+        }
+    }
+    Ok(translation_eval::compare(&saved, &providers, &references).await?)
 }
 
 // ============================================================================
@@ -1386,27 +1017,74 @@ pub struct RenderRequest {
     pub text_blocks: Vec<TextBlock>,
     pub render_method: String,
     pub default_font: String,
+    #[serde(default)]
+    pub export: Option<ExportOptions>, // None = saved export settings
+    #[serde(default)]
+    pub page_id: Option<String>, // Page whose edit history and source ICC/EXIF are used
 }
 
 #[tauri::command]
-pub async fn render_and_export_image(request: RenderRequest) -> CommandResult<Vec<u8>> {
+pub async fn render_and_export_image(
+    app: AppHandle,
+    request: RenderRequest,
+    task_id: Option<String>,
+) -> CommandResult<Vec<u8>> {
+    let state = app.state::<AppState>();
+    let task = TaskHandle::start(&app, "rendering", task_id);
     tracing::info!(
         "[RUST_EXPORT] Starting render with method='{}', {} text blocks",
         request.render_method,
         request.text_blocks.len()
     );
 
-    // Validate render method
-    if request.render_method != "rectangle"
-        && request.render_method != "lama"
-        && request.render_method != "newlama"
-    {
-        return Err(anyhow::anyhow!("Invalid render method: {}", request.render_method).into());
+    pipeline::validate_render_method(&request.render_method)?;
+    if let Some(export) = &request.export {
+        export.validate()?;
     }
 
+    // The page's edit history holds what was last edited; the request's blocks and base are
+    // used for pages the editor has no history for
+    let edited = match &request.page_id {
+        Some(page_id) => state.history.lock().await.current(page_id).cloned(),
+        None => None,
+    };
+    let text_blocks = match &edited {
+        Some(edited) => {
+            let blocks = serde_json::Value::Array(edited.blocks.to_vec());
+            match serde_json::from_value::<Vec<TextBlock>>(blocks) {
+                Ok(blocks) => blocks,
+                Err(err) => {
+                    tracing::warn!("[RUST_EXPORT] Ignoring unreadable history blocks: {}", err);
+                    request.text_blocks
+                }
+            }
+        }
+        None => request.text_blocks,
+    };
+
     // Load base image from buffer
-    let base_image =
-        image::load_from_memory(&request.base_image_buffer).context("Failed to load base image")?;
+    let image_io::Decoded {
+        image: base_image,
+        metadata,
+    } = image_io::decode_with_metadata(&request.base_image_buffer)
+        .context("Failed to load base image")?;
+
+    // Canvas-encoded bases lose ICC/EXIF; fall back to the original upload's metadata
+    let source = match &request.page_id {
+        Some(page_id) if metadata.is_empty() => state
+            .pages
+            .lock()
+            .await
+            .get(page_id)
+            .map(|page| page.source.clone())
+            .unwrap_or_default(),
+        _ => Arc::new(metadata),
+    };
+
+    let base_image = match edited.and_then(|edited| edited.inpainted) {
+        Some(inpainted) if request.render_method != "rectangle" => (*inpainted).clone(),
+        _ => base_image,
+    };
 
     tracing::info!(
         "[RUST_EXPORT] Base image loaded: {}x{}",
@@ -1414,28 +1092,1068 @@ pub async fn render_and_export_image(request: RenderRequest) -> CommandResult<Ve
         base_image.height()
     );
 
+    // "auto" fills plain bubbles through the page's detection mask; without one every block is
+    // drawn over the base
+    let mask = match (request.render_method.as_str(), &request.page_id) {
+        ("auto", Some(page_id)) => state
+            .pages
+            .lock()
+            .await
+            .get(page_id)
+            .and_then(|page| page.mask.clone()),
+        _ => None,
+    };
+    let base_image = match mask {
+        Some(mask) => {
+            let bboxes: Vec<BBox> = text_blocks
+                .iter()
+                .map(|block| BBox {
+                    xmin: block.xmin,
+                    ymin: block.ymin,
+                    xmax: block.xmax,
+                    ymax: block.ymax,
+                })
+                .collect();
+            pipeline::fill_plain_blocks(base_image, &mask, &bboxes)
+        }
+        None => base_image,
+    };
+
     // Render text on image (fonts loaded dynamically per text block)
+    task.report("render", 0, 2, None);
     let rendered_image = render_text_on_image(
         base_image,
-        request.text_blocks,
+        text_blocks,
         &request.render_method,
         &request.default_font,
     )
     .context("Rendering failed")?;
 
-    // Convert to PNG buffer
-    let mut png_buffer = Vec::new();
-    rendered_image
-        .write_to(
-            &mut std::io::Cursor::new(&mut png_buffer),
-            image::ImageFormat::Png,
+    let export = match request.export {
+        Some(export) => state.project_export(export).await,
+        None => state.effective_settings().await.export,
+    };
+
+    task.report("encode", 1, 2, Some(export.format.extension().to_string()));
+    let buffer = image_io::encode(&rendered_image, &export, &source)?;
+
+    tracing::info!(
+        "[RUST_EXPORT] Export complete, {} size: {} bytes",
+        export.format.extension(),
+        buffer.len()
+    );
+    task.finish();
+
+    Ok(buffer)
+}
+
+/// Render the golden suite in `suite_dir` and compare it to the approved images
+///
+/// With `update`, the current output becomes the new golden images instead.
+#[tauri::command]
+pub async fn run_golden_tests(
+    app: AppHandle,
+    suite_dir: String,
+    update: Option<bool>,
+) -> CommandResult<GoldenReport> {
+    let state = app.state::<AppState>();
+    // Writes goldens or failure images into the suite folder
+    let suite_dir = state.export_scope.check(Path::new(&suite_dir)).await?;
+    let report = tokio::task::spawn_blocking(move || {
+        golden::run(&suite_dir, Tolerance::default(), update.unwrap_or(false))
+    })
+    .await
+    .context("Golden test task panicked")??;
+    Ok(report)
+}
+
+// ============================================================================
+// Export Root Commands
+// ============================================================================
+
+/// Folders that commands writing files are allowed to write into
+#[tauri::command]
+pub async fn list_export_roots(app: AppHandle) -> CommandResult<Vec<PathBuf>> {
+    let state = app.state::<AppState>();
+    Ok(state.export_scope.roots().await)
+}
+
+/// Ask the user to pick a folder to trust; `suggested` only sets where the dialog opens
+///
+/// Returns None if the dialog was cancelled. The webview can't add a root without the dialog.
+#[tauri::command]
+pub async fn add_export_root(
+    app: AppHandle,
+    suggested: Option<String>,
+) -> CommandResult<Option<Vec<PathBuf>>> {
+    let state = app.state::<AppState>();
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let mut dialog = app.dialog().file().set_title("Trust export folder");
+    if let Some(dir) = suggested {
+        dialog = dialog.set_directory(dir);
+    }
+    dialog.pick_folder(move |folder| {
+        let _ = tx.send(folder);
+    });
+
+    let Some(folder) = rx.await.context("Folder dialog closed unexpectedly")? else {
+        return Ok(None);
+    };
+    let folder = folder
+        .into_path()
+        .context("Selected folder is not a local path")?;
+    Ok(Some(state.export_scope.add(&folder).await?))
+}
+
+#[tauri::command]
+pub async fn remove_export_root(app: AppHandle, path: String) -> CommandResult<Vec<PathBuf>> {
+    let state = app.state::<AppState>();
+    Ok(state.export_scope.remove(Path::new(&path)).await?)
+}
+
+// ============================================================================
+// Backend Pipeline Commands
+// ============================================================================
+
+#[tauri::command]
+pub async fn process_page(
+    app: AppHandle,
+    image: Vec<u8>,
+    options: Option<ProcessOptions>,
+    task_id: Option<String>,
+) -> CommandResult<PageOutput> {
+    let state = app.state::<AppState>();
+    let task = TaskHandle::start(&app, "process_page", task_id);
+    let options = match options {
+        Some(options) => options,
+        None => state.effective_settings().await.process_options(),
+    };
+
+    let img = image_io::decode(&image).context("Failed to load image")?;
+    let debug_dir = if options.inpaint.debug_mode {
+        Some(inpaint_debug_dir(&app)?)
+    } else {
+        None
+    };
+
+    let report = |progress: pipeline::StageProgress| {
+        task.report(
+            progress.stage.as_str(),
+            progress.current,
+            progress.total,
+            None,
         )
-        .context("Failed to encode PNG")?;
+    };
+
+    let result = pipeline::run_page(&state, &img, &options, debug_dir.as_deref(), &report).await;
+    let output = task.complete(result.and_then(PageOutput::from_result))?;
+
+    Ok(output)
+}
+
+#[tauri::command]
+pub async fn process_folder(
+    app: AppHandle,
+    input_dir: String,
+    output_dir: String,
+    options: Option<ProcessOptions>,
+    workers: Option<usize>,
+    task_id: Option<String>,
+) -> CommandResult<FolderSummary> {
+    let state = app.state::<AppState>();
+    let task = TaskHandle::start(&app, "process_folder", task_id);
+    let options = match options {
+        Some(mut options) => {
+            options.export = state.project_export(options.export).await;
+            options
+        }
+        None => state.effective_settings().await.process_options(),
+    };
+    let workers = workers.unwrap_or(2);
+    let output_dir = state.export_scope.check(Path::new(&output_dir)).await?;
+
+    let debug_dir = if options.inpaint.debug_mode {
+        Some(inpaint_debug_dir(&app)?)
+    } else {
+        None
+    };
+
+    // Pages finish out of order, so progress counts finished pages rather than indices
+    let finished = AtomicUsize::new(0);
+    let report = |event: batch::FolderPageEvent| {
+        let done = match event.status {
+            batch::PageStatus::Started => finished.load(Ordering::Relaxed),
+            batch::PageStatus::Completed | batch::PageStatus::Failed => {
+                finished.fetch_add(1, Ordering::Relaxed) + 1
+            }
+        };
+        let message = match &event.error {
+            Some(error) => format!("{}: {}", event.file, error),
+            None => event.file.clone(),
+        };
+        task.report(event.status.as_str(), done, event.total, Some(message));
+    };
+
+    let summary = batch::process_folder(
+        &state,
+        Path::new(&input_dir),
+        &output_dir,
+        &options,
+        workers,
+        debug_dir.as_deref(),
+        &report,
+    )
+    .await;
+
+    Ok(task.complete(summary)?)
+}
+
+#[tauri::command]
+pub async fn export_pdf(
+    app: AppHandle,
+    image_paths: Vec<String>,
+    output_path: String,
+    options: Option<PdfOptions>,
+    task_id: Option<String>,
+) -> CommandResult<()> {
+    let state = app.state::<AppState>();
+    let output_path = state.export_scope.check(Path::new(&output_path)).await?;
+    let options = options.unwrap_or_default();
+    let task = TaskHandle::start(&app, "export_pdf", task_id);
+
+    tokio::task::spawn_blocking(move || {
+        let report = |current: usize, total: usize| task.report("page", current, total, None);
+        let result = pdf_export::export_pdf(&image_paths, &output_path, &options, &report);
+        task.complete(result)
+    })
+    .await
+    .context("PDF export task panicked")??;
+
+    Ok(())
+}
+
+/// Before/after image of a page (side by side, or an animated GIF/WebP)
+#[tauri::command]
+pub async fn create_comparison(
+    original: Vec<u8>,
+    translated: Vec<u8>,
+    options: Option<ComparisonOptions>,
+) -> CommandResult<Vec<u8>> {
+    let options = options.unwrap_or_default();
+
+    let bytes = tokio::task::spawn_blocking(move || {
+        let original = image_io::decode(&original).context("Failed to load original page")?;
+        let translated = image_io::decode(&translated).context("Failed to load translated page")?;
+        comparison::encode(&original, &translated, &options)
+    })
+    .await
+    .context("Comparison task panicked")??;
+
+    tracing::info!("[comparison] created {} byte comparison", bytes.len());
+    Ok(bytes)
+}
+
+/// Read pages saved by manga-image-translator; each comes back with its image name and blocks
+#[tauri::command]
+pub async fn import_mit_json(path: String) -> CommandResult<Vec<ImportedPage>> {
+    let pages = mit_json::import(Path::new(&path))?;
+    tracing::info!("[mit] imported {} page(s) from {:?}", pages.len(), path);
+    Ok(pages)
+}
+
+/// Save one page's blocks as manga-image-translator JSON, keyed by `image`
+#[tauri::command]
+pub async fn export_mit_json(
+    app: AppHandle,
+    path: String,
+    image: String,
+    blocks: Vec<PageBlock>,
+) -> CommandResult<()> {
+    let state = app.state::<AppState>();
+    let path = state.export_scope.check(Path::new(&path)).await?;
+    mit_json::export(&path, &image, &blocks)?;
+    tracing::info!("[mit] wrote {} block(s) to {:?}", blocks.len(), path);
+    Ok(())
+}
+
+/// Write a cached page with its boxes, mask, and text as COCO or labelme training data
+#[tauri::command]
+pub async fn export_annotations(
+    app: AppHandle,
+    page_id: String,
+    blocks: Vec<AnnotatedBlock>,
+    output_dir: String,
+    name: Option<String>,
+    format: Option<AnnotationFormat>,
+) -> CommandResult<Vec<PathBuf>> {
+    let state = app.state::<AppState>();
+    let output_dir = state.export_scope.check(Path::new(&output_dir)).await?;
+    let format = format.unwrap_or_default();
+
+    let (image, mask) = {
+        let mut pages = state.pages.lock().await;
+        let page = pages
+            .get(&page_id)
+            .with_context(|| format!("Page '{}' is not loaded", page_id))?;
+        let image = page
+            .image
+            .clone()
+            .with_context(|| format!("Page '{}' has no image", page_id))?;
+        (image, page.mask.clone())
+    };
+
+    // Page ids may contain archive paths; keep the file name flat
+    let stem = name
+        .unwrap_or_else(|| page_id.clone())
+        .replace(['/', '\\'], "_");
+    let block_count = blocks.len();
+
+    let written = tokio::task::spawn_blocking(move || {
+        annotations::export(&output_dir, &stem, format, &image, &blocks, mask.as_deref())
+    })
+    .await
+    .context("Annotation export task panicked")??;
 
     tracing::info!(
-        "[RUST_EXPORT] Export complete, PNG size: {} bytes",
-        png_buffer.len()
+        "[annotations] exported {} blocks of page '{}' as {:?}",
+        block_count,
+        page_id,
+        format
     );
+    Ok(written)
+}
+
+// ============================================================================
+// Archive Import Commands
+// ============================================================================
 
-    Ok(png_buffer)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedPage {
+    pub page_id: String,
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedArchive {
+    pub workspace: String,
+    pub pages: Vec<ImportedPage>,
+}
+
+/// Extract a CBZ/ZIP or CBR/RAR chapter and register its pages in the page store
+///
+/// Page ids are `<workspace name>/<index>`, in natural file name order.
+#[tauri::command]
+pub async fn import_archive(
+    app: AppHandle,
+    path: String,
+    task_id: Option<String>,
+) -> CommandResult<ImportedArchive> {
+    let state = app.state::<AppState>();
+    let task = TaskHandle::start(&app, "import_archive", task_id);
+    let workspace_root = app
+        .path()
+        .app_data_dir()
+        .context("Failed to get app data dir")?
+        .join(archive::WORKSPACE_DIR);
+
+    task.report("extract", 0, 1, None);
+    let archive_path = PathBuf::from(&path);
+    let extracted =
+        tokio::task::spawn_blocking(move || archive::extract(&archive_path, &workspace_root))
+            .await
+            .context("Archive import task panicked")?;
+    let extracted = match extracted {
+        Ok(extracted) => extracted,
+        Err(err) => {
+            task.fail(&err);
+            return Err(err.into());
+        }
+    };
+    let workspace = extracted
+        .dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let total = extracted.pages.len();
+    let mut pages = Vec::with_capacity(total);
+    for (index, page_path) in extracted.pages.into_iter().enumerate() {
+        task.report("decode", index, total, None);
+        let decode_path = page_path.clone();
+        let decoded =
+            tokio::task::spawn_blocking(move || image_io::open_with_metadata(&decode_path))
+                .await
+                .context("Archive page decode panicked")?;
+        let source = match decoded {
+            Ok(source) => source,
+            Err(err) => {
+                tracing::warn!("[archive] skipping {:?}: {:#}", page_path, err);
+                continue;
+            }
+        };
+
+        let page_id = format!("{}/{}", workspace, index);
+        let (width, height) = source.image.dimensions();
+        state
+            .pages
+            .lock()
+            .await
+            .set_image(&page_id, source.image, source.metadata);
+        pages.push(ImportedPage {
+            page_id,
+            path: page_path.to_string_lossy().to_string(),
+            width,
+            height,
+        });
+    }
+    task.finish();
+
+    // Relaunching reopens this chapter at its first page
+    state
+        .session
+        .update(|session| {
+            session.workspace = Some(workspace.clone());
+            session.page_index = Some(0);
+        })
+        .await?;
+
+    tracing::info!(
+        "[archive] imported {}/{} page(s) from {} into '{}'",
+        pages.len(),
+        total,
+        path,
+        workspace
+    );
+    Ok(ImportedArchive { workspace, pages })
+}
+
+// ============================================================================
+// Job Queue Commands
+// ============================================================================
+
+/// Output folders and files must be inside a trusted export root
+#[tauri::command]
+pub async fn enqueue_job(app: AppHandle, params: JobParams) -> CommandResult<Job> {
+    let state = app.state::<AppState>();
+    params.validate()?;
+    for path in params.output_paths() {
+        state.export_scope.check(Path::new(path)).await?;
+    }
+    let job = state.jobs.enqueue(params).await;
+    tracing::info!("[jobs] enqueued {}", job.id);
+    emit_job_update(&app, &job);
+    Ok(job)
+}
+
+#[tauri::command]
+pub async fn pause_job(app: AppHandle, job_id: String) -> CommandResult<Job> {
+    let state = app.state::<AppState>();
+    let job = state.jobs.pause(&job_id).await?;
+    emit_job_update(&app, &job);
+    Ok(job)
+}
+
+#[tauri::command]
+pub async fn resume_job(app: AppHandle, job_id: String) -> CommandResult<Job> {
+    let state = app.state::<AppState>();
+    let job = state.jobs.resume(&job_id).await?;
+    emit_job_update(&app, &job);
+    Ok(job)
+}
+
+#[tauri::command]
+pub async fn cancel_job(app: AppHandle, job_id: String) -> CommandResult<Job> {
+    let state = app.state::<AppState>();
+    let job = state.jobs.cancel(&job_id).await?;
+    emit_job_update(&app, &job);
+    Ok(job)
+}
+
+#[tauri::command]
+pub async fn list_jobs(app: AppHandle) -> CommandResult<Vec<Job>> {
+    let state = app.state::<AppState>();
+    Ok(state.jobs.list().await)
+}
+
+#[tauri::command]
+pub async fn clear_finished_jobs(app: AppHandle) -> CommandResult<usize> {
+    let state = app.state::<AppState>();
+    Ok(state.jobs.clear_finished().await)
+}
+
+// ============================================================================
+// Progress Commands
+// ============================================================================
+
+#[tauri::command]
+pub fn list_active_tasks(app: AppHandle) -> CommandResult<Vec<TaskProgress>> {
+    let state = app.state::<AppState>();
+    Ok(state.tasks.list())
+}
+
+// ============================================================================
+// Settings Commands
+// ============================================================================
+
+#[tauri::command]
+pub async fn get_settings(app: AppHandle) -> CommandResult<Settings> {
+    let state = app.state::<AppState>();
+    Ok(state.settings.get().await)
+}
+
+/// Save settings; GPU changes apply on restart, the OCR engine switches immediately
+#[tauri::command]
+pub async fn update_settings(app: AppHandle, settings: Settings) -> CommandResult<Settings> {
+    let state = app.state::<AppState>();
+    Ok(save_settings(&state, settings).await?)
+}
+
+async fn save_settings(state: &AppState, settings: Settings) -> anyhow::Result<Settings> {
+    settings
+        .export
+        .validate()
+        .map_err(|err| coded(ErrorCode::InvalidInput, err.to_string()))?;
+    if let Some(engine) = &settings.ocr.engine {
+        if !state.ocr_pipelines.read().await.contains_key(engine) {
+            return Err(coded(
+                ErrorCode::OcrEngineUnavailable,
+                format!("OCR model '{}' not found", engine),
+            ));
+        }
+        *state.active_ocr.write().await = engine.clone();
+    }
+
+    let scripting_changed =
+        state.settings.get().await.scripting.enabled != settings.scripting.enabled;
+    let saved = state.settings.set(settings).await?;
+    crash::set_enabled(saved.diagnostics.crash_reports);
+    if scripting_changed {
+        state
+            .scripts
+            .write()
+            .await
+            .reload(saved.scripting.enabled)?;
+    }
+    tracing::info!("[settings] saved settings (gpu={})", saved.gpu.preference);
+    Ok(saved)
+}
+
+/// Write settings (and the frontend's own preferences) to a portable JSON file
+///
+/// API keys are left out unless `include_secrets` is set, so the file can be shared.
+#[tauri::command]
+pub async fn export_settings(
+    app: AppHandle,
+    path: String,
+    include_secrets: bool,
+    frontend: Option<serde_json::Value>,
+) -> CommandResult<()> {
+    let state = app.state::<AppState>();
+    let target = state.export_scope.check(Path::new(&path)).await?;
+    let settings = state.settings.get().await;
+    SettingsBundle::new(&settings, include_secrets, frontend).write(&target)?;
+    tracing::info!("[settings] exported settings to {}", path);
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedSettings {
+    pub settings: Settings,
+    pub frontend: Option<serde_json::Value>, // For the frontend to restore into localStorage
+}
+
+/// Apply a file written by `export_settings`; GPU changes apply on restart
+#[tauri::command]
+pub async fn import_settings(app: AppHandle, path: String) -> CommandResult<ImportedSettings> {
+    let state = app.state::<AppState>();
+    let bundle = SettingsBundle::read(Path::new(&path))?;
+    let mut settings = bundle.merge_into(&state.settings.get().await)?;
+
+    // The source machine may have OCR models this one doesn't
+    if let Some(engine) = &settings.ocr.engine {
+        if !state.ocr_pipelines.read().await.contains_key(engine) {
+            tracing::warn!(
+                "[settings] imported OCR engine '{}' is not installed; keeping automatic selection",
+                engine
+            );
+            settings.ocr.engine = None;
+        }
+    }
+
+    let settings = save_settings(&state, settings).await?;
+    tracing::info!(
+        "[settings] imported settings from {} (exported by v{})",
+        path,
+        bundle.app_version
+    );
+    Ok(ImportedSettings {
+        settings,
+        frontend: bundle.frontend,
+    })
+}
+
+// ============================================================================
+// Project Commands
+// ============================================================================
+
+/// Open a project file (or the nearest one above a chapter folder) and apply it
+///
+/// The project's glossary, tone, provider, fonts, inpaint defaults and export tags override
+/// the saved settings for backend pipelines until the project is closed.
+#[tauri::command]
+pub async fn open_project(app: AppHandle, path: String) -> CommandResult<OpenProject> {
+    let state = app.state::<AppState>();
+    let open = Project::open(Path::new(&path))?;
+    tracing::info!(
+        "[project] opened {:?} ({} glossary entries)",
+        open.path,
+        open.project.glossary.len()
+    );
+    *state.project.write().await = Some(open.clone());
+    remember_project(&state, Some(open.path.clone())).await?;
+    Ok(open)
+}
+
+/// Write a project file and make it the open project
+///
+/// Without `project`, the current settings are snapshotted so the next chapter starts
+/// from the same decisions. A directory `path` gets the default project file name.
+#[tauri::command]
+pub async fn save_project(
+    app: AppHandle,
+    path: String,
+    project: Option<Project>,
+    name: Option<String>,
+) -> CommandResult<OpenProject> {
+    let state = app.state::<AppState>();
+    let mut path = PathBuf::from(path);
+    if path.is_dir() {
+        path = path.join(PROJECT_FILE);
+    }
+    let path = state.export_scope.check(&path).await?;
+    let project = match project {
+        Some(project) => project,
+        None => Project::from_settings(&state.effective_settings().await, name),
+    };
+
+    project.save(&path)?;
+    tracing::info!("[project] saved {:?}", path);
+    let open = OpenProject { path, project };
+    *state.project.write().await = Some(open.clone());
+    remember_project(&state, Some(open.path.clone())).await?;
+    Ok(open)
+}
+
+#[tauri::command]
+pub async fn get_project(app: AppHandle) -> CommandResult<Option<OpenProject>> {
+    let state = app.state::<AppState>();
+    Ok(state.project.read().await.clone())
+}
+
+/// Stop applying the open project; saved settings take effect again
+#[tauri::command]
+pub async fn close_project(app: AppHandle) -> CommandResult<()> {
+    let state = app.state::<AppState>();
+    if let Some(open) = state.project.write().await.take() {
+        tracing::info!("[project] closed {:?}", open.path);
+    }
+    remember_project(&state, None).await?;
+    Ok(())
+}
+
+/// Keep the session's project in step so the next launch reopens it
+async fn remember_project(state: &AppState, path: Option<PathBuf>) -> anyhow::Result<()> {
+    state
+        .session
+        .update(|session| session.project = path)
+        .await?;
+    Ok(())
+}
+
+// ============================================================================
+// Session Commands
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoredPage {
+    pub index: usize,
+    pub count: usize,
+    pub path: PathBuf,
+    pub image: Vec<u8>, // File bytes, decoded by the frontend like an opened image
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoredSession {
+    pub session: Session,
+    pub project: Option<OpenProject>,
+    pub page: Option<RestoredPage>,
+}
+
+/// Record the frontend's view state; the project and workspace are tracked by the backend
+#[tauri::command]
+pub async fn save_session(app: AppHandle, session: Session) -> CommandResult<Session> {
+    let state = app.state::<AppState>();
+    let project = state
+        .project
+        .read()
+        .await
+        .as_ref()
+        .map(|open| open.path.clone());
+    let update = Session {
+        project: None,
+        workspace: None,
+        ..session
+    };
+    let saved = state
+        .session
+        .update(|saved| {
+            saved.merge(update);
+            saved.project = project;
+        })
+        .await?;
+    Ok(saved)
+}
+
+/// Reopen the last project, engine and page; called once at startup
+///
+/// Anything that no longer exists (project file, OCR engine, workspace) is skipped.
+#[tauri::command]
+pub async fn restore_session(app: AppHandle) -> CommandResult<RestoredSession> {
+    let state = app.state::<AppState>();
+    let session = state.session.get().await;
+
+    let project = match &session.project {
+        Some(path) => match Project::open(path) {
+            Ok(open) => {
+                *state.project.write().await = Some(open.clone());
+                Some(open)
+            }
+            Err(err) => {
+                tracing::warn!("[session] not reopening project: {:#}", err);
+                None
+            }
+        },
+        None => None,
+    };
+
+    if let Some(engine) = &session.ocr_engine {
+        if state.ocr_pipelines.read().await.contains_key(engine) {
+            *state.active_ocr.write().await = engine.clone();
+        } else {
+            tracing::warn!("[session] OCR engine '{}' is no longer available", engine);
+        }
+    }
+
+    let mut page = None;
+    if let Some(workspace) = &session.workspace {
+        // Only a single plain name, so a tampered session file can't point elsewhere
+        let mut components = Path::new(workspace).components();
+        if let (Some(std::path::Component::Normal(_)), None) =
+            (components.next(), components.next())
+        {
+            let dir = app
+                .path()
+                .app_data_dir()
+                .context("Failed to get app data dir")?
+                .join(archive::WORKSPACE_DIR)
+                .join(workspace);
+            let pages = archive::workspace_pages(&dir);
+            if !pages.is_empty() {
+                let index = session.page_index.unwrap_or(0).min(pages.len() - 1);
+                let image = fs::read(&pages[index])
+                    .with_context(|| format!("Failed to read {:?}", pages[index]))?;
+                page = Some(RestoredPage {
+                    index,
+                    count: pages.len(),
+                    path: pages[index].clone(),
+                    image,
+                });
+            }
+        }
+    }
+
+    tracing::info!(
+        "[session] restored (project={:?}, page={:?})",
+        project.as_ref().map(|open| &open.path),
+        page.as_ref().map(|page| &page.path)
+    );
+    Ok(RestoredSession {
+        session,
+        project,
+        page,
+    })
+}
+
+// ============================================================================
+// Scripting Commands
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptsStatus {
+    pub dir: PathBuf,
+    pub scripts: Vec<ScriptInfo>,
+}
+
+/// Recompile the scripts folder, e.g. after editing a script
+#[tauri::command]
+pub async fn reload_scripts(app: AppHandle) -> CommandResult<ScriptsStatus> {
+    let state = app.state::<AppState>();
+    let enabled = state.settings.get().await.scripting.enabled;
+    let mut hooks = state.scripts.write().await;
+    let scripts = hooks.reload(enabled)?;
+    Ok(ScriptsStatus {
+        dir: hooks.dir().to_path_buf(),
+        scripts,
+    })
+}
+
+/// Run one hook over blocks produced by the frontend's own OCR/translation flow
+#[tauri::command]
+pub async fn run_script_hook(
+    app: AppHandle,
+    hook: Hook,
+    mut blocks: Vec<PageBlock>,
+) -> CommandResult<Vec<PageBlock>> {
+    let state = app.state::<AppState>();
+    state.scripts.read().await.run(hook, &mut blocks)?;
+    Ok(blocks)
+}
+
+// ============================================================================
+// Plugin Commands
+// ============================================================================
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginsStatus {
+    pub dir: PathBuf,
+    pub plugins: Vec<PluginInfo>,
+}
+
+/// Installed provider plugins; OCR ones appear as `plugin:<id>` engines
+#[tauri::command]
+pub async fn list_plugins() -> CommandResult<PluginsStatus> {
+    let registry = plugins::registry()?;
+    Ok(PluginsStatus {
+        dir: registry.dir().to_path_buf(),
+        plugins: registry.list().await,
+    })
+}
+
+/// Stop running plugins, rescan the plugins folder and re-register plugin OCR engines
+#[tauri::command]
+pub async fn reload_plugins(app: AppHandle) -> CommandResult<PluginsStatus> {
+    let state = app.state::<AppState>();
+    let registry = plugins::registry()?;
+    let installed = registry.reload().await;
+
+    let mut pipelines = state.ocr_pipelines.write().await;
+    pipelines.retain(|key, _| !key.starts_with(plugins::PLUGIN_PREFIX));
+    pipelines.extend(registry.ocr_pipelines().await);
+    tracing::info!("[plugins] {} plugin(s) installed", installed.len());
+
+    Ok(PluginsStatus {
+        dir: registry.dir().to_path_buf(),
+        plugins: installed,
+    })
+}
+
+// ============================================================================
+// Localization Commands
+// ============================================================================
+
+/// Language for backend error messages, e.g. navigator.language; returns the catalog used
+#[tauri::command]
+pub fn set_ui_language(language: String) -> CommandResult<String> {
+    Ok(i18n::set_language(&language).to_string())
+}
+
+// ============================================================================
+// Logging Commands
+// ============================================================================
+
+/// Change the log level without restarting, e.g. "debug" or "info,koharu_lib=trace"
+#[tauri::command]
+pub fn set_log_level(level: String) -> CommandResult<String> {
+    logging::set_level(&level)?;
+    Ok(logging::level())
+}
+
+#[tauri::command]
+pub fn get_log_level() -> CommandResult<String> {
+    Ok(logging::level())
+}
+
+/// Folder holding the rotated log files, if file logging could be set up
+#[tauri::command]
+pub fn get_log_dir() -> CommandResult<Option<String>> {
+    Ok(logging::log_dir().map(|dir| dir.to_string_lossy().to_string()))
+}
+
+/// The last `n` log lines, oldest first, for attaching to bug reports
+#[tauri::command]
+pub fn get_recent_logs(n: usize) -> CommandResult<Vec<String>> {
+    Ok(logging::recent_lines(n))
+}
+
+/// Crash report from a previous run that hasn't been dismissed yet
+#[tauri::command]
+pub fn get_pending_crash_report() -> CommandResult<Option<CrashReport>> {
+    Ok(crash::latest_pending()?)
+}
+
+#[tauri::command]
+pub fn dismiss_crash_reports() -> CommandResult<usize> {
+    Ok(crash::dismiss_pending()?)
+}
+
+// ============================================================================
+// Edit History Commands
+// ============================================================================
+
+fn decode_mask_png(mask_png: &[u8]) -> anyhow::Result<GrayImage> {
+    Ok(image_io::decode(mask_png)
+        .context("Failed to decode mask")?
+        .to_luma8())
+}
+
+/// Start history for a newly opened page
+#[tauri::command]
+pub async fn load_editor_state(
+    app: AppHandle,
+    page_id: String,
+    blocks: Vec<serde_json::Value>,
+    mask_png: Option<Vec<u8>>,
+    inpainted_png: Option<Vec<u8>>,
+) -> CommandResult<EditorSnapshot> {
+    let state = app.state::<AppState>();
+
+    let mask = match mask_png {
+        Some(png) => Some(Arc::new(decode_mask_png(&png)?)),
+        None => None,
+    };
+    let inpainted = match inpainted_png {
+        Some(png) => Some(Arc::new(
+            image_io::decode(&png).context("Failed to decode inpainted image")?,
+        )),
+        None => None,
+    };
+
+    let mut histories = state.history.lock().await;
+    let history = histories.page(&page_id);
+    history.reset(EditorState {
+        blocks: Arc::new(blocks),
+        mask,
+        inpainted,
+    });
+    let before = history.current().clone();
+    Ok(history.snapshot(Some(&before))?)
+}
+
+/// Current editor state, e.g. after a frontend reload
+#[tauri::command]
+pub async fn get_editor_state(
+    app: AppHandle,
+    page_id: String,
+    include_images: Option<bool>,
+) -> CommandResult<EditorSnapshot> {
+    let state = app.state::<AppState>();
+    let histories = state.history.lock().await;
+    // A page the editor never opened reads as empty without creating a history for it
+    let empty = History::default();
+    let history = histories.get(&page_id).unwrap_or(&empty);
+
+    let snapshot = if include_images.unwrap_or(true) {
+        history.snapshot(None)?
+    } else {
+        history.snapshot(Some(history.current()))?
+    };
+    Ok(snapshot)
+}
+
+async fn apply_edit(
+    app: &AppHandle,
+    page_id: &str,
+    label: Option<String>,
+    edit: Edit,
+) -> anyhow::Result<EditorSnapshot> {
+    let state = app.state::<AppState>();
+    let mut histories = state.history.lock().await;
+    let history = histories.page(page_id);
+    history.apply(label, edit);
+    // The caller already has the images it just sent
+    let current = history.current().clone();
+    history.snapshot(Some(&current))
+}
+
+#[tauri::command]
+pub async fn update_blocks(
+    app: AppHandle,
+    page_id: String,
+    blocks: Vec<serde_json::Value>,
+    label: Option<String>,
+) -> CommandResult<EditorSnapshot> {
+    Ok(apply_edit(&app, &page_id, label, Edit::Blocks(blocks)).await?)
+}
+
+#[tauri::command]
+pub async fn update_mask(
+    app: AppHandle,
+    page_id: String,
+    mask_png: Vec<u8>,
+    label: Option<String>,
+) -> CommandResult<EditorSnapshot> {
+    let mask = decode_mask_png(&mask_png)?;
+    Ok(apply_edit(&app, &page_id, label, Edit::Mask(mask)).await?)
+}
+
+#[tauri::command]
+pub async fn update_inpainted(
+    app: AppHandle,
+    page_id: String,
+    image_png: Vec<u8>,
+    label: Option<String>,
+) -> CommandResult<EditorSnapshot> {
+    let image = image_io::decode(&image_png).context("Failed to decode inpainted image")?;
+    Ok(apply_edit(&app, &page_id, label, Edit::Inpainted(image)).await?)
+}
+
+#[tauri::command]
+pub async fn undo(app: AppHandle, page_id: String) -> CommandResult<EditorSnapshot> {
+    let state = app.state::<AppState>();
+    let mut histories = state.history.lock().await;
+    let history = histories
+        .get_mut(&page_id)
+        .ok_or_else(|| coded(ErrorCode::NothingToUndo, "Nothing to undo"))?;
+
+    let before = history.current().clone();
+    let label = history
+        .undo()
+        .ok_or_else(|| coded(ErrorCode::NothingToUndo, "Nothing to undo"))?;
+    tracing::info!("[history] undo '{}' on page {}", label, page_id);
+    Ok(history.snapshot(Some(&before))?)
+}
+
+#[tauri::command]
+pub async fn redo(app: AppHandle, page_id: String) -> CommandResult<EditorSnapshot> {
+    let state = app.state::<AppState>();
+    let mut histories = state.history.lock().await;
+    let history = histories
+        .get_mut(&page_id)
+        .ok_or_else(|| coded(ErrorCode::NothingToUndo, "Nothing to redo"))?;
+
+    let before = history.current().clone();
+    let label = history
+        .redo()
+        .ok_or_else(|| coded(ErrorCode::NothingToUndo, "Nothing to redo"))?;
+    tracing::info!("[history] redo '{}' on page {}", label, page_id);
+    Ok(history.snapshot(Some(&before))?)
 }
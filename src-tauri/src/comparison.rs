@@ -0,0 +1,261 @@
+// Before/after comparisons of a page for QC review and social previews
+// Stills place the pages side by side and use the normal export encoder; animations alternate them.
+
+use anyhow::{Context, anyhow};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::codecs::webp::WebPEncoder;
+use image::imageops::{self, FilterType};
+use image::{Delay, DynamicImage, ExtendedColorType, Frame, ImageEncoder, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use crate::image_io::{self, ExportOptions, OutputFormat, SourceMetadata};
+
+const GAP_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const GIF_SPEED: i32 = 10; // Palette quantization speed, 1 (best) to 30 (fastest)
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ComparisonMode {
+    SideBySide,
+    AnimatedGif,
+    AnimatedWebp, // Lossless frames
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ComparisonOptions {
+    pub mode: ComparisonMode,
+    pub format: OutputFormat, // Still image format for side by side
+    pub gap: u32,             // Pixels between the pages
+    pub max_height: Option<u32>,
+    pub frame_ms: u32, // How long each page is shown in animations
+}
+
+impl Default for ComparisonOptions {
+    fn default() -> Self {
+        ComparisonOptions {
+            mode: ComparisonMode::SideBySide,
+            format: OutputFormat::Png,
+            gap: 16,
+            max_height: None,
+            frame_ms: 1500,
+        }
+    }
+}
+
+/// Both pages at the original's size, then scaled down to `max_height` if needed
+///
+/// Exports may have been downscaled, so the translated page is matched to the original.
+fn prepare(
+    original: &DynamicImage,
+    translated: &DynamicImage,
+    max_height: Option<u32>,
+) -> (RgbaImage, RgbaImage) {
+    let (mut width, mut height) = (original.width(), original.height());
+    if let Some(max) = max_height.filter(|max| *max > 0 && *max < height) {
+        width = ((width as u64 * max as u64) / height as u64).max(1) as u32;
+        height = max;
+    }
+
+    let fit = |image: &DynamicImage| {
+        if image.width() == width && image.height() == height {
+            image.to_rgba8()
+        } else {
+            imageops::resize(&image.to_rgba8(), width, height, FilterType::Lanczos3)
+        }
+    };
+    (fit(original), fit(translated))
+}
+
+/// Original on the left, translation on the right
+pub fn side_by_side(original: &RgbaImage, translated: &RgbaImage, gap: u32) -> RgbaImage {
+    let width = original.width() + gap + translated.width();
+    let height = original.height().max(translated.height());
+    let mut canvas = RgbaImage::from_pixel(width, height, GAP_COLOR);
+    imageops::overlay(&mut canvas, original, 0, 0);
+    imageops::overlay(&mut canvas, translated, (original.width() + gap) as i64, 0);
+    canvas
+}
+
+/// Encode a comparison of the two pages according to `options`
+pub fn encode(
+    original: &DynamicImage,
+    translated: &DynamicImage,
+    options: &ComparisonOptions,
+) -> anyhow::Result<Vec<u8>> {
+    let (original, translated) = prepare(original, translated, options.max_height);
+
+    match options.mode {
+        ComparisonMode::SideBySide => {
+            let canvas = side_by_side(&original, &translated, options.gap);
+            let export = ExportOptions {
+                format: options.format,
+                ..ExportOptions::default()
+            };
+            image_io::encode(
+                &DynamicImage::ImageRgba8(canvas),
+                &export,
+                &SourceMetadata::default(),
+            )
+        }
+        ComparisonMode::AnimatedGif => encode_gif(&[original, translated], options.frame_ms),
+        ComparisonMode::AnimatedWebp => {
+            encode_animated_webp(&[original, translated], options.frame_ms)
+        }
+    }
+}
+
+fn encode_gif(frames: &[RgbaImage], frame_ms: u32) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = GifEncoder::new_with_speed(&mut bytes, GIF_SPEED);
+        encoder.set_repeat(Repeat::Infinite)?;
+        let delay = Delay::from_numer_denom_ms(frame_ms, 1);
+        encoder
+            .encode_frames(
+                frames
+                    .iter()
+                    .map(|frame| Frame::from_parts(frame.clone(), 0, 0, delay)),
+            )
+            .context("Failed to encode GIF")?;
+    }
+    Ok(bytes)
+}
+
+/// Animated WebP built from lossless VP8L frames, since the image crate only writes stills
+fn encode_animated_webp(frames: &[RgbaImage], frame_ms: u32) -> anyhow::Result<Vec<u8>> {
+    let (width, height) = frames
+        .first()
+        .map(|f| f.dimensions())
+        .ok_or_else(|| anyhow!("No frames to encode"))?;
+
+    // VP8X: animation + alpha flags, canvas size
+    let mut vp8x = vec![0x12, 0, 0, 0];
+    vp8x.extend_from_slice(&u24(width - 1));
+    vp8x.extend_from_slice(&u24(height - 1));
+
+    // ANIM: white background, loop forever
+    let mut anim = vec![0xff, 0xff, 0xff, 0xff];
+    anim.extend_from_slice(&0u16.to_le_bytes());
+
+    let mut body = b"WEBP".to_vec();
+    body.extend(riff_chunk(b"VP8X", &vp8x));
+    body.extend(riff_chunk(b"ANIM", &anim));
+
+    for frame in frames {
+        let mut still = Vec::new();
+        WebPEncoder::new_lossless(&mut still)
+            .write_image(
+                frame.as_raw(),
+                frame.width(),
+                frame.height(),
+                ExtendedColorType::Rgba8,
+            )
+            .context("Failed to encode WebP frame")?;
+        let vp8l = find_riff_chunk(&still, b"VP8L")
+            .ok_or_else(|| anyhow!("WebP encoder did not produce a VP8L chunk"))?;
+
+        // ANMF: offset 0,0, frame size, duration, no blending
+        let mut anmf = Vec::with_capacity(vp8l.len() + 24);
+        anmf.extend_from_slice(&u24(0));
+        anmf.extend_from_slice(&u24(0));
+        anmf.extend_from_slice(&u24(frame.width() - 1));
+        anmf.extend_from_slice(&u24(frame.height() - 1));
+        anmf.extend_from_slice(&u24(frame_ms.min(0xff_ffff)));
+        anmf.push(0x02);
+        anmf.extend(riff_chunk(b"VP8L", vp8l));
+        body.extend(riff_chunk(b"ANMF", &anmf));
+    }
+
+    let mut bytes = b"RIFF".to_vec();
+    bytes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    bytes.extend(body);
+    Ok(bytes)
+}
+
+fn u24(value: u32) -> [u8; 3] {
+    let bytes = value.to_le_bytes();
+    [bytes[0], bytes[1], bytes[2]]
+}
+
+fn riff_chunk(fourcc: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(data.len() + 9);
+    chunk.extend_from_slice(fourcc);
+    chunk.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        chunk.push(0);
+    }
+    chunk
+}
+
+/// Payload of the first `fourcc` chunk in a RIFF/WEBP file
+fn find_riff_chunk<'a>(bytes: &'a [u8], fourcc: &[u8; 4]) -> Option<&'a [u8]> {
+    let mut offset = 12; // "RIFF", size, "WEBP"
+    while offset + 8 <= bytes.len() {
+        let size = u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().ok()?) as usize;
+        let data = bytes.get(offset + 8..offset + 8 + size)?;
+        if &bytes[offset..offset + 4] == fourcc {
+            return Some(data);
+        }
+        offset += 8 + size + size % 2;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::AnimationDecoder;
+    use image::codecs::gif::GifDecoder;
+    use image::codecs::webp::WebPDecoder;
+    use std::io::Cursor;
+
+    fn page(width: u32, height: u32, value: u8) -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+            width,
+            height,
+            Rgba([value, value, value, 255]),
+        ))
+    }
+
+    #[test]
+    fn test_side_by_side_matches_translated_to_original() {
+        // Translated export was downscaled to half size
+        let (original, translated) = prepare(&page(40, 20, 0), &page(20, 10, 200), None);
+        let canvas = side_by_side(&original, &translated, 4);
+        assert_eq!(canvas.dimensions(), (84, 20));
+        assert_eq!(canvas.get_pixel(0, 0)[0], 0);
+        assert_eq!(canvas.get_pixel(42, 10), &GAP_COLOR);
+        assert_eq!(canvas.get_pixel(83, 19)[0], 200);
+
+        let (original, _) = prepare(&page(40, 20, 0), &page(40, 20, 0), Some(10));
+        assert_eq!(original.dimensions(), (20, 10));
+    }
+
+    #[test]
+    fn test_animations_have_two_frames() {
+        let options = ComparisonOptions {
+            mode: ComparisonMode::AnimatedGif,
+            ..ComparisonOptions::default()
+        };
+        let gif = encode(&page(8, 8, 0), &page(8, 8, 255), &options).unwrap();
+        let frames = GifDecoder::new(Cursor::new(gif))
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+
+        let options = ComparisonOptions {
+            mode: ComparisonMode::AnimatedWebp,
+            ..options
+        };
+        let webp = encode(&page(8, 8, 0), &page(8, 8, 255), &options).unwrap();
+        let decoder = WebPDecoder::new(Cursor::new(webp)).unwrap();
+        assert!(decoder.has_animation());
+        let frames = decoder.into_frames().collect_frames().unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].buffer().get_pixel(3, 3)[0], 255);
+    }
+}
@@ -0,0 +1,266 @@
+// Opt-in crash reports: a panic hook writes a JSON report to app_data_dir/crashes
+// Panics are always logged; the report (backtrace, GPU init, recent logs, model revisions)
+// is only written when enabled in settings. The frontend offers to copy it on the next launch.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::logging;
+use crate::state::GpuInitResult;
+
+pub const CRASH_DIR: &str = "crashes";
+const SEEN_DIR: &str = "seen";
+const REPORT_LOG_LINES: usize = 200;
+const SEEN_REPORTS_KEPT: usize = 10;
+
+/// Hugging Face repos the bundled models are downloaded from
+const MODEL_REPOS: [&str; 3] = [
+    "mayocream/comic-text-detector-onnx",
+    "mayocream/lama-manga-onnx",
+    "mayocream/manga-ocr-onnx",
+];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static REPORT_DIR: OnceLock<PathBuf> = OnceLock::new();
+static GPU: Mutex<Option<GpuInitResult>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelVersion {
+    pub repo: String,
+    pub revision: Option<String>, // Cached snapshot commit; None if not downloaded
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    pub created_at: u64, // Unix seconds
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub thread: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub backtrace: String,
+    pub gpu: Option<GpuInitResult>,
+    pub models: Vec<ModelVersion>,
+    pub recent_logs: Vec<String>,
+}
+
+/// Install the panic hook; reports go to `dir` once `set_enabled(true)` is called
+pub fn install(dir: Option<PathBuf>) {
+    if let Some(dir) = dir {
+        let _ = REPORT_DIR.set(dir);
+    }
+
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let message = match info.payload().downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => info
+                .payload()
+                .downcast_ref::<String>()
+                .cloned()
+                .unwrap_or_else(|| "Box<dyn Any>".to_string()),
+        };
+        let location = info.location().map(|l| l.to_string());
+        let thread = std::thread::current()
+            .name()
+            .unwrap_or("<unnamed>")
+            .to_string();
+
+        // Panics in spawned tasks otherwise only reach stderr, which GUI builds don't have
+        tracing::error!(
+            "[crash] thread '{}' panicked at {}: {}",
+            thread,
+            location.as_deref().unwrap_or("<unknown>"),
+            message
+        );
+
+        if let (true, Some(dir)) = (ENABLED.load(Ordering::Relaxed), REPORT_DIR.get()) {
+            let report = CrashReport {
+                created_at: unix_now(),
+                app_version: env!("CARGO_PKG_VERSION").to_string(),
+                os: std::env::consts::OS.to_string(),
+                arch: std::env::consts::ARCH.to_string(),
+                thread,
+                message,
+                location,
+                backtrace: Backtrace::force_capture().to_string(),
+                gpu: GPU.lock().ok().and_then(|gpu| gpu.clone()),
+                models: model_versions(),
+                recent_logs: logging::recent_lines(REPORT_LOG_LINES),
+            };
+            match write_report(dir, &report) {
+                Ok(path) => eprintln!("Crash report written to {:?}", path),
+                Err(err) => eprintln!("Failed to write crash report: {:#}", err),
+            }
+        }
+
+        previous(info);
+    }));
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Remember the GPU init result so it can be included in reports
+pub fn set_gpu(gpu: &GpuInitResult) {
+    if let Ok(mut slot) = GPU.lock() {
+        *slot = Some(gpu.clone());
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn write_report(dir: &Path, report: &CrashReport) -> anyhow::Result<PathBuf> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    let path = dir.join(format!("crash-{}.json", report.created_at));
+    let json = serde_json::to_string_pretty(report)?;
+    fs::write(&path, json).with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(path)
+}
+
+/// Revisions of the cached model snapshots, read from the Hugging Face cache refs
+fn model_versions() -> Vec<ModelVersion> {
+    let hub = std::env::var_os("HF_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cache").join("huggingface")))
+        .map(|dir| dir.join("hub"));
+
+    MODEL_REPOS
+        .iter()
+        .map(|repo| ModelVersion {
+            repo: repo.to_string(),
+            revision: hub.as_ref().and_then(|hub| {
+                let dir = hub.join(format!("models--{}", repo.replace('/', "--")));
+                fs::read_to_string(dir.join("refs").join("main"))
+                    .ok()
+                    .map(|rev| rev.trim().to_string())
+            }),
+        })
+        .collect()
+}
+
+/// Reports not yet shown to the user, newest first
+fn pending_reports(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut reports: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    reports.sort();
+    reports.reverse();
+    reports
+}
+
+fn latest_in(dir: &Path) -> anyhow::Result<Option<CrashReport>> {
+    let Some(path) = pending_reports(dir).into_iter().next() else {
+        return Ok(None);
+    };
+    let contents =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+    let report = serde_json::from_str(&contents)
+        .with_context(|| format!("Invalid crash report {:?}", path))?;
+    Ok(Some(report))
+}
+
+/// Move pending reports into `seen/`, keeping only the most recent few
+fn dismiss_in(dir: &Path) -> anyhow::Result<usize> {
+    let pending = pending_reports(dir);
+    let seen = dir.join(SEEN_DIR);
+    if !pending.is_empty() {
+        fs::create_dir_all(&seen).with_context(|| format!("Failed to create {:?}", seen))?;
+    }
+    for path in &pending {
+        if let Some(name) = path.file_name() {
+            fs::rename(path, seen.join(name))
+                .with_context(|| format!("Failed to move {:?}", path))?;
+        }
+    }
+
+    for old in pending_reports(&seen).into_iter().skip(SEEN_REPORTS_KEPT) {
+        let _ = fs::remove_file(old);
+    }
+    Ok(pending.len())
+}
+
+/// Newest report from a previous run that the user hasn't dismissed
+pub fn latest_pending() -> anyhow::Result<Option<CrashReport>> {
+    match REPORT_DIR.get() {
+        Some(dir) => latest_in(dir),
+        None => Ok(None),
+    }
+}
+
+/// Mark all pending reports as seen; returns how many there were
+pub fn dismiss_pending() -> anyhow::Result<usize> {
+    match REPORT_DIR.get() {
+        Some(dir) => dismiss_in(dir),
+        None => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(created_at: u64) -> CrashReport {
+        CrashReport {
+            created_at,
+            app_version: "0.0.0".to_string(),
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            thread: "main".to_string(),
+            message: format!("boom {}", created_at),
+            location: Some("src/lib.rs:1:1".to_string()),
+            backtrace: String::new(),
+            gpu: None,
+            models: Vec::new(),
+            recent_logs: vec!["INFO started".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_latest_pending_report_until_dismissed() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(latest_in(dir.path()).unwrap().is_none());
+
+        write_report(dir.path(), &report(100)).unwrap();
+        write_report(dir.path(), &report(200)).unwrap();
+        let latest = latest_in(dir.path()).unwrap().unwrap();
+        assert_eq!(latest.message, "boom 200");
+
+        assert_eq!(dismiss_in(dir.path()).unwrap(), 2);
+        assert!(latest_in(dir.path()).unwrap().is_none());
+        assert_eq!(pending_reports(&dir.path().join(SEEN_DIR)).len(), 2);
+    }
+
+    #[test]
+    fn test_dismiss_prunes_old_seen_reports() {
+        let dir = tempfile::tempdir().unwrap();
+        for created_at in 0..(SEEN_REPORTS_KEPT as u64 + 3) {
+            write_report(dir.path(), &report(1000 + created_at)).unwrap();
+        }
+        dismiss_in(dir.path()).unwrap();
+        assert_eq!(
+            pending_reports(&dir.path().join(SEEN_DIR)).len(),
+            SEEN_REPORTS_KEPT
+        );
+    }
+}
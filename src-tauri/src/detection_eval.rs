@@ -0,0 +1,457 @@
+// Detector accuracy on a labelled dataset: IoU matching, precision/recall, and mAP@IoU
+// Ground truth is read from the files `export_annotations` writes: `<name>.coco.json`, or
+// labelme `<name>.json`, next to each page image.
+
+use anyhow::{Context, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::annotations::CATEGORIES;
+use crate::batch;
+use crate::image_io;
+use crate::ocr_eval::SkippedSample;
+use crate::pipeline::{self, PageBlock};
+use crate::state::AppState;
+
+pub const DEFAULT_IOU_THRESHOLD: f32 = 0.5;
+
+/// Labelled box; `class` follows the detector (0 = dark text, 1 = light text)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GroundTruthBox {
+    pub xmin: f32,
+    pub ymin: f32,
+    pub xmax: f32,
+    pub ymax: f32,
+    pub class: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageDetectionResult {
+    pub file: String,
+    pub ground_truth: usize,
+    pub detections: usize,
+    pub true_positives: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassDetectionResult {
+    pub class: usize,
+    pub name: String,
+    pub ground_truth: usize,
+    pub detections: usize,
+    pub true_positives: usize,
+    pub average_precision: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectionEvaluation {
+    pub samples: Vec<PageDetectionResult>,
+    pub classes: Vec<ClassDetectionResult>,
+    pub skipped: Vec<SkippedSample>,
+    pub precision: f64,
+    pub recall: f64,
+    pub mean_average_precision: f64, // Over classes that have ground truth
+    pub iou_threshold: f32,
+    pub confidence_threshold: f32,
+    pub nms_threshold: f32,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Deserialize)]
+struct CocoFile {
+    annotations: Vec<CocoAnnotation>,
+}
+
+#[derive(Deserialize)]
+struct CocoAnnotation {
+    bbox: [f32; 4], // x, y, width, height
+    category_id: usize,
+}
+
+#[derive(Deserialize)]
+struct LabelmeFile {
+    shapes: Vec<LabelmeShape>,
+}
+
+#[derive(Deserialize)]
+struct LabelmeShape {
+    label: String,
+    points: Vec<[f32; 2]>,
+}
+
+fn class_name(class: usize) -> &'static str {
+    CATEGORIES[class.min(CATEGORIES.len() - 1)]
+}
+
+fn parse_coco(json: &str) -> anyhow::Result<Vec<GroundTruthBox>> {
+    let file: CocoFile = serde_json::from_str(json)?;
+    Ok(file
+        .annotations
+        .into_iter()
+        .map(|a| {
+            let [x, y, w, h] = a.bbox;
+            GroundTruthBox {
+                xmin: x,
+                ymin: y,
+                xmax: x + w,
+                ymax: y + h,
+                class: a.category_id.saturating_sub(1),
+            }
+        })
+        .collect())
+}
+
+/// Rectangles and masks use their two corner points, polygons their bounding box
+fn parse_labelme(json: &str) -> anyhow::Result<Vec<GroundTruthBox>> {
+    let file: LabelmeFile = serde_json::from_str(json)?;
+    Ok(file
+        .shapes
+        .into_iter()
+        .filter(|shape| !shape.points.is_empty())
+        .map(|shape| {
+            let xs = shape.points.iter().map(|p| p[0]);
+            let ys = shape.points.iter().map(|p| p[1]);
+            GroundTruthBox {
+                xmin: xs.clone().fold(f32::INFINITY, f32::min),
+                ymin: ys.clone().fold(f32::INFINITY, f32::min),
+                xmax: xs.fold(f32::NEG_INFINITY, f32::max),
+                ymax: ys.fold(f32::NEG_INFINITY, f32::max),
+                class: CATEGORIES
+                    .iter()
+                    .position(|name| *name == shape.label)
+                    .unwrap_or(0),
+            }
+        })
+        .collect())
+}
+
+/// Ground truth for `image`, or None when it has no annotation file
+fn ground_truth_for(image: &Path) -> anyhow::Result<Option<Vec<GroundTruthBox>>> {
+    let stem = image
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let coco = image.with_file_name(format!("{}.coco.json", stem));
+    let labelme = image.with_extension("json");
+    let (path, parse): (_, fn(&str) -> anyhow::Result<Vec<GroundTruthBox>>) = if coco.is_file() {
+        (coco, parse_coco)
+    } else if labelme.is_file() {
+        (labelme, parse_labelme)
+    } else {
+        return Ok(None);
+    };
+    let json = fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+    parse(&json)
+        .with_context(|| format!("Invalid annotations in {:?}", path))
+        .map(Some)
+}
+
+fn iou(a: &PageBlock, b: &GroundTruthBox) -> f32 {
+    let width = (a.xmax.min(b.xmax) - a.xmin.max(b.xmin)).max(0.0);
+    let height = (a.ymax.min(b.ymax) - a.ymin.max(b.ymin)).max(0.0);
+    let intersection = width * height;
+    let union = (a.xmax - a.xmin) * (a.ymax - a.ymin) + (b.xmax - b.xmin) * (b.ymax - b.ymin)
+        - intersection;
+    if union > 0.0 {
+        intersection / union
+    } else {
+        0.0
+    }
+}
+
+/// (class, confidence, matched) for every detection on a page
+///
+/// Detections are matched most confident first, each to the unmatched ground truth box of
+/// the same class it overlaps most, if that overlap reaches `iou_threshold`.
+fn match_page(
+    detections: &[PageBlock],
+    truth: &[GroundTruthBox],
+    iou_threshold: f32,
+) -> Vec<(usize, f32, bool)> {
+    let mut order: Vec<usize> = (0..detections.len()).collect();
+    order.sort_by(|&a, &b| {
+        detections[b]
+            .confidence
+            .total_cmp(&detections[a].confidence)
+    });
+    let mut taken = vec![false; truth.len()];
+    order
+        .into_iter()
+        .map(|index| {
+            let detection = &detections[index];
+            let best = truth
+                .iter()
+                .enumerate()
+                .filter(|(i, gt)| !taken[*i] && gt.class == detection.class)
+                .map(|(i, gt)| (i, iou(detection, gt)))
+                .filter(|(_, overlap)| *overlap >= iou_threshold)
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((i, _)) = best {
+                taken[i] = true;
+            }
+            (detection.class, detection.confidence, best.is_some())
+        })
+        .collect()
+}
+
+/// Area under the precision/recall curve, with precision made monotonically decreasing
+/// (all-point interpolation, as in Pascal VOC 2010+)
+fn average_precision(mut matches: Vec<(f32, bool)>, ground_truth: usize) -> f64 {
+    if ground_truth == 0 {
+        return 0.0;
+    }
+    matches.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let mut true_positives = 0usize;
+    let mut points: Vec<(f64, f64)> = Vec::with_capacity(matches.len()); // (recall, precision)
+    for (rank, (_, matched)) in matches.iter().enumerate() {
+        if *matched {
+            true_positives += 1;
+        }
+        points.push((
+            true_positives as f64 / ground_truth as f64,
+            true_positives as f64 / (rank + 1) as f64,
+        ));
+    }
+    for i in (0..points.len().saturating_sub(1)).rev() {
+        points[i].1 = points[i].1.max(points[i + 1].1);
+    }
+    let mut area = 0.0;
+    let mut previous_recall = 0.0;
+    for (recall, precision) in points {
+        area += (recall - previous_recall) * precision;
+        previous_recall = recall;
+    }
+    area
+}
+
+/// A page's labels next to what the detector found on it
+struct ScoredPage {
+    file: String,
+    truth: Vec<GroundTruthBox>,
+    detections: Vec<PageBlock>,
+}
+
+fn summarize(
+    pages: &[ScoredPage],
+    iou_threshold: f32,
+) -> (Vec<PageDetectionResult>, Vec<ClassDetectionResult>) {
+    let mut samples = Vec::with_capacity(pages.len());
+    let mut per_class: Vec<(usize, Vec<(f32, bool)>)> = vec![(0, Vec::new()); CATEGORIES.len()];
+    for page in pages {
+        let matches = match_page(&page.detections, &page.truth, iou_threshold);
+        samples.push(PageDetectionResult {
+            file: page.file.clone(),
+            ground_truth: page.truth.len(),
+            detections: page.detections.len(),
+            true_positives: matches.iter().filter(|m| m.2).count(),
+        });
+        for gt in &page.truth {
+            per_class[gt.class.min(CATEGORIES.len() - 1)].0 += 1;
+        }
+        for (class, confidence, matched) in matches {
+            per_class[class.min(CATEGORIES.len() - 1)]
+                .1
+                .push((confidence, matched));
+        }
+    }
+    let classes = per_class
+        .into_iter()
+        .enumerate()
+        .map(|(class, (ground_truth, matches))| ClassDetectionResult {
+            class,
+            name: class_name(class).to_string(),
+            ground_truth,
+            detections: matches.len(),
+            true_positives: matches.iter().filter(|m| m.1).count(),
+            average_precision: average_precision(matches, ground_truth),
+        })
+        .collect();
+    (samples, classes)
+}
+
+fn report(
+    pages: &[ScoredPage],
+    skipped: Vec<SkippedSample>,
+    thresholds: (f32, f32, f32),
+    elapsed_ms: u64,
+) -> DetectionEvaluation {
+    let (confidence_threshold, nms_threshold, iou_threshold) = thresholds;
+    let (samples, classes) = summarize(pages, iou_threshold);
+    let ratio = |a: usize, b: usize| if b > 0 { a as f64 / b as f64 } else { 0.0 };
+    let true_positives = samples.iter().map(|s| s.true_positives).sum();
+    let detections = samples.iter().map(|s| s.detections).sum();
+    let ground_truth = samples.iter().map(|s| s.ground_truth).sum();
+    let labelled: Vec<&ClassDetectionResult> =
+        classes.iter().filter(|c| c.ground_truth > 0).collect();
+    let mean_average_precision = match labelled.len() {
+        0 => 0.0,
+        n => labelled.iter().map(|c| c.average_precision).sum::<f64>() / n as f64,
+    };
+    DetectionEvaluation {
+        precision: ratio(true_positives, detections),
+        recall: ratio(true_positives, ground_truth),
+        mean_average_precision,
+        samples,
+        classes,
+        skipped,
+        iou_threshold,
+        confidence_threshold,
+        nms_threshold,
+        elapsed_ms,
+    }
+}
+
+/// Run the detector over every annotated image in `dir` and score it against the labels
+pub async fn evaluate(
+    state: &AppState,
+    dir: &Path,
+    confidence_threshold: f32,
+    nms_threshold: f32,
+    iou_threshold: f32,
+) -> anyhow::Result<DetectionEvaluation> {
+    if !dir.is_dir() {
+        return Err(anyhow!("Dataset directory does not exist: {:?}", dir));
+    }
+    let start = Instant::now();
+    let mut pages = Vec::new();
+    let mut skipped = Vec::new();
+    for path in batch::list_images(dir)? {
+        let file = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let truth = match ground_truth_for(&path) {
+            Ok(Some(truth)) => truth,
+            Ok(None) => {
+                skipped.push(SkippedSample {
+                    file,
+                    reason: "no .coco.json or labelme .json annotations".to_string(),
+                });
+                continue;
+            }
+            Err(err) => {
+                skipped.push(SkippedSample {
+                    file,
+                    reason: format!("{:#}", err),
+                });
+                continue;
+            }
+        };
+        let detected = match image_io::open(&path) {
+            Ok(image) => {
+                pipeline::detect_blocks(state, &image, confidence_threshold, nms_threshold).await
+            }
+            Err(err) => Err(err),
+        };
+        match detected {
+            Ok((detections, _)) => pages.push(ScoredPage {
+                file,
+                truth,
+                detections,
+            }),
+            Err(err) => skipped.push(SkippedSample {
+                file,
+                reason: format!("{:#}", err),
+            }),
+        }
+    }
+
+    let evaluation = report(
+        &pages,
+        skipped,
+        (confidence_threshold, nms_threshold, iou_threshold),
+        start.elapsed().as_millis() as u64,
+    );
+    tracing::info!(
+        "[detection-eval] mAP@{} {:.3}, precision {:.3}, recall {:.3} over {} page(s), {} skipped",
+        iou_threshold,
+        evaluation.mean_average_precision,
+        evaluation.precision,
+        evaluation.recall,
+        evaluation.samples.len(),
+        evaluation.skipped.len()
+    );
+    Ok(evaluation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gt(xmin: f32, ymin: f32, xmax: f32, ymax: f32) -> GroundTruthBox {
+        GroundTruthBox {
+            xmin,
+            ymin,
+            xmax,
+            ymax,
+            class: 0,
+        }
+    }
+
+    fn detection(xmin: f32, ymin: f32, xmax: f32, ymax: f32, confidence: f32) -> PageBlock {
+        PageBlock::from_detection(&comic_text_detector::ClassifiedBbox {
+            xmin,
+            ymin,
+            xmax,
+            ymax,
+            confidence,
+            class: 0,
+        })
+    }
+
+    #[test]
+    fn test_ground_truth_parses_exported_formats() {
+        let coco = r#"{"annotations": [{"bbox": [10, 20, 30, 40], "category_id": 2}]}"#;
+        assert_eq!(
+            parse_coco(coco).unwrap(),
+            vec![GroundTruthBox {
+                xmin: 10.0,
+                ymin: 20.0,
+                xmax: 40.0,
+                ymax: 60.0,
+                class: 1,
+            }]
+        );
+        let labelme = r#"{"shapes": [{"label": "text-light", "points": [[40, 60], [10, 20]]}]}"#;
+        assert_eq!(parse_labelme(labelme).unwrap(), parse_coco(coco).unwrap());
+    }
+
+    #[test]
+    fn test_each_ground_truth_box_matches_once() {
+        let truth = [gt(0.0, 0.0, 10.0, 10.0)];
+        let detections = [
+            detection(0.0, 0.0, 10.0, 9.0, 0.6),
+            detection(0.0, 0.0, 10.0, 10.0, 0.9),
+            detection(50.0, 50.0, 60.0, 60.0, 0.8),
+        ];
+        let matches = match_page(&detections, &truth, 0.5);
+        assert_eq!(
+            matches,
+            vec![(0, 0.9, true), (0, 0.8, false), (0, 0.6, false)]
+        );
+    }
+
+    #[test]
+    fn test_average_precision_and_report() {
+        // Ranked hit, miss, hit against 3 boxes: precision 1 up to recall 1/3, then 2/3 up to 2/3
+        let ap = average_precision(vec![(0.9, true), (0.8, false), (0.7, true)], 3);
+        assert!((ap - (1.0 / 3.0 + 2.0 / 9.0)).abs() < 1e-9);
+
+        let pages = [ScoredPage {
+            file: "p1.png".to_string(),
+            truth: vec![gt(0.0, 0.0, 10.0, 10.0), gt(20.0, 0.0, 30.0, 10.0)],
+            detections: vec![detection(0.0, 0.0, 10.0, 10.0, 0.9)],
+        }];
+        let evaluation = report(&pages, Vec::new(), (0.5, 0.4, 0.5), 0);
+        assert_eq!(evaluation.precision, 1.0);
+        assert_eq!(evaluation.recall, 0.5);
+        // Only the dark-text class has labels, so it alone makes up the mean
+        assert_eq!(evaluation.mean_average_precision, 0.5);
+        assert_eq!(evaluation.classes[1].ground_truth, 0);
+    }
+}
@@ -1,5 +1,149 @@
 // refer: https://crates.io/crates/anyhow-tauri
+// Errors reach the frontend as { code, message, detail }: `code` is stable for UI logic,
+// `message` comes from the locales/*/errors.ftl catalog in the UI language, and `detail` is
+// the raw English anyhow chain for logs and bug reports.
 use serde::Serialize;
+use serde::ser::SerializeStruct;
+
+use crate::i18n;
+
+/// Stable error identifiers; each one has an `error-<code>` message in every catalog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    Unknown,
+    CudaUnavailable,
+    DirectmlUnavailable,
+    OcrEngineUnavailable,
+    ApiKeyMissing,
+    ApiKeyInvalid,
+    QuotaExceeded,
+    RateLimited,
+    Network,
+    ProviderUnavailable, // A local service such as Ollama is not running
+    PluginFailed,
+    FileNotFound,
+    PermissionDenied,
+    UnsupportedFormat,
+    NothingToUndo,
+    OutsideExportRoots,
+    ModelMissing,    // A model failed to download or load
+    InvalidInput,    // Malformed image or JSON given to a command
+    InferenceFailed, // ONNX Runtime failed while running a model
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 19] = [
+        ErrorCode::Unknown,
+        ErrorCode::CudaUnavailable,
+        ErrorCode::DirectmlUnavailable,
+        ErrorCode::OcrEngineUnavailable,
+        ErrorCode::ApiKeyMissing,
+        ErrorCode::ApiKeyInvalid,
+        ErrorCode::QuotaExceeded,
+        ErrorCode::RateLimited,
+        ErrorCode::Network,
+        ErrorCode::ProviderUnavailable,
+        ErrorCode::PluginFailed,
+        ErrorCode::FileNotFound,
+        ErrorCode::PermissionDenied,
+        ErrorCode::UnsupportedFormat,
+        ErrorCode::NothingToUndo,
+        ErrorCode::OutsideExportRoots,
+        ErrorCode::ModelMissing,
+        ErrorCode::InvalidInput,
+        ErrorCode::InferenceFailed,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::Unknown => "unknown",
+            ErrorCode::CudaUnavailable => "cuda-unavailable",
+            ErrorCode::DirectmlUnavailable => "directml-unavailable",
+            ErrorCode::OcrEngineUnavailable => "ocr-engine-unavailable",
+            ErrorCode::ApiKeyMissing => "api-key-missing",
+            ErrorCode::ApiKeyInvalid => "api-key-invalid",
+            ErrorCode::QuotaExceeded => "quota-exceeded",
+            ErrorCode::RateLimited => "rate-limited",
+            ErrorCode::Network => "network",
+            ErrorCode::ProviderUnavailable => "provider-unavailable",
+            ErrorCode::PluginFailed => "plugin-failed",
+            ErrorCode::FileNotFound => "file-not-found",
+            ErrorCode::PermissionDenied => "permission-denied",
+            ErrorCode::UnsupportedFormat => "unsupported-format",
+            ErrorCode::NothingToUndo => "nothing-to-undo",
+            ErrorCode::OutsideExportRoots => "outside-export-roots",
+            ErrorCode::ModelMissing => "model-missing",
+            ErrorCode::InvalidInput => "invalid-input",
+            ErrorCode::InferenceFailed => "inference-failed",
+        }
+    }
+}
+
+/// Root error carrying a code; context added on top keeps the code reachable
+#[derive(Debug)]
+pub struct CodedError {
+    pub code: ErrorCode,
+    message: String,
+}
+
+impl std::fmt::Display for CodedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CodedError {}
+
+/// An error with `code`, keeping `message` as the English detail
+pub fn coded(code: ErrorCode, message: impl Into<String>) -> anyhow::Error {
+    CodedError {
+        code,
+        message: message.into(),
+    }
+    .into()
+}
+
+/// Tag an existing error with `code`; its chain stays in the detail
+pub fn with_code(
+    error: anyhow::Error,
+    code: ErrorCode,
+    message: impl Into<String>,
+) -> anyhow::Error {
+    error.context(CodedError {
+        code,
+        message: message.into(),
+    })
+}
+
+/// Code for an error chain: an explicit CodedError, else well-known error types, else Unknown
+pub fn error_code(error: &anyhow::Error) -> ErrorCode {
+    if let Some(coded) = error.downcast_ref::<CodedError>() {
+        return coded.code;
+    }
+    for cause in error.chain() {
+        if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+            match io.kind() {
+                std::io::ErrorKind::NotFound => return ErrorCode::FileNotFound,
+                std::io::ErrorKind::PermissionDenied => return ErrorCode::PermissionDenied,
+                _ => {}
+            }
+        }
+        if let Some(http) = cause.downcast_ref::<reqwest::Error>() {
+            if http.is_connect() || http.is_timeout() {
+                return ErrorCode::Network;
+            }
+        }
+        match cause.downcast_ref::<image::ImageError>() {
+            Some(image::ImageError::Unsupported(_)) => return ErrorCode::UnsupportedFormat,
+            Some(image::ImageError::Decoding(_)) => return ErrorCode::InvalidInput,
+            _ => {}
+        }
+        if cause.downcast_ref::<ort::Error>().is_some() {
+            return ErrorCode::InferenceFailed;
+        }
+    }
+    ErrorCode::Unknown
+}
 
 #[derive(Debug)]
 pub struct CommandError(pub anyhow::Error);
@@ -17,7 +161,13 @@ impl Serialize for CommandError {
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&format!("{:#}", self.0))
+        let code = error_code(&self.0);
+        let detail = format!("{:#}", self.0);
+        let mut error = serializer.serialize_struct("CommandError", 3)?;
+        error.serialize_field("code", code.as_str())?;
+        error.serialize_field("message", &i18n::error_message(code, &detail))?;
+        error.serialize_field("detail", &detail)?;
+        error.end()
     }
 }
 
@@ -28,3 +178,46 @@ impl From<anyhow::Error> for CommandError {
 }
 
 pub type CommandResult<T> = std::result::Result<T, CommandError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_code_survives_context_and_falls_back_to_known_types() {
+        let quota = Err::<(), _>(coded(ErrorCode::QuotaExceeded, "DeepL 456"))
+            .context("Translation failed")
+            .unwrap_err();
+        assert_eq!(error_code(&quota), ErrorCode::QuotaExceeded);
+
+        let missing = Err::<(), _>(std::io::Error::from(std::io::ErrorKind::NotFound))
+            .context("Failed to read page.png")
+            .unwrap_err();
+        assert_eq!(error_code(&missing), ErrorCode::FileNotFound);
+
+        assert_eq!(
+            error_code(&anyhow::anyhow!("something else")),
+            ErrorCode::Unknown
+        );
+
+        let model = with_code(
+            anyhow::anyhow!("connection reset"),
+            ErrorCode::ModelMissing,
+            "Failed to load the text detector",
+        )
+        .context("Startup failed");
+        assert_eq!(error_code(&model), ErrorCode::ModelMissing);
+        assert!(format!("{:#}", model).contains("connection reset"));
+    }
+
+    #[test]
+    fn test_serializes_code_message_and_detail() {
+        i18n::set_language("en");
+        let error = CommandError(coded(ErrorCode::NothingToUndo, "Nothing to undo"));
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["code"], "nothing-to-undo");
+        assert_eq!(json["detail"], "Nothing to undo");
+        assert!(!json["message"].as_str().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,180 @@
+// Trusted export roots: folders the user picked in a native dialog opened by the backend
+// Commands that write files refuse paths outside them, so the webview can't pick arbitrary
+// destinations. Kept in app_config_dir/export-roots.json rather than settings.json, since
+// update_settings accepts whatever the webview sends.
+
+use anyhow::Context;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tokio::sync::RwLock;
+
+use crate::error::{ErrorCode, coded};
+
+pub const ROOTS_FILE: &str = "export-roots.json";
+
+#[derive(Debug)]
+pub struct ExportScope {
+    path: PathBuf,
+    roots: RwLock<Vec<PathBuf>>,
+}
+
+/// Absolute, symlink-free form of `target`, which may not exist yet
+///
+/// The deepest existing ancestor is canonicalized; the rest may only contain plain names.
+fn resolve(target: &Path) -> anyhow::Result<PathBuf> {
+    if !target.is_absolute() {
+        return Err(coded(
+            ErrorCode::OutsideExportRoots,
+            format!("Export path {:?} must be absolute", target),
+        ));
+    }
+
+    let mut existing = target;
+    let mut rest = Vec::new();
+    while !existing.exists() {
+        let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+            break;
+        };
+        rest.push(name);
+        existing = parent;
+    }
+    let mut resolved = existing
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {:?}", existing))?;
+
+    let remainder: PathBuf = rest.iter().rev().collect();
+    if remainder
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return Err(coded(
+            ErrorCode::OutsideExportRoots,
+            format!("Export path {:?} is not a plain path", target),
+        ));
+    }
+    resolved.push(remainder);
+    Ok(resolved)
+}
+
+impl ExportScope {
+    /// Roots saved in `config_dir`; a missing or unreadable file means no roots
+    pub fn load(config_dir: &Path) -> Self {
+        let path = config_dir.join(ROOTS_FILE);
+        let roots = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                tracing::warn!("[export] ignoring invalid {:?}: {}", path, err);
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        ExportScope {
+            path,
+            roots: RwLock::new(roots),
+        }
+    }
+
+    pub async fn roots(&self) -> Vec<PathBuf> {
+        self.roots.read().await.clone()
+    }
+
+    fn save(&self, roots: &[PathBuf]) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(roots)?;
+        fs::write(&self.path, json).with_context(|| format!("Failed to write {:?}", self.path))
+    }
+
+    /// Trust `dir`; only call with a folder the user picked in a native dialog
+    pub async fn add(&self, dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let dir = dir
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve {:?}", dir))?;
+        let mut roots = self.roots.write().await;
+        if !roots.contains(&dir) {
+            roots.push(dir.clone());
+            roots.sort();
+            self.save(&roots)?;
+            tracing::info!("[export] trusted {:?}", dir);
+        }
+        Ok(roots.clone())
+    }
+
+    pub async fn remove(&self, dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let mut roots = self.roots.write().await;
+        roots.retain(|root| root != dir);
+        self.save(&roots)?;
+        tracing::info!("[export] no longer trusting {:?}", dir);
+        Ok(roots.clone())
+    }
+
+    /// Resolved `target` if it lies inside a trusted root, an OutsideExportRoots error otherwise
+    pub async fn check(&self, target: &Path) -> anyhow::Result<PathBuf> {
+        let resolved = resolve(target)?;
+        if self
+            .roots
+            .read()
+            .await
+            .iter()
+            .any(|root| resolved.starts_with(root))
+        {
+            Ok(resolved)
+        } else {
+            Err(coded(
+                ErrorCode::OutsideExportRoots,
+                format!("{:?} is outside the trusted export folders", target),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::error_code;
+
+    #[tokio::test]
+    async fn test_check_allows_only_paths_inside_roots() {
+        let config = tempfile::tempdir().unwrap();
+        let trusted = tempfile::tempdir().unwrap();
+        let other = tempfile::tempdir().unwrap();
+
+        let scope = ExportScope::load(config.path());
+        let out = trusted.path().join("ch01/out.png");
+        assert!(scope.check(&out).await.is_err());
+
+        scope.add(trusted.path()).await.unwrap();
+        let resolved = scope.check(&out).await.unwrap();
+        assert!(resolved.ends_with("ch01/out.png"));
+
+        let err = scope
+            .check(&other.path().join("out.png"))
+            .await
+            .unwrap_err();
+        assert_eq!(error_code(&err), ErrorCode::OutsideExportRoots);
+        assert!(scope.check(Path::new("relative/out.png")).await.is_err());
+
+        // Reloaded from disk
+        assert_eq!(
+            ExportScope::load(config.path()).roots().await,
+            scope.roots().await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_rejects_escapes() {
+        let config = tempfile::tempdir().unwrap();
+        let trusted = tempfile::tempdir().unwrap();
+        let scope = ExportScope::load(config.path());
+        scope.add(trusted.path()).await.unwrap();
+
+        // `..` after a missing directory can't be resolved by canonicalize
+        let escape = trusted.path().join("missing/../../out.png");
+        assert!(scope.check(&escape).await.is_err());
+
+        #[cfg(unix)]
+        {
+            let outside = tempfile::tempdir().unwrap();
+            let link = trusted.path().join("link");
+            std::os::unix::fs::symlink(outside.path(), &link).unwrap();
+            assert!(scope.check(&link.join("out.png")).await.is_err());
+        }
+    }
+}
@@ -0,0 +1,326 @@
+// Golden-image regression checks for text rendering
+// A suite folder holds `<name>.json` render cases next to their approved `<name>.png` output.
+// Each case is rendered and compared with a perceptual color tolerance, so antialiasing noise
+// passes but moved, resized, or missing text fails. Failures leave `<name>.actual.png` and a
+// `<name>.diff.png` in `failures/` for review; `update` approves the current output instead.
+
+use anyhow::{Context, anyhow};
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::image_io;
+use crate::text_renderer::{RgbColor, TextBlock, render_text_on_image};
+
+pub const FAILURES_DIR: &str = "failures";
+
+/// One render request, with the base page given as a file or a blank canvas
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoldenCase {
+    pub base_image: Option<PathBuf>, // Relative to the suite folder
+    #[serde(default = "default_size")]
+    pub width: u32,
+    #[serde(default = "default_size")]
+    pub height: u32,
+    pub background: Option<RgbColor>, // Blank canvas color, white by default
+    pub text_blocks: Vec<TextBlock>,
+    #[serde(default = "default_method")]
+    pub render_method: String,
+    #[serde(default = "default_font")]
+    pub default_font: String,
+}
+
+fn default_size() -> u32 {
+    512
+}
+
+fn default_method() -> String {
+    "rectangle".to_string()
+}
+
+fn default_font() -> String {
+    "Noto Sans".to_string()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Tolerance {
+    pub pixel_delta: f32, // Color distance (0-255 scale) a pixel may move before it counts
+    pub max_diff_ratio: f64, // Share of counted pixels allowed before the case fails
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance {
+            pixel_delta: 24.0,
+            max_diff_ratio: 0.002,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CaseStatus {
+    Passed,
+    Failed,
+    Updated,
+    Missing, // No golden PNG yet
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaseResult {
+    pub name: String,
+    pub status: CaseStatus,
+    pub diff_ratio: Option<f64>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoldenReport {
+    pub cases: Vec<CaseResult>,
+    pub passed: usize,
+    pub failed: usize, // Failed, missing, and errored cases
+}
+
+/// Color distance weighted by how the eye sees the channels ("redmean" approximation),
+/// scaled to 0-255; alpha differences count in full
+fn color_distance(a: &Rgba<u8>, b: &Rgba<u8>) -> f32 {
+    let mean_red = (a[0] as f32 + b[0] as f32) / 2.0;
+    let dr = a[0] as f32 - b[0] as f32;
+    let dg = a[1] as f32 - b[1] as f32;
+    let db = a[2] as f32 - b[2] as f32;
+    let rgb = ((2.0 + mean_red / 256.0) * dr * dr
+        + 4.0 * dg * dg
+        + (2.0 + (255.0 - mean_red) / 256.0) * db * db)
+        .sqrt()
+        / 3.0;
+    rgb.max((a[3] as f32 - b[3] as f32).abs())
+}
+
+/// Share of pixels beyond `pixel_delta`, and a diff image marking them red over a faded copy
+pub fn compare(
+    expected: &RgbaImage,
+    actual: &RgbaImage,
+    pixel_delta: f32,
+) -> anyhow::Result<(f64, RgbaImage)> {
+    if expected.dimensions() != actual.dimensions() {
+        return Err(anyhow!(
+            "Size changed from {:?} to {:?}",
+            expected.dimensions(),
+            actual.dimensions()
+        ));
+    }
+    let mut diff = RgbaImage::new(actual.width(), actual.height());
+    let mut differing = 0usize;
+    for ((e, a), d) in expected
+        .pixels()
+        .zip(actual.pixels())
+        .zip(diff.pixels_mut())
+    {
+        if color_distance(e, a) > pixel_delta {
+            differing += 1;
+            *d = Rgba([255, 0, 0, 255]);
+        } else {
+            let luma = (0.299 * a[0] as f32 + 0.587 * a[1] as f32 + 0.114 * a[2] as f32) as u8;
+            let faded = 255 - (255 - luma) / 4;
+            *d = Rgba([faded, faded, faded, 255]);
+        }
+    }
+    let total = (actual.width() as usize * actual.height() as usize).max(1);
+    Ok((differing as f64 / total as f64, diff))
+}
+
+fn render_case(dir: &Path, case: &GoldenCase) -> anyhow::Result<RgbaImage> {
+    let base = match &case.base_image {
+        Some(path) => image_io::open(&dir.join(path))?,
+        None => {
+            let color = case.background.clone().unwrap_or(RgbColor {
+                r: 255,
+                g: 255,
+                b: 255,
+            });
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+                case.width,
+                case.height,
+                Rgba([color.r, color.g, color.b, 255]),
+            ))
+        }
+    };
+    let rendered = render_text_on_image(
+        base,
+        case.text_blocks.clone(),
+        &case.render_method,
+        &case.default_font,
+    )?;
+    Ok(rendered.to_rgba8())
+}
+
+fn save_png(image: &RgbaImage, path: &Path) -> anyhow::Result<()> {
+    image
+        .save_with_format(path, ImageFormat::Png)
+        .with_context(|| format!("Failed to write {:?}", path))
+}
+
+fn check_case(
+    dir: &Path,
+    name: &str,
+    case_path: &Path,
+    tolerance: Tolerance,
+    update: bool,
+) -> anyhow::Result<CaseResult> {
+    let json =
+        fs::read_to_string(case_path).with_context(|| format!("Failed to read {:?}", case_path))?;
+    let case: GoldenCase = serde_json::from_str(&json)
+        .with_context(|| format!("Invalid render case {:?}", case_path))?;
+    let actual = render_case(dir, &case)?;
+    let golden_path = dir.join(format!("{}.png", name));
+    let result =
+        |status: CaseStatus, diff_ratio: Option<f64>, message: Option<String>| CaseResult {
+            name: name.to_string(),
+            status,
+            diff_ratio,
+            message,
+        };
+
+    if update {
+        save_png(&actual, &golden_path)?;
+        return Ok(result(CaseStatus::Updated, None, None));
+    }
+    if !golden_path.is_file() {
+        return Ok(result(
+            CaseStatus::Missing,
+            None,
+            Some("No golden image; approve the current output with update".to_string()),
+        ));
+    }
+
+    let expected = image_io::open(&golden_path)?.to_rgba8();
+    let failures = dir.join(FAILURES_DIR);
+    let failed = |message: String, diff: Option<(f64, &RgbaImage)>| -> anyhow::Result<CaseResult> {
+        fs::create_dir_all(&failures)
+            .with_context(|| format!("Failed to create {:?}", failures))?;
+        save_png(&actual, &failures.join(format!("{}.actual.png", name)))?;
+        if let Some((_, image)) = diff {
+            save_png(image, &failures.join(format!("{}.diff.png", name)))?;
+        }
+        Ok(result(
+            CaseStatus::Failed,
+            diff.map(|(ratio, _)| ratio),
+            Some(message),
+        ))
+    };
+    match compare(&expected, &actual, tolerance.pixel_delta) {
+        Ok((ratio, _)) if ratio <= tolerance.max_diff_ratio => {
+            Ok(result(CaseStatus::Passed, Some(ratio), None))
+        }
+        Ok((ratio, diff)) => failed(
+            format!(
+                "{:.3}% of pixels differ (allowed {:.3}%)",
+                ratio * 100.0,
+                tolerance.max_diff_ratio * 100.0
+            ),
+            Some((ratio, &diff)),
+        ),
+        Err(err) => failed(format!("{:#}", err), None),
+    }
+}
+
+/// Render every case in the suite folder and compare (or with `update`, replace) its golden
+pub fn run(dir: &Path, tolerance: Tolerance, update: bool) -> anyhow::Result<GoldenReport> {
+    let mut cases: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read golden suite {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    cases.sort();
+    if cases.is_empty() {
+        return Err(anyhow!("No render cases (*.json) in {:?}", dir));
+    }
+
+    let results: Vec<CaseResult> = cases
+        .iter()
+        .map(|path| {
+            let name = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            check_case(dir, &name, path, tolerance, update).unwrap_or_else(|err| CaseResult {
+                name,
+                status: CaseStatus::Error,
+                diff_ratio: None,
+                message: Some(format!("{:#}", err)),
+            })
+        })
+        .collect();
+
+    let passed = results
+        .iter()
+        .filter(|r| matches!(r.status, CaseStatus::Passed | CaseStatus::Updated))
+        .count();
+    tracing::info!(
+        "[golden] {}/{} case(s) passed in {:?}",
+        passed,
+        results.len(),
+        dir
+    );
+    Ok(GoldenReport {
+        failed: results.len() - passed,
+        passed,
+        cases: results,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_ignores_small_shifts_and_flags_real_changes() {
+        let expected = RgbaImage::from_pixel(10, 10, Rgba([255, 255, 255, 255]));
+        let mut actual = expected.clone();
+        actual.put_pixel(0, 0, Rgba([250, 248, 252, 255])); // Antialiasing noise
+        actual.put_pixel(5, 5, Rgba([0, 0, 0, 255])); // A stray glyph pixel
+        let (ratio, diff) = compare(&expected, &actual, Tolerance::default().pixel_delta).unwrap();
+        assert_eq!(ratio, 0.01);
+        assert_eq!(diff.get_pixel(5, 5), &Rgba([255, 0, 0, 255]));
+        assert_ne!(diff.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+
+        let resized = RgbaImage::new(10, 11);
+        assert!(compare(&expected, &resized, 24.0).is_err());
+    }
+
+    #[test]
+    fn test_case_defaults() {
+        let case: GoldenCase = serde_json::from_str(
+            r#"{ "textBlocks": [{ "xmin": 10, "ymin": 10, "xmax": 200, "ymax": 80,
+                 "translatedText": "Hello" }] }"#,
+        )
+        .unwrap();
+        assert_eq!((case.width, case.height), (512, 512));
+        assert_eq!(case.render_method, "rectangle");
+        assert!(case.base_image.is_none());
+    }
+
+    #[test]
+    fn test_missing_goldens_fail_until_updated() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("blank.json"),
+            r#"{ "width": 32, "height": 16, "textBlocks": [] }"#,
+        )
+        .unwrap();
+
+        let report = run(dir.path(), Tolerance::default(), false).unwrap();
+        assert_eq!(report.cases[0].status, CaseStatus::Missing);
+        assert_eq!(report.failed, 1);
+
+        run(dir.path(), Tolerance::default(), true).unwrap();
+        let report = run(dir.path(), Tolerance::default(), false).unwrap();
+        assert_eq!(report.cases[0].status, CaseStatus::Passed);
+        assert_eq!(report.passed, 1);
+    }
+}
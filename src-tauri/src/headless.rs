@@ -0,0 +1,1944 @@
+// Headless mode: `koharu --headless <subcommand>` runs the backend pipeline without opening a window
+// Uses the same AppState, settings, and model directory as the GUI.
+//
+// Note: release builds on Windows use the GUI subsystem, so run from a terminal that
+// captures stdout (e.g. redirect to a file) to see printed results.
+
+use anyhow::{Context, anyhow};
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use futures::stream::{self, StreamExt};
+use image::{GrayImage, Luma};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::accuracy::ReportFormat;
+use crate::backup::Backup;
+use crate::batch;
+use crate::benchmark;
+use crate::build_state;
+use crate::cli_config::{CliConfig, DetectConfig};
+use crate::comparison::{self, ComparisonMode, ComparisonOptions};
+use crate::detection_eval;
+use crate::error::{CommandError, ErrorCode, coded, error_code, with_code};
+use crate::golden::{self, Tolerance};
+use crate::image_io::{self, ExportOptions, OutputFormat, SourceMetadata};
+use crate::inpaint_eval;
+use crate::mit_json;
+use crate::ocr_compare::{self, OcrConfig};
+use crate::pipeline::{self, PageBlock, ProcessOptions, StageProgress};
+use crate::project::Project;
+use crate::settings::{Settings, SettingsStore};
+use crate::stages::{BBox, crop_to_bbox, evaluate_ocr_dataset, ocr_pipeline_for};
+use crate::state::AppState;
+use crate::text_renderer::FontStack;
+use crate::translation::TranslatorConfig;
+use crate::translation_eval;
+use crate::visualize::{self, DrawOptions};
+use crate::watch::{self, FolderWatcher};
+
+/// Flag that switches the binary from the GUI to the CLI
+pub const HEADLESS_FLAG: &str = "--headless";
+
+/// Path argument meaning stdin for inputs and stdout for outputs
+const STDIO: &str = "-";
+
+// Must match the bundle identifier in tauri.conf.json so the CLI shares the GUI's directories
+pub(crate) const APP_IDENTIFIER: &str = "koharu";
+
+#[derive(Parser)]
+#[command(
+    name = "koharu --headless",
+    about = "Run the Koharu pipeline without a GUI"
+)]
+struct Cli {
+    /// Override the data directory (models, jobs)
+    #[arg(long, global = true, value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+
+    /// Override the config directory (settings.json)
+    #[arg(long, global = true, value_name = "DIR")]
+    config_dir: Option<PathBuf>,
+
+    /// Project file (or a folder to search upwards from) whose glossary and style apply
+    #[arg(long, global = true, value_name = "PATH")]
+    project: Option<PathBuf>,
+
+    /// Print failures as a JSON object on stderr ({"error": {code, exitCode, message, detail}})
+    #[arg(long, global = true)]
+    json_errors: bool,
+
+    /// TOML file with thresholds, inpaint, translation, render, and export defaults
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args, Clone)]
+struct DetectArgs {
+    /// Defaults to the --config value, else 0.5
+    #[arg(long)]
+    confidence_threshold: Option<f32>,
+
+    /// Defaults to the --config value, else 0.4
+    #[arg(long)]
+    nms_threshold: Option<f32>,
+}
+
+impl DetectArgs {
+    /// (confidence, NMS) thresholds: flags, then the config file, then the pipeline defaults
+    fn thresholds(&self, config: &DetectConfig) -> (f32, f32) {
+        let defaults = ProcessOptions::default();
+        (
+            self.confidence_threshold
+                .or(config.confidence_threshold)
+                .unwrap_or(defaults.confidence_threshold),
+            self.nms_threshold
+                .or(config.nms_threshold)
+                .unwrap_or(defaults.nms_threshold),
+        )
+    }
+}
+
+#[derive(Args, Clone)]
+struct DrawArgs {
+    /// Save a QA image with the detected boxes drawn on the page
+    #[arg(long, value_name = "FILE")]
+    draw: Option<PathBuf>,
+
+    /// Label each box with its index, class, and confidence
+    #[arg(long, requires = "draw")]
+    labels: bool,
+
+    /// Opacity of the segmentation mask overlay, 0 to hide it
+    #[arg(long, default_value_t = 0.4, requires = "draw")]
+    mask_alpha: f32,
+
+    /// Draw every box in one color instead of coloring by class
+    #[arg(long, requires = "draw")]
+    single_color: bool,
+
+    /// Merge overlapping boxes and number them in reading order
+    #[arg(long, requires = "draw")]
+    ordered: bool,
+}
+
+impl DrawArgs {
+    fn options(&self) -> DrawOptions {
+        DrawOptions {
+            class_colors: !self.single_color,
+            labels: self.labels,
+            mask_alpha: self.mask_alpha,
+            ordered: self.ordered,
+        }
+    }
+
+    /// Label font, or none (boxes only) when it can't be loaded
+    fn font(&self, family: &str) -> Option<ab_glyph::FontArc> {
+        if !self.labels && !self.ordered {
+            return None;
+        }
+        match FontStack::from_font_family(family) {
+            Ok(fonts) => Some(fonts.primary().clone()),
+            Err(err) => {
+                tracing::warn!("[headless] drawing without labels: {}", err);
+                None
+            }
+        }
+    }
+}
+
+#[derive(Args, Clone)]
+struct TranslatorArgs {
+    /// Translation provider: deepl-free, deepl-pro, or ollama (defaults to saved settings)
+    #[arg(long)]
+    provider: Option<String>,
+
+    /// DeepL API key. Prefer the DEEPL_API_KEY environment variable or `api_key` in the
+    /// --config file: a key on the command line shows up in `ps` and shell history.
+    #[arg(long, env = "DEEPL_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+
+    /// Ollama model name
+    #[arg(long)]
+    model: Option<String>,
+
+    #[arg(long)]
+    source_lang: Option<String>,
+
+    #[arg(long)]
+    target_lang: Option<String>,
+}
+
+#[derive(Args, Clone)]
+struct ExportArgs {
+    /// JPEG/AVIF quality, 1-100 (defaults to saved settings); PNG and WebP are lossless
+    #[arg(long)]
+    quality: Option<u8>,
+
+    /// PNG compression: fast, default, or best
+    #[arg(long)]
+    png_compression: Option<String>,
+
+    /// Downscale so the longest side is at most this many pixels
+    #[arg(long)]
+    max_dimension: Option<u32>,
+}
+
+impl ExportArgs {
+    /// Saved export options with CLI overrides applied, for output in `format` if known
+    fn resolve(
+        &self,
+        saved: &ExportOptions,
+        format: Option<OutputFormat>,
+    ) -> anyhow::Result<ExportOptions> {
+        let mut options = saved.clone();
+        if let Some(format) = format {
+            options.set_format(format);
+        }
+        if self.quality.is_some() {
+            options.quality = self.quality;
+        }
+        if let Some(compression) = &self.png_compression {
+            options.png_compression = serde_json::from_value(compression.as_str().into())
+                .map_err(|_| anyhow!("Unknown PNG compression '{}'", compression))?;
+        }
+        if self.max_dimension.is_some() {
+            options.max_dimension = self.max_dimension;
+        }
+        options
+            .validate()
+            .map_err(|err| coded(ErrorCode::InvalidInput, err.to_string()))?;
+        Ok(options)
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TableFormat {
+    Json,
+    Tsv,
+}
+
+/// OCR result for one crop in `ocr --crops`
+#[derive(serde::Serialize)]
+struct CropText {
+    file: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Detect text blocks and write them as JSON
+    Detect {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Also save the segmentation mask as PNG
+        #[arg(long)]
+        mask: Option<PathBuf>,
+        /// Also save each block as `<index>_class<class>_<confidence>.png` in this folder
+        #[arg(long, value_name = "DIR")]
+        export_crops: Option<PathBuf>,
+        #[command(flatten)]
+        draw: DrawArgs,
+        #[command(flatten)]
+        detect: DetectArgs,
+    },
+    /// Detect text blocks and recognize their text
+    Ocr {
+        /// Page image, or with --crops a folder or glob of cropped text regions
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Output JSON of blocks with text (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Treat the input as pre-cut crops and write a file -> text table
+        #[arg(long)]
+        crops: bool,
+        /// Table format for --crops
+        #[arg(long, value_enum, default_value_t = TableFormat::Json)]
+        format: TableFormat,
+        /// Crops recognized at once with --crops
+        #[arg(short, long, default_value_t = 1)]
+        jobs: usize,
+        #[command(flatten)]
+        detect: DetectArgs,
+    },
+    /// Translate the text of every block in a blocks JSON file
+    Translate {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        translator: TranslatorArgs,
+    },
+    /// Convert a manga-image-translator JSON page to a blocks JSON file
+    ImportMit {
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Blocks JSON (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Image key to take when the file holds several pages
+        #[arg(long)]
+        page: Option<String>,
+    },
+    /// Convert a blocks JSON file to a manga-image-translator JSON page
+    ExportMit {
+        /// Blocks JSON
+        #[arg(short, long)]
+        input: PathBuf,
+        /// manga-image-translator JSON (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Image path the page is stored under, e.g. the page's file name
+        #[arg(long)]
+        image: String,
+    },
+    /// Score a provider's translations against reference translations with BLEU and chrF
+    ScoreTranslations {
+        /// Reference set: JSON array of {"source", "reference"} or source<TAB>reference lines
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Scores and translations as JSON (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        translator: TranslatorArgs,
+        /// Also score these providers on the same references, e.g. --compare ollama
+        #[arg(long, value_name = "PROVIDER")]
+        compare: Vec<String>,
+        /// Fail when any provider's corpus chrF is below this (0-100)
+        #[arg(long)]
+        min_chrf: Option<f64>,
+    },
+    /// Remove text from a page
+    Inpaint {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Blocks to inpaint instead of running detection: `detect` output, or
+        /// {"bboxes": [...], "mask": "segment.png"} with the mask relative to the JSON
+        #[arg(long)]
+        blocks: Option<PathBuf>,
+        /// Segmentation mask PNG (white = text); without one, given blocks are inpainted whole
+        #[arg(long)]
+        mask: Option<PathBuf>,
+        /// Context around each block in pixels (defaults to saved settings)
+        #[arg(long)]
+        padding: Option<i32>,
+        #[command(flatten)]
+        detect: DetectArgs,
+        #[command(flatten)]
+        export: ExportArgs,
+    },
+    /// Render translated blocks onto a (textless) page
+    Render {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        blocks: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        /// rectangle, lama, newlama, or auto (defaults to saved settings)
+        #[arg(long)]
+        method: Option<String>,
+        /// Segmentation mask PNG from `detect --mask`; with auto, plain bubbles are filled
+        #[arg(long)]
+        mask: Option<PathBuf>,
+        #[arg(long)]
+        font: Option<String>,
+        #[command(flatten)]
+        export: ExportArgs,
+    },
+    /// Run the full pipeline on one page
+    #[command(alias = "process")]
+    Pipeline {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        #[command(flatten)]
+        translator: TranslatorArgs,
+        #[command(flatten)]
+        export: ExportArgs,
+    },
+    /// Run the full pipeline on every image in a folder
+    ProcessFolder {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Pages processed at once
+        #[arg(
+            short,
+            long,
+            visible_alias = "jobs",
+            visible_short_alias = 'j',
+            default_value_t = 2
+        )]
+        workers: usize,
+        /// Output format: png, jpg, webp or avif (defaults to saved settings)
+        #[arg(long)]
+        format: Option<String>,
+        /// Output naming, e.g. "{title}/{chapter}/{page:03}_{lang}" (defaults to saved settings)
+        #[arg(long)]
+        name_template: Option<String>,
+        /// Value for {title} and the embedded title tag
+        #[arg(long)]
+        title: Option<String>,
+        /// Value for {chapter} and the embedded chapter tag
+        #[arg(long)]
+        chapter: Option<String>,
+        /// Keep running and process new images as they appear in the input folder
+        #[arg(long)]
+        watch: bool,
+        #[command(flatten)]
+        translator: TranslatorArgs,
+        #[command(flatten)]
+        export: ExportArgs,
+    },
+    /// Measure detector precision, recall, and mAP on pages with COCO or labelme annotations
+    EvaluateDetection {
+        /// Dataset folder, e.g. pages saved with the GUI's annotation export
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Full report as JSON (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Overlap needed for a detection to count as a hit
+        #[arg(long, default_value_t = detection_eval::DEFAULT_IOU_THRESHOLD)]
+        iou_threshold: f32,
+        /// Fail when the mAP is below this
+        #[arg(long)]
+        min_map: Option<f64>,
+        #[command(flatten)]
+        detect: DetectArgs,
+    },
+    /// Measure OCR accuracy (CER/WER) on a folder of images with `<name>.txt` ground truth
+    EvaluateOcr {
+        /// Dataset folder
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Full report as JSON (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Also save per-sample CER/WER with the averages as .csv or .json
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+        /// OCR engine key, e.g. manga-ocr or paddle-ocr (defaults to the active engine)
+        #[arg(long)]
+        engine: Option<String>,
+        /// Fail when the average CER is above this
+        #[arg(long)]
+        max_cer: Option<f64>,
+        /// Fail when the average WER is above this
+        #[arg(long)]
+        max_wer: Option<f64>,
+    },
+    /// Compare two OCR configurations on the same labelled dataset, per sample and per subfolder
+    CompareOcr {
+        /// Dataset folder; images in subfolders are grouped by the subfolder name
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Full comparison as JSON (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Configuration A as JSON: label, engine, preprocess, decode
+        #[arg(long, value_name = "FILE")]
+        config_a: Option<PathBuf>,
+        /// Configuration B as JSON
+        #[arg(long, value_name = "FILE")]
+        config_b: Option<PathBuf>,
+        /// OCR engine for A, overriding its config file
+        #[arg(long)]
+        engine_a: Option<String>,
+        /// OCR engine for B, overriding its config file
+        #[arg(long)]
+        engine_b: Option<String>,
+    },
+    /// Erase text-shaped holes from clean pages, inpaint them, and score the fill
+    EvaluateInpaint {
+        /// Folder of clean pages, optionally with `<name>.mask.png` holes
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Full report as JSON (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Context padding around each hole (defaults to saved settings)
+        #[arg(long)]
+        padding: Option<i32>,
+        /// Inference resolution (defaults to saved settings)
+        #[arg(long)]
+        target_size: Option<u32>,
+        /// Fail when the average SSIM is below this
+        #[arg(long)]
+        min_ssim: Option<f64>,
+    },
+    /// Time detection, OCR, inpainting, and rendering on built-in reference pages
+    Benchmark {
+        /// Runs of each reference page, after one warm-up page
+        #[arg(long, default_value_t = benchmark::DEFAULT_ITERATIONS)]
+        iterations: usize,
+        /// Full results as JSON (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Render the golden suite and compare it to the approved images
+    Golden {
+        /// Suite folder of `<name>.json` render cases and `<name>.png` goldens
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Approve the current output as the new goldens
+        #[arg(long)]
+        update: bool,
+        /// Color distance (0-255) a pixel may move before it counts as different
+        #[arg(long, default_value_t = Tolerance::default().pixel_delta)]
+        pixel_delta: f32,
+        /// Share of differing pixels allowed per case
+        #[arg(long, default_value_t = Tolerance::default().max_diff_ratio)]
+        max_diff_ratio: f64,
+    },
+    /// Put an original and translated page side by side (or alternate them) for QC
+    Compare {
+        /// Original page
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(short, long)]
+        translated: PathBuf,
+        /// Output image; .gif or .webp with --animate
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Alternate the two pages as an animation instead
+        #[arg(long)]
+        animate: bool,
+        #[arg(long, default_value_t = 16)]
+        gap: u32,
+        #[arg(long)]
+        max_height: Option<u32>,
+        /// Milliseconds each page is shown when animating
+        #[arg(long, default_value_t = 1500)]
+        frame_ms: u32,
+    },
+}
+
+/// True when the process was started with `--headless`
+pub fn requested() -> bool {
+    std::env::args().skip(1).any(|arg| arg == HEADLESS_FLAG)
+}
+
+/// Parse the CLI (ignoring `--headless`) and run the chosen subcommand
+pub fn run() -> anyhow::Result<()> {
+    run_as(
+        "koharu --headless",
+        std::env::args().filter(|arg| arg != HEADLESS_FLAG),
+    )
+}
+
+/// Same CLI under another program name, for the standalone `koharu-cli` binary
+pub fn run_as(bin_name: &'static str, args: impl Iterator<Item = String>) -> anyhow::Result<()> {
+    let matches = Cli::command().bin_name(bin_name).get_matches_from(args);
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+    let json_errors = cli.json_errors;
+    let runtime = tokio::runtime::Runtime::new().context("Failed to start async runtime")?;
+    let result = runtime.block_on(execute(cli));
+    drop(runtime);
+
+    match result {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            let status = ExitStatus::of(error_code(&err));
+            report_error(err, status, json_errors);
+            std::process::exit(status as i32)
+        }
+    }
+}
+
+/// Stable process exit codes for wrapper scripts; clap exits with 2 on usage errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExitStatus {
+    Failure = 1,
+    ModelMissing = 3,
+    BadInput = 4,
+    InferenceFailed = 5,
+    Network = 6,
+}
+
+impl ExitStatus {
+    fn of(code: ErrorCode) -> Self {
+        match code {
+            ErrorCode::ModelMissing
+            | ErrorCode::CudaUnavailable
+            | ErrorCode::DirectmlUnavailable
+            | ErrorCode::OcrEngineUnavailable => ExitStatus::ModelMissing,
+            ErrorCode::InvalidInput
+            | ErrorCode::FileNotFound
+            | ErrorCode::PermissionDenied
+            | ErrorCode::UnsupportedFormat
+            | ErrorCode::OutsideExportRoots
+            | ErrorCode::ApiKeyMissing => ExitStatus::BadInput,
+            ErrorCode::InferenceFailed | ErrorCode::PluginFailed => ExitStatus::InferenceFailed,
+            ErrorCode::Network
+            | ErrorCode::ApiKeyInvalid
+            | ErrorCode::QuotaExceeded
+            | ErrorCode::RateLimited
+            | ErrorCode::ProviderUnavailable => ExitStatus::Network,
+            ErrorCode::Unknown | ErrorCode::NothingToUndo => ExitStatus::Failure,
+        }
+    }
+}
+
+/// Print a failure to stderr: the anyhow chain, or one JSON object with `--json-errors`
+fn report_error(err: anyhow::Error, status: ExitStatus, json: bool) {
+    if !json {
+        eprintln!("Error: {:#}", err);
+        return;
+    }
+    // Same { code, message, detail } shape the GUI receives, plus the exit code
+    let mut error = serde_json::to_value(CommandError(err)).unwrap_or_default();
+    error["exitCode"] = (status as i32).into();
+    eprintln!("{}", serde_json::json!({ "error": error }));
+}
+
+fn default_dir(base: Option<PathBuf>, kind: &str) -> anyhow::Result<PathBuf> {
+    base.map(|dir| dir.join(APP_IDENTIFIER)).ok_or_else(|| {
+        anyhow!(
+            "Could not determine the {} directory; pass --{}-dir",
+            kind,
+            kind
+        )
+    })
+}
+
+async fn execute(cli: Cli) -> anyhow::Result<()> {
+    let config_dir = match cli.config_dir {
+        Some(dir) => dir,
+        None => default_dir(dirs::config_dir(), "config")?,
+    };
+    let data_dir = match cli.data_dir {
+        Some(dir) => dir,
+        None => default_dir(dirs::data_dir(), "data")?,
+    };
+    let config = match &cli.config {
+        Some(path) => CliConfig::load(path)?,
+        None => CliConfig::default(),
+    };
+
+    // Translation needs no models, so skip the expensive state setup
+    if let Command::Translate {
+        input,
+        output,
+        translator,
+    } = &cli.command
+    {
+        let settings = load_settings(
+            &SettingsStore::load(&config_dir)?,
+            cli.project.as_deref(),
+            &config,
+        )
+        .await?;
+        let translator = resolve_translator(translator, &settings.translation)?;
+        let mut blocks = read_blocks(input)?;
+        pipeline::translate_blocks(&translator, &mut blocks, &log_progress).await?;
+        return write_json(output.as_deref(), &blocks);
+    }
+
+    // Rendering needs no models either
+    if let Command::Golden {
+        input,
+        update,
+        pixel_delta,
+        max_diff_ratio,
+    } = &cli.command
+    {
+        let tolerance = Tolerance {
+            pixel_delta: *pixel_delta,
+            max_diff_ratio: *max_diff_ratio,
+        };
+        let report = golden::run(input, tolerance, *update)?;
+        for case in &report.cases {
+            match &case.message {
+                Some(message) => println!("{:?} {}: {}", case.status, case.name, message),
+                None => println!("{:?} {}", case.status, case.name),
+            }
+        }
+        println!("{} passed, {} failed", report.passed, report.failed);
+        return match report.failed {
+            0 => Ok(()),
+            failed => Err(anyhow!(
+                "{} golden case(s) failed; see {:?}",
+                failed,
+                input.join(golden::FAILURES_DIR)
+            )),
+        };
+    }
+
+    // Format conversion needs no models either
+    if let Command::ImportMit {
+        input,
+        output,
+        page,
+    } = &cli.command
+    {
+        let mut pages = mit_json::import(input)?;
+        let index = match (page, pages.len()) {
+            (Some(page), _) => pages
+                .iter()
+                .position(|p| &p.image == page)
+                .ok_or_else(|| anyhow!("No page '{}' in {:?}", page, input))?,
+            (None, 1) => 0,
+            (None, _) => {
+                let names: Vec<&str> = pages.iter().map(|p| p.image.as_str()).collect();
+                return Err(anyhow!(
+                    "{:?} holds {} pages; pick one with --page: {:?}",
+                    input,
+                    pages.len(),
+                    names
+                ));
+            }
+        };
+        return write_json(output.as_deref(), &pages.swap_remove(index).blocks);
+    }
+    if let Command::ExportMit {
+        input,
+        output,
+        image,
+    } = &cli.command
+    {
+        let blocks = read_blocks(input)?;
+        return write_text(output.as_deref(), &mit_json::to_json(image, &blocks)?);
+    }
+
+    if let Command::ScoreTranslations {
+        input,
+        output,
+        translator,
+        compare,
+        min_chrf,
+    } = &cli.command
+    {
+        let settings = load_settings(
+            &SettingsStore::load(&config_dir)?,
+            cli.project.as_deref(),
+            &config,
+        )
+        .await?;
+        let saved = translation_settings(translator, &settings.translation);
+        let primary = resolve_translator(translator, &settings.translation)?;
+        let references = translation_eval::load_references(input)?;
+        let mut scores =
+            vec![translation_eval::evaluate(&saved.provider, &primary, &references).await];
+        scores.extend(translation_eval::compare(&saved, compare, &references).await?);
+        for score in &scores {
+            eprintln!(
+                "{}: BLEU {:.1}, chrF {:.1} over {} line(s), {} failed",
+                score.provider,
+                score.score.bleu,
+                score.score.chrf,
+                score.score.samples.len(),
+                score.failed.len()
+            );
+        }
+        write_json(output.as_deref(), &scores)?;
+        let below = min_chrf.and_then(|min| {
+            scores
+                .iter()
+                .find(|score| score.score.chrf < min)
+                .map(|score| (score, min))
+        });
+        return match below {
+            Some((low, min)) => Err(anyhow!(
+                "'{}' chrF {:.1} is below {:.1}",
+                low.provider,
+                low.score.chrf,
+                min
+            )),
+            None => Ok(()),
+        };
+    }
+
+    if let Command::Compare {
+        input,
+        translated,
+        output,
+        animate,
+        gap,
+        max_height,
+        frame_ms,
+    } = &cli.command
+    {
+        let options = ComparisonOptions {
+            gap: *gap,
+            max_height: *max_height,
+            frame_ms: *frame_ms,
+            ..comparison_options(output, *animate)?
+        };
+        if is_stdio(input) && is_stdio(translated) {
+            return Err(anyhow!("Only one input can be read from stdin"));
+        }
+        let bytes = comparison::encode(&open_image(input)?, &open_image(translated)?, &options)?;
+        return write_output(output, &bytes);
+    }
+
+    let state = build_state(&config_dir, &data_dir).await?;
+    let settings = load_settings(&state.settings, cli.project.as_deref(), &config).await?;
+    let backup = Backup::new(settings.backup.clone(), SystemTime::now());
+
+    match cli.command {
+        Command::Detect {
+            input,
+            output,
+            mask,
+            export_crops,
+            draw,
+            detect,
+        } => {
+            let image = open_image(&input)?;
+            let (confidence, nms) = detect.thresholds(&config.detect);
+            let (blocks, segment) =
+                pipeline::detect_blocks(&state, &image, confidence, nms).await?;
+            let to_stdout = [Some(&output), mask.as_ref(), draw.draw.as_ref()]
+                .into_iter()
+                .flatten()
+                .filter(|path| is_stdio(path))
+                .count();
+            if to_stdout > 1 {
+                return Err(anyhow!(
+                    "Only one of --output, --mask, and --draw can be written to stdout"
+                ));
+            }
+            if let Some(path) = &draw.draw {
+                let font = draw.font(&settings.render.default_font);
+                let canvas = visualize::draw(
+                    &image,
+                    &blocks,
+                    Some(&segment),
+                    &draw.options(),
+                    font.as_ref(),
+                );
+                let png = pipeline::encode_png(&image::DynamicImage::ImageRgba8(canvas))?;
+                write_output(path, &png)?;
+            }
+            if let Some(mask) = mask {
+                if is_stdio(&mask) {
+                    let png = pipeline::encode_png(&image::DynamicImage::ImageLuma8(segment))?;
+                    write_output(&mask, &png)?;
+                } else {
+                    segment
+                        .save(&mask)
+                        .with_context(|| format!("Failed to write {:?}", mask))?;
+                }
+            }
+            if let Some(dir) = export_crops {
+                save_crops(&image, &blocks, &dir)?;
+            }
+            write_json(Some(&output), &blocks)
+        }
+        Command::Ocr {
+            input,
+            output,
+            crops: true,
+            format,
+            jobs,
+            ..
+        } => {
+            let results = recognize_crops(&state, &input, jobs).await?;
+            match format {
+                TableFormat::Json => write_json(output.as_deref(), &results)?,
+                TableFormat::Tsv => write_text(output.as_deref(), &crop_table(&results))?,
+            }
+            let failed = results.iter().filter(|r| r.error.is_some()).count();
+            if failed == 0 {
+                Ok(())
+            } else {
+                Err(anyhow!("{} of {} crop(s) failed", failed, results.len()))
+            }
+        }
+        Command::Ocr {
+            input,
+            output,
+            detect,
+            ..
+        } => {
+            let image = open_image(&input)?;
+            let (confidence, nms) = detect.thresholds(&config.detect);
+            let (mut blocks, _) = pipeline::detect_blocks(&state, &image, confidence, nms).await?;
+            pipeline::recognize_blocks(&state, &image, &mut blocks, &log_progress).await?;
+            write_json(output.as_deref(), &blocks)
+        }
+        Command::EvaluateInpaint {
+            input,
+            output,
+            padding,
+            target_size,
+            min_ssim,
+        } => {
+            let mut inpaint = settings.inpaint.clone();
+            if let Some(padding) = padding {
+                inpaint.padding = padding;
+            }
+            if let Some(target_size) = target_size {
+                inpaint.target_size = target_size;
+            }
+            let bar = progress_bar(0, "pages");
+            let on_progress = |current: usize, total: usize| {
+                bar.set_length(total as u64);
+                bar.set_position(current as u64);
+            };
+            let evaluation = inpaint_eval::evaluate(&state, &input, &inpaint, &on_progress).await?;
+            bar.finish_and_clear();
+            eprintln!(
+                "SSIM {:.4}, GMSD {:.4}, PSNR {:.2}dB over {} page(s), {} skipped",
+                evaluation.average_ssim,
+                evaluation.average_gmsd,
+                evaluation.average_psnr,
+                evaluation.samples.len(),
+                evaluation.skipped.len()
+            );
+            write_json(output.as_deref(), &evaluation)?;
+            match min_ssim {
+                Some(min) if evaluation.average_ssim < min => Err(anyhow!(
+                    "Average SSIM {:.4} is below {:.4}",
+                    evaluation.average_ssim,
+                    min
+                )),
+                _ => Ok(()),
+            }
+        }
+        Command::Benchmark { iterations, output } => {
+            let bar = progress_bar(0, "pages");
+            let on_progress = |current: usize, total: usize| {
+                bar.set_length(total as u64);
+                bar.set_position(current as u64);
+            };
+            let result = benchmark::run(&state, &settings, iterations, &on_progress).await?;
+            bar.finish_and_clear();
+            for stage in &result.stages {
+                eprintln!(
+                    "{:<11} {:>8.1}ms (min {}ms, max {}ms)",
+                    stage.stage, stage.avg_ms, stage.min_ms, stage.max_ms
+                );
+            }
+            eprintln!(
+                "{} / {}: {:.0}ms per page, {:.1} images/min",
+                result.provider, result.ocr_engine, result.page_ms, result.images_per_minute
+            );
+            write_json(output.as_deref(), &result)
+        }
+        Command::EvaluateDetection {
+            input,
+            output,
+            iou_threshold,
+            min_map,
+            detect,
+        } => {
+            let (confidence, nms) = detect.thresholds(&config.detect);
+            let evaluation =
+                detection_eval::evaluate(&state, &input, confidence, nms, iou_threshold).await?;
+            eprintln!(
+                "mAP@{} {:.3}, precision {:.3}, recall {:.3} over {} page(s), {} skipped",
+                iou_threshold,
+                evaluation.mean_average_precision,
+                evaluation.precision,
+                evaluation.recall,
+                evaluation.samples.len(),
+                evaluation.skipped.len()
+            );
+            write_json(output.as_deref(), &evaluation)?;
+            match min_map {
+                Some(min) if evaluation.mean_average_precision < min => {
+                    Err(anyhow!("mAP is below {}", min))
+                }
+                _ => Ok(()),
+            }
+        }
+        Command::EvaluateOcr {
+            input,
+            output,
+            report,
+            engine,
+            max_cer,
+            max_wer,
+        } => {
+            let evaluation = evaluate_ocr_dataset(&state, &input, engine).await?;
+            eprintln!(
+                "{}: CER {:.3} (max {:.3}), WER {:.3} (max {:.3}) over {} sample(s), {} skipped",
+                evaluation.engine,
+                evaluation.accuracy.average_cer,
+                evaluation.accuracy.max_cer,
+                evaluation.accuracy.average_wer,
+                evaluation.accuracy.max_wer,
+                evaluation.accuracy.samples.len(),
+                evaluation.skipped.len()
+            );
+            write_json(output.as_deref(), &evaluation)?;
+            if let Some(path) = report {
+                evaluation
+                    .accuracy
+                    .write_report(&path, ReportFormat::for_path(&path))?;
+            }
+            if evaluation.accuracy.meets_thresholds(
+                max_cer.unwrap_or(f64::INFINITY),
+                max_wer.unwrap_or(f64::INFINITY),
+            ) {
+                Ok(())
+            } else {
+                Err(anyhow!("Accuracy is below the given thresholds"))
+            }
+        }
+        Command::CompareOcr {
+            input,
+            output,
+            config_a,
+            config_b,
+            engine_a,
+            engine_b,
+        } => {
+            let a = ocr_config(config_a.as_deref(), engine_a)?;
+            let b = ocr_config(config_b.as_deref(), engine_b)?;
+            let (_, pipeline_a) = ocr_pipeline_for(&state, a.engine.clone()).await?;
+            let (_, pipeline_b) = ocr_pipeline_for(&state, b.engine.clone()).await?;
+            let comparison = ocr_compare::compare(&input, (a, pipeline_a), (b, pipeline_b)).await?;
+            for class in &comparison.classes {
+                eprintln!(
+                    "{}: {} sample(s), CER {:.3} vs {:.3} (p = {:.3}), winner {:?}",
+                    class.class,
+                    class.samples,
+                    class.cer.mean_a,
+                    class.cer.mean_b,
+                    class.cer.p_value,
+                    class.winner
+                );
+            }
+            eprintln!(
+                "overall: CER {:.3} vs {:.3}, WER {:.3} vs {:.3} (p = {:.3}{}), winner {:?}, {} skipped",
+                comparison.cer.mean_a,
+                comparison.cer.mean_b,
+                comparison.wer.mean_a,
+                comparison.wer.mean_b,
+                comparison.cer.p_value,
+                if comparison.cer.significant {
+                    ", significant"
+                } else {
+                    ""
+                },
+                comparison.winner,
+                comparison.skipped.len()
+            );
+            write_json(output.as_deref(), &comparison)
+        }
+        Command::Translate { .. }
+        | Command::ImportMit { .. }
+        | Command::ExportMit { .. }
+        | Command::ScoreTranslations { .. }
+        | Command::Compare { .. }
+        | Command::Golden { .. } => {
+            unreachable!("handled before loading models")
+        }
+        Command::Inpaint {
+            input,
+            output,
+            blocks,
+            mask,
+            padding,
+            detect,
+            export,
+        } => {
+            let source = open_source(&input)?;
+            let image = source.image;
+            let (blocks, segment) = match blocks {
+                Some(path) => {
+                    let regions = read_inpaint_regions(&path)?;
+                    let segment = match mask.or(regions.mask.clone()) {
+                        Some(mask) => open_image(&mask)?.to_luma8(),
+                        None => GrayImage::from_pixel(image.width(), image.height(), Luma([255])),
+                    };
+                    (regions.blocks(), segment)
+                }
+                None => {
+                    let (confidence, nms) = detect.thresholds(&config.detect);
+                    let (detected, segment) =
+                        pipeline::detect_blocks(&state, &image, confidence, nms).await?;
+                    match mask {
+                        Some(mask) => (detected, open_image(&mask)?.to_luma8()),
+                        None => (detected, segment),
+                    }
+                }
+            };
+            let mut inpaint = settings.inpaint.clone();
+            if let Some(padding) = padding {
+                inpaint.padding = padding;
+            }
+            let textless = pipeline::inpaint_blocks(
+                &state,
+                &image,
+                &segment,
+                &blocks,
+                &inpaint,
+                None,
+                &log_progress,
+            )
+            .await?;
+            let export = export.resolve(&settings.export, OutputFormat::from_path(&output))?;
+            save_image(
+                &textless,
+                &input,
+                &output,
+                &export,
+                &source.metadata,
+                &backup,
+            )
+        }
+        Command::Render {
+            input,
+            blocks,
+            output,
+            method,
+            mask,
+            font,
+            export,
+        } => {
+            let source = open_source(&input)?;
+            let mut blocks = read_blocks(&blocks)?;
+            let method = method.unwrap_or(settings.render.method.clone());
+            pipeline::validate_render_method(&method)
+                .map_err(|err| coded(ErrorCode::InvalidInput, err.to_string()))?;
+            let font = font.unwrap_or(settings.render.default_font.clone());
+            let base = match (method.as_str(), mask) {
+                ("auto", Some(mask)) => {
+                    let mask = open_image(&mask)?.to_luma8();
+                    let bboxes: Vec<_> = blocks.iter().map(PageBlock::bbox).collect();
+                    pipeline::fill_plain_blocks(source.image, &mask, &bboxes)
+                }
+                _ => source.image,
+            };
+            let rendered = pipeline::typeset_and_render(base, &mut blocks, &method, &font)?;
+            let export = export.resolve(&settings.export, OutputFormat::from_path(&output))?;
+            save_image(
+                &rendered,
+                &input,
+                &output,
+                &export,
+                &source.metadata,
+                &backup,
+            )
+        }
+        Command::Pipeline {
+            input,
+            output,
+            translator,
+            export,
+        } => {
+            let options = process_options(&settings, &config, &translator)?;
+            let source = open_source(&input)?;
+            let result =
+                pipeline::run_page(&state, &source.image, &options, None, &log_progress).await?;
+            let export = export.resolve(&settings.export, OutputFormat::from_path(&output))?;
+            save_image(
+                &result.rendered,
+                &input,
+                &output,
+                &export,
+                &source.metadata,
+                &backup,
+            )
+        }
+        Command::ProcessFolder {
+            input,
+            output,
+            workers,
+            format,
+            name_template,
+            title,
+            chapter,
+            watch,
+            translator,
+            export,
+        } => {
+            let mut options = process_options(&settings, &config, &translator)?;
+            let format = match format {
+                Some(format) => Some(
+                    OutputFormat::from_extension(&format)
+                        .ok_or_else(|| anyhow!("Unsupported output format '{}'", format))?,
+                ),
+                None => None,
+            };
+            options.export = export.resolve(&settings.export, format)?;
+            if name_template.is_some() {
+                options.export.filename_template = name_template;
+            }
+            if title.is_some() {
+                options.export.metadata.title = title;
+            }
+            if chapter.is_some() {
+                options.export.metadata.chapter = chapter;
+            }
+            if watch && same_dir(&input, &output) {
+                return Err(anyhow!(
+                    "--watch needs an output folder other than the input folder"
+                ));
+            }
+            let summary = process_folder(&state, &input, &output, &options, workers).await?;
+            println!(
+                "{}/{} page(s) processed in {}ms",
+                summary.succeeded, summary.total, summary.elapsed_ms
+            );
+            if watch {
+                return watch_folder(&state, &input, &output, &options, &backup, summary.total)
+                    .await;
+            }
+            if summary.failed.is_empty() {
+                Ok(())
+            } else {
+                Err(anyhow!("{} page(s) failed", summary.failed.len()))
+            }
+        }
+    }
+}
+
+async fn process_folder(
+    state: &AppState,
+    input: &Path,
+    output: &Path,
+    options: &ProcessOptions,
+    workers: usize,
+) -> anyhow::Result<batch::FolderSummary> {
+    let bar = progress_bar(batch::list_images(input)?.len(), "pages");
+    let on_event = |event: batch::FolderPageEvent| {
+        tracing::info!(
+            "[headless] {} {} ({}/{})",
+            event.status.as_str(),
+            event.file,
+            event.index + 1,
+            event.total
+        );
+        match event.status {
+            batch::PageStatus::Started => bar.set_message(event.file),
+            batch::PageStatus::Completed => bar.inc(1),
+            batch::PageStatus::Failed => {
+                bar.println(format!(
+                    "failed: {}: {}",
+                    event.file,
+                    event.error.unwrap_or_default()
+                ));
+                bar.inc(1);
+            }
+        }
+    };
+    let summary =
+        batch::process_folder(state, input, output, options, workers, None, &on_event).await;
+    bar.finish_and_clear();
+    summary
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Process images added to `input` until Ctrl+C; a failing page is reported and skipped
+async fn watch_folder(
+    state: &AppState,
+    input: &Path,
+    output: &Path,
+    options: &ProcessOptions,
+    backup: &Backup,
+    processed: usize,
+) -> anyhow::Result<()> {
+    let existing = batch::list_images(input)?;
+    let mut watcher = FolderWatcher::new(input, &existing, watch::SETTLE_TIME)?;
+    eprintln!("Watching {:?} for new pages (Ctrl+C to stop)", input);
+
+    // Ctrl+C also abandons the page in progress
+    let stop = tokio::signal::ctrl_c();
+    tokio::pin!(stop);
+    let mut index = processed;
+    loop {
+        let file = tokio::select! {
+            file = watcher.next() => match file {
+                Some(file) => file,
+                None => return Err(anyhow!("Stopped watching {:?}", input)),
+            },
+            _ = &mut stop => return Ok(()),
+        };
+        let page = batch::process_file(state, &file, index, output, options, backup, None);
+        let result = tokio::select! {
+            result = page => result,
+            _ = &mut stop => return Ok(()),
+        };
+        match result {
+            Ok(path) => println!("{} -> {}", file.display(), path.display()),
+            Err(err) => eprintln!("failed: {}: {:#}", file.display(), err),
+        }
+        index += 1;
+    }
+}
+
+/// Progress bar on stderr; hidden when stderr isn't a terminal
+fn progress_bar(total: usize, unit: &str) -> ProgressBar {
+    let bar = ProgressBar::new(total as u64);
+    let template = format!(
+        "[{{elapsed_precise}}] {{bar:40}} {{pos}}/{{len}} {} (eta {{eta}}) {{msg}}",
+        unit
+    );
+    if let Ok(style) = ProgressStyle::with_template(&template) {
+        bar.set_style(style);
+    }
+    bar
+}
+
+/// Save every block of `image` as its own PNG, e.g. as input for `ocr --crops`
+fn save_crops(image: &image::DynamicImage, blocks: &[PageBlock], dir: &Path) -> anyhow::Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    for (index, block) in blocks.iter().enumerate() {
+        let crop = match crop_to_bbox(image, &block.bbox()) {
+            Ok(crop) => crop,
+            Err(err) => {
+                tracing::warn!("[headless] skipping crop {}: {}", index, err);
+                continue;
+            }
+        };
+        let path = dir.join(format!(
+            "{:03}_class{}_{:.2}.png",
+            index, block.class, block.confidence
+        ));
+        crop.save(&path)
+            .with_context(|| format!("Failed to write {:?}", path))?;
+    }
+    Ok(())
+}
+
+/// Images in a folder (non-recursive) or matching a glob pattern, sorted by path
+fn crop_inputs(input: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = if input.is_dir() {
+        fs::read_dir(input)
+            .with_context(|| format!("Failed to read {:?}", input))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file() && image_io::is_supported_input(path))
+            .collect::<Vec<_>>()
+    } else {
+        glob::glob(&input.to_string_lossy())
+            .with_context(|| format!("Invalid pattern {:?}", input))?
+            .filter_map(Result::ok)
+            .filter(|path| path.is_file())
+            .collect()
+    };
+    paths.sort();
+    if paths.is_empty() {
+        return Err(coded(
+            ErrorCode::InvalidInput,
+            format!("No images found for {:?}", input),
+        ));
+    }
+    Ok(paths)
+}
+
+/// OCR each crop with the active engine, `jobs` at a time; a failing crop is reported, not fatal
+async fn recognize_crops(
+    state: &AppState,
+    input: &Path,
+    jobs: usize,
+) -> anyhow::Result<Vec<CropText>> {
+    let files = crop_inputs(input)?;
+    let bar = progress_bar(files.len(), "crops");
+    let bar = &bar;
+
+    // `buffered` keeps the input order, so the table lines up with the sorted file list
+    let results = stream::iter(files)
+        .map(|file| async move {
+            let recognized = match open_image(&file) {
+                Ok(image) => pipeline::recognize_crop(state, &image).await,
+                Err(err) => Err(err),
+            };
+            let (text, error) = match recognized {
+                Ok(text) => (Some(text), None),
+                Err(err) => {
+                    bar.println(format!("failed: {}: {:#}", file.display(), err));
+                    (None, Some(format!("{:#}", err)))
+                }
+            };
+            bar.inc(1);
+            CropText { file, text, error }
+        })
+        .buffered(jobs.max(1))
+        .collect::<Vec<_>>()
+        .await;
+
+    bar.finish_and_clear();
+    Ok(results)
+}
+
+/// `file<TAB>text` lines with tabs and newlines escaped; failed crops are left out
+fn crop_table(results: &[CropText]) -> String {
+    let escape = |value: &str| {
+        value
+            .replace('\\', "\\\\")
+            .replace('\t', "\\t")
+            .replace('\n', "\\n")
+            .replace('\r', "\\r")
+    };
+    let mut table = String::from("file\ttext\n");
+    for result in results {
+        if let Some(text) = &result.text {
+            table.push_str(&escape(&result.file.to_string_lossy()));
+            table.push('\t');
+            table.push_str(&escape(text));
+            table.push('\n');
+        }
+    }
+    table
+}
+
+fn log_progress(progress: StageProgress) {
+    tracing::debug!(
+        "[headless] {} {}/{}",
+        progress.stage.as_str(),
+        progress.current,
+        progress.total
+    );
+}
+
+/// Saved settings with the `--project` and then the `--config` overrides applied
+async fn load_settings(
+    store: &SettingsStore,
+    project: Option<&Path>,
+    config: &CliConfig,
+) -> anyhow::Result<Settings> {
+    let mut settings = store.get().await;
+    if let Some(path) = project {
+        let open = Project::open(path)?;
+        tracing::info!("[headless] applying project {:?}", open.path);
+        open.project.apply(&mut settings);
+    }
+    config.apply(&mut settings)?;
+    Ok(settings)
+}
+
+/// Saved translation settings with the command line overrides applied
+fn translation_settings(
+    args: &TranslatorArgs,
+    saved: &crate::settings::TranslationSettings,
+) -> crate::settings::TranslationSettings {
+    let mut settings = saved.clone();
+    if let Some(provider) = &args.provider {
+        settings.provider = provider.clone();
+    }
+    if let Some(api_key) = &args.api_key {
+        settings.deepl_api_key = Some(api_key.clone());
+    }
+    if let Some(model) = &args.model {
+        settings.ollama_model = model.clone();
+    }
+    if args.source_lang.is_some() {
+        settings.source_lang = args.source_lang.clone();
+    }
+    if args.target_lang.is_some() {
+        settings.target_lang = args.target_lang.clone();
+    }
+    settings
+}
+
+/// OCR configuration from an optional JSON file, with the engine flag taking precedence
+fn ocr_config(path: Option<&Path>, engine: Option<String>) -> anyhow::Result<OcrConfig> {
+    let mut config = match path {
+        Some(path) => {
+            let json =
+                fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+            serde_json::from_str(&json)
+                .with_context(|| format!("Invalid OCR configuration {:?}", path))?
+        }
+        None => OcrConfig::default(),
+    };
+    if engine.is_some() {
+        config.engine = engine;
+    }
+    Ok(config)
+}
+
+/// Translator from CLI flags, falling back to the provider saved in settings
+fn resolve_translator(
+    args: &TranslatorArgs,
+    saved: &crate::settings::TranslationSettings,
+) -> anyhow::Result<TranslatorConfig> {
+    let settings = translation_settings(args, saved);
+    settings.translator().ok_or_else(|| {
+        anyhow!(
+            "Provider '{}' is not available headless; use --provider deepl-free, deepl-pro (with --api-key), or ollama",
+            settings.provider
+        )
+    })
+}
+
+fn process_options(
+    settings: &crate::settings::Settings,
+    config: &CliConfig,
+    translator: &TranslatorArgs,
+) -> anyhow::Result<ProcessOptions> {
+    let mut options = settings.process_options();
+    if let Some(threshold) = config.detect.confidence_threshold {
+        options.confidence_threshold = threshold;
+    }
+    if let Some(threshold) = config.detect.nms_threshold {
+        options.nms_threshold = threshold;
+    }
+    options.translator = match resolve_translator(translator, &settings.translation) {
+        Ok(translator) => Some(translator),
+        // Without an explicit provider, a page can still be cleaned without translation
+        Err(err) if translator.provider.is_none() => {
+            tracing::warn!("[headless] {:#}; skipping translation", err);
+            None
+        }
+        Err(err) => return Err(err),
+    };
+    Ok(options)
+}
+
+/// Comparison mode and still format implied by the output extension
+fn comparison_options(output: &Path, animate: bool) -> anyhow::Result<ComparisonOptions> {
+    // stdout has no extension: PNG stills, GIF animations
+    let extension = match (is_stdio(output), animate) {
+        (true, false) => "png".to_string(),
+        (true, true) => "gif".to_string(),
+        (false, _) => output
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default(),
+    };
+    let mode = match (animate, extension.as_str()) {
+        (true, "gif") => ComparisonMode::AnimatedGif,
+        (true, "webp") => ComparisonMode::AnimatedWebp,
+        (true, _) => {
+            return Err(anyhow!(
+                "Animated comparisons must be written as .gif or .webp"
+            ));
+        }
+        (false, _) => ComparisonMode::SideBySide,
+    };
+    let format = match mode {
+        ComparisonMode::SideBySide => OutputFormat::from_extension(&extension)
+            .ok_or_else(|| anyhow!("Unsupported output extension '{}'", extension))?,
+        _ => OutputFormat::Png,
+    };
+    Ok(ComparisonOptions {
+        mode,
+        format,
+        ..ComparisonOptions::default()
+    })
+}
+
+/// `-` in place of a path: read from stdin or write to stdout
+fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == STDIO
+}
+
+fn read_input(path: &Path) -> anyhow::Result<Vec<u8>> {
+    if is_stdio(path) {
+        let mut bytes = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut bytes)
+            .context("Failed to read stdin")?;
+        Ok(bytes)
+    } else {
+        fs::read(path).with_context(|| format!("Failed to read {:?}", path))
+    }
+}
+
+fn write_output(path: &Path, bytes: &[u8]) -> anyhow::Result<()> {
+    if is_stdio(path) {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(bytes).context("Failed to write stdout")?;
+        stdout.flush().context("Failed to write stdout")
+    } else {
+        fs::write(path, bytes).with_context(|| format!("Failed to write {:?}", path))
+    }
+}
+
+fn open_source(path: &Path) -> anyhow::Result<image_io::Decoded> {
+    if is_stdio(path) {
+        image_io::decode_with_metadata(&read_input(path)?).context("Failed to load stdin")
+    } else {
+        image_io::open_with_metadata(path)
+    }
+}
+
+fn open_image(path: &Path) -> anyhow::Result<image::DynamicImage> {
+    open_source(path).map(|decoded| decoded.image)
+}
+
+fn save_image(
+    image: &image::DynamicImage,
+    input: &Path,
+    path: &Path,
+    export: &ExportOptions,
+    source: &SourceMetadata,
+    backup: &Backup,
+) -> anyhow::Result<()> {
+    // stdout gets the export format from settings or --config, PNG by default
+    if is_stdio(path) {
+        return write_output(path, &image_io::encode(image, export, source)?);
+    }
+    if let Some(source_dir) = input.parent() {
+        backup.protect(path, source_dir)?;
+    }
+    image_io::save_as_extension(image, path, export, source)
+}
+
+fn read_blocks(path: &Path) -> anyhow::Result<Vec<PageBlock>> {
+    let contents = read_input(path)?;
+    serde_json::from_slice(&contents).map_err(|err| {
+        with_code(
+            err.into(),
+            ErrorCode::InvalidInput,
+            format!("Invalid blocks JSON in {:?}", path),
+        )
+    })
+}
+
+/// Regions for `inpaint --blocks`
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum InpaintRegionsFile {
+    Blocks(Vec<BBox>), // `detect` output; the other block fields are not needed
+    Detections {
+        #[serde(alias = "blocks")]
+        bboxes: Vec<BBox>,
+        mask: Option<PathBuf>,
+    },
+}
+
+struct InpaintRegions {
+    bboxes: Vec<BBox>,
+    mask: Option<PathBuf>, // Resolved against the JSON's folder
+}
+
+impl InpaintRegions {
+    fn blocks(&self) -> Vec<PageBlock> {
+        self.bboxes
+            .iter()
+            .map(|bbox| {
+                PageBlock::from_detection(&comic_text_detector::ClassifiedBbox {
+                    xmin: bbox.xmin,
+                    ymin: bbox.ymin,
+                    xmax: bbox.xmax,
+                    ymax: bbox.ymax,
+                    confidence: 1.0,
+                    class: 0,
+                })
+            })
+            .collect()
+    }
+}
+
+fn read_inpaint_regions(path: &Path) -> anyhow::Result<InpaintRegions> {
+    let contents = read_input(path)?;
+    let file: InpaintRegionsFile = serde_json::from_slice(&contents).map_err(|err| {
+        with_code(
+            err.into(),
+            ErrorCode::InvalidInput,
+            format!("Invalid blocks JSON in {:?}", path),
+        )
+    })?;
+    Ok(match file {
+        InpaintRegionsFile::Blocks(bboxes) => InpaintRegions { bboxes, mask: None },
+        InpaintRegionsFile::Detections { bboxes, mask } => {
+            let dir = path.parent().filter(|_| !is_stdio(path));
+            InpaintRegions {
+                bboxes,
+                mask: mask.map(|mask| match dir {
+                    Some(dir) => dir.join(mask),
+                    None => mask,
+                }),
+            }
+        }
+    })
+}
+
+/// Write `text` to `path`, or stdout when no path is given
+fn write_text(path: Option<&Path>, text: &str) -> anyhow::Result<()> {
+    write_output(path.unwrap_or(Path::new(STDIO)), text.as_bytes())
+}
+
+/// Write pretty JSON to `path`, or stdout when no path is given
+fn write_json<T: serde::Serialize>(path: Option<&Path>, value: &T) -> anyhow::Result<()> {
+    let json = serde_json::to_string_pretty(value)? + "\n";
+    write_output(path.unwrap_or(Path::new(STDIO)), json.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_parses_subcommands() {
+        let cli = Cli::try_parse_from([
+            "koharu",
+            "process-folder",
+            "-i",
+            "in",
+            "-o",
+            "out",
+            "--provider",
+            "ollama",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::ProcessFolder { workers: 2, .. }
+        ));
+
+        let cli = Cli::try_parse_from([
+            "koharu",
+            "process-folder",
+            "-i",
+            "in",
+            "-o",
+            "out",
+            "-j",
+            "4",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::ProcessFolder { workers: 4, .. }
+        ));
+    }
+
+    #[test]
+    fn test_detect_draw_flags_need_draw() {
+        let cli = Cli::try_parse_from([
+            "koharu",
+            "detect",
+            "-i",
+            "in.png",
+            "-o",
+            "-",
+            "--draw",
+            "qa.png",
+            "--labels",
+            "--ordered",
+        ])
+        .unwrap();
+        let Command::Detect { draw, .. } = cli.command else {
+            panic!("expected detect");
+        };
+        let options = draw.options();
+        assert!(options.labels && options.ordered && options.class_colors);
+        assert_eq!(options.mask_alpha, 0.4);
+
+        assert!(
+            Cli::try_parse_from(["koharu", "detect", "-i", "in.png", "-o", "-", "--labels"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_pipeline_keeps_process_alias_and_ocr_takes_crops() {
+        let cli =
+            Cli::try_parse_from(["koharu", "process", "-i", "in.png", "-o", "out.png"]).unwrap();
+        assert!(matches!(cli.command, Command::Pipeline { .. }));
+
+        let cli = Cli::try_parse_from([
+            "koharu", "ocr", "-i", "crops/", "--crops", "--format", "tsv",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::Ocr {
+                crops: true,
+                format: TableFormat::Tsv,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_inpaint_regions_read_detect_output_and_lama_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let detected = dir.path().join("blocks.json");
+        fs::write(
+            &detected,
+            r#"[{"xmin": 1, "ymin": 2, "xmax": 30, "ymax": 40, "confidence": 0.9, "class": 0, "text": ""}]"#,
+        )
+        .unwrap();
+        let regions = read_inpaint_regions(&detected).unwrap();
+        assert_eq!(regions.blocks().len(), 1);
+        assert_eq!(regions.blocks()[0].xmax, 30.0);
+        assert!(regions.mask.is_none());
+
+        let lama = dir.path().join("detections.json");
+        fs::write(
+            &lama,
+            r#"{"bboxes": [{"xmin": 0, "ymin": 0, "xmax": 8, "ymax": 8}], "mask": "segment.png"}"#,
+        )
+        .unwrap();
+        let regions = read_inpaint_regions(&lama).unwrap();
+        assert_eq!(regions.bboxes.len(), 1);
+        assert_eq!(regions.mask, Some(dir.path().join("segment.png")));
+
+        let cli = Cli::try_parse_from([
+            "koharu",
+            "inpaint",
+            "-i",
+            "in.png",
+            "-o",
+            "out.png",
+            "--blocks",
+            "b.json",
+            "--mask",
+            "m.png",
+            "--padding",
+            "30",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::Inpaint {
+                padding: Some(30),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_crop_table_escapes_and_skips_failures() {
+        let results = [
+            CropText {
+                file: PathBuf::from("001.png"),
+                text: Some("一行目\n二行目".to_string()),
+                error: None,
+            },
+            CropText {
+                file: PathBuf::from("002.png"),
+                text: None,
+                error: Some("broken".to_string()),
+            },
+        ];
+        assert_eq!(
+            crop_table(&results),
+            "file\ttext\n001.png\t一行目\\n二行目\n"
+        );
+    }
+
+    #[test]
+    fn test_exit_status_groups_error_codes() {
+        assert_eq!(
+            ExitStatus::of(ErrorCode::ModelMissing),
+            ExitStatus::ModelMissing
+        );
+        assert_eq!(
+            ExitStatus::of(ErrorCode::FileNotFound),
+            ExitStatus::BadInput
+        );
+        assert_eq!(
+            ExitStatus::of(ErrorCode::InferenceFailed),
+            ExitStatus::InferenceFailed
+        );
+        assert_eq!(ExitStatus::of(ErrorCode::RateLimited), ExitStatus::Network);
+        assert_eq!(ExitStatus::of(ErrorCode::Unknown), ExitStatus::Failure);
+
+        let err = read_blocks(Path::new("missing-blocks.json")).unwrap_err();
+        assert_eq!(ExitStatus::of(error_code(&err)), ExitStatus::BadInput);
+    }
+
+    #[test]
+    fn test_detect_flags_override_config() {
+        let config = DetectConfig {
+            confidence_threshold: Some(0.3),
+            nms_threshold: Some(0.6),
+        };
+        let args = DetectArgs {
+            confidence_threshold: Some(0.7),
+            nms_threshold: None,
+        };
+        assert_eq!(args.thresholds(&config), (0.7, 0.6));
+        assert_eq!(args.thresholds(&DetectConfig::default()), (0.7, 0.4));
+    }
+
+    #[test]
+    fn test_export_args_override_saved_options() {
+        let args = ExportArgs {
+            quality: Some(70),
+            png_compression: Some("best".to_string()),
+            max_dimension: None,
+        };
+        let saved = ExportOptions {
+            max_dimension: Some(1600),
+            ..ExportOptions::default()
+        };
+        let options = args.resolve(&saved, Some(OutputFormat::Jpeg)).unwrap();
+        assert_eq!(options.quality, Some(70));
+        assert_eq!(options.png_compression, image_io::PngCompression::Best);
+        assert_eq!(options.max_dimension, Some(1600));
+
+        // WebP is lossless, so an explicit quality is an error rather than ignored
+        assert!(args.resolve(&saved, Some(OutputFormat::Webp)).is_err());
+
+        let args = ExportArgs {
+            quality: None,
+            png_compression: Some("maximum".to_string()),
+            ..args
+        };
+        assert!(args.resolve(&saved, None).is_err());
+    }
+
+    #[test]
+    fn test_comparison_options_follow_output_extension() {
+        let options = comparison_options(Path::new("qc/page.jpg"), false).unwrap();
+        assert_eq!(options.mode, ComparisonMode::SideBySide);
+        assert_eq!(options.format, OutputFormat::Jpeg);
+
+        let options = comparison_options(Path::new("preview.WEBP"), true).unwrap();
+        assert_eq!(options.mode, ComparisonMode::AnimatedWebp);
+
+        assert!(comparison_options(Path::new("preview.png"), true).is_err());
+
+        let options = comparison_options(Path::new("-"), false).unwrap();
+        assert_eq!(options.format, OutputFormat::Png);
+        let options = comparison_options(Path::new("-"), true).unwrap();
+        assert_eq!(options.mode, ComparisonMode::AnimatedGif);
+    }
+
+    #[test]
+    fn test_resolve_translator_overrides_settings() {
+        let args = TranslatorArgs {
+            provider: Some("ollama".to_string()),
+            api_key: None,
+            model: Some("qwen2.5".to_string()),
+            source_lang: None,
+            target_lang: None,
+        };
+        let translator =
+            resolve_translator(&args, &crate::settings::TranslationSettings::default()).unwrap();
+        assert!(matches!(
+            translator,
+            TranslatorConfig::Ollama { ref model, .. } if model == "qwen2.5"
+        ));
+    }
+
+    #[test]
+    fn test_resolve_translator_rejects_google() {
+        let args = TranslatorArgs {
+            provider: None,
+            api_key: None,
+            model: None,
+            source_lang: None,
+            target_lang: None,
+        };
+        assert!(
+            resolve_translator(&args, &crate::settings::TranslationSettings::default()).is_err()
+        );
+    }
+}
@@ -0,0 +1,400 @@
+// Undo/redo history for editor state: text blocks, segmentation mask, and inpainted image
+// Lives in AppState so history survives frontend reloads; memory is bounded by entry count and bytes.
+// Each page keeps its own history, so undo never reaches into another page.
+
+use image::{DynamicImage, GrayImage};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use crate::pipeline::encode_png;
+
+const DEFAULT_MAX_ENTRIES: usize = 100;
+const DEFAULT_MAX_BYTES: usize = 512 * 1024 * 1024;
+const MAX_PAGES: usize = 8; // Least recently edited pages beyond this lose their history
+
+/// Current editor document; components are shared between history entries
+///
+/// Blocks are kept as the frontend's JSON so every field round-trips, including
+/// ones the backend does not model.
+#[derive(Debug, Clone, Default)]
+pub struct EditorState {
+    pub blocks: Arc<Vec<Value>>,
+    pub mask: Option<Arc<GrayImage>>,
+    pub inpainted: Option<Arc<DynamicImage>>,
+}
+
+/// Editor state sent to the frontend
+///
+/// Images are only included when they changed (or were explicitly requested) to
+/// avoid re-encoding full pages on every block edit.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditorSnapshot {
+    pub blocks: Vec<Value>,
+    pub mask_png: Option<Vec<u8>>,
+    pub inpainted_png: Option<Vec<u8>>,
+    pub has_mask: bool,
+    pub has_inpainted: bool,
+    pub can_undo: bool,
+    pub can_redo: bool,
+    pub undo_label: Option<String>,
+    pub redo_label: Option<String>,
+}
+
+/// A mutation of one component of the editor state
+pub enum Edit {
+    Blocks(Vec<Value>),
+    Mask(GrayImage),
+    Inpainted(DynamicImage),
+}
+
+impl Edit {
+    fn kind(&self) -> &'static str {
+        match self {
+            Edit::Blocks(_) => "blocks",
+            Edit::Mask(_) => "mask",
+            Edit::Inpainted(_) => "inpaint",
+        }
+    }
+}
+
+/// A previous state plus the memory it keeps alive
+#[derive(Debug)]
+struct Entry {
+    label: String,
+    state: EditorState,
+    bytes: usize, // Size of the component this entry alone holds on to
+}
+
+#[derive(Debug)]
+pub struct History {
+    current: EditorState,
+    undo: VecDeque<Entry>,
+    redo: Vec<Entry>,
+    max_entries: usize,
+    max_bytes: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_ENTRIES, DEFAULT_MAX_BYTES)
+    }
+}
+
+fn option_ptr_eq<T>(a: &Option<Arc<T>>, b: &Option<Arc<T>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn blocks_bytes(blocks: &[Value]) -> usize {
+    // Rough estimate; blocks are tiny next to images
+    blocks.len() * 512
+}
+
+fn image_bytes(image: &DynamicImage) -> usize {
+    image.as_bytes().len()
+}
+
+impl History {
+    pub fn new(max_entries: usize, max_bytes: usize) -> Self {
+        Self {
+            current: EditorState::default(),
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            max_entries: max_entries.max(1),
+            max_bytes,
+        }
+    }
+
+    pub fn current(&self) -> &EditorState {
+        &self.current
+    }
+
+    /// Start a fresh document (e.g. a new page was opened), dropping all history
+    pub fn reset(&mut self, state: EditorState) {
+        self.current = state;
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Apply an edit, recording the previous state for undo
+    pub fn apply(&mut self, label: Option<String>, edit: Edit) {
+        let label = label.unwrap_or_else(|| edit.kind().to_string());
+        let previous = self.current.clone();
+
+        let bytes = match edit {
+            Edit::Blocks(blocks) => {
+                self.current.blocks = Arc::new(blocks);
+                blocks_bytes(&previous.blocks)
+            }
+            Edit::Mask(mask) => {
+                self.current.mask = Some(Arc::new(mask));
+                previous
+                    .mask
+                    .as_ref()
+                    .map(|m| m.as_raw().len())
+                    .unwrap_or(0)
+            }
+            Edit::Inpainted(image) => {
+                self.current.inpainted = Some(Arc::new(image));
+                previous.inpainted.as_deref().map(image_bytes).unwrap_or(0)
+            }
+        };
+
+        self.undo.push_back(Entry {
+            label,
+            state: previous,
+            bytes,
+        });
+        self.redo.clear();
+        self.enforce_limits();
+    }
+
+    /// Step back one edit, returning its label
+    pub fn undo(&mut self) -> Option<String> {
+        let entry = self.undo.pop_back()?;
+        let label = entry.label.clone();
+        let state = std::mem::replace(&mut self.current, entry.state);
+        self.redo.push(Entry {
+            label: entry.label,
+            state,
+            bytes: entry.bytes,
+        });
+        Some(label)
+    }
+
+    /// Re-apply the last undone edit, returning its label
+    pub fn redo(&mut self) -> Option<String> {
+        let entry = self.redo.pop()?;
+        let label = entry.label.clone();
+        let state = std::mem::replace(&mut self.current, entry.state);
+        self.undo.push_back(Entry {
+            label: entry.label,
+            state,
+            bytes: entry.bytes,
+        });
+        Some(label)
+    }
+
+    /// Snapshot of the current state; images are encoded only if they differ from `since`
+    pub fn snapshot(&self, since: Option<&EditorState>) -> anyhow::Result<EditorSnapshot> {
+        let current = &self.current;
+
+        let mask_changed = match since {
+            Some(prev) => !option_ptr_eq(&prev.mask, &current.mask),
+            None => true,
+        };
+        let inpainted_changed = match since {
+            Some(prev) => !option_ptr_eq(&prev.inpainted, &current.inpainted),
+            None => true,
+        };
+
+        let mask_png = match &current.mask {
+            Some(mask) if mask_changed => {
+                Some(encode_png(&DynamicImage::ImageLuma8((**mask).clone()))?)
+            }
+            _ => None,
+        };
+        let inpainted_png = match &current.inpainted {
+            Some(image) if inpainted_changed => Some(encode_png(image)?),
+            _ => None,
+        };
+
+        Ok(EditorSnapshot {
+            blocks: current.blocks.as_ref().clone(),
+            mask_png,
+            inpainted_png,
+            has_mask: current.mask.is_some(),
+            has_inpainted: current.inpainted.is_some(),
+            can_undo: !self.undo.is_empty(),
+            can_redo: !self.redo.is_empty(),
+            undo_label: self.undo_label().map(str::to_string),
+            redo_label: self.redo_label().map(str::to_string),
+        })
+    }
+
+    pub fn undo_label(&self) -> Option<&str> {
+        self.undo.back().map(|e| e.label.as_str())
+    }
+
+    pub fn redo_label(&self) -> Option<&str> {
+        self.redo.last().map(|e| e.label.as_str())
+    }
+
+    #[cfg(test)]
+    fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    #[cfg(test)]
+    fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    fn history_bytes(&self) -> usize {
+        self.undo
+            .iter()
+            .chain(self.redo.iter())
+            .map(|e| e.bytes)
+            .sum()
+    }
+
+    /// Drop the oldest undo entries until both limits hold
+    fn enforce_limits(&mut self) {
+        while self.undo.len() > self.max_entries
+            || (self.history_bytes() > self.max_bytes && !self.undo.is_empty())
+        {
+            if let Some(dropped) = self.undo.pop_front() {
+                tracing::debug!(
+                    "[history] dropped oldest entry '{}' ({} bytes)",
+                    dropped.label,
+                    dropped.bytes
+                );
+            }
+        }
+    }
+}
+
+/// Histories keyed by page id, most recently used last
+#[derive(Debug, Default)]
+pub struct PageHistories {
+    pages: VecDeque<(String, History)>,
+}
+
+impl PageHistories {
+    /// History of `page_id`, created empty on first use
+    ///
+    /// Only editing or opening a page should create its history; lookups use
+    /// [`PageHistories::get`] so they cannot push out pages that have one.
+    pub fn page(&mut self, page_id: &str) -> &mut History {
+        let entry = self
+            .pages
+            .iter()
+            .position(|(id, _)| id == page_id)
+            .and_then(|index| self.pages.remove(index))
+            .unwrap_or_else(|| (page_id.to_string(), History::default()));
+        self.pages.push_back(entry);
+        while self.pages.len() > MAX_PAGES {
+            if let Some((id, _)) = self.pages.pop_front() {
+                tracing::debug!("[history] dropped history of page {}", id);
+            }
+        }
+        &mut self.pages.back_mut().expect("page was just pushed").1
+    }
+
+    /// History of `page_id`, if the editor has opened it
+    pub fn get(&self, page_id: &str) -> Option<&History> {
+        self.pages
+            .iter()
+            .find(|(id, _)| id == page_id)
+            .map(|(_, history)| history)
+    }
+
+    /// Mutable history of `page_id`, if the editor has opened it
+    pub fn get_mut(&mut self, page_id: &str) -> Option<&mut History> {
+        self.pages
+            .iter_mut()
+            .find(|(id, _)| id == page_id)
+            .map(|(_, history)| history)
+    }
+
+    /// Current state of `page_id`, if the editor has opened it
+    pub fn current(&self, page_id: &str) -> Option<&EditorState> {
+        self.get(page_id).map(History::current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn blocks(n: usize) -> Vec<Value> {
+        (0..n).map(|i| json!({ "xmin": i })).collect()
+    }
+
+    #[test]
+    fn test_undo_redo_round_trip() {
+        let mut history = History::default();
+        history.apply(None, Edit::Blocks(blocks(1)));
+        history.apply(Some("move block".to_string()), Edit::Blocks(blocks(2)));
+
+        assert_eq!(history.undo_label(), Some("move block"));
+        assert_eq!(history.undo().as_deref(), Some("move block"));
+        assert_eq!(history.current().blocks.len(), 1);
+
+        assert_eq!(history.redo().as_deref(), Some("move block"));
+        assert_eq!(history.current().blocks.len(), 2);
+        assert!(history.redo().is_none());
+    }
+
+    #[test]
+    fn test_new_edit_clears_redo() {
+        let mut history = History::default();
+        history.apply(None, Edit::Blocks(blocks(1)));
+        history.undo();
+        assert_eq!(history.redo_len(), 1);
+
+        history.apply(None, Edit::Mask(GrayImage::new(4, 4)));
+        assert_eq!(history.redo_len(), 0);
+        assert!(history.current().mask.is_some());
+        assert!(history.current().blocks.is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_skips_unchanged_images() {
+        let mut history = History::default();
+        history.apply(None, Edit::Mask(GrayImage::new(4, 4)));
+        let before = history.current().clone();
+        history.apply(None, Edit::Blocks(blocks(1)));
+
+        let snapshot = history.snapshot(Some(&before)).unwrap();
+        assert!(snapshot.mask_png.is_none());
+        assert!(snapshot.has_mask);
+        assert!(snapshot.can_undo);
+
+        let full = history.snapshot(None).unwrap();
+        assert!(full.mask_png.is_some());
+    }
+
+    #[test]
+    fn test_limits_drop_oldest_entries() {
+        let mut history = History::new(3, usize::MAX);
+        for i in 0..5 {
+            history.apply(None, Edit::Blocks(blocks(i)));
+        }
+        assert_eq!(history.undo_len(), 3);
+
+        // Each replaced 10x10 RGBA image holds 400 bytes; only two fit
+        let mut history = History::new(100, 800);
+        for _ in 0..5 {
+            history.apply(None, Edit::Inpainted(DynamicImage::new_rgba8(10, 10)));
+        }
+        assert!(history.history_bytes() <= 800);
+        assert_eq!(history.undo_len(), 2);
+    }
+
+    #[test]
+    fn test_pages_keep_separate_histories() {
+        let mut pages = PageHistories::default();
+        pages.page("a").apply(None, Edit::Blocks(blocks(1)));
+        pages.page("b").apply(None, Edit::Blocks(blocks(2)));
+
+        assert!(pages.get_mut("a").unwrap().undo().is_some());
+        assert!(pages.get_mut("a").unwrap().undo().is_none());
+        assert_eq!(pages.current("b").unwrap().blocks.len(), 2);
+        assert!(pages.get_mut("c").is_none());
+        assert_eq!(pages.pages.len(), 2);
+
+        for i in 0..MAX_PAGES {
+            pages.page(&format!("page-{}", i));
+        }
+        assert!(pages.current("a").is_none());
+        assert_eq!(pages.pages.len(), MAX_PAGES);
+    }
+}
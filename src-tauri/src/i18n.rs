@@ -0,0 +1,130 @@
+// Fluent message catalogs for text the backend sends to the UI (currently error messages)
+// Catalogs are compiled in from src-tauri/locales/<language>/errors.ftl; English is the fallback.
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use std::sync::{OnceLock, RwLock};
+use unic_langid::LanguageIdentifier;
+
+use crate::error::ErrorCode;
+
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en/errors.ftl")),
+    ("ja", include_str!("../locales/ja/errors.ftl")),
+    ("zh-CN", include_str!("../locales/zh-CN/errors.ftl")),
+];
+
+static LANGUAGE: RwLock<&'static str> = RwLock::new(DEFAULT_LANGUAGE);
+static BUNDLES: OnceLock<Vec<(&'static str, FluentBundle<FluentResource>)>> = OnceLock::new();
+
+fn bundles() -> &'static [(&'static str, FluentBundle<FluentResource>)] {
+    BUNDLES.get_or_init(|| {
+        CATALOGS
+            .iter()
+            .map(|(language, source)| {
+                let id: LanguageIdentifier = language.parse().expect("valid catalog language");
+                let resource =
+                    FluentResource::try_new(source.to_string()).unwrap_or_else(|(_, errors)| {
+                        panic!("invalid {} catalog: {:?}", language, errors)
+                    });
+                let mut bundle = FluentBundle::new_concurrent(vec![id]);
+                bundle.set_use_isolating(false); // Messages are shown as plain text, not mixed into RTL
+                bundle
+                    .add_resource(resource)
+                    .expect("catalog has no duplicate messages");
+                (*language, bundle)
+            })
+            .collect()
+    })
+}
+
+/// Best catalog for a BCP 47 tag: exact match, then same base language ("ja-JP" -> "ja")
+pub fn negotiate(requested: &str) -> &'static str {
+    let requested = requested.replace('_', "-");
+    let base = requested.split('-').next().unwrap_or_default();
+    CATALOGS
+        .iter()
+        .find(|(language, _)| language.eq_ignore_ascii_case(&requested))
+        .or_else(|| {
+            CATALOGS.iter().find(|(language, _)| {
+                language
+                    .split('-')
+                    .next()
+                    .is_some_and(|l| l.eq_ignore_ascii_case(base))
+            })
+        })
+        .map(|(language, _)| *language)
+        .unwrap_or(DEFAULT_LANGUAGE)
+}
+
+/// Switch the UI language; returns the catalog actually used
+pub fn set_language(requested: &str) -> &'static str {
+    let language = negotiate(requested);
+    if let Ok(mut current) = LANGUAGE.write() {
+        *current = language;
+    }
+    tracing::info!("[i18n] UI language {} (requested {})", language, requested);
+    language
+}
+
+pub fn language() -> &'static str {
+    LANGUAGE.read().map(|l| *l).unwrap_or(DEFAULT_LANGUAGE)
+}
+
+fn format_message(language: &str, id: &str, args: &FluentArgs) -> Option<String> {
+    let (_, bundle) = bundles().iter().find(|(l, _)| *l == language)?;
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut errors = Vec::new();
+    let text = bundle.format_pattern(pattern, Some(args), &mut errors);
+    if !errors.is_empty() {
+        tracing::warn!("[i18n] {} in {}: {:?}", id, language, errors);
+    }
+    Some(text.into_owned())
+}
+
+/// Localized message for an error code in the current UI language
+pub fn error_message(code: ErrorCode, detail: &str) -> String {
+    let id = format!("error-{}", code.as_str());
+    let mut args = FluentArgs::new();
+    args.set("detail", detail.to_string());
+    format_message(language(), &id, &args)
+        .or_else(|| format_message(DEFAULT_LANGUAGE, &id, &args))
+        .unwrap_or_else(|| detail.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(negotiate("ja-JP"), "ja");
+        assert_eq!(negotiate("zh_CN"), "zh-CN");
+        assert_eq!(negotiate("zh-TW"), "zh-CN");
+        assert_eq!(negotiate("EN-us"), "en");
+        assert_eq!(negotiate("fr"), DEFAULT_LANGUAGE);
+    }
+
+    #[test]
+    fn test_every_catalog_has_every_error_code() {
+        let mut args = FluentArgs::new();
+        args.set("detail", "x");
+        for (language, _) in CATALOGS {
+            for code in ErrorCode::ALL {
+                let id = format!("error-{}", code.as_str());
+                assert!(
+                    format_message(language, &id, &args).is_some_and(|m| !m.is_empty()),
+                    "{} is missing {}",
+                    language,
+                    id
+                );
+            }
+        }
+        assert_eq!(
+            format_message("en", "error-unknown", &args).as_deref(),
+            Some("x")
+        );
+    }
+}
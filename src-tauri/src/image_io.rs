@@ -0,0 +1,444 @@
+// Image decoding/encoding shared by every load and export path
+// Adds JPEG XL input (jxl-oxide) on top of the image crate; AVIF input needs the `avif-decode` feature (dav1d).
+// Pixels are never color-converted: they stay in the source's encoding and the source ICC profile is
+// re-embedded on export, so wide-gamut scans survive a decode/re-encode round trip.
+// Selected EXIF fields can be carried over the same way (see metadata.rs).
+
+use anyhow::{Context, anyhow};
+use image::codecs::avif::AvifEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::codecs::webp::WebPEncoder;
+use image::imageops::FilterType as ResizeFilter;
+use image::{DynamicImage, ImageDecoder, ImageEncoder, ImageReader, ImageResult};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use crate::metadata::{self, ExportMetadata};
+
+#[cfg(feature = "avif-decode")]
+pub const INPUT_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "webp", "bmp", "avif", "jxl"];
+#[cfg(not(feature = "avif-decode"))]
+pub const INPUT_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "webp", "bmp", "jxl"];
+
+const AVIF_SPEED: u8 = 6;
+const DEFAULT_QUALITY: u8 = 90;
+
+pub fn is_supported_input(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| INPUT_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn is_jxl(bytes: &[u8]) -> bool {
+    // Bare codestream or ISOBMFF container
+    bytes.starts_with(&[0xFF, 0x0A])
+        || bytes.starts_with(&[
+            0x00, 0x00, 0x00, 0x0C, b'J', b'X', b'L', b' ', 0x0D, 0x0A, 0x87, 0x0A,
+        ])
+}
+
+fn is_avif(bytes: &[u8]) -> bool {
+    bytes.len() >= 12 && &bytes[4..8] == b"ftyp" && matches!(&bytes[8..12], b"avif" | b"avis")
+}
+
+/// Metadata from the source file that is carried through to exports
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMetadata {
+    pub icc_profile: Option<Vec<u8>>,
+    pub exif: Option<Vec<u8>>, // Raw TIFF-structured EXIF
+}
+
+impl SourceMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.icc_profile.is_none() && self.exif.is_none()
+    }
+}
+
+/// Decoded pixels plus the metadata embedded in the source file
+#[derive(Debug, Clone)]
+pub struct Decoded {
+    pub image: DynamicImage,
+    pub metadata: SourceMetadata,
+}
+
+fn read_with_metadata(mut decoder: impl ImageDecoder, bytes: &[u8]) -> ImageResult<Decoded> {
+    // Malformed metadata shouldn't make the page unreadable
+    let icc_profile = decoder.icc_profile().ok().flatten();
+    let image = DynamicImage::from_decoder(decoder)?;
+    Ok(Decoded {
+        image,
+        metadata: SourceMetadata {
+            icc_profile,
+            exif: metadata::read_exif(bytes),
+        },
+    })
+}
+
+/// Decode an image from memory, sniffing the format from its contents
+pub fn decode_with_metadata(bytes: &[u8]) -> anyhow::Result<Decoded> {
+    if is_jxl(bytes) {
+        let decoder = jxl_oxide::integration::JxlDecoder::new(Cursor::new(bytes))
+            .context("Failed to read JPEG XL header")?;
+        return read_with_metadata(decoder, bytes).context("Failed to decode JPEG XL image");
+    }
+
+    if is_avif(bytes) && !cfg!(feature = "avif-decode") {
+        return Err(anyhow!(
+            "AVIF input requires a build with the `avif-decode` feature"
+        ));
+    }
+
+    let decoder = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .context("Failed to read image header")?
+        .into_decoder()?;
+    Ok(read_with_metadata(decoder, bytes)?)
+}
+
+pub fn decode(bytes: &[u8]) -> anyhow::Result<DynamicImage> {
+    decode_with_metadata(bytes).map(|decoded| decoded.image)
+}
+
+pub fn open_with_metadata(path: &Path) -> anyhow::Result<Decoded> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+    decode_with_metadata(&bytes).with_context(|| format!("Failed to load {:?}", path))
+}
+
+pub fn open(path: &Path) -> anyhow::Result<DynamicImage> {
+    open_with_metadata(path).map(|decoded| decoded.image)
+}
+
+/// Encoded output format for exported pages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    Jpeg,
+    Webp, // Lossless; the image crate has no lossy WebP encoder, so it takes no quality
+    Avif,
+}
+
+impl OutputFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::Webp => "webp",
+            OutputFormat::Avif => "avif",
+        }
+    }
+
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_ascii_lowercase().as_str() {
+            "png" => Some(OutputFormat::Png),
+            "jpg" | "jpeg" => Some(OutputFormat::Jpeg),
+            "webp" => Some(OutputFormat::Webp),
+            "avif" => Some(OutputFormat::Avif),
+            _ => None,
+        }
+    }
+
+    /// Whether `ExportOptions::quality` applies; PNG and WebP are always lossless
+    pub fn takes_quality(self) -> bool {
+        matches!(self, OutputFormat::Jpeg | OutputFormat::Avif)
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_extension)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PngCompression {
+    Fast,
+    #[default]
+    Default,
+    Best,
+}
+
+/// How exported pages are encoded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportOptions {
+    pub format: OutputFormat,
+    pub quality: Option<u8>, // 1-100, JPEG and AVIF only; None = 90
+    pub png_compression: PngCompression,
+    pub max_dimension: Option<u32>, // Downscale so the longest side fits, e.g. for web releases
+    pub metadata: ExportMetadata,
+    pub filename_template: Option<String>, // Batch output naming, see naming.rs; None keeps the input name
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            format: OutputFormat::Png,
+            quality: None,
+            png_compression: PngCompression::Default,
+            max_dimension: None,
+            metadata: ExportMetadata::default(),
+            filename_template: None,
+        }
+    }
+}
+
+impl ExportOptions {
+    /// Reject a quality the format would ignore instead of silently dropping it
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self.quality {
+            Some(quality) if !self.format.takes_quality() => Err(anyhow!(
+                "{} export is lossless and takes no quality (got {})",
+                self.format.extension().to_uppercase(),
+                quality
+            )),
+            Some(quality) if !(1..=100).contains(&quality) => {
+                Err(anyhow!("Quality must be 1-100, got {}", quality))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Switch to `format`, dropping a quality kept for a format that took one
+    pub fn set_format(&mut self, format: OutputFormat) {
+        if !format.takes_quality() {
+            self.quality = None;
+        }
+        self.format = format;
+    }
+}
+
+/// Shrink `image` so neither side exceeds `max_dimension`; never upscales
+fn fit_within(image: &DynamicImage, max_dimension: Option<u32>) -> Cow<'_, DynamicImage> {
+    match max_dimension {
+        Some(max) if max > 0 && image.width().max(image.height()) > max => {
+            Cow::Owned(image.resize(max, max, ResizeFilter::Lanczos3))
+        }
+        _ => Cow::Borrowed(image),
+    }
+}
+
+fn write_with_profile(
+    image: &DynamicImage,
+    mut encoder: impl ImageEncoder,
+    icc_profile: Option<&[u8]>,
+) -> ImageResult<()> {
+    if let Some(profile) = icc_profile {
+        if encoder.set_icc_profile(profile.to_vec()).is_err() {
+            tracing::warn!("[image] output format can't embed ICC profiles; colors may shift");
+        }
+    }
+    image.write_with_encoder(encoder)
+}
+
+/// Encode `image`, embedding the source's ICC profile and any requested metadata
+pub fn encode(
+    image: &DynamicImage,
+    options: &ExportOptions,
+    source: &SourceMetadata,
+) -> anyhow::Result<Vec<u8>> {
+    let icc_profile = source.icc_profile.as_deref();
+    let image = fit_within(image, options.max_dimension);
+    let quality = options.quality.unwrap_or(DEFAULT_QUALITY).clamp(1, 100);
+
+    let mut bytes = Vec::new();
+    match options.format {
+        OutputFormat::Png => {
+            let compression = match options.png_compression {
+                PngCompression::Fast => CompressionType::Fast,
+                PngCompression::Default => CompressionType::Default,
+                PngCompression::Best => CompressionType::Best,
+            };
+            let encoder =
+                PngEncoder::new_with_quality(&mut bytes, compression, FilterType::Adaptive);
+            write_with_profile(&image, encoder, icc_profile)
+        }
+        // JPEG has no alpha channel
+        OutputFormat::Jpeg => write_with_profile(
+            &DynamicImage::ImageRgb8(image.to_rgb8()),
+            JpegEncoder::new_with_quality(&mut bytes, quality),
+            icc_profile,
+        ),
+        OutputFormat::Webp => write_with_profile(
+            &DynamicImage::ImageRgba8(image.to_rgba8()),
+            WebPEncoder::new_lossless(&mut bytes),
+            icc_profile,
+        ),
+        OutputFormat::Avif => write_with_profile(
+            &image,
+            AvifEncoder::new_with_speed_quality(&mut bytes, AVIF_SPEED, quality),
+            icc_profile,
+        ),
+    }
+    .with_context(|| format!("Failed to encode {}", options.format.extension()))?;
+
+    metadata::embed(
+        bytes,
+        options.format,
+        &options.metadata,
+        source.exif.as_deref(),
+    )
+}
+
+pub fn save(
+    image: &DynamicImage,
+    path: &Path,
+    options: &ExportOptions,
+    source: &SourceMetadata,
+) -> anyhow::Result<()> {
+    let bytes = encode(image, options, source)?;
+    fs::write(path, bytes).with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// Save with the format implied by the path's extension, keeping the other options
+pub fn save_as_extension(
+    image: &DynamicImage,
+    path: &Path,
+    options: &ExportOptions,
+    source: &SourceMetadata,
+) -> anyhow::Result<()> {
+    let format = OutputFormat::from_path(path).ok_or_else(|| {
+        anyhow!(
+            "Unsupported output extension for {:?} (expected png, jpg, webp or avif)",
+            path
+        )
+    })?;
+    let options = ExportOptions {
+        format,
+        ..options.clone()
+    };
+    save(image, path, &options, source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> DynamicImage {
+        let mut image = image::RgbaImage::new(8, 8);
+        for (x, y, pixel) in image.enumerate_pixels_mut() {
+            *pixel = image::Rgba([(x * 30) as u8, (y * 30) as u8, 128, 255]);
+        }
+        DynamicImage::ImageRgba8(image)
+    }
+
+    #[test]
+    fn test_lossless_formats_round_trip() {
+        let image = sample();
+        for format in [OutputFormat::Png, OutputFormat::Webp] {
+            let options = ExportOptions {
+                format,
+                ..ExportOptions::default()
+            };
+            let decoded =
+                decode(&encode(&image, &options, &SourceMetadata::default()).unwrap()).unwrap();
+            assert_eq!(decoded.to_rgba8(), image.to_rgba8(), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_jpeg_drops_alpha() {
+        let options = ExportOptions {
+            format: OutputFormat::Jpeg,
+            ..ExportOptions::default()
+        };
+        let decoded =
+            decode(&encode(&sample(), &options, &SourceMetadata::default()).unwrap()).unwrap();
+        assert_eq!(decoded.width(), 8);
+        assert!(!decoded.color().has_alpha());
+    }
+
+    #[test]
+    fn test_icc_profile_round_trips() {
+        let profile = b"not a real profile, but the bytes must survive".to_vec();
+        for format in [OutputFormat::Png, OutputFormat::Jpeg, OutputFormat::Webp] {
+            let options = ExportOptions {
+                format,
+                ..ExportOptions::default()
+            };
+            let source = SourceMetadata {
+                icc_profile: Some(profile.clone()),
+                exif: None,
+            };
+            let bytes = encode(&sample(), &options, &source).unwrap();
+            let decoded = decode_with_metadata(&bytes).unwrap();
+            assert_eq!(
+                decoded.metadata.icc_profile,
+                Some(profile.clone()),
+                "{:?}",
+                format
+            );
+        }
+
+        let bytes = encode(
+            &sample(),
+            &ExportOptions::default(),
+            &SourceMetadata::default(),
+        );
+        let decoded = decode_with_metadata(&bytes.unwrap()).unwrap();
+        assert!(decoded.metadata.is_empty());
+    }
+
+    #[test]
+    fn test_max_dimension_downscales_without_upscaling() {
+        let options = ExportOptions {
+            max_dimension: Some(4),
+            ..ExportOptions::default()
+        };
+        let decoded =
+            decode(&encode(&sample(), &options, &SourceMetadata::default()).unwrap()).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (4, 4));
+
+        let options = ExportOptions {
+            max_dimension: Some(100),
+            ..ExportOptions::default()
+        };
+        let decoded =
+            decode(&encode(&sample(), &options, &SourceMetadata::default()).unwrap()).unwrap();
+        assert_eq!(decoded.width(), 8);
+    }
+
+    #[test]
+    fn test_quality_only_for_lossy_formats() {
+        let mut options = ExportOptions {
+            format: OutputFormat::Jpeg,
+            quality: Some(75),
+            ..ExportOptions::default()
+        };
+        assert!(options.validate().is_ok());
+
+        options.format = OutputFormat::Webp;
+        assert!(options.validate().is_err());
+        options.set_format(OutputFormat::Webp);
+        assert_eq!(options.quality, None);
+        assert!(options.validate().is_ok());
+
+        options.set_format(OutputFormat::Avif);
+        options.quality = Some(0);
+        assert!(options.validate().is_err());
+    }
+
+    #[test]
+    fn test_format_sniffing() {
+        assert!(is_jxl(&[0xFF, 0x0A, 0x00]));
+        assert!(is_avif(b"\x00\x00\x00\x1cftypavif\x00\x00"));
+        assert!(!is_avif(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0d"));
+        assert!(is_supported_input(Path::new("page.JXL")));
+        assert_eq!(
+            is_supported_input(Path::new("page.avif")),
+            cfg!(feature = "avif-decode")
+        );
+        assert_eq!(
+            OutputFormat::from_extension("JPEG"),
+            Some(OutputFormat::Jpeg)
+        );
+        assert_eq!(OutputFormat::from_path(Path::new("out.txt")), None);
+    }
+}
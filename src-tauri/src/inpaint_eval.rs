@@ -0,0 +1,305 @@
+// Inpainting quality on known-clean pages: text-shaped holes are erased from each page, the
+// page is inpainted, and the fill is scored against the original with SSIM, GMSD, and PSNR.
+// A page can bring its own `<name>.mask.png` (white = hole); otherwise a fixed synthetic
+// layout of text columns is used, so runs with different inpaint settings stay comparable.
+
+use image::{DynamicImage, GrayImage, Luma, Rgba};
+use imageproc::region_labelling::{Connectivity, connected_components};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::accuracy::InpaintMetrics;
+use crate::batch;
+use crate::image_io;
+use crate::ocr_eval::SkippedSample;
+use crate::pipeline::{self, PageBlock, StageProgress};
+use crate::stages::InpaintConfig;
+use crate::state::AppState;
+use crate::visualize;
+
+const BLOCK_MARGIN: f32 = 12.0; // Strokes closer than twice this join one block
+const MASK_SUFFIX: &str = "mask";
+
+/// Synthetic text regions as fractions of the page (x, y, width, height)
+const SYNTHETIC_REGIONS: [(f32, f32, f32, f32); 3] = [
+    (0.12, 0.08, 0.22, 0.24),
+    (0.60, 0.35, 0.18, 0.28),
+    (0.30, 0.70, 0.26, 0.18),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MaskSource {
+    Synthetic,
+    File,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InpaintSample {
+    pub file: String,
+    pub mask: MaskSource,
+    pub blocks: usize,
+    #[serde(flatten)]
+    pub metrics: InpaintMetrics, // Masked area only
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InpaintEvaluation {
+    pub config: InpaintConfig,
+    pub samples: Vec<InpaintSample>,
+    pub average_ssim: f64,
+    pub average_gmsd: f64,
+    pub average_psnr: f64,
+    pub skipped: Vec<SkippedSample>,
+    pub elapsed_ms: u64,
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn is_mask(path: &Path) -> bool {
+    path.file_stem()
+        .and_then(|stem| Path::new(stem).extension())
+        .is_some_and(|ext| ext == MASK_SUFFIX)
+}
+
+/// Clean pages in `dir`, each with its own mask file when there is one
+pub fn dataset_pages(dir: &Path) -> anyhow::Result<Vec<(PathBuf, Option<PathBuf>)>> {
+    Ok(batch::list_images(dir)?
+        .into_iter()
+        .filter(|path| !is_mask(path))
+        .map(|path| {
+            let mask = path.with_extension(format!("{}.png", MASK_SUFFIX));
+            let mask = mask.is_file().then_some(mask);
+            (path, mask)
+        })
+        .collect())
+}
+
+/// Columns of character-sized strokes in each synthetic region, like vertical manga text
+pub fn synthetic_mask(width: u32, height: u32) -> GrayImage {
+    let mut mask = GrayImage::new(width, height);
+    for (rx, ry, rw, rh) in SYNTHETIC_REGIONS {
+        let (x0, y0) = ((rx * width as f32) as u32, (ry * height as f32) as u32);
+        let (x1, y1) = (
+            ((rx + rw) * width as f32) as u32,
+            ((ry + rh) * height as f32) as u32,
+        );
+        let column = ((x1 - x0) / 8).max(2);
+        let mut x = x0;
+        while x + column <= x1 {
+            let mut y = y0;
+            while y + column <= y1 {
+                for py in y..(y + column).min(height) {
+                    for px in x..(x + column).min(width) {
+                        mask.put_pixel(px, py, Luma([255]));
+                    }
+                }
+                y += column + column / 4; // Small gap between characters
+            }
+            x += column + column / 2; // Wider gap between columns
+        }
+    }
+    mask
+}
+
+/// One block per group of nearby mask strokes, for the per-block inpainting pipeline
+pub fn mask_blocks(mask: &GrayImage) -> Vec<PageBlock> {
+    let labels = connected_components(mask, Connectivity::Eight, Luma([0u8]));
+    let mut bounds: Vec<Option<(u32, u32, u32, u32)>> = Vec::new();
+    for (x, y, label) in labels.enumerate_pixels() {
+        let label = label[0] as usize;
+        if label == 0 {
+            continue;
+        }
+        if bounds.len() < label {
+            bounds.resize(label, None);
+        }
+        let bound = bounds[label - 1].get_or_insert((x, y, x, y));
+        *bound = (
+            bound.0.min(x),
+            bound.1.min(y),
+            bound.2.max(x),
+            bound.3.max(y),
+        );
+    }
+
+    let (width, height) = (mask.width() as f32, mask.height() as f32);
+    let blocks: Vec<PageBlock> = bounds
+        .into_iter()
+        .flatten()
+        .map(|(xmin, ymin, xmax, ymax)| {
+            PageBlock::from_detection(&comic_text_detector::ClassifiedBbox {
+                xmin: (xmin as f32 - BLOCK_MARGIN).max(0.0),
+                ymin: (ymin as f32 - BLOCK_MARGIN).max(0.0),
+                xmax: (xmax as f32 + 1.0 + BLOCK_MARGIN).min(width),
+                ymax: (ymax as f32 + 1.0 + BLOCK_MARGIN).min(height),
+                confidence: 1.0,
+                class: 0,
+            })
+        })
+        .collect();
+    visualize::merge_overlapping(&blocks)
+}
+
+/// The page with its holes painted white, as after text removal
+fn erase(page: &DynamicImage, mask: &GrayImage) -> DynamicImage {
+    let mut erased = page.to_rgba8();
+    for (x, y, pixel) in mask.enumerate_pixels() {
+        if pixel[0] > 0 {
+            erased.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+        }
+    }
+    DynamicImage::ImageRgba8(erased)
+}
+
+async fn evaluate_page(
+    state: &AppState,
+    path: &Path,
+    mask_path: Option<&Path>,
+    config: &InpaintConfig,
+) -> anyhow::Result<InpaintSample> {
+    let start = Instant::now();
+    let page = image_io::open(path)?;
+    let (mask, source) = match mask_path {
+        Some(mask_path) => {
+            let mask = image_io::open(mask_path)?.to_luma8();
+            let mask = if mask.dimensions() == (page.width(), page.height()) {
+                mask
+            } else {
+                image::imageops::resize(
+                    &mask,
+                    page.width(),
+                    page.height(),
+                    image::imageops::FilterType::Nearest,
+                )
+            };
+            (mask, MaskSource::File)
+        }
+        None => (
+            synthetic_mask(page.width(), page.height()),
+            MaskSource::Synthetic,
+        ),
+    };
+    let blocks = mask_blocks(&mask);
+    if blocks.is_empty() {
+        return Err(anyhow::anyhow!("Mask is empty"));
+    }
+
+    let quiet = |_: StageProgress| {};
+    let erased = erase(&page, &mask);
+    let inpainted =
+        pipeline::inpaint_blocks(state, &erased, &mask, &blocks, config, None, &quiet).await?;
+    Ok(InpaintSample {
+        file: file_name(path),
+        mask: source,
+        blocks: blocks.len(),
+        metrics: InpaintMetrics::calculate(&page, &inpainted, Some(&mask))?,
+        elapsed_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+fn average(samples: &[InpaintSample], metric: impl Fn(&InpaintMetrics) -> f64) -> f64 {
+    match samples.len() {
+        0 => 0.0,
+        count => samples.iter().map(|s| metric(&s.metrics)).sum::<f64>() / count as f64,
+    }
+}
+
+/// Erase, inpaint, and score every clean page in `dir` with `config`
+///
+/// `on_progress` gets (completed, total) pages; pages that fail are skipped.
+pub async fn evaluate(
+    state: &AppState,
+    dir: &Path,
+    config: &InpaintConfig,
+    on_progress: &(dyn Fn(usize, usize) + Send + Sync),
+) -> anyhow::Result<InpaintEvaluation> {
+    let start = Instant::now();
+    let pages = dataset_pages(dir)?;
+    if pages.is_empty() {
+        return Err(anyhow::anyhow!("No images in {:?}", dir));
+    }
+    tracing::info!(
+        "[inpaint-eval] evaluating {} page(s) from {:?}",
+        pages.len(),
+        dir
+    );
+
+    let mut samples = Vec::with_capacity(pages.len());
+    let mut skipped = Vec::new();
+    for (index, (path, mask)) in pages.iter().enumerate() {
+        match evaluate_page(state, path, mask.as_deref(), config).await {
+            Ok(sample) => samples.push(sample),
+            Err(err) => skipped.push(SkippedSample {
+                file: file_name(path),
+                reason: format!("{:#}", err),
+            }),
+        }
+        on_progress(index + 1, pages.len());
+    }
+
+    let evaluation = InpaintEvaluation {
+        config: config.clone(),
+        average_ssim: average(&samples, |m| m.ssim),
+        average_gmsd: average(&samples, |m| m.gmsd),
+        average_psnr: average(&samples, |m| m.psnr),
+        samples,
+        skipped,
+        elapsed_ms: start.elapsed().as_millis() as u64,
+    };
+    tracing::info!(
+        "[inpaint-eval] SSIM {:.4}, GMSD {:.4}, PSNR {:.2}dB over {} page(s), {} skipped",
+        evaluation.average_ssim,
+        evaluation.average_gmsd,
+        evaluation.average_psnr,
+        evaluation.samples.len(),
+        evaluation.skipped.len()
+    );
+    Ok(evaluation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_synthetic_mask_groups_into_one_block_per_region() {
+        let mask = synthetic_mask(800, 1200);
+        let holes = mask.pixels().filter(|p| p[0] > 0).count();
+        assert!(holes > 0 && holes < 800 * 1200 / 4);
+
+        let blocks = mask_blocks(&mask);
+        assert_eq!(blocks.len(), SYNTHETIC_REGIONS.len());
+        for block in &blocks {
+            assert!(block.xmin >= 0.0 && block.xmax <= 800.0);
+            assert!(block.ymin >= 0.0 && block.ymax <= 1200.0);
+        }
+        assert!(mask_blocks(&GrayImage::new(10, 10)).is_empty());
+    }
+
+    #[test]
+    fn test_dataset_pages_pair_mask_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("001.png"), b"").unwrap();
+        fs::write(dir.path().join("001.mask.png"), b"").unwrap();
+        fs::write(dir.path().join("002.jpg"), b"").unwrap();
+
+        let pages = dataset_pages(dir.path()).unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(file_name(&pages[0].0), "001.png");
+        assert_eq!(
+            pages[0].1.as_deref().map(file_name),
+            Some("001.mask.png".to_string())
+        );
+        assert!(pages[1].1.is_none());
+    }
+}
@@ -0,0 +1,1013 @@
+// Persistent job queue for long-running batch work
+// Jobs are stored in app_data_dir/jobs.json and run one at a time by a background worker.
+// Each job is split into items (pages or texts) so it can pause, resume, or cancel between items.
+
+use anyhow::{Context, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "gui")]
+use std::time::Duration;
+use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(feature = "gui")]
+use tauri::{AppHandle, Emitter, Manager};
+#[cfg(feature = "gui")]
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::{Notify, RwLock};
+
+use crate::backup::Backup;
+use crate::batch;
+#[cfg(feature = "gui")]
+use crate::commands::inpaint_debug_dir;
+use crate::image_io::{self, ExportOptions};
+use crate::naming;
+use crate::pipeline::{self, PageBlock, ProcessOptions, StageProgress};
+#[cfg(feature = "gui")]
+use crate::progress::TaskHandle;
+use crate::stages::InpaintConfig;
+use crate::state::AppState;
+use crate::translation::TranslatorConfig;
+
+/// Event emitted whenever a job changes state or finishes an item
+pub const JOB_UPDATE_EVENT: &str = "job://update";
+
+const JOBS_FILE: &str = "jobs.json";
+
+/// A page for a rendering job: source image, destination, and the blocks to draw
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderPage {
+    pub image_path: String,
+    pub output_path: String,
+    pub blocks: Vec<PageBlock>,
+    #[serde(default)]
+    pub mask_path: Option<String>, // Detection mask PNG, lets "auto" fill plain bubbles
+}
+
+/// Job type and parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum JobParams {
+    /// Full pipeline over every image in a folder
+    #[serde(rename_all = "camelCase")]
+    ProcessFolder {
+        input_dir: String,
+        output_dir: String,
+        #[serde(default)]
+        options: ProcessOptions,
+    },
+    /// Detection only; writes one `<stem>.json` of blocks per image
+    #[serde(rename_all = "camelCase")]
+    Detection {
+        input_dir: String,
+        output_dir: String,
+        confidence_threshold: f32,
+        nms_threshold: f32,
+    },
+    /// Detection + inpainting; writes one textless `<stem>.png` per image
+    #[serde(rename_all = "camelCase")]
+    Inpainting {
+        input_dir: String,
+        output_dir: String,
+        confidence_threshold: f32,
+        nms_threshold: f32,
+        #[serde(default)]
+        inpaint: InpaintConfig,
+    },
+    /// Translate a list of texts; results are stored as item outputs
+    ///
+    /// A DeepL translator is stored without its key and uses the one saved in settings.
+    #[serde(rename_all = "camelCase")]
+    Translation {
+        texts: Vec<String>,
+        translator: TranslatorConfig,
+    },
+    /// Render translated blocks onto already-inpainted pages
+    #[serde(rename_all = "camelCase")]
+    Rendering {
+        pages: Vec<RenderPage>,
+        render_method: String,
+        default_font: String,
+    },
+}
+
+impl JobParams {
+    /// What the job does and what its items are, for logs and notifications
+    fn kind(&self) -> (&'static str, &'static str) {
+        match self {
+            JobParams::ProcessFolder { .. } => ("Folder processing", "page"),
+            JobParams::Detection { .. } => ("Detection", "page"),
+            JobParams::Inpainting { .. } => ("Inpainting", "page"),
+            JobParams::Translation { .. } => ("Translation", "text"),
+            JobParams::Rendering { .. } => ("Chapter export", "page"),
+        }
+    }
+
+    fn translator_mut(&mut self) -> Option<&mut TranslatorConfig> {
+        match self {
+            JobParams::ProcessFolder { options, .. } => options.translator.as_mut(),
+            JobParams::Translation { translator, .. } => Some(translator),
+            _ => None,
+        }
+    }
+
+    /// Drop API keys before the job is stored or sent to the frontend
+    fn forget_keys(&mut self) {
+        if let Some(translator) = self.translator_mut() {
+            translator.forget_key();
+        }
+    }
+
+    /// Params with the key of the saved provider filled in, read when the job runs
+    async fn with_saved_keys(&self, state: &AppState) -> anyhow::Result<JobParams> {
+        let mut params = self.clone();
+        if let Some(translator) = params.translator_mut() {
+            let saved = state.settings.get().await.translation.deepl_api_key;
+            *translator = translator.clone().with_saved_key(saved.as_deref())?;
+        }
+        Ok(params)
+    }
+
+    /// Folders or files the job writes to
+    pub fn output_paths(&self) -> Vec<&str> {
+        match self {
+            JobParams::ProcessFolder { output_dir, .. }
+            | JobParams::Detection { output_dir, .. }
+            | JobParams::Inpainting { output_dir, .. } => vec![output_dir.as_str()],
+            JobParams::Translation { .. } => Vec::new(),
+            JobParams::Rendering { pages, .. } => {
+                pages.iter().map(|page| page.output_path.as_str()).collect()
+            }
+        }
+    }
+
+    /// Resolve the list of items this job will work through
+    fn resolve_items(&self) -> anyhow::Result<Vec<String>> {
+        if let JobParams::ProcessFolder { options, .. } = self {
+            if let Some(template) = &options.export.filename_template {
+                naming::validate(template)?;
+            }
+        }
+
+        match self {
+            JobParams::ProcessFolder { input_dir, .. }
+            | JobParams::Detection { input_dir, .. }
+            | JobParams::Inpainting { input_dir, .. } => {
+                let files = batch::list_images(Path::new(input_dir))?;
+                Ok(files
+                    .into_iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect())
+            }
+            JobParams::Translation { texts, .. } => Ok(texts.clone()),
+            JobParams::Rendering { pages, .. } => {
+                Ok(pages.iter().map(|p| p.image_path.clone()).collect())
+            }
+        }
+    }
+
+    /// Reject parameters no item could run with
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match self {
+            JobParams::ProcessFolder { options, .. } => {
+                pipeline::validate_render_method(&options.render_method)?;
+                options.export.validate()
+            }
+            JobParams::Rendering { render_method, .. } => {
+                pipeline::validate_render_method(render_method)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn output_dir(&self) -> Option<&str> {
+        match self {
+            JobParams::ProcessFolder { output_dir, .. }
+            | JobParams::Detection { output_dir, .. }
+            | JobParams::Inpainting { output_dir, .. } => Some(output_dir),
+            JobParams::Translation { .. } | JobParams::Rendering { .. } => None,
+        }
+    }
+
+    fn debug_mode(&self) -> bool {
+        match self {
+            JobParams::ProcessFolder { options, .. } => options.inpaint.debug_mode,
+            JobParams::Inpainting { inpaint, .. } => inpaint.debug_mode,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobState {
+    Queued,
+    Running,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_finished(self) -> bool {
+        matches!(
+            self,
+            JobState::Completed | JobState::Failed | JobState::Cancelled
+        )
+    }
+}
+
+/// Outcome of a single item; failed items are recorded without failing the job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobItemResult {
+    pub item: String,
+    pub output: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Job {
+    pub id: String,
+    pub params: JobParams,
+    pub state: JobState,
+    pub items: Option<Vec<String>>, // Resolved on first run so resume sees the same list
+    pub results: Vec<JobItemResult>, // One per finished item, in item order
+    pub error: Option<String>,
+    pub created_at: u64, // Unix millis
+    pub updated_at: u64,
+    #[serde(default)]
+    pub running_ms: u64, // Time spent running, excluding queued and paused time
+    #[serde(default)]
+    running_since: Option<u64>, // Start of the current run, not yet counted in running_ms
+}
+
+impl Job {
+    /// Number of finished items and total items (0 until items are resolved)
+    pub fn progress(&self) -> (usize, usize) {
+        let total = self.items.as_ref().map(Vec::len).unwrap_or(0);
+        (self.results.len(), total)
+    }
+
+    /// Fold the time since the last update into `running_ms`, restarting the clock while the
+    /// job is still running
+    fn accrue_running_time(&mut self, now: u64) {
+        if let Some(since) = self.running_since {
+            self.running_ms += now.saturating_sub(since);
+            self.running_since = (self.state == JobState::Running).then_some(now);
+        }
+    }
+}
+
+/// Job update payload sent to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobUpdate {
+    pub job: Job,
+    pub current: usize,
+    pub total: usize,
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// In-memory job list mirrored to disk after every change
+#[derive(Debug)]
+pub struct JobManager {
+    jobs: RwLock<Vec<Job>>,
+    store_path: Option<PathBuf>,
+    wake: Notify,
+    next_id: AtomicU64,
+}
+
+impl JobManager {
+    /// Load persisted jobs; jobs interrupted mid-run are re-queued
+    pub fn load(store_dir: &Path) -> Self {
+        let store_path = store_dir.join(JOBS_FILE);
+        let mut jobs: Vec<Job> = match fs::read_to_string(&store_path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                tracing::warn!(
+                    "[jobs] ignoring unreadable job store {:?}: {}",
+                    store_path,
+                    err
+                );
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+
+        for job in jobs.iter_mut() {
+            job.params.forget_keys(); // Stores written before keys were left out
+            if job.state == JobState::Running {
+                job.state = JobState::Queued;
+            }
+            job.running_since = None; // Time after the last update before a crash is lost
+        }
+
+        let pending = jobs.iter().filter(|j| !j.state.is_finished()).count();
+        tracing::info!(
+            "[jobs] loaded {} job(s) from {:?} ({} pending)",
+            jobs.len(),
+            store_path,
+            pending
+        );
+
+        Self {
+            jobs: RwLock::new(jobs),
+            store_path: Some(store_path),
+            wake: Notify::new(),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Manager without persistence, used by tests
+    #[cfg(test)]
+    fn in_memory() -> Self {
+        Self {
+            jobs: RwLock::new(Vec::new()),
+            store_path: None,
+            wake: Notify::new(),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    fn persist(&self, jobs: &[Job]) {
+        let Some(path) = &self.store_path else {
+            return;
+        };
+        let result = serde_json::to_string_pretty(jobs)
+            .context("Failed to serialize jobs")
+            .and_then(|json| {
+                // Write then rename so a crash never leaves a truncated store
+                let tmp = path.with_extension("json.tmp");
+                fs::write(&tmp, json).context("Failed to write job store")?;
+                fs::rename(&tmp, path).context("Failed to replace job store")
+            });
+        if let Err(err) = result {
+            tracing::warn!("[jobs] failed to persist jobs: {:#}", err);
+        }
+    }
+
+    pub async fn enqueue(&self, mut params: JobParams) -> Job {
+        params.forget_keys();
+        let now = now_millis();
+        let seq = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let job = Job {
+            id: format!("job-{}-{}", now, seq),
+            params,
+            state: JobState::Queued,
+            items: None,
+            results: Vec::new(),
+            error: None,
+            created_at: now,
+            updated_at: now,
+            running_ms: 0,
+            running_since: None,
+        };
+
+        let mut jobs = self.jobs.write().await;
+        jobs.push(job.clone());
+        self.persist(&jobs);
+        drop(jobs);
+
+        self.wake.notify_one();
+        job
+    }
+
+    pub async fn list(&self) -> Vec<Job> {
+        self.jobs.read().await.clone()
+    }
+
+    /// Apply `f` to a job, persist, and return the updated job
+    pub async fn update(&self, id: &str, f: impl FnOnce(&mut Job)) -> anyhow::Result<Job> {
+        let mut jobs = self.jobs.write().await;
+        let job = jobs
+            .iter_mut()
+            .find(|j| j.id == id)
+            .ok_or_else(|| anyhow!("Job not found: {}", id))?;
+        f(job);
+        job.updated_at = now_millis();
+        job.accrue_running_time(job.updated_at);
+        let job = job.clone();
+        self.persist(&jobs);
+        Ok(job)
+    }
+
+    /// Move a job between states, rejecting transitions not listed in `from`
+    async fn transition(&self, id: &str, from: &[JobState], to: JobState) -> anyhow::Result<Job> {
+        let mut rejected = None;
+        let job = self
+            .update(id, |job| {
+                if from.contains(&job.state) {
+                    job.state = to;
+                } else {
+                    rejected = Some(job.state);
+                }
+            })
+            .await?;
+
+        match rejected {
+            Some(state) => Err(anyhow!(
+                "Cannot move job {} from {:?} to {:?}",
+                id,
+                state,
+                to
+            )),
+            None => Ok(job),
+        }
+    }
+
+    /// Pause a queued or running job; a running job stops after its current item
+    pub async fn pause(&self, id: &str) -> anyhow::Result<Job> {
+        self.transition(id, &[JobState::Queued, JobState::Running], JobState::Paused)
+            .await
+    }
+
+    pub async fn resume(&self, id: &str) -> anyhow::Result<Job> {
+        let job = self
+            .transition(id, &[JobState::Paused], JobState::Queued)
+            .await?;
+        self.wake.notify_one();
+        Ok(job)
+    }
+
+    pub async fn cancel(&self, id: &str) -> anyhow::Result<Job> {
+        self.transition(
+            id,
+            &[JobState::Queued, JobState::Running, JobState::Paused],
+            JobState::Cancelled,
+        )
+        .await
+    }
+
+    /// Drop completed, failed, and cancelled jobs from the list
+    pub async fn clear_finished(&self) -> usize {
+        let mut jobs = self.jobs.write().await;
+        let before = jobs.len();
+        jobs.retain(|j| !j.state.is_finished());
+        let removed = before - jobs.len();
+        self.persist(&jobs);
+        removed
+    }
+
+    /// Mark the oldest queued job as running and return it
+    async fn claim_next(&self) -> Option<Job> {
+        let mut jobs = self.jobs.write().await;
+        let job = jobs.iter_mut().find(|j| j.state == JobState::Queued)?;
+        job.state = JobState::Running;
+        job.updated_at = now_millis();
+        job.running_since = Some(job.updated_at);
+        let job = job.clone();
+        self.persist(&jobs);
+        Some(job)
+    }
+
+    async fn current(&self, id: &str) -> Option<Job> {
+        self.jobs.read().await.iter().find(|j| j.id == id).cloned()
+    }
+
+    async fn current_state(&self, id: &str) -> Option<JobState> {
+        self.jobs
+            .read()
+            .await
+            .iter()
+            .find(|j| j.id == id)
+            .map(|j| j.state)
+    }
+}
+
+/// Emit a job update event to the frontend
+#[cfg(feature = "gui")]
+pub fn emit_job_update(app: &AppHandle, job: &Job) {
+    let (current, total) = job.progress();
+    let update = JobUpdate {
+        job: job.clone(),
+        current,
+        total,
+    };
+    if let Err(err) = app.emit(JOB_UPDATE_EVENT, &update) {
+        tracing::warn!("[jobs] failed to emit job update: {}", err);
+    }
+}
+
+/// Background worker: runs queued jobs one at a time for the lifetime of the app
+#[cfg(feature = "gui")]
+pub async fn run_worker(app: AppHandle) {
+    let state = app.state::<AppState>();
+    loop {
+        match state.jobs.claim_next().await {
+            Some(job) => {
+                emit_job_update(&app, &job);
+                let id = job.id.clone();
+                let task = TaskHandle::start(&app, "job", Some(id.clone()));
+                run_job(&app, &state, job, &task).await;
+
+                match state.jobs.current(&id).await {
+                    Some(job) if job.state == JobState::Failed => {
+                        let error = job.error.unwrap_or_else(|| "Job failed".to_string());
+                        task.fail(&anyhow!(error));
+                    }
+                    _ => task.finish(),
+                }
+            }
+            None => state.jobs.wake.notified().await,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+async fn run_job(app: &AppHandle, state: &AppState, job: Job, task: &TaskHandle) {
+    let id = job.id.clone();
+    tracing::info!("[jobs] starting {} ({})", id, job.params.kind().0);
+
+    // Checked on every run, not only at enqueue: a persisted job can resume after the trusted
+    // export folders or the saved DeepL key have changed
+    let params = match check_job(state, &job.params).await {
+        Ok(params) => params,
+        Err(err) => {
+            finish_job(
+                app,
+                state,
+                &id,
+                JobState::Failed,
+                Some(format!("{:#}", err)),
+            )
+            .await;
+            return;
+        }
+    };
+
+    let items = match &job.items {
+        Some(items) => items.clone(),
+        None => match job.params.resolve_items() {
+            Ok(items) => {
+                let resolved = items.clone();
+                if let Ok(job) = state
+                    .jobs
+                    .update(&id, |job| job.items = Some(resolved))
+                    .await
+                {
+                    emit_job_update(app, &job);
+                }
+                items
+            }
+            Err(err) => {
+                finish_job(
+                    app,
+                    state,
+                    &id,
+                    JobState::Failed,
+                    Some(format!("{:#}", err)),
+                )
+                .await;
+                return;
+            }
+        },
+    };
+
+    if let Some(output_dir) = job.params.output_dir() {
+        if let Err(err) = fs::create_dir_all(output_dir) {
+            let message = format!("Failed to create output directory {}: {}", output_dir, err);
+            finish_job(app, state, &id, JobState::Failed, Some(message)).await;
+            return;
+        }
+    }
+
+    let debug_dir = if job.params.debug_mode() {
+        inpaint_debug_dir(app).ok()
+    } else {
+        None
+    };
+    let started = UNIX_EPOCH + Duration::from_millis(job.created_at);
+    let settings = state.settings.get().await;
+    let backup = Backup::new(settings.backup, started);
+    // Resolved once so every page of the job is encoded with the same options
+    let export = match &params {
+        JobParams::ProcessFolder { options, .. } => options.export.clone(),
+        _ => settings.export,
+    };
+    let export = state.project_export(export).await;
+
+    // Resume from the first item without a result
+    for (index, item) in items.iter().enumerate().skip(job.results.len()) {
+        match state.jobs.current_state(&id).await {
+            Some(JobState::Running) => {}
+            other => {
+                tracing::info!("[jobs] {} stopped before item {} ({:?})", id, index, other);
+                return;
+            }
+        }
+
+        let result = match run_item(
+            state,
+            &params,
+            index,
+            item,
+            &export,
+            &backup,
+            debug_dir.as_deref(),
+        )
+        .await
+        {
+            Ok(output) => JobItemResult {
+                item: item.clone(),
+                output,
+                error: None,
+            },
+            Err(err) => {
+                tracing::warn!("[jobs] {} item {} failed: {:#}", id, index, err);
+                JobItemResult {
+                    item: item.clone(),
+                    output: None,
+                    error: Some(format!("{:#}", err)),
+                }
+            }
+        };
+
+        if let Ok(job) = state.jobs.update(&id, |job| job.results.push(result)).await {
+            let (current, total) = job.progress();
+            task.report("item", current, total, Some(item.clone()));
+            emit_job_update(app, &job);
+        }
+    }
+
+    finish_job(app, state, &id, JobState::Completed, None).await;
+}
+
+/// Params the job runs with, once its outputs are allowed and its saved key is found
+async fn check_job(state: &AppState, params: &JobParams) -> anyhow::Result<JobParams> {
+    params.validate()?;
+    for path in params.output_paths() {
+        state.export_scope.check(Path::new(path)).await?;
+    }
+    params.with_saved_keys(state).await
+}
+
+#[cfg(feature = "gui")]
+async fn finish_job(
+    app: &AppHandle,
+    state: &AppState,
+    id: &str,
+    final_state: JobState,
+    error: Option<String>,
+) {
+    let result = state
+        .jobs
+        .update(id, |job| {
+            // A pause or cancel that landed during the last item wins
+            if job.state == JobState::Running {
+                job.state = final_state;
+                job.error = error;
+            }
+        })
+        .await;
+
+    if let Ok(job) = result {
+        let (current, total) = job.progress();
+        tracing::info!(
+            "[jobs] {} finished as {:?} ({}/{} item(s))",
+            id,
+            job.state,
+            current,
+            total
+        );
+        emit_job_update(app, &job);
+
+        let finished = matches!(job.state, JobState::Completed | JobState::Failed);
+        if finished && state.settings.get().await.notifications.job_finished {
+            notify_finished(app, &job);
+        }
+    }
+}
+
+fn format_elapsed(millis: u64) -> String {
+    let seconds = millis / 1000;
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+/// Notification title and body for a finished job: what ran, how it ended, items done,
+/// errors, and time taken
+fn completion_summary(job: &Job) -> (String, String) {
+    let (kind, unit) = job.params.kind();
+    let outcome = match job.state {
+        JobState::Failed => "failed",
+        _ => "finished",
+    };
+    let errors = job.results.iter().filter(|r| r.error.is_some()).count();
+    let done = job.results.len() - errors;
+    let mut body = format!(
+        "{} {}(s) done, {} error(s) in {}",
+        done,
+        unit,
+        errors,
+        format_elapsed(job.running_ms)
+    );
+    if let Some(error) = &job.error {
+        body.push_str(&format!("\n{}", error));
+    }
+    (format!("{} {}", kind, outcome), body)
+}
+
+/// OS notification so users can leave a long job running and come back when it is done
+#[cfg(feature = "gui")]
+fn notify_finished(app: &AppHandle, job: &Job) {
+    let (title, body) = completion_summary(job);
+    if let Err(err) = app.notification().builder().title(title).body(body).show() {
+        tracing::warn!("[jobs] failed to show notification for {}: {}", job.id, err);
+    }
+}
+
+/// Process one item of a job, returning its output (a path or translated text)
+async fn run_item(
+    state: &AppState,
+    params: &JobParams,
+    index: usize,
+    item: &str,
+    export: &ExportOptions,
+    backup: &Backup,
+    debug_dir: Option<&Path>,
+) -> anyhow::Result<Option<String>> {
+    let noop = |_: StageProgress| {};
+
+    match params {
+        JobParams::ProcessFolder {
+            output_dir,
+            options,
+            ..
+        } => {
+            let options = ProcessOptions {
+                export: export.clone(),
+                ..options.clone()
+            };
+            let output = batch::process_file(
+                state,
+                Path::new(item),
+                index,
+                Path::new(output_dir),
+                &options,
+                backup,
+                debug_dir,
+            )
+            .await?;
+            Ok(Some(output.to_string_lossy().to_string()))
+        }
+        JobParams::Detection {
+            output_dir,
+            confidence_threshold,
+            nms_threshold,
+            ..
+        } => {
+            let image = image_io::open(Path::new(item))?;
+            let (blocks, _) =
+                pipeline::detect_blocks(state, &image, *confidence_threshold, *nms_threshold)
+                    .await?;
+
+            let output = output_path(item, output_dir, "json");
+            let json = serde_json::to_string_pretty(&blocks)?;
+            fs::write(&output, json).with_context(|| format!("Failed to write {:?}", output))?;
+            Ok(Some(output.to_string_lossy().to_string()))
+        }
+        JobParams::Inpainting {
+            output_dir,
+            confidence_threshold,
+            nms_threshold,
+            inpaint,
+            ..
+        } => {
+            let source = image_io::open_with_metadata(Path::new(item))?;
+            let image = &source.image;
+            let (blocks, mask) =
+                pipeline::detect_blocks(state, image, *confidence_threshold, *nms_threshold)
+                    .await?;
+            let textless =
+                pipeline::inpaint_blocks(state, image, &mask, &blocks, inpaint, debug_dir, &noop)
+                    .await?;
+
+            let output = output_path(item, output_dir, "png");
+            if let Some(source_dir) = Path::new(item).parent() {
+                backup.protect(&output, source_dir)?;
+            }
+            image_io::save_as_extension(&textless, &output, export, &source.metadata)?;
+            Ok(Some(output.to_string_lossy().to_string()))
+        }
+        JobParams::Translation { translator, .. } => {
+            if item.trim().is_empty() {
+                return Ok(Some(String::new()));
+            }
+            Ok(Some(translator.translate(item).await?))
+        }
+        JobParams::Rendering {
+            pages,
+            render_method,
+            default_font,
+        } => {
+            let page = pages
+                .get(index)
+                .ok_or_else(|| anyhow!("Missing render page {}", index))?;
+            let source = image_io::open_with_metadata(Path::new(&page.image_path))?;
+            let base = match (render_method.as_str(), &page.mask_path) {
+                ("auto", Some(mask_path)) => {
+                    let mask = image_io::open(Path::new(mask_path))?.to_luma8();
+                    let bboxes: Vec<_> = page.blocks.iter().map(PageBlock::bbox).collect();
+                    pipeline::fill_plain_blocks(source.image, &mask, &bboxes)
+                }
+                _ => source.image,
+            };
+
+            let mut blocks = page.blocks.clone();
+            let rendered =
+                pipeline::typeset_and_render(base, &mut blocks, render_method, default_font)?;
+            let output = Path::new(&page.output_path);
+            if let Some(source_dir) = Path::new(&page.image_path).parent() {
+                backup.protect(output, source_dir)?;
+            }
+            image_io::save_as_extension(&rendered, output, export, &source.metadata)?;
+            Ok(Some(page.output_path.clone()))
+        }
+    }
+}
+
+fn output_path(input: &str, output_dir: &str, extension: &str) -> PathBuf {
+    let stem = Path::new(input)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "page".to_string());
+    Path::new(output_dir).join(format!("{}.{}", stem, extension))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::translation::TranslationStyle;
+
+    fn translation_job() -> JobParams {
+        JobParams::Translation {
+            texts: vec!["こんにちは".to_string()],
+            translator: TranslatorConfig::Ollama {
+                model: "qwen".to_string(),
+                system_prompt: None,
+                style: TranslationStyle::default(),
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pause_resume_cancel_transitions() {
+        let manager = JobManager::in_memory();
+        let job = manager.enqueue(translation_job()).await;
+        assert_eq!(job.state, JobState::Queued);
+
+        assert_eq!(
+            manager.pause(&job.id).await.unwrap().state,
+            JobState::Paused
+        );
+        assert!(manager.claim_next().await.is_none());
+        assert!(manager.pause(&job.id).await.is_err());
+
+        assert_eq!(
+            manager.resume(&job.id).await.unwrap().state,
+            JobState::Queued
+        );
+        assert_eq!(manager.claim_next().await.unwrap().state, JobState::Running);
+
+        assert_eq!(
+            manager.cancel(&job.id).await.unwrap().state,
+            JobState::Cancelled
+        );
+        assert!(manager.resume(&job.id).await.is_err());
+        assert_eq!(manager.clear_finished().await, 1);
+        assert!(manager.list().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_load_requeues_running_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let manager = JobManager::load(dir.path());
+            let job = manager.enqueue(translation_job()).await;
+            manager.claim_next().await.unwrap();
+            assert_eq!(
+                manager.current_state(&job.id).await,
+                Some(JobState::Running)
+            );
+        }
+
+        let reloaded = JobManager::load(dir.path());
+        let jobs = reloaded.list().await;
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].state, JobState::Queued);
+    }
+
+    #[tokio::test]
+    async fn test_completion_summary_counts_items_and_time() {
+        let manager = JobManager::in_memory();
+        let job = manager.enqueue(translation_job()).await;
+        let claimed = manager.claim_next().await.unwrap();
+        assert_eq!(claimed.running_since, Some(claimed.updated_at));
+
+        // Paused time is not counted; a run only accrues until it stops
+        let mut paused = manager
+            .update(&job.id, |job| {
+                job.running_since = Some(job.running_since.unwrap() - 40_000);
+                job.state = JobState::Paused;
+            })
+            .await
+            .unwrap();
+        assert!((40_000..45_000).contains(&paused.running_ms));
+        assert_eq!(paused.running_since, None);
+        paused.accrue_running_time(paused.updated_at + 600_000);
+        assert!(paused.running_ms < 45_000);
+
+        manager.resume(&job.id).await.unwrap();
+        manager.claim_next().await.unwrap();
+        let mut job = manager
+            .update(&job.id, |job| {
+                job.running_since = Some(job.running_since.unwrap() - 85_000);
+            })
+            .await
+            .unwrap();
+        assert!((125_000..130_000).contains(&job.running_ms));
+        assert!(job.running_since.is_some());
+
+        job.running_ms = 125_000;
+        job.results = vec![
+            JobItemResult {
+                item: "a".to_string(),
+                output: Some("A".to_string()),
+                error: None,
+            },
+            JobItemResult {
+                item: "b".to_string(),
+                output: None,
+                error: Some("timeout".to_string()),
+            },
+        ];
+        job.state = JobState::Completed;
+        let (title, body) = completion_summary(&job);
+        assert_eq!(title, "Translation finished");
+        assert_eq!(body, "1 text(s) done, 1 error(s) in 2m 05s");
+
+        job.state = JobState::Failed;
+        job.error = Some("Output folder is read-only".to_string());
+        let (title, body) = completion_summary(&job);
+        assert_eq!(title, "Translation failed");
+        assert!(body.ends_with("\nOutput folder is read-only"));
+        assert_eq!(format_elapsed(3_725_000), "1h 02m");
+    }
+
+    #[test]
+    fn test_job_params_tagged_by_type() {
+        let json = r#"{"type":"detection","inputDir":"/in","outputDir":"/out","confidenceThreshold":0.5,"nmsThreshold":0.4}"#;
+        let params: JobParams = serde_json::from_str(json).unwrap();
+        assert!(matches!(params, JobParams::Detection { .. }));
+        assert_eq!(params.output_dir(), Some("/out"));
+        assert!(params.validate().is_ok());
+
+        let rendering = JobParams::Rendering {
+            pages: Vec::new(),
+            render_method: "sketch".to_string(),
+            default_font: "Arial".to_string(),
+        };
+        assert!(rendering.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_forgets_deepl_key() {
+        let manager = JobManager::in_memory();
+        let job = manager
+            .enqueue(JobParams::Translation {
+                texts: vec!["こんにちは".to_string()],
+                translator: TranslatorConfig::Deepl {
+                    api_key: "secret-key".to_string(),
+                    use_pro: false,
+                    source_lang: None,
+                    target_lang: None,
+                    style: TranslationStyle::default(),
+                },
+            })
+            .await;
+        assert!(!serde_json::to_string(&job).unwrap().contains("secret-key"));
+
+        let JobParams::Translation { translator, .. } = job.params else {
+            panic!("expected a translation job");
+        };
+        assert!(translator.clone().with_saved_key(None).is_err());
+        let translator = translator.with_saved_key(Some("saved-key")).unwrap();
+        assert!(matches!(
+            &translator,
+            TranslatorConfig::Deepl { api_key, .. } if api_key == "saved-key"
+        ));
+        assert!(!format!("{:?}", translator).contains("saved-key"));
+    }
+}
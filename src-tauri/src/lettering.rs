@@ -0,0 +1,224 @@
+// Source lettering scale from the detection mask: inside a block, ink runs across the reading
+// direction give the glyph size (column width for vertical text, line height for horizontal),
+// and ink area over half its outline gives the stroke width. From those a font size and CSS
+// weight are proposed, so the translation is typeset at the scale of the original lettering.
+
+use image::{GrayImage, imageops};
+use serde::Serialize;
+
+use crate::stages::BBox;
+
+const INK_THRESHOLD: u8 = 128;
+const MIN_LINE_FILL: f32 = 0.1; // Share of a row or column that must be ink to be part of a line
+const MIN_GLYPH_PX: u32 = 4; // Shorter runs are stray marks
+const INK_PER_EM: f32 = 0.9; // Glyph ink extent as a share of the font size
+const REGULAR_STROKE_RATIO: f32 = 0.08; // Stroke width per font size at weight 400
+const STROKE_RATIO_PER_100: f32 = 0.02; // Extra stroke ratio per 100 weight
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LetteringEstimate {
+    pub vertical: bool,
+    pub glyph_size_px: f32,
+    pub stroke_width_px: f32,
+    pub font_size: f32,
+    pub font_weight: u16, // 100-900
+}
+
+/// The mask under `bbox`, scaled from mask to page resolution
+fn mask_crop(
+    mask: &GrayImage,
+    bbox: &BBox,
+    image_width: u32,
+    image_height: u32,
+) -> Option<GrayImage> {
+    let scale_x = mask.width() as f32 / image_width as f32;
+    let scale_y = mask.height() as f32 / image_height as f32;
+    let xmin = (bbox.xmin * scale_x)
+        .floor()
+        .clamp(0.0, mask.width() as f32) as u32;
+    let ymin = (bbox.ymin * scale_y)
+        .floor()
+        .clamp(0.0, mask.height() as f32) as u32;
+    let xmax = (bbox.xmax * scale_x).ceil().clamp(0.0, mask.width() as f32) as u32;
+    let ymax = (bbox.ymax * scale_y)
+        .ceil()
+        .clamp(0.0, mask.height() as f32) as u32;
+    if xmax <= xmin || ymax <= ymin {
+        return None;
+    }
+
+    let crop = imageops::crop_imm(mask, xmin, ymin, xmax - xmin, ymax - ymin).to_image();
+    let width = ((xmax - xmin) as f32 / scale_x).round().max(1.0) as u32;
+    let height = ((ymax - ymin) as f32 / scale_y).round().max(1.0) as u32;
+    Some(if crop.dimensions() == (width, height) {
+        crop
+    } else {
+        imageops::resize(&crop, width, height, imageops::FilterType::Triangle)
+    })
+}
+
+/// Lengths of each stretch of consecutive `true`s
+fn runs(filled: &[bool]) -> Vec<u32> {
+    let mut runs = Vec::new();
+    let mut current = 0;
+    for &on in filled.iter().chain([&false]) {
+        if on {
+            current += 1;
+        } else if current > 0 {
+            runs.push(current);
+            current = 0;
+        }
+    }
+    runs
+}
+
+fn median(values: &mut [u32]) -> f32 {
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    match values.len() % 2 {
+        0 => (values[mid - 1] + values[mid]) as f32 / 2.0,
+        _ => values[mid] as f32,
+    }
+}
+
+/// Glyph size, stroke width, and the font size and weight that match them, for the text
+/// under `bbox`; `None` when the mask has no text there
+///
+/// Blocks taller than wide are read as vertical columns. `image_width` and `image_height` are
+/// the page size `bbox` is in, as the detection mask has its own resolution.
+pub fn estimate(
+    mask: &GrayImage,
+    bbox: &BBox,
+    image_width: u32,
+    image_height: u32,
+) -> Option<LetteringEstimate> {
+    let crop = mask_crop(mask, bbox, image_width, image_height)?;
+    let (width, height) = crop.dimensions();
+    let ink = |x: u32, y: u32| crop.get_pixel(x, y)[0] >= INK_THRESHOLD;
+
+    // Columns of vertical text, rows of horizontal text
+    let vertical = height > width;
+    let (across, along) = if vertical {
+        (width, height)
+    } else {
+        (height, width)
+    };
+    let filled: Vec<bool> = (0..across)
+        .map(|i| {
+            let count = (0..along)
+                .filter(|&j| if vertical { ink(i, j) } else { ink(j, i) })
+                .count();
+            count as f32 >= along as f32 * MIN_LINE_FILL
+        })
+        .collect();
+    let mut lines: Vec<u32> = runs(&filled)
+        .into_iter()
+        .filter(|&run| run >= MIN_GLYPH_PX)
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+    let glyph_size = median(&mut lines);
+
+    let mut area = 0usize;
+    let mut edge = 0usize;
+    for y in 0..height {
+        for x in 0..width {
+            if !ink(x, y) {
+                continue;
+            }
+            area += 1;
+            let interior = x > 0
+                && y > 0
+                && x + 1 < width
+                && y + 1 < height
+                && ink(x - 1, y)
+                && ink(x + 1, y)
+                && ink(x, y - 1)
+                && ink(x, y + 1);
+            if !interior {
+                edge += 1;
+            }
+        }
+    }
+    // A stroke of width w and length l has area w*l and an outline of about 2*l
+    let stroke_width = (2.0 * area as f32 / edge.max(1) as f32).min(glyph_size);
+
+    let font_size = glyph_size / INK_PER_EM;
+    let steps = ((stroke_width / font_size - REGULAR_STROKE_RATIO) / STROKE_RATIO_PER_100).round();
+    let font_weight = (400.0 + steps * 100.0).clamp(100.0, 900.0) as u16;
+    Some(LetteringEstimate {
+        vertical,
+        glyph_size_px: glyph_size,
+        stroke_width_px: stroke_width,
+        font_size: font_size.round(),
+        font_weight,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Luma;
+
+    /// Hollow square glyphs of `size` px with `stroke` px sides, in a grid of `columns` x `rows`
+    fn glyph_grid(
+        canvas: u32,
+        size: u32,
+        stroke: u32,
+        columns: u32,
+        rows: u32,
+        gap: u32,
+    ) -> GrayImage {
+        let mut mask = GrayImage::new(canvas, canvas);
+        for column in 0..columns {
+            for row in 0..rows {
+                let (x0, y0) = (gap + column * (size + gap), gap + row * (size + gap));
+                for y in y0..y0 + size {
+                    for x in x0..x0 + size {
+                        let inside = (x0 + stroke..x0 + size - stroke).contains(&x)
+                            && (y0 + stroke..y0 + size - stroke).contains(&y);
+                        if !inside {
+                            mask.put_pixel(x, y, Luma([255]));
+                        }
+                    }
+                }
+            }
+        }
+        mask
+    }
+
+    fn bbox(xmin: f32, ymin: f32, xmax: f32, ymax: f32) -> BBox {
+        BBox {
+            xmin,
+            ymin,
+            xmax,
+            ymax,
+        }
+    }
+
+    #[test]
+    fn test_vertical_columns_give_glyph_size_and_weight() {
+        let mask = glyph_grid(200, 30, 4, 3, 4, 8);
+        let lettering = estimate(&mask, &bbox(0.0, 0.0, 120.0, 160.0), 200, 200).unwrap();
+        assert!(lettering.vertical);
+        assert_eq!(lettering.glyph_size_px, 30.0);
+        assert!((3.5..=4.5).contains(&lettering.stroke_width_px));
+        assert_eq!(lettering.font_size, 33.0);
+        assert_eq!(lettering.font_weight, 600);
+
+        let empty = GrayImage::new(200, 200);
+        assert!(estimate(&empty, &bbox(10.0, 10.0, 90.0, 90.0), 200, 200).is_none());
+    }
+
+    #[test]
+    fn test_mask_at_lower_resolution_is_scaled_to_the_page() {
+        // Two horizontal lines of 15 px glyphs on a mask half the page size
+        let mask = glyph_grid(100, 15, 2, 5, 2, 4);
+        let lettering = estimate(&mask, &bbox(0.0, 0.0, 190.0, 80.0), 200, 200).unwrap();
+        assert!(!lettering.vertical);
+        assert!((28.0..=32.0).contains(&lettering.glyph_size_px));
+        assert!((3.0..=5.0).contains(&lettering.stroke_width_px));
+    }
+}
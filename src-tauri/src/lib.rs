@@ -1,53 +1,102 @@
+// Without `gui` the editor-only parts of the state and job queue are built but never called
+#![cfg_attr(not(feature = "gui"), allow(dead_code))]
+
 mod accuracy;
+mod annotations;
+mod appearance;
+mod archive;
+mod backup;
+mod batch;
+mod benchmark;
+mod bubble;
+mod cli_config;
+#[cfg(feature = "gui")]
 mod commands;
+mod comparison;
+mod crash;
+mod detection_eval;
 mod error;
+mod export_scope;
+mod golden;
+mod headless;
+mod history;
 mod hot_reload;
+mod i18n;
+mod image_io;
+mod inpaint_eval;
+mod jobs;
+mod lettering;
+mod logging;
+mod metadata;
+mod mit_json;
 mod model_package;
+mod naming;
+mod ocr_compare;
+mod ocr_eval;
 mod ocr_pipeline;
+mod page_store;
+mod pdf_export;
+mod pipeline;
+mod plugins;
+mod progress;
+mod project;
+mod scripting;
+mod session;
+mod settings;
+mod stages;
 mod state;
 mod text_renderer;
+mod translation;
+mod translation_eval;
 mod vertical_text_tests;
+mod visualize;
+mod watch;
 
 use comic_text_detector::ComicTextDetector;
 use lama::Lama;
 use manga_ocr::MangaOCR;
 use std::collections::HashMap;
-use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+#[cfg(feature = "gui")]
 use tauri::{AppHandle, Manager, async_runtime::spawn};
+#[cfg(feature = "gui")]
 use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
 use tokio::sync::Mutex;
 use tokio::sync::RwLock;
 
+#[cfg(feature = "gui")]
 use crate::commands::{
-    cache_inpainting_data, cache_ocr_image, clear_inpainting_cache, clear_ocr_cache, detection,
-    get_current_gpu_status, get_gpu_devices, get_system_fonts, inpaint_region,
-    inpaint_region_cached, ocr, ocr_cached_block, render_and_export_image, run_gpu_stress_test,
-    set_active_ocr, set_gpu_preference, translate_with_deepl, translate_with_ollama,
+    add_export_root, analyze_block_appearance, cache_inpainting_data, cache_ocr_image, cancel_job,
+    clear_finished_jobs, close_project, compare_ocr_configs, create_comparison, decode_image,
+    detection, dismiss_crash_reports, enqueue_job, estimate_block_lettering, evaluate_detection,
+    evaluate_inpainting, evaluate_ocr, export_accuracy_report, export_annotations, export_mit_json,
+    export_pdf, export_settings, get_current_gpu_status, get_editor_state, get_gpu_devices,
+    get_log_dir, get_log_level, get_page_composite, get_pending_crash_report, get_project,
+    get_recent_logs, get_settings, get_system_fonts, import_archive, import_mit_json,
+    import_settings, inpaint_region, inpaint_region_cached, list_active_tasks, list_export_roots,
+    list_jobs, list_plugins, load_editor_state, ocr, ocr_cached_block, open_project, pause_job,
+    process_folder, process_page, redo, release_page, reload_plugins, reload_scripts,
+    remove_export_root, render_and_export_image, restore_session, resume_job, run_benchmark_suite,
+    run_golden_tests, run_gpu_stress_test, run_script_hook, save_project, save_session,
+    score_translations, set_active_ocr, set_gpu_preference, set_log_level, set_ui_language,
+    translate_with_deepl, translate_with_ollama, undo, update_blocks, update_inpainted,
+    update_mask, update_settings,
 };
+use crate::error::{ErrorCode, coded, with_code};
+use crate::export_scope::ExportScope;
+use crate::history::PageHistories;
+use crate::jobs::JobManager;
 use crate::ocr_pipeline::{
     DeviceConfig, MANGA_OCR_KEY, MangaOcrPipeline, OcrPipeline, PADDLE_OCR_KEY, PaddleOcrPipeline,
 };
+use crate::page_store::PageStore;
+use crate::progress::TaskRegistry;
+use crate::scripting::ScriptHooks;
+use crate::session::SessionStore;
+use crate::settings::SettingsStore;
 use crate::state::{AppState, GpuInitResult};
 
-// Read GPU preference from config file
-fn read_gpu_preference(app: &AppHandle) -> String {
-    let app_dir = app
-        .path()
-        .app_config_dir()
-        .expect("Failed to get app config directory");
-
-    fs::create_dir_all(&app_dir).ok();
-
-    let config_path = app_dir.join("gpu_preference.txt");
-
-    fs::read_to_string(&config_path)
-        .unwrap_or_else(|_| "cuda".to_string())
-        .trim()
-        .to_string()
-}
-
 // Get GPU device name based on provider
 #[cfg(feature = "cuda")]
 fn get_cuda_device_name(_device_id: u32) -> Option<String> {
@@ -108,10 +157,18 @@ fn get_wgpu_adapter_name(device_id: u32) -> Option<String> {
     }
 }
 
-// Initialize models with GPU verification
-async fn initialize(app: AppHandle) -> anyhow::Result<()> {
-    let gpu_pref = read_gpu_preference(&app);
-    let device_id = 0u32; // Default to device 0
+// Load settings and models with GPU verification and build the backend state
+// Independent of AppHandle so the headless CLI can share it
+pub(crate) async fn build_state(config_dir: &Path, data_dir: &Path) -> anyhow::Result<AppState> {
+    let settings = SettingsStore::load(config_dir)?;
+    let saved = settings.get().await;
+    crash::set_enabled(saved.diagnostics.crash_reports);
+    let mut scripts = ScriptHooks::new(config_dir.join(scripting::SCRIPTS_DIR));
+    if let Err(err) = scripts.reload(saved.scripting.enabled) {
+        tracing::warn!("[script] scripts disabled: {:#}", err);
+    }
+    let gpu_pref = saved.gpu.preference.clone();
+    let device_id = saved.gpu.device_id;
 
     tracing::info!("GPU Preference: {} (device {})", gpu_pref, device_id);
 
@@ -130,7 +187,7 @@ async fn initialize(app: AppHandle) -> anyhow::Result<()> {
     };
 
     // Define model directory
-    let model_dir = app.path().app_data_dir()?.join("models");
+    let model_dir = data_dir.join("models");
     std::fs::create_dir_all(&model_dir)?;
 
     // Map GPU preference to DeviceConfig for OCR pipeline
@@ -145,16 +202,20 @@ async fn initialize(app: AppHandle) -> anyhow::Result<()> {
         "cuda" => {
             #[cfg(not(feature = "cuda"))]
             {
-                return Err(anyhow::anyhow!(
-                    "CUDA requested but not compiled. Rebuild with --features cuda"
+                return Err(coded(
+                    ErrorCode::CudaUnavailable,
+                    "CUDA requested but not compiled. Rebuild with --features cuda",
                 ));
             }
             #[cfg(feature = "cuda")]
             {
                 if !available_providers.iter().any(|p| p == "CUDA") {
-                    return Err(anyhow::anyhow!(
-                        "CUDA requested but not available. Check NVIDIA drivers and CUDA toolkit installation.\nAvailable providers: {:?}",
-                        available_providers
+                    return Err(coded(
+                        ErrorCode::CudaUnavailable,
+                        format!(
+                            "CUDA requested but not available. Check NVIDIA drivers and CUDA toolkit installation.\nAvailable providers: {:?}",
+                            available_providers
+                        ),
                     ));
                 }
             }
@@ -162,7 +223,10 @@ async fn initialize(app: AppHandle) -> anyhow::Result<()> {
         "directml" => {
             #[cfg(not(windows))]
             {
-                return Err(anyhow::anyhow!("DirectML only available on Windows"));
+                return Err(coded(
+                    ErrorCode::DirectmlUnavailable,
+                    "DirectML only available on Windows",
+                ));
             }
         }
         _ => {}
@@ -217,8 +281,20 @@ async fn initialize(app: AppHandle) -> anyhow::Result<()> {
     }
 
     // Load models
-    let comic_text_detector = ComicTextDetector::new()?;
-    let mut lama = Lama::new()?;
+    let comic_text_detector = ComicTextDetector::new().map_err(|err| {
+        with_code(
+            err,
+            ErrorCode::ModelMissing,
+            "Failed to load the text detector",
+        )
+    })?;
+    let mut lama = Lama::new().map_err(|err| {
+        with_code(
+            err,
+            ErrorCode::ModelMissing,
+            "Failed to load the LaMa model",
+        )
+    })?;
 
     let mut ocr_pipelines: HashMap<String, Arc<dyn OcrPipeline + Send + Sync>> = HashMap::new();
 
@@ -259,6 +335,12 @@ async fn initialize(app: AppHandle) -> anyhow::Result<()> {
         }
     }
 
+    let plugin_registry = plugins::init(data_dir.join(plugins::PLUGINS_DIR));
+    for (key, pipeline) in plugin_registry.ocr_pipelines().await {
+        tracing::info!("✓ Plugin OCR registered (key={})", key);
+        ocr_pipelines.insert(key, pipeline);
+    }
+
     // Run warmup profiling to verify GPU is actually used
     tracing::info!("Running warmup profiling...");
     let start = std::time::Instant::now();
@@ -303,7 +385,16 @@ async fn initialize(app: AppHandle) -> anyhow::Result<()> {
         );
     }
 
-    let default_active_key = if ocr_pipelines.contains_key(PADDLE_OCR_KEY) {
+    crash::set_gpu(&init_result);
+
+    let saved_engine = saved
+        .ocr
+        .engine
+        .clone()
+        .filter(|key| ocr_pipelines.contains_key(key));
+    let default_active_key = if let Some(key) = saved_engine {
+        key
+    } else if ocr_pipelines.contains_key(PADDLE_OCR_KEY) {
         PADDLE_OCR_KEY.to_string()
     } else if ocr_pipelines.contains_key(MANGA_OCR_KEY) {
         MANGA_OCR_KEY.to_string()
@@ -323,16 +414,33 @@ async fn initialize(app: AppHandle) -> anyhow::Result<()> {
         );
     }
 
-    app.manage(AppState {
+    Ok(AppState {
         comic_text_detector: Mutex::new(comic_text_detector),
         lama: Mutex::new(lama),
         gpu_init_result: Mutex::new(init_result),
         ocr_pipelines: RwLock::new(ocr_pipelines),
         active_ocr: RwLock::new(default_active_key),
-        inpaint_image_cache: RwLock::new(None),
-        inpaint_mask_cache: RwLock::new(None),
-        ocr_image_cache: RwLock::new(None),
-    });
+        pages: Mutex::new(PageStore::default()),
+        jobs: JobManager::load(data_dir),
+        tasks: TaskRegistry::new(),
+        settings,
+        export_scope: ExportScope::load(config_dir),
+        project: RwLock::new(None),
+        scripts: RwLock::new(scripts),
+        session: SessionStore::load(config_dir),
+        history: Mutex::new(PageHistories::default()),
+        last_ocr_evaluation: Mutex::new(None),
+    })
+}
+
+// Initialize the backend state and swap the splashscreen for the main window
+#[cfg(feature = "gui")]
+async fn initialize(app: AppHandle) -> anyhow::Result<()> {
+    let state = build_state(&app.path().app_config_dir()?, &app.path().app_data_dir()?).await?;
+    app.manage(state);
+
+    // Start the job worker; persisted jobs left over from the last session resume here
+    spawn(jobs::run_worker(app.clone()));
 
     app.get_webview_window("splashscreen").unwrap().close()?;
     app.get_webview_window("main").unwrap().show()?;
@@ -340,11 +448,35 @@ async fn initialize(app: AppHandle) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Logging and crash reports, shared by the GUI and the CLI
+fn init_diagnostics() -> anyhow::Result<()> {
+    // Same directory Tauri resolves as app_data_dir, but needed before the app is built
+    let data_dir = dirs::data_dir().map(|dir| dir.join(headless::APP_IDENTIFIER));
+    let log_dir = data_dir.as_ref().map(|dir| dir.join(logging::LOG_DIR));
+    logging::init(log_dir.as_deref())?;
+    crash::install(data_dir.map(|dir| dir.join(crash::CRASH_DIR)));
+    Ok(())
+}
+
+/// Entry point of the standalone `koharu-cli` binary (the cli crate)
+pub fn run_cli() -> anyhow::Result<()> {
+    init_diagnostics()?;
+    headless::run_as("koharu-cli", std::env::args())
+}
+
+#[cfg(feature = "gui")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() -> anyhow::Result<()> {
+    init_diagnostics()?;
+
+    if headless::requested() {
+        return headless::run();
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
             // initialize the app state
             let app_handle = app.handle().clone();
@@ -365,6 +497,7 @@ pub fn run() -> anyhow::Result<()> {
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
+            decode_image,
             detection,
             ocr,
             set_active_ocr,
@@ -372,17 +505,73 @@ pub fn run() -> anyhow::Result<()> {
             inpaint_region,
             cache_inpainting_data,
             inpaint_region_cached,
-            clear_inpainting_cache,
             set_gpu_preference,
             get_gpu_devices,
             get_current_gpu_status,
             run_gpu_stress_test,
             translate_with_deepl,
             translate_with_ollama,
+            score_translations,
             render_and_export_image,
             cache_ocr_image,
-            clear_ocr_cache,
-            ocr_cached_block
+            release_page,
+            get_page_composite,
+            ocr_cached_block,
+            analyze_block_appearance,
+            estimate_block_lettering,
+            evaluate_ocr,
+            compare_ocr_configs,
+            export_accuracy_report,
+            evaluate_detection,
+            evaluate_inpainting,
+            run_benchmark_suite,
+            run_golden_tests,
+            list_export_roots,
+            add_export_root,
+            remove_export_root,
+            process_page,
+            process_folder,
+            export_pdf,
+            create_comparison,
+            export_annotations,
+            import_archive,
+            import_mit_json,
+            export_mit_json,
+            enqueue_job,
+            pause_job,
+            resume_job,
+            cancel_job,
+            list_jobs,
+            clear_finished_jobs,
+            list_active_tasks,
+            get_settings,
+            update_settings,
+            export_settings,
+            import_settings,
+            open_project,
+            save_project,
+            get_project,
+            close_project,
+            save_session,
+            restore_session,
+            reload_scripts,
+            run_script_hook,
+            list_plugins,
+            reload_plugins,
+            set_ui_language,
+            set_log_level,
+            get_log_level,
+            get_log_dir,
+            get_recent_logs,
+            get_pending_crash_report,
+            dismiss_crash_reports,
+            load_editor_state,
+            get_editor_state,
+            update_blocks,
+            update_mask,
+            update_inpainted,
+            undo,
+            redo
         ])
         .run(tauri::generate_context!())?;
 
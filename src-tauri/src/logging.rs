@@ -0,0 +1,167 @@
+// Logging: stdout plus daily-rotated files under app_data_dir/logs, with a runtime-adjustable level
+// The most recent lines are also kept in memory so they can be attached to bug reports.
+
+use anyhow::{Context, anyhow};
+use std::collections::VecDeque;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Registry, fmt, reload};
+
+pub const LOG_DIR: &str = "logs";
+const LOG_FILE_PREFIX: &str = "koharu";
+const LOG_FILES_KEPT: usize = 7; // One file per day
+const RECENT_LINES_KEPT: usize = 2000;
+const DEFAULT_LEVEL: &str = "info";
+
+struct Logging {
+    filter: reload::Handle<EnvFilter, Registry>,
+    level: Mutex<String>,
+    dir: Option<PathBuf>,
+}
+
+static LOGGING: OnceLock<Logging> = OnceLock::new();
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Copies formatted log output into the in-memory ring buffer
+#[derive(Clone, Copy)]
+struct RecentWriter;
+
+impl io::Write for RecentWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+        for line in String::from_utf8_lossy(buf).lines() {
+            if line.is_empty() {
+                continue;
+            }
+            if recent.len() >= RECENT_LINES_KEPT {
+                recent.pop_front();
+            }
+            recent.push_back(line.to_string());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Install the global subscriber; files go to `log_dir` when given
+///
+/// `RUST_LOG` overrides the default level. A log directory that can't be created only
+/// disables file output.
+pub fn init(log_dir: Option<&Path>) -> anyhow::Result<()> {
+    let (filter, level) = match EnvFilter::try_from_default_env() {
+        Ok(filter) => {
+            let level = filter.to_string();
+            (filter, level)
+        }
+        Err(_) => (EnvFilter::new(DEFAULT_LEVEL), DEFAULT_LEVEL.to_string()),
+    };
+    let (filter, handle) = reload::Layer::new(filter);
+
+    let file = match log_dir.map(open_appender).transpose() {
+        Ok(file) => file,
+        Err(err) => {
+            eprintln!("File logging disabled: {:#}", err);
+            None
+        }
+    };
+    let dir = file.as_ref().and(log_dir.map(Path::to_path_buf));
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr)) // Keeps stdout free for CLI output
+        .with(file.map(|file| fmt::layer().with_ansi(false).with_writer(file)))
+        .with(fmt::layer().with_ansi(false).with_writer(|| RecentWriter))
+        .try_init()
+        .context("Failed to install the log subscriber")?;
+
+    let _ = LOGGING.set(Logging {
+        filter: handle,
+        level: Mutex::new(level),
+        dir,
+    });
+    if let Some(dir) = log_dir {
+        tracing::info!("[logging] writing logs to {:?}", dir);
+    }
+    Ok(())
+}
+
+fn open_appender(dir: &Path) -> anyhow::Result<RollingFileAppender> {
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix("log")
+        .max_log_files(LOG_FILES_KEPT)
+        .build(dir)
+        .with_context(|| format!("Failed to open log directory {:?}", dir))
+}
+
+/// Change the level at runtime, e.g. "debug" or "info,koharu_lib=trace"
+pub fn set_level(directive: &str) -> anyhow::Result<()> {
+    let logging = LOGGING
+        .get()
+        .ok_or_else(|| anyhow!("Logging is not initialized"))?;
+    let filter = EnvFilter::try_new(directive)
+        .with_context(|| format!("Invalid log level '{}'", directive))?;
+    logging
+        .filter
+        .reload(filter)
+        .context("Failed to apply log level")?;
+
+    *logging.level.lock().unwrap_or_else(|e| e.into_inner()) = directive.to_string();
+    tracing::info!("[logging] level set to '{}'", directive);
+    Ok(())
+}
+
+/// Current level directive
+pub fn level() -> String {
+    LOGGING
+        .get()
+        .map(|logging| {
+            logging
+                .level
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone()
+        })
+        .unwrap_or_else(|| DEFAULT_LEVEL.to_string())
+}
+
+/// Directory log files are written to, if file logging is active
+pub fn log_dir() -> Option<PathBuf> {
+    LOGGING.get().and_then(|logging| logging.dir.clone())
+}
+
+/// The last `n` formatted log lines, oldest first
+pub fn recent_lines(n: usize) -> Vec<String> {
+    let recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    recent
+        .iter()
+        .skip(recent.len().saturating_sub(n))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_recent_writer_keeps_last_lines() {
+        let mut writer = RecentWriter;
+        writer.write_all(b"first\nsecond\n").unwrap();
+        writer.write_all(b"third\n").unwrap();
+
+        let lines = recent_lines(2);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines.last().unwrap(), "third");
+        assert!(recent_lines(usize::MAX).len() <= RECENT_LINES_KEPT);
+    }
+}
@@ -0,0 +1,356 @@
+// Export metadata: credits/title tags as PNG iTXt + XMP, and selected EXIF fields copied from the source
+// Metadata is spliced into the encoded bytes so it works regardless of which encoder produced them.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::image_io::OutputFormat;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const XMP_KEYWORD: &str = "XML:com.adobe.xmp";
+const JPEG_XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const JPEG_EXIF_HEADER: &[u8] = b"Exif\0\0";
+
+/// Source EXIF fields worth carrying over; camera/GPS data and thumbnails are dropped
+const PRESERVED_EXIF_TAGS: [exif::Tag; 5] = [
+    exif::Tag::Artist,
+    exif::Tag::Copyright,
+    exif::Tag::ImageDescription,
+    exif::Tag::DateTimeOriginal,
+    exif::Tag::DateTime,
+];
+
+/// Metadata to embed in exported pages; saved with the export settings or sent per job
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportMetadata {
+    pub embed_tags: bool, // Title/chapter/credit/version/timestamp tags
+    pub title: Option<String>,
+    pub chapter: Option<String>,
+    pub translator: Option<String>,
+    pub preserve_exif: bool,
+}
+
+impl ExportMetadata {
+    fn is_empty(&self) -> bool {
+        !self.embed_tags && !self.preserve_exif
+    }
+
+    /// Key/value tags written to the file
+    fn tags(&self, timestamp: &str) -> Vec<(&'static str, String)> {
+        let mut tags = Vec::new();
+        if let Some(title) = &self.title {
+            tags.push(("Title", title.clone()));
+        }
+        if let Some(chapter) = &self.chapter {
+            tags.push(("Chapter", chapter.clone()));
+        }
+        if let Some(translator) = &self.translator {
+            tags.push(("Translator", translator.clone()));
+        }
+        tags.push(("Software", format!("Koharu {}", env!("CARGO_PKG_VERSION"))));
+        tags.push(("Creation Time", timestamp.to_string()));
+        tags
+    }
+}
+
+/// Add tags and preserved EXIF to already-encoded image bytes
+///
+/// PNG and JPEG are supported; other formats are returned unchanged.
+pub fn embed(
+    bytes: Vec<u8>,
+    format: OutputFormat,
+    metadata: &ExportMetadata,
+    source_exif: Option<&[u8]>,
+) -> anyhow::Result<Vec<u8>> {
+    if metadata.is_empty() {
+        return Ok(bytes);
+    }
+
+    let timestamp = utc_timestamp(SystemTime::now());
+    let xmp = metadata
+        .embed_tags
+        .then(|| xmp_packet(metadata, &timestamp));
+    let exif = match source_exif {
+        Some(raw) if metadata.preserve_exif => filter_exif(raw)?,
+        _ => None,
+    };
+
+    match format {
+        OutputFormat::Png => {
+            let mut chunks = Vec::new();
+            if metadata.embed_tags {
+                for (keyword, text) in metadata.tags(&timestamp) {
+                    chunks.push(png_chunk(b"iTXt", &itxt_payload(keyword, &text)));
+                }
+            }
+            if let Some(xmp) = &xmp {
+                chunks.push(png_chunk(b"iTXt", &itxt_payload(XMP_KEYWORD, xmp)));
+            }
+            if let Some(exif) = &exif {
+                chunks.push(png_chunk(b"eXIf", exif));
+            }
+            insert_png_chunks(bytes, &chunks.concat())
+        }
+        OutputFormat::Jpeg => {
+            let mut segments = Vec::new();
+            if let Some(exif) = &exif {
+                segments.push(jpeg_app1(&[JPEG_EXIF_HEADER, exif].concat())?);
+            }
+            if let Some(xmp) = &xmp {
+                segments.push(jpeg_app1(&[JPEG_XMP_HEADER, xmp.as_bytes()].concat())?);
+            }
+            insert_jpeg_segments(bytes, &segments.concat())
+        }
+        OutputFormat::Webp | OutputFormat::Avif => {
+            tracing::warn!(
+                "[metadata] {} export does not support embedded metadata; skipping",
+                format.extension()
+            );
+            Ok(bytes)
+        }
+    }
+}
+
+/// Raw TIFF-structured EXIF from a source file, if it has any
+pub fn read_exif(bytes: &[u8]) -> Option<Vec<u8>> {
+    exif::Reader::new()
+        .read_from_container(&mut Cursor::new(bytes))
+        .ok()
+        .map(|exif| exif.buf().to_vec())
+}
+
+/// Keep only PRESERVED_EXIF_TAGS from the primary image
+fn filter_exif(raw: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+    let Ok(exif) = exif::Reader::new().read_raw(raw.to_vec()) else {
+        tracing::warn!("[metadata] source EXIF is unreadable; not preserving it");
+        return Ok(None);
+    };
+
+    let mut writer = exif::experimental::Writer::new();
+    let mut kept = 0;
+    for field in exif.fields() {
+        if field.ifd_num == exif::In::PRIMARY && PRESERVED_EXIF_TAGS.contains(&field.tag) {
+            writer.push_field(field);
+            kept += 1;
+        }
+    }
+    if kept == 0 {
+        return Ok(None);
+    }
+
+    let mut buffer = Cursor::new(Vec::new());
+    writer
+        .write(&mut buffer, exif.little_endian())
+        .context("Failed to write preserved EXIF")?;
+    Ok(Some(buffer.into_inner()))
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn xmp_packet(metadata: &ExportMetadata, timestamp: &str) -> String {
+    let mut properties = String::new();
+    if let Some(title) = &metadata.title {
+        properties.push_str(&format!(
+            "<dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:title>",
+            xml_escape(title)
+        ));
+    }
+    if let Some(translator) = &metadata.translator {
+        properties.push_str(&format!(
+            "<dc:contributor><rdf:Bag><rdf:li>{}</rdf:li></rdf:Bag></dc:contributor>",
+            xml_escape(translator)
+        ));
+    }
+    if let Some(chapter) = &metadata.chapter {
+        properties.push_str(&format!(
+            "<koharu:chapter>{}</koharu:chapter>",
+            xml_escape(chapter)
+        ));
+    }
+
+    format!(
+        concat!(
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>",
+            "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">",
+            "<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">",
+            "<rdf:Description rdf:about=\"\"",
+            " xmlns:dc=\"http://purl.org/dc/elements/1.1/\"",
+            " xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"",
+            " xmlns:koharu=\"https://github.com/mayocream/koharu/ns/1.0/\">",
+            "<xmp:CreatorTool>Koharu {}</xmp:CreatorTool>",
+            "<xmp:CreateDate>{}</xmp:CreateDate>",
+            "{}",
+            "</rdf:Description></rdf:RDF></x:xmpmeta>",
+            "<?xpacket end=\"w\"?>"
+        ),
+        env!("CARGO_PKG_VERSION"),
+        timestamp,
+        properties
+    )
+}
+
+/// Uncompressed international text chunk; UTF-8 so Japanese titles survive
+fn itxt_payload(keyword: &str, text: &str) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(keyword.len() + text.len() + 5);
+    payload.extend_from_slice(keyword.as_bytes());
+    payload.extend_from_slice(&[0, 0, 0]); // Separator, compression flag, compression method
+    payload.push(0); // Empty language tag
+    payload.push(0); // Empty translated keyword
+    payload.extend_from_slice(text.as_bytes());
+    payload
+}
+
+fn png_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(data.len() + 12);
+    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(kind);
+    chunk.extend_from_slice(data);
+
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+    chunk.extend_from_slice(&crc.finalize().to_be_bytes());
+    chunk
+}
+
+/// Insert chunks right after IHDR, ahead of the image data
+fn insert_png_chunks(mut png: Vec<u8>, chunks: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let ihdr_end = PNG_SIGNATURE.len() + 8 + 13 + 4;
+    if !png.starts_with(PNG_SIGNATURE) || png.len() < ihdr_end || &png[12..16] != b"IHDR" {
+        anyhow::bail!("Encoded PNG has an unexpected layout");
+    }
+    png.splice(ihdr_end..ihdr_end, chunks.iter().copied());
+    Ok(png)
+}
+
+fn jpeg_app1(data: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let length = u16::try_from(data.len() + 2).context("JPEG metadata segment is too large")?;
+    let mut segment = vec![0xFF, 0xE1];
+    segment.extend_from_slice(&length.to_be_bytes());
+    segment.extend_from_slice(data);
+    Ok(segment)
+}
+
+/// Insert segments after SOI and any leading APP0 (JFIF) segment
+fn insert_jpeg_segments(mut jpeg: Vec<u8>, segments: &[u8]) -> anyhow::Result<Vec<u8>> {
+    if !jpeg.starts_with(&[0xFF, 0xD8]) {
+        anyhow::bail!("Encoded JPEG is missing its SOI marker");
+    }
+    let mut offset = 2;
+    if jpeg.len() >= 6 && jpeg[2..4] == [0xFF, 0xE0] {
+        offset += 2 + u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize;
+    }
+    jpeg.splice(offset..offset, segments.iter().copied());
+    Ok(jpeg)
+}
+
+/// RFC 3339 UTC timestamp without pulling in a date crate
+pub(crate) fn utc_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Civil-from-days (Howard Hinnant)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn tagged() -> ExportMetadata {
+        ExportMetadata {
+            embed_tags: true,
+            title: Some("ぼっち・ざ・ろっく！".to_string()),
+            chapter: Some("12".to_string()),
+            translator: Some("Scanlation <Group>".to_string()),
+            preserve_exif: false,
+        }
+    }
+
+    #[test]
+    fn test_utc_timestamp() {
+        assert_eq!(utc_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        let leap_day = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        assert_eq!(utc_timestamp(leap_day), "2024-02-29T12:34:56Z");
+    }
+
+    #[test]
+    fn test_png_tags_are_inserted_after_ihdr() {
+        let image = image::DynamicImage::new_rgb8(4, 4);
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let tagged_png = embed(png.clone(), OutputFormat::Png, &tagged(), None).unwrap();
+        assert!(tagged_png.len() > png.len());
+        assert_eq!(&tagged_png[37..41], b"iTXt");
+
+        // Chunks must keep the file decodable, and the UTF-8 title must be intact
+        let decoded = image::load_from_memory(&tagged_png).unwrap();
+        assert_eq!(decoded.width(), 4);
+        let text = String::from_utf8_lossy(&tagged_png);
+        assert!(text.contains("ぼっち・ざ・ろっく！"));
+        assert!(text.contains("Scanlation &lt;Group&gt;"));
+    }
+
+    #[test]
+    fn test_disabled_metadata_leaves_bytes_untouched() {
+        let bytes = vec![1, 2, 3];
+        let out = embed(
+            bytes.clone(),
+            OutputFormat::Png,
+            &ExportMetadata::default(),
+            None,
+        );
+        assert_eq!(out.unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_jpeg_segments_follow_jfif_header() {
+        let image = image::DynamicImage::new_rgb8(4, 4);
+        let mut jpeg = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut jpeg), image::ImageFormat::Jpeg)
+            .unwrap();
+
+        let tagged_jpeg = embed(jpeg, OutputFormat::Jpeg, &tagged(), None).unwrap();
+        assert!(image::load_from_memory(&tagged_jpeg).is_ok());
+        let xmp_at = tagged_jpeg
+            .windows(JPEG_XMP_HEADER.len())
+            .position(|w| w == JPEG_XMP_HEADER)
+            .unwrap();
+        assert_eq!(&tagged_jpeg[xmp_at - 4..xmp_at - 2], &[0xFF, 0xE1]);
+    }
+}
@@ -0,0 +1,246 @@
+// manga-image-translator per-page JSON, so pages can move between it and Koharu
+// Its text export maps each image path to `{ "regions": [...] }`. A region carries its bounds
+// as `xyxy` and as text line quads in `lines`, the source text (`text`, or one entry per line
+// in `texts`), the `translation`, and optionally font size and colors. Fields Koharu has no use
+// for are ignored on import; export writes the fields the tool needs to re-render a page.
+
+use anyhow::{Context, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+use crate::pipeline::PageBlock;
+use crate::text_renderer::RgbColor;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MitRegion {
+    pub xyxy: Option<[f32; 4]>,
+    pub lines: Vec<Vec<[f32; 2]>>,
+    pub text: Option<String>,
+    pub texts: Vec<String>,
+    pub translation: Option<String>,
+    pub font_size: Option<f32>,
+    #[serde(alias = "fg_colors")]
+    pub fg_color: Option<[u8; 3]>,
+    #[serde(alias = "bg_colors")]
+    pub bg_color: Option<[u8; 3]>,
+    pub prob: Option<f32>,
+    pub direction: Option<String>, // "h" or "v"
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MitPage {
+    #[serde(default)]
+    pub regions: Vec<MitRegion>,
+}
+
+/// One imported page: the image path as the file names it, and its blocks
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedPage {
+    pub image: String,
+    pub blocks: Vec<PageBlock>,
+}
+
+fn color(rgb: Option<[u8; 3]>) -> Option<RgbColor> {
+    rgb.map(|[r, g, b]| RgbColor { r, g, b })
+}
+
+fn luma(color: &RgbColor) -> f32 {
+    0.299 * color.r as f32 + 0.587 * color.g as f32 + 0.114 * color.b as f32
+}
+
+impl MitRegion {
+    /// Bounds from `xyxy`, or the union of the line quads
+    fn bounds(&self) -> Option<[f32; 4]> {
+        if let Some(xyxy) = self.xyxy {
+            return Some(xyxy);
+        }
+        let points = self.lines.iter().flatten();
+        points.clone().next()?;
+        Some(points.fold(
+            [f32::MAX, f32::MAX, f32::MIN, f32::MIN],
+            |[x0, y0, x1, y1], [x, y]| [x0.min(*x), y0.min(*y), x1.max(*x), y1.max(*y)],
+        ))
+    }
+
+    fn source_text(&self) -> String {
+        match &self.text {
+            Some(text) => text.clone(),
+            None => self.texts.join(""),
+        }
+    }
+
+    pub fn to_block(&self) -> Option<PageBlock> {
+        let [xmin, ymin, xmax, ymax] = self.bounds()?;
+        let text_color = color(self.fg_color);
+        let background_color = color(self.bg_color);
+        // Koharu's detector classes: light text on a darker background is class 1
+        let class = match (&text_color, &background_color) {
+            (Some(fg), Some(bg)) if luma(fg) > luma(bg) => 1,
+            _ => 0,
+        };
+        Some(PageBlock {
+            xmin,
+            ymin,
+            xmax,
+            ymax,
+            confidence: self.prob.unwrap_or(1.0),
+            class,
+            text: self.source_text(),
+            translated_text: self.translation.clone().filter(|t| !t.is_empty()),
+            font_size: self.font_size,
+            font_weight: None,
+            text_color,
+            background_color,
+        })
+    }
+
+    pub fn from_block(block: &PageBlock) -> Self {
+        let (xmin, ymin, xmax, ymax) = (block.xmin, block.ymin, block.xmax, block.ymax);
+        let rgb = |color: &Option<RgbColor>| color.as_ref().map(|c| [c.r, c.g, c.b]);
+        MitRegion {
+            xyxy: Some([xmin, ymin, xmax, ymax]),
+            lines: vec![vec![[xmin, ymin], [xmax, ymin], [xmax, ymax], [xmin, ymax]]],
+            text: Some(block.text.clone()),
+            texts: vec![block.text.clone()],
+            translation: block.translated_text.clone(),
+            font_size: block.font_size,
+            fg_color: rgb(&block.text_color),
+            bg_color: rgb(&block.background_color),
+            prob: Some(block.confidence),
+            direction: Some(if ymax - ymin > xmax - xmin { "v" } else { "h" }.to_string()),
+        }
+    }
+}
+
+/// Pages in a manga-image-translator JSON file, sorted by image name
+///
+/// Accepts the usual `{ "<image>": { "regions": [...] } }` map and a bare `{ "regions": [...] }`
+/// page, which is named after the JSON file. Regions without bounds are dropped.
+pub fn parse(json: &str, fallback_name: &str) -> anyhow::Result<Vec<ImportedPage>> {
+    let value: Value = serde_json::from_str(json)?;
+    let Value::Object(map) = value else {
+        return Err(anyhow!("Expected a JSON object of pages"));
+    };
+    let pages: Vec<(String, Value)> = if map.contains_key("regions") {
+        vec![(fallback_name.to_string(), Value::Object(map))]
+    } else {
+        map.into_iter().collect()
+    };
+    pages
+        .into_iter()
+        .map(|(image, page)| {
+            let page: MitPage = serde_json::from_value(page)
+                .with_context(|| format!("Invalid regions for '{}'", image))?;
+            let blocks = page
+                .regions
+                .iter()
+                .filter_map(MitRegion::to_block)
+                .collect();
+            Ok(ImportedPage { image, blocks })
+        })
+        .collect()
+}
+
+pub fn import(path: &Path) -> anyhow::Result<Vec<ImportedPage>> {
+    let json = fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    parse(&json, &name).with_context(|| format!("Invalid manga-image-translator JSON {:?}", path))
+}
+
+/// `{ "<image>": { "regions": [...] } }` for one page
+pub fn to_json(image: &str, blocks: &[PageBlock]) -> anyhow::Result<String> {
+    let page = MitPage {
+        regions: blocks.iter().map(MitRegion::from_block).collect(),
+    };
+    let mut map = Map::new();
+    map.insert(image.to_string(), serde_json::to_value(page)?);
+    Ok(serde_json::to_string_pretty(&Value::Object(map))? + "\n")
+}
+
+pub fn export(path: &Path, image: &str, blocks: &[PageBlock]) -> anyhow::Result<()> {
+    fs::write(path, to_json(image, blocks)?).with_context(|| format!("Failed to write {:?}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"{
+        "pages/001.png": {
+            "regions": [
+                {
+                    "lines": [[[100, 50], [140, 50], [140, 300], [100, 300]],
+                              [[60, 50], [95, 50], [95, 260], [60, 260]]],
+                    "texts": ["こんにちは", "元気?"],
+                    "translation": "Hello, how are you?",
+                    "font_size": 28,
+                    "fg_colors": [255, 255, 255],
+                    "bg_colors": [0, 0, 0],
+                    "direction": "v",
+                    "angle": 0,
+                    "alignment": "center"
+                },
+                { "xyxy": [10, 10, 80, 40], "text": "ドン", "translation": "", "prob": 0.8 },
+                { "text": "no bounds" }
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn test_import_reads_bounds_text_and_colors() {
+        let pages = parse(SAMPLE, "unused").unwrap();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].image, "pages/001.png");
+        let blocks = &pages[0].blocks;
+        assert_eq!(blocks.len(), 2);
+
+        let first = &blocks[0];
+        assert_eq!(
+            (first.xmin, first.ymin, first.xmax, first.ymax),
+            (60.0, 50.0, 140.0, 300.0)
+        );
+        assert_eq!(first.text, "こんにちは元気?");
+        assert_eq!(
+            first.translated_text.as_deref(),
+            Some("Hello, how are you?")
+        );
+        assert_eq!(first.font_size, Some(28.0));
+        assert_eq!(first.class, 1); // White on black
+
+        let second = &blocks[1];
+        assert_eq!(second.text, "ドン");
+        assert_eq!(second.translated_text, None);
+        assert_eq!(second.confidence, 0.8);
+    }
+
+    #[test]
+    fn test_export_round_trips_and_accepts_bare_pages() {
+        let blocks = parse(SAMPLE, "unused").unwrap().remove(0).blocks;
+        let json = to_json("001.png", &blocks).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        let region = &value["001.png"]["regions"][0];
+        assert_eq!(
+            region["xyxy"],
+            serde_json::json!([60.0, 50.0, 140.0, 300.0])
+        );
+        assert_eq!(region["direction"], "v");
+        assert_eq!(region["fg_color"], serde_json::json!([255, 255, 255]));
+
+        let again = parse(&json, "unused").unwrap().remove(0);
+        assert_eq!(again.image, "001.png");
+        assert_eq!(again.blocks[0].text, blocks[0].text);
+        assert_eq!(again.blocks[0].translated_text, blocks[0].translated_text);
+        assert_eq!(again.blocks[1].xmax, 80.0);
+
+        let bare = parse(r#"{ "regions": [{ "xyxy": [0, 0, 5, 5] }] }"#, "002").unwrap();
+        assert_eq!(bare[0].image, "002");
+        assert!(parse("[]", "x").is_err());
+    }
+}
@@ -0,0 +1,188 @@
+// Output filename templates for batch exports, e.g. "{title}/ch{chapter}/{page:03}_{lang}"
+// `/` in a template creates subdirectories; the output format's extension is appended.
+
+use anyhow::anyhow;
+use std::path::PathBuf;
+
+/// Values available to a filename template
+#[derive(Debug, Clone)]
+pub struct NameFields<'a> {
+    pub title: Option<&'a str>,
+    pub chapter: Option<&'a str>,
+    pub page: usize,   // 1-based position in the sorted input listing
+    pub lang: &'a str, // Translation target language
+    pub name: &'a str, // Input file stem
+}
+
+impl NameFields<'_> {
+    fn value(&self, key: &str) -> anyhow::Result<String> {
+        let value = match key {
+            "title" => self.title.map(str::to_string),
+            "chapter" => self.chapter.map(str::to_string),
+            "page" => Some(self.page.to_string()),
+            "lang" => Some(self.lang.to_lowercase()),
+            "name" => Some(self.name.to_string()),
+            _ => {
+                return Err(anyhow!(
+                    "Unknown template field {{{}}}; use title, chapter, page, lang, or name",
+                    key
+                ));
+            }
+        };
+        value
+            .filter(|v| !v.trim().is_empty())
+            .ok_or_else(|| anyhow!("Template uses {{{}}} but no {} is set", key, key))
+    }
+}
+
+/// Check a template before a batch starts so a typo fails once instead of per page
+pub fn validate(template: &str) -> anyhow::Result<()> {
+    if !template.contains("{page") && !template.contains("{name") {
+        return Err(anyhow!(
+            "Filename template must include {{page}} or {{name}} so pages don't overwrite each other"
+        ));
+    }
+    let fields = NameFields {
+        title: Some("title"),
+        chapter: Some("1"),
+        page: 1,
+        lang: "en",
+        name: "page",
+    };
+    render(template, &fields, "png").map(|_| ())
+}
+
+/// Expand `template` into a relative output path ending in `.extension`
+///
+/// `{field:0N}` left-pads the value with zeros to N characters; `{{` and `}}` are literal braces.
+pub fn render(template: &str, fields: &NameFields, extension: &str) -> anyhow::Result<PathBuf> {
+    let mut rendered = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                rendered.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                rendered.push('}');
+            }
+            '{' => {
+                let mut spec = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => spec.push(c),
+                        None => return Err(anyhow!("Unclosed '{{' in template '{}'", template)),
+                    }
+                }
+                let (key, width) = match spec.split_once(':') {
+                    Some((key, format)) => (key, parse_width(format)?),
+                    None => (spec.as_str(), 0),
+                };
+                let value = sanitize(&fields.value(key.trim())?);
+                rendered.push_str(&format!("{:0>width$}", value, width = width));
+            }
+            '}' => return Err(anyhow!("Unmatched '}}' in template '{}'", template)),
+            '\\' => rendered.push('/'),
+            c => rendered.push(c),
+        }
+    }
+
+    let mut path = PathBuf::new();
+    for component in rendered.split('/') {
+        let component = component.trim();
+        if component.is_empty() || component == "." || component == ".." {
+            return Err(anyhow!(
+                "Template '{}' produced an invalid path '{}'",
+                template,
+                rendered
+            ));
+        }
+        path.push(component);
+    }
+    let file_name = format!(
+        "{}.{}",
+        path.file_name().unwrap_or_default().to_string_lossy(),
+        extension
+    );
+    path.set_file_name(file_name);
+    Ok(path)
+}
+
+fn parse_width(format: &str) -> anyhow::Result<usize> {
+    format
+        .strip_prefix('0')
+        .and_then(|digits| digits.parse().ok())
+        .ok_or_else(|| {
+            anyhow!(
+                "Unsupported format ':{}'; use zero padding like :03",
+                format
+            )
+        })
+}
+
+/// Replace characters that are not allowed in file names on Windows/macOS/Linux
+fn sanitize(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    cleaned.trim().trim_end_matches(['.', ' ']).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields() -> NameFields<'static> {
+        NameFields {
+            title: Some("Yotsuba&!"),
+            chapter: Some("12"),
+            page: 7,
+            lang: "EN-US",
+            name: "scan_0007",
+        }
+    }
+
+    #[test]
+    fn test_render_expands_fields_and_padding() {
+        let path = render("{title}/ch{chapter:03}/{page:03}_{lang}", &fields(), "png").unwrap();
+        assert_eq!(path, PathBuf::from("Yotsuba&!/ch012/007_en-us.png"));
+
+        let path = render("{{{name}}}", &fields(), "webp").unwrap();
+        assert_eq!(path, PathBuf::from("{scan_0007}.webp"));
+    }
+
+    #[test]
+    fn test_render_sanitizes_values() {
+        let fields = NameFields {
+            title: Some("What? / Why: ..."),
+            ..fields()
+        };
+        let path = render("{title} {page}", &fields, "jpg").unwrap();
+        assert_eq!(path, PathBuf::from("What_ _ Why_ 7.jpg"));
+    }
+
+    #[test]
+    fn test_invalid_templates_are_rejected() {
+        assert!(validate("{title}").is_err()); // Every page would get the same name
+        assert!(validate("{page").is_err());
+        assert!(validate("{volume}/{page}").is_err());
+        assert!(validate("{page:3}").is_err());
+        assert!(validate("../{page}").is_err());
+        assert!(validate("{title}/{chapter}/{page:02}").is_ok());
+
+        let untitled = NameFields {
+            title: None,
+            ..fields()
+        };
+        assert!(render("{title}/{page}", &untitled, "png").is_err());
+    }
+}
@@ -0,0 +1,536 @@
+// A/B comparison of two OCR configurations on the same labelled dataset
+// The dataset is laid out as for `ocr_eval`; images in a subfolder belong to the class named
+// after it (e.g. `vertical/`, `sfx/`), images at the top level to "default". Each sample is
+// scored under both configurations, so the CER/WER deltas are paired and tested with the
+// Wilcoxon signed-rank test.
+
+use anyhow::anyhow;
+use image::DynamicImage;
+use image::imageops::FilterType;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::accuracy::AccuracyMetrics;
+use crate::image_io;
+use crate::ocr_eval::{self, SkippedSample};
+use crate::ocr_pipeline::OcrPipeline;
+
+pub const DEFAULT_CLASS: &str = "default";
+const SIGNIFICANCE_LEVEL: f64 = 0.05;
+
+/// Image changes applied before recognition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Preprocess {
+    pub grayscale: bool,
+    pub scale: f32,            // Resize factor, 1.0 keeps the size
+    pub threshold: Option<u8>, // Binarize: luma above is white, the rest black
+}
+
+impl Default for Preprocess {
+    fn default() -> Self {
+        Preprocess {
+            grayscale: false,
+            scale: 1.0,
+            threshold: None,
+        }
+    }
+}
+
+impl Preprocess {
+    pub fn apply(&self, image: DynamicImage) -> DynamicImage {
+        let mut image = image;
+        if self.scale > 0.0 && (self.scale - 1.0).abs() > f32::EPSILON {
+            let width = ((image.width() as f32 * self.scale).round() as u32).max(1);
+            let height = ((image.height() as f32 * self.scale).round() as u32).max(1);
+            image = image.resize_exact(width, height, FilterType::CatmullRom);
+        }
+        if let Some(threshold) = self.threshold {
+            let mut luma = image.to_luma8();
+            for pixel in luma.pixels_mut() {
+                pixel[0] = if pixel[0] > threshold { 255 } else { 0 };
+            }
+            image = DynamicImage::ImageLuma8(luma);
+        } else if self.grayscale {
+            image = DynamicImage::ImageLuma8(image.to_luma8());
+        }
+        image
+    }
+}
+
+/// How recognized region texts become the sample's prediction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DecodeOptions {
+    pub separator: String,       // Between regions
+    pub remove_whitespace: bool, // Useful for Japanese, where spaces are recognition noise
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        DecodeOptions {
+            separator: " ".to_string(),
+            remove_whitespace: false,
+        }
+    }
+}
+
+impl DecodeOptions {
+    pub fn apply(&self, texts: &[String]) -> String {
+        let joined = texts.join(&self.separator);
+        if self.remove_whitespace {
+            joined.chars().filter(|c| !c.is_whitespace()).collect()
+        } else {
+            joined.trim().to_string()
+        }
+    }
+}
+
+/// One side of the comparison
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OcrConfig {
+    pub label: Option<String>,
+    pub engine: Option<String>, // Active engine when unset
+    pub preprocess: Preprocess,
+    pub decode: DecodeOptions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Winner {
+    A,
+    B,
+    Tie,
+}
+
+/// Paired differences of one metric, as B minus A (negative means B is better)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairedDelta {
+    pub mean_a: f64,
+    pub mean_b: f64,
+    pub mean_delta: f64,
+    pub a_better: usize,
+    pub b_better: usize,
+    pub ties: usize,
+    pub p_value: f64, // Two-sided Wilcoxon signed-rank, normal approximation
+    pub significant: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PairedSample {
+    pub file: String,
+    pub class: String,
+    pub ground_truth: String,
+    pub predicted_a: String,
+    pub predicted_b: String,
+    pub cer_a: f64,
+    pub cer_b: f64,
+    pub wer_a: f64,
+    pub wer_b: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassComparison {
+    pub class: String,
+    pub samples: usize,
+    pub cer: PairedDelta,
+    pub wer: PairedDelta,
+    pub winner: Winner,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrComparison {
+    pub a: OcrConfig,
+    pub b: OcrConfig,
+    pub samples: Vec<PairedSample>,
+    pub cer: PairedDelta,
+    pub wer: PairedDelta,
+    pub winner: Winner,
+    pub classes: Vec<ClassComparison>,
+    pub skipped: Vec<SkippedSample>, // Failed under either configuration
+    pub elapsed_ms: u64,
+}
+
+/// Labelled samples as (class, image, ground truth), from `dir` and its immediate subfolders
+pub fn classified_pairs(
+    dir: &Path,
+) -> anyhow::Result<(Vec<(String, PathBuf, String)>, Vec<SkippedSample>)> {
+    let (pairs, mut skipped) = ocr_eval::dataset_pairs(dir)?;
+    let mut samples: Vec<(String, PathBuf, String)> = pairs
+        .into_iter()
+        .map(|(image, truth)| (DEFAULT_CLASS.to_string(), image, truth))
+        .collect();
+
+    let mut subdirs: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    subdirs.sort();
+    for subdir in subdirs {
+        let class = subdir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let (pairs, class_skipped) = ocr_eval::dataset_pairs(&subdir)?;
+        samples.extend(
+            pairs
+                .into_iter()
+                .map(|(image, truth)| (class.clone(), image, truth)),
+        );
+        skipped.extend(class_skipped.into_iter().map(|sample| SkippedSample {
+            file: format!("{}/{}", class, sample.file),
+            ..sample
+        }));
+    }
+    Ok((samples, skipped))
+}
+
+/// Standard normal CDF, via the Abramowitz-Stegun erf approximation (error < 1.5e-7)
+fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-x * x).exp();
+    if z >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+/// Two-sided p-value of the Wilcoxon signed-rank test on paired differences
+///
+/// Zero differences are dropped and tied ranks averaged. Uses the normal approximation with
+/// continuity and tie corrections, which is rough below about ten non-zero pairs.
+pub fn wilcoxon_signed_rank(deltas: &[f64]) -> f64 {
+    let mut nonzero: Vec<f64> = deltas.iter().copied().filter(|d| *d != 0.0).collect();
+    let n = nonzero.len();
+    if n == 0 {
+        return 1.0;
+    }
+    nonzero.sort_by(|a, b| a.abs().total_cmp(&b.abs()));
+
+    let mut positive_rank_sum = 0.0;
+    let mut tie_correction = 0.0;
+    let mut i = 0;
+    while i < n {
+        let mut j = i;
+        while j + 1 < n && nonzero[j + 1].abs() == nonzero[i].abs() {
+            j += 1;
+        }
+        let rank = (i + j) as f64 / 2.0 + 1.0; // Average of ranks i+1..=j+1
+        let tied = (j - i + 1) as f64;
+        tie_correction += tied * tied * tied - tied;
+        positive_rank_sum += rank * nonzero[i..=j].iter().filter(|d| **d > 0.0).count() as f64;
+        i = j + 1;
+    }
+
+    let n = n as f64;
+    let mean = n * (n + 1.0) / 4.0;
+    let variance = n * (n + 1.0) * (2.0 * n + 1.0) / 24.0 - tie_correction / 48.0;
+    if variance <= 0.0 {
+        return 1.0;
+    }
+    let distance = ((positive_rank_sum - mean).abs() - 0.5).max(0.0);
+    (2.0 * (1.0 - normal_cdf(distance / variance.sqrt()))).min(1.0)
+}
+
+fn paired_delta(pairs: &[(f64, f64)]) -> PairedDelta {
+    let count = pairs.len().max(1) as f64;
+    let deltas: Vec<f64> = pairs.iter().map(|(a, b)| b - a).collect();
+    let p_value = wilcoxon_signed_rank(&deltas);
+    PairedDelta {
+        mean_a: pairs.iter().map(|(a, _)| a).sum::<f64>() / count,
+        mean_b: pairs.iter().map(|(_, b)| b).sum::<f64>() / count,
+        mean_delta: deltas.iter().sum::<f64>() / count,
+        a_better: deltas.iter().filter(|d| **d > 0.0).count(),
+        b_better: deltas.iter().filter(|d| **d < 0.0).count(),
+        ties: deltas.iter().filter(|d| **d == 0.0).count(),
+        p_value,
+        significant: p_value < SIGNIFICANCE_LEVEL,
+    }
+}
+
+/// Lower mean CER wins; equal CER falls back to WER
+fn winner(cer: &PairedDelta, wer: &PairedDelta) -> Winner {
+    let delta = if cer.mean_delta.abs() > f64::EPSILON {
+        cer.mean_delta
+    } else {
+        wer.mean_delta
+    };
+    if delta.abs() <= f64::EPSILON {
+        Winner::Tie
+    } else if delta < 0.0 {
+        Winner::B
+    } else {
+        Winner::A
+    }
+}
+
+fn compare_samples(samples: &[&PairedSample]) -> (PairedDelta, PairedDelta, Winner) {
+    let cer = paired_delta(
+        &samples
+            .iter()
+            .map(|s| (s.cer_a, s.cer_b))
+            .collect::<Vec<_>>(),
+    );
+    let wer = paired_delta(
+        &samples
+            .iter()
+            .map(|s| (s.wer_a, s.wer_b))
+            .collect::<Vec<_>>(),
+    );
+    let winner = winner(&cer, &wer);
+    (cer, wer, winner)
+}
+
+/// Overall and per-class results from scored samples
+fn report(
+    a: OcrConfig,
+    b: OcrConfig,
+    samples: Vec<PairedSample>,
+    skipped: Vec<SkippedSample>,
+    elapsed_ms: u64,
+) -> OcrComparison {
+    let all: Vec<&PairedSample> = samples.iter().collect();
+    let (cer, wer, overall) = compare_samples(&all);
+
+    let mut class_names: Vec<&str> = samples.iter().map(|s| s.class.as_str()).collect();
+    class_names.sort();
+    class_names.dedup();
+    let classes = class_names
+        .into_iter()
+        .map(|class| {
+            let members: Vec<&PairedSample> = samples.iter().filter(|s| s.class == class).collect();
+            let (cer, wer, winner) = compare_samples(&members);
+            ClassComparison {
+                class: class.to_string(),
+                samples: members.len(),
+                cer,
+                wer,
+                winner,
+            }
+        })
+        .collect();
+
+    OcrComparison {
+        a,
+        b,
+        samples,
+        cer,
+        wer,
+        winner: overall,
+        classes,
+        skipped,
+        elapsed_ms,
+    }
+}
+
+async fn recognize(
+    pipeline: &dyn OcrPipeline,
+    config: &OcrConfig,
+    image: &DynamicImage,
+) -> anyhow::Result<String> {
+    let image = config.preprocess.apply(image.clone());
+    let regions = pipeline.detect_text_regions(&image).await?;
+    let texts = pipeline.recognize_text(&image, &regions).await?;
+    Ok(config.decode.apply(&texts))
+}
+
+fn config_name(config: &OcrConfig, fallback: &str) -> String {
+    config
+        .label
+        .clone()
+        .or_else(|| config.engine.clone())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+/// Run every labelled image in `dir` through both configurations and compare them
+///
+/// `a` and `b` come with the pipelines for their engines; a sample failing under either
+/// configuration is skipped so every reported delta stays paired.
+pub async fn compare(
+    dir: &Path,
+    (a, pipeline_a): (OcrConfig, Arc<dyn OcrPipeline + Send + Sync>),
+    (b, pipeline_b): (OcrConfig, Arc<dyn OcrPipeline + Send + Sync>),
+) -> anyhow::Result<OcrComparison> {
+    let start = Instant::now();
+    let (pairs, mut skipped) = classified_pairs(dir)?;
+    if pairs.is_empty() {
+        return Err(anyhow!("No labelled images in {:?}", dir));
+    }
+    tracing::info!(
+        "[ocr-compare] '{}' vs '{}' on {} sample(s) from {:?}",
+        config_name(&a, "A"),
+        config_name(&b, "B"),
+        pairs.len(),
+        dir
+    );
+
+    let mut samples = Vec::with_capacity(pairs.len());
+    for (class, path, truth) in pairs {
+        let file = match class.as_str() {
+            DEFAULT_CLASS => path.file_name(),
+            _ => path.strip_prefix(dir).ok().map(|p| p.as_os_str()),
+        }
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+        let result = async {
+            let image = image_io::open(&path)?;
+            let predicted_a = recognize(pipeline_a.as_ref(), &a, &image).await?;
+            let predicted_b = recognize(pipeline_b.as_ref(), &b, &image).await?;
+            anyhow::Ok((predicted_a, predicted_b))
+        }
+        .await;
+        match result {
+            Ok((predicted_a, predicted_b)) => {
+                let metrics_a = AccuracyMetrics::calculate(&truth, &predicted_a);
+                let metrics_b = AccuracyMetrics::calculate(&truth, &predicted_b);
+                samples.push(PairedSample {
+                    file,
+                    class,
+                    ground_truth: truth,
+                    predicted_a,
+                    predicted_b,
+                    cer_a: metrics_a.cer,
+                    cer_b: metrics_b.cer,
+                    wer_a: metrics_a.wer,
+                    wer_b: metrics_b.wer,
+                });
+            }
+            Err(err) => skipped.push(SkippedSample {
+                file,
+                reason: format!("{:#}", err),
+            }),
+        }
+    }
+
+    let comparison = report(a, b, samples, skipped, start.elapsed().as_millis() as u64);
+    tracing::info!(
+        "[ocr-compare] CER {:.3} -> {:.3} (p = {:.3}), winner {:?}",
+        comparison.cer.mean_a,
+        comparison.cer.mean_b,
+        comparison.cer.p_value,
+        comparison.winner
+    );
+    Ok(comparison)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(class: &str, cer_a: f64, cer_b: f64) -> PairedSample {
+        PairedSample {
+            file: String::new(),
+            class: class.to_string(),
+            ground_truth: String::new(),
+            predicted_a: String::new(),
+            predicted_b: String::new(),
+            cer_a,
+            cer_b,
+            wer_a: cer_a,
+            wer_b: cer_b,
+        }
+    }
+
+    #[test]
+    fn test_wilcoxon_separates_consistent_and_noisy_deltas() {
+        // B better on every one of 12 pairs
+        let consistent: Vec<f64> = (1..=12).map(|i| -0.01 * i as f64).collect();
+        assert!(wilcoxon_signed_rank(&consistent) < 0.01);
+
+        let noisy = [0.1, -0.1, 0.2, -0.2, 0.05, -0.05, 0.3, -0.3];
+        assert!(wilcoxon_signed_rank(&noisy) > 0.5);
+        assert_eq!(wilcoxon_signed_rank(&[0.0, 0.0]), 1.0);
+        assert!((normal_cdf(1.96) - 0.975).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_report_picks_winners_per_class() {
+        let mut samples: Vec<PairedSample> = (0..10)
+            .map(|i| sample("vertical", 0.3 + i as f64 * 0.01, 0.1))
+            .collect();
+        samples.push(sample("sfx", 0.2, 0.25));
+        samples.push(sample("sfx", 0.2, 0.22));
+        samples.push(sample("default", 0.1, 0.1));
+
+        let comparison = report(
+            OcrConfig::default(),
+            OcrConfig::default(),
+            samples,
+            Vec::new(),
+            0,
+        );
+        assert_eq!(comparison.winner, Winner::B);
+        assert!(comparison.cer.significant);
+        assert_eq!(comparison.cer.b_better, 10);
+
+        let classes: Vec<(&str, Winner)> = comparison
+            .classes
+            .iter()
+            .map(|c| (c.class.as_str(), c.winner))
+            .collect();
+        assert_eq!(
+            classes,
+            vec![
+                ("default", Winner::Tie),
+                ("sfx", Winner::A),
+                ("vertical", Winner::B)
+            ]
+        );
+        assert!(!comparison.classes[1].cer.significant); // Two samples are not enough
+    }
+
+    #[test]
+    fn test_preprocess_and_decode() {
+        let image = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            10,
+            4,
+            image::Rgba([200, 200, 200, 255]),
+        ));
+        let processed = Preprocess {
+            grayscale: false,
+            scale: 2.0,
+            threshold: Some(128),
+        }
+        .apply(image);
+        assert_eq!((processed.width(), processed.height()), (20, 8));
+        assert_eq!(processed.to_luma8().get_pixel(0, 0)[0], 255);
+
+        let texts = vec!["こんにちは ".to_string(), "世界".to_string()];
+        assert_eq!(DecodeOptions::default().apply(&texts), "こんにちは  世界");
+        let compact = DecodeOptions {
+            separator: String::new(),
+            remove_whitespace: true,
+        };
+        assert_eq!(compact.apply(&texts), "こんにちは世界");
+    }
+
+    #[test]
+    fn test_classified_pairs_use_subfolders() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.png"), b"").unwrap();
+        fs::write(dir.path().join("a.txt"), "top").unwrap();
+        fs::create_dir(dir.path().join("sfx")).unwrap();
+        fs::write(dir.path().join("sfx").join("b.png"), b"").unwrap();
+        fs::write(dir.path().join("sfx").join("b.txt"), "ドン").unwrap();
+        fs::write(dir.path().join("sfx").join("c.png"), b"").unwrap();
+
+        let (pairs, skipped) = classified_pairs(dir.path()).unwrap();
+        let classes: Vec<&str> = pairs.iter().map(|(class, _, _)| class.as_str()).collect();
+        assert_eq!(classes, vec![DEFAULT_CLASS, "sfx"]);
+        assert_eq!(pairs[1].2, "ドン");
+        assert_eq!(skipped[0].file, "sfx/c.png");
+    }
+}
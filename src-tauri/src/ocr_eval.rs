@@ -0,0 +1,172 @@
+// OCR accuracy on a labelled dataset: each `<name>.png` (or any supported image) sits next to
+// a `<name>.txt` with its ground truth. Runs one engine over every pair and reports CER/WER.
+
+use anyhow::{Context, anyhow};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::accuracy::BatchAccuracy;
+use crate::batch;
+use crate::image_io;
+use crate::ocr_pipeline::OcrPipeline;
+
+/// Image left out of the averages, with why
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedSample {
+    pub file: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrEvaluation {
+    pub engine: String,
+    #[serde(flatten)]
+    pub accuracy: BatchAccuracy, // Samples are labelled with their file names
+    pub skipped: Vec<SkippedSample>,
+    pub elapsed_ms: u64,
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// (image, ground truth) pairs in `dir`, plus the images that have no ground truth file
+pub fn dataset_pairs(dir: &Path) -> anyhow::Result<(Vec<(PathBuf, String)>, Vec<SkippedSample>)> {
+    if !dir.is_dir() {
+        return Err(anyhow!("Dataset directory does not exist: {:?}", dir));
+    }
+    let mut pairs = Vec::new();
+    let mut skipped = Vec::new();
+    for image in batch::list_images(dir)? {
+        let truth_path = image.with_extension("txt");
+        if !truth_path.is_file() {
+            skipped.push(SkippedSample {
+                file: file_name(&image),
+                reason: "no ground truth .txt".to_string(),
+            });
+            continue;
+        }
+        let truth = fs::read_to_string(&truth_path)
+            .with_context(|| format!("Failed to read {:?}", truth_path))?;
+        pairs.push((image, truth.trim().to_string()));
+    }
+    Ok((pairs, skipped))
+}
+
+async fn recognize(pipeline: &dyn OcrPipeline, path: &Path) -> anyhow::Result<String> {
+    let image = image_io::open(path)?;
+    let regions = pipeline.detect_text_regions(&image).await?;
+    let texts = pipeline.recognize_text(&image, &regions).await?;
+    Ok(texts.join(" ").trim().to_string())
+}
+
+/// Aggregate scored samples; `samples` is (file, ground truth, predicted)
+fn report(
+    engine: &str,
+    samples: Vec<(String, String, String)>,
+    skipped: Vec<SkippedSample>,
+    elapsed_ms: u64,
+) -> OcrEvaluation {
+    let accuracy = BatchAccuracy::calculate_labeled(
+        samples
+            .into_iter()
+            .map(|(file, truth, predicted)| (Some(file), truth, predicted))
+            .collect(),
+    );
+    OcrEvaluation {
+        engine: engine.to_string(),
+        accuracy,
+        skipped,
+        elapsed_ms,
+    }
+}
+
+/// Run `pipeline` over every labelled image in `dir`; unreadable or failing images are skipped
+pub async fn evaluate(
+    pipeline: Arc<dyn OcrPipeline + Send + Sync>,
+    engine: &str,
+    dir: &Path,
+) -> anyhow::Result<OcrEvaluation> {
+    let start = Instant::now();
+    let (pairs, mut skipped) = dataset_pairs(dir)?;
+    tracing::info!(
+        "[ocr-eval] evaluating '{}' on {} sample(s) from {:?}",
+        engine,
+        pairs.len(),
+        dir
+    );
+
+    let mut samples = Vec::with_capacity(pairs.len());
+    for (path, truth) in pairs {
+        match recognize(pipeline.as_ref(), &path).await {
+            Ok(predicted) => samples.push((file_name(&path), truth, predicted)),
+            Err(err) => skipped.push(SkippedSample {
+                file: file_name(&path),
+                reason: format!("{:#}", err),
+            }),
+        }
+    }
+
+    let evaluation = report(engine, samples, skipped, start.elapsed().as_millis() as u64);
+    tracing::info!(
+        "[ocr-eval] '{}': CER {:.3}, WER {:.3} over {} sample(s), {} skipped",
+        engine,
+        evaluation.accuracy.average_cer,
+        evaluation.accuracy.average_wer,
+        evaluation.accuracy.samples.len(),
+        evaluation.skipped.len()
+    );
+    Ok(evaluation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dataset_pairs_need_ground_truth() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("001.png"), b"").unwrap();
+        fs::write(dir.path().join("001.txt"), "こんにちは\n").unwrap();
+        fs::write(dir.path().join("002.png"), b"").unwrap();
+        fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let (pairs, skipped) = dataset_pairs(dir.path()).unwrap();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].1, "こんにちは");
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].file, "002.png");
+    }
+
+    #[test]
+    fn test_report_keeps_file_names_with_scores() {
+        let evaluation = report(
+            "manga-ocr",
+            vec![
+                ("a.png".into(), "hello".into(), "hello".into()),
+                ("b.png".into(), "hello".into(), "hxllo".into()),
+            ],
+            Vec::new(),
+            0,
+        );
+        let accuracy = &evaluation.accuracy;
+        assert_eq!(accuracy.samples[1].label.as_deref(), Some("b.png"));
+        assert_eq!(accuracy.samples[1].cer, 0.2);
+        assert_eq!(accuracy.average_cer, 0.1);
+        assert_eq!(accuracy.max_wer, 1.0);
+        assert!(accuracy.meets_thresholds(0.1, 0.5));
+        assert!(!accuracy.meets_thresholds(0.05, 0.5));
+
+        // Flattened, so reports keep the same top-level shape as BatchAccuracy
+        let json = serde_json::to_value(&evaluation).unwrap();
+        assert_eq!(json["engine"], "manga-ocr");
+        assert_eq!(json["samples"][1]["label"], "b.png");
+    }
+}
@@ -0,0 +1,181 @@
+// Per-page backend cache keyed by a frontend-assigned page id
+// Holds decoded images, masks, detections, and inpaint composites with an LRU memory budget,
+// so switching between pages doesn't force re-uploading and re-decoding everything.
+
+use image::{DynamicImage, GrayImage};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use crate::image_io::SourceMetadata;
+use crate::pipeline::PageBlock;
+
+pub const DEFAULT_PAGE_BUDGET_BYTES: usize = 1024 * 1024 * 1024;
+
+#[derive(Debug, Clone, Default)]
+pub struct PageEntry {
+    pub image: Option<Arc<DynamicImage>>,
+    pub mask: Option<Arc<GrayImage>>,
+    pub detections: Option<Arc<Vec<PageBlock>>>,
+    pub composite: Option<Arc<DynamicImage>>, // Image with inpainted regions pasted back
+    pub source: Arc<SourceMetadata>,          // From the original upload; re-embedded on export
+}
+
+impl PageEntry {
+    fn bytes(&self) -> usize {
+        let image = self.image.as_ref().map(|i| i.as_bytes().len()).unwrap_or(0);
+        let mask = self.mask.as_ref().map(|m| m.as_raw().len()).unwrap_or(0);
+        let composite = self
+            .composite
+            .as_ref()
+            .map(|i| i.as_bytes().len())
+            .unwrap_or(0);
+        let detections = self.detections.as_ref().map(|d| d.len() * 256).unwrap_or(0);
+        let source = self.source.icc_profile.as_ref().map(Vec::len).unwrap_or(0)
+            + self.source.exif.as_ref().map(Vec::len).unwrap_or(0);
+        image + mask + composite + detections + source
+    }
+}
+
+#[derive(Debug)]
+pub struct PageStore {
+    pages: HashMap<String, PageEntry>,
+    recency: VecDeque<String>, // Least recently used first
+    budget_bytes: usize,
+}
+
+impl Default for PageStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_PAGE_BUDGET_BYTES)
+    }
+}
+
+impl PageStore {
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            pages: HashMap::new(),
+            recency: VecDeque::new(),
+            budget_bytes,
+        }
+    }
+
+    fn touch(&mut self, page_id: &str) {
+        if let Some(pos) = self.recency.iter().position(|id| id == page_id) {
+            let id = self.recency.remove(pos).unwrap();
+            self.recency.push_back(id);
+        } else {
+            self.recency.push_back(page_id.to_string());
+        }
+    }
+
+    /// Look up a page, marking it as most recently used
+    pub fn get(&mut self, page_id: &str) -> Option<&PageEntry> {
+        if !self.pages.contains_key(page_id) {
+            return None;
+        }
+        self.touch(page_id);
+        self.pages.get(page_id)
+    }
+
+    /// Modify (or create) a page, then evict other pages until the budget holds
+    pub fn update(&mut self, page_id: &str, f: impl FnOnce(&mut PageEntry)) {
+        f(self.pages.entry(page_id.to_string()).or_default());
+        self.touch(page_id);
+        self.evict(page_id);
+    }
+
+    /// Replace the page image; derived data from a previous image is dropped
+    ///
+    /// Re-uploads produced by the webview's canvas carry no ICC profile or EXIF, so
+    /// existing source metadata is kept unless the new upload brings its own.
+    pub fn set_image(&mut self, page_id: &str, image: DynamicImage, source: SourceMetadata) {
+        self.update(page_id, |page| {
+            let unchanged = page.image.as_ref().is_some_and(|old| {
+                old.width() == image.width()
+                    && old.height() == image.height()
+                    && old.as_bytes() == image.as_bytes()
+            });
+            let source = if source.is_empty() {
+                std::mem::take(&mut page.source)
+            } else {
+                Arc::new(source)
+            };
+            if !unchanged {
+                *page = PageEntry::default();
+            }
+            page.image = Some(Arc::new(image));
+            page.source = source;
+        });
+    }
+
+    pub fn remove(&mut self, page_id: &str) -> bool {
+        self.recency.retain(|id| id != page_id);
+        self.pages.remove(page_id).is_some()
+    }
+
+    pub fn used_bytes(&self) -> usize {
+        self.pages.values().map(PageEntry::bytes).sum()
+    }
+
+    /// Drop least recently used pages (never `keep`) until within budget
+    fn evict(&mut self, keep: &str) {
+        while self.used_bytes() > self.budget_bytes {
+            let Some(victim) = self.recency.iter().find(|id| id.as_str() != keep).cloned() else {
+                break;
+            };
+            tracing::debug!("[pages] evicting page '{}' to stay within budget", victim);
+            self.remove(&victim);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::new_rgba8(width, height)
+    }
+
+    #[test]
+    fn test_lru_evicts_least_recently_used_page() {
+        // Each 10x10 RGBA image is 400 bytes; room for two pages
+        let mut store = PageStore::new(800);
+        store.set_image("a", rgba(10, 10), SourceMetadata::default());
+        store.set_image("b", rgba(10, 10), SourceMetadata::default());
+        assert!(store.get("a").is_some()); // "b" is now least recent
+
+        store.set_image("c", rgba(10, 10), SourceMetadata::default());
+        assert!(store.get("b").is_none());
+        assert!(store.get("a").is_some());
+        assert!(store.get("c").is_some());
+        assert!(store.used_bytes() <= 800);
+    }
+
+    #[test]
+    fn test_new_image_resets_derived_data() {
+        let mut store = PageStore::default();
+        let source = SourceMetadata {
+            icc_profile: Some(vec![1, 2, 3]),
+            exif: None,
+        };
+        store.set_image("p", rgba(4, 4), source.clone());
+        store.update("p", |page| page.mask = Some(Arc::new(GrayImage::new(4, 4))));
+
+        // Same pixels keep the mask
+        store.set_image("p", rgba(4, 4), SourceMetadata::default());
+        assert!(store.get("p").unwrap().mask.is_some());
+
+        // New pixels drop the mask, but metadata from the original upload stays
+        store.set_image("p", rgba(8, 8), SourceMetadata::default());
+        let page = store.get("p").unwrap();
+        assert!(page.mask.is_none());
+        assert_eq!(*page.source, source);
+    }
+
+    #[test]
+    fn test_oversized_page_is_kept() {
+        let mut store = PageStore::new(100);
+        store.set_image("big", rgba(10, 10), SourceMetadata::default());
+        assert!(store.get("big").is_some());
+    }
+}
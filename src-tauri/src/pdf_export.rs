@@ -0,0 +1,282 @@
+// PDF export: one page per image, sized from the image resolution and a target DPI
+// Writes the PDF structure directly so pages can be streamed without holding a whole chapter
+
+use anyhow::{Context, anyhow};
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use image::DynamicImage;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::image_io;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PdfImageEncoding {
+    Jpeg, // DCTDecode, lossy but small
+    Png,  // FlateDecode, lossless
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PdfOptions {
+    pub dpi: f32,
+    pub encoding: PdfImageEncoding,
+    pub jpeg_quality: u8,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        PdfOptions {
+            dpi: 300.0,
+            encoding: PdfImageEncoding::Jpeg,
+            jpeg_quality: 90,
+        }
+    }
+}
+
+/// Writer that tracks the byte offset of every object for the xref table
+struct PdfWriter<W: Write> {
+    inner: W,
+    position: u64,
+    offsets: Vec<u64>,
+}
+
+impl<W: Write> PdfWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            position: 0,
+            offsets: Vec::new(),
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.inner.write_all(bytes)?;
+        self.position += bytes.len() as u64;
+        Ok(())
+    }
+
+    /// Record the offset of object `id` (1-based, must be written in order)
+    fn begin_object(&mut self, id: usize) -> std::io::Result<()> {
+        debug_assert_eq!(self.offsets.len() + 1, id);
+        self.offsets.push(self.position);
+        self.write(format!("{} 0 obj\n", id).as_bytes())
+    }
+
+    fn end_object(&mut self) -> std::io::Result<()> {
+        self.write(b"\nendobj\n")
+    }
+
+    fn write_stream(&mut self, id: usize, dictionary: &str, data: &[u8]) -> std::io::Result<()> {
+        self.begin_object(id)?;
+        self.write(format!("<< {} /Length {} >>\nstream\n", dictionary, data.len()).as_bytes())?;
+        self.write(data)?;
+        self.write(b"\nendstream")?;
+        self.end_object()
+    }
+
+    fn finish(mut self, root_id: usize) -> std::io::Result<W> {
+        let xref_offset = self.position;
+        let size = self.offsets.len() + 1;
+
+        let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", size);
+        for offset in &self.offsets {
+            xref.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+        xref.push_str(&format!(
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            size, root_id, xref_offset
+        ));
+        self.write(xref.as_bytes())?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Encode page pixels for embedding, returning (filter, data)
+fn encode_page_image(
+    image: &DynamicImage,
+    options: &PdfOptions,
+) -> anyhow::Result<(&'static str, Vec<u8>)> {
+    let rgb = image.to_rgb8();
+
+    match options.encoding {
+        PdfImageEncoding::Jpeg => {
+            let mut data = Vec::new();
+            image::codecs::jpeg::JpegEncoder::new_with_quality(
+                &mut data,
+                options.jpeg_quality.clamp(1, 100),
+            )
+            .encode_image(&rgb)
+            .context("Failed to encode page as JPEG")?;
+            Ok(("/DCTDecode", data))
+        }
+        PdfImageEncoding::Png => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(rgb.as_raw())?;
+            let data = encoder.finish().context("Failed to compress page data")?;
+            Ok(("/FlateDecode", data))
+        }
+    }
+}
+
+/// Write a PDF with one page per image to `out`, calling `on_page(done, total)` after each page
+pub fn write_pdf<W: Write>(
+    out: W,
+    pages: impl ExactSizeIterator<Item = anyhow::Result<DynamicImage>>,
+    options: &PdfOptions,
+    on_page: &dyn Fn(usize, usize),
+) -> anyhow::Result<W> {
+    if options.dpi <= 0.0 {
+        return Err(anyhow!("DPI must be positive, got {}", options.dpi));
+    }
+
+    let page_count = pages.len();
+    if page_count == 0 {
+        return Err(anyhow!("Cannot export a PDF without pages"));
+    }
+
+    let mut writer = PdfWriter::new(out);
+    writer.write(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n")?;
+
+    // Objects 1 and 2 are the catalog and page tree; each page then uses three objects
+    let page_ids: Vec<usize> = (0..page_count).map(|i| 3 + i * 3).collect();
+    let kids = page_ids
+        .iter()
+        .map(|id| format!("{} 0 R", id))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    writer.begin_object(1)?;
+    writer.write(b"<< /Type /Catalog /Pages 2 0 R >>")?;
+    writer.end_object()?;
+
+    writer.begin_object(2)?;
+    writer
+        .write(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids, page_count).as_bytes())?;
+    writer.end_object()?;
+
+    let scale = 72.0 / options.dpi;
+    for (i, page) in pages.enumerate() {
+        let page = page.with_context(|| format!("Failed to load page {}", i + 1))?;
+        let (width, height) = (page.width(), page.height());
+        let page_width = width as f32 * scale;
+        let page_height = height as f32 * scale;
+
+        let page_id = page_ids[i];
+        let image_id = page_id + 1;
+        let content_id = page_id + 2;
+
+        writer.begin_object(page_id)?;
+        writer.write(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+                 /Resources << /XObject << /Im0 {} 0 R >> >> /Contents {} 0 R >>",
+                page_width, page_height, image_id, content_id
+            )
+            .as_bytes(),
+        )?;
+        writer.end_object()?;
+
+        let (filter, data) = encode_page_image(&page, options)?;
+        writer.write_stream(
+            image_id,
+            &format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} \
+                 /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter {}",
+                width, height, filter
+            ),
+            &data,
+        )?;
+
+        let content = format!(
+            "q {:.2} 0 0 {:.2} 0 0 cm /Im0 Do Q",
+            page_width, page_height
+        );
+        writer.write_stream(content_id, "", content.as_bytes())?;
+
+        tracing::debug!(
+            "[pdf] wrote page {}/{} ({}x{}px, {} bytes)",
+            i + 1,
+            page_count,
+            width,
+            height,
+            data.len()
+        );
+        on_page(i + 1, page_count);
+    }
+
+    Ok(writer.finish(1)?)
+}
+
+/// Export the given image files as a PDF at `output_path`
+pub fn export_pdf(
+    image_paths: &[String],
+    output_path: &Path,
+    options: &PdfOptions,
+    on_page: &dyn Fn(usize, usize),
+) -> anyhow::Result<()> {
+    let file = File::create(output_path)
+        .with_context(|| format!("Failed to create PDF at {:?}", output_path))?;
+
+    let pages = image_paths
+        .iter()
+        .map(|path| image_io::open(Path::new(path)));
+
+    write_pdf(BufWriter::new(file), pages, options, on_page)?;
+
+    tracing::info!(
+        "[pdf] exported {} page(s) to {:?} (dpi={}, encoding={:?})",
+        image_paths.len(),
+        output_path,
+        options.dpi,
+        options.encoding
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blank_page(width: u32, height: u32) -> anyhow::Result<DynamicImage> {
+        Ok(DynamicImage::new_rgb8(width, height))
+    }
+
+    #[test]
+    fn test_write_pdf_structure() {
+        let pages = vec![blank_page(300, 600), blank_page(150, 150)];
+        let options = PdfOptions {
+            dpi: 150.0,
+            encoding: PdfImageEncoding::Png,
+            jpeg_quality: 90,
+        };
+
+        let bytes = write_pdf(Vec::new(), pages.into_iter(), &options, &|_, _| {}).unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.contains("/Count 2"));
+        assert!(text.contains("/MediaBox [0 0 144.00 288.00]"));
+        assert!(text.contains("/Filter /FlateDecode"));
+        assert!(text.trim_end().ends_with("%%EOF"));
+    }
+
+    #[test]
+    fn test_write_pdf_rejects_empty() {
+        let pages: Vec<anyhow::Result<DynamicImage>> = Vec::new();
+        assert!(
+            write_pdf(
+                Vec::new(),
+                pages.into_iter(),
+                &PdfOptions::default(),
+                &|_, _| {}
+            )
+            .is_err()
+        );
+    }
+}
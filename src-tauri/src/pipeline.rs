@@ -0,0 +1,719 @@
+// Backend page pipeline: detect → OCR → translate → inpaint → render
+// Runs every stage against AppState so a single call processes a whole page
+
+use anyhow::{Context, anyhow};
+use image::{DynamicImage, GenericImageView, GrayImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::bubble::{self, Background};
+use crate::image_io::ExportOptions;
+use crate::lettering;
+use crate::scripting::Hook;
+use crate::stages::{
+    BBox, InpaintConfig, InpaintedRegion, crop_to_bbox, run_inpainting_pipeline,
+    run_ocr_with_pipelines,
+};
+use crate::state::AppState;
+use crate::text_renderer::{FontStack, RgbColor, TextBlock, fit_font_size, render_text_on_image};
+use crate::translation::TranslatorConfig;
+
+const RENDER_METHODS: [&str; 4] = ["rectangle", "lama", "newlama", "auto"];
+
+pub fn validate_render_method(method: &str) -> anyhow::Result<()> {
+    if RENDER_METHODS.contains(&method) {
+        Ok(())
+    } else {
+        Err(anyhow!("Invalid render method: {}", method))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PipelineStage {
+    Detection,
+    Ocr,
+    Translation,
+    Inpainting,
+    Rendering,
+    Complete,
+}
+
+impl PipelineStage {
+    /// Stage name used in task progress events
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PipelineStage::Detection => "detection",
+            PipelineStage::Ocr => "ocr",
+            PipelineStage::Translation => "translation",
+            PipelineStage::Inpainting => "inpainting",
+            PipelineStage::Rendering => "rendering",
+            PipelineStage::Complete => "complete",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageProgress {
+    pub stage: PipelineStage,
+    pub current: usize,
+    pub total: usize,
+}
+
+impl StageProgress {
+    pub fn new(stage: PipelineStage, current: usize, total: usize) -> Self {
+        Self {
+            stage,
+            current,
+            total,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProcessOptions {
+    pub confidence_threshold: f32,
+    pub nms_threshold: f32,
+    pub translator: Option<TranslatorConfig>, // None = skip translation
+    pub render_method: String,                // "rectangle", "lama", "newlama", or "auto"
+    pub default_font: String,
+    pub inpaint: InpaintConfig,
+    pub export: ExportOptions, // Encoding for pages written to disk
+}
+
+impl Default for ProcessOptions {
+    fn default() -> Self {
+        ProcessOptions {
+            confidence_threshold: 0.5,
+            nms_threshold: 0.4,
+            translator: None,
+            render_method: "lama".to_string(),
+            default_font: "Arial".to_string(),
+            inpaint: InpaintConfig::default(),
+            export: ExportOptions::default(),
+        }
+    }
+}
+
+/// Text block produced by the backend pipeline (mirrors the frontend TextBlock fields)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageBlock {
+    pub xmin: f32,
+    pub ymin: f32,
+    pub xmax: f32,
+    pub ymax: f32,
+    pub confidence: f32,
+    pub class: usize,
+    pub text: String,
+    pub translated_text: Option<String>,
+    pub font_size: Option<f32>,
+    #[serde(default)]
+    pub font_weight: Option<String>, // CSS weight, "100"-"900"
+    pub text_color: Option<RgbColor>,
+    pub background_color: Option<RgbColor>,
+}
+
+impl PageBlock {
+    pub fn from_detection(bbox: &comic_text_detector::ClassifiedBbox) -> Self {
+        Self {
+            xmin: bbox.xmin,
+            ymin: bbox.ymin,
+            xmax: bbox.xmax,
+            ymax: bbox.ymax,
+            confidence: bbox.confidence,
+            class: bbox.class,
+            text: String::new(),
+            translated_text: None,
+            font_size: None,
+            font_weight: None,
+            text_color: None,
+            background_color: None,
+        }
+    }
+
+    pub fn bbox(&self) -> BBox {
+        BBox {
+            xmin: self.xmin,
+            ymin: self.ymin,
+            xmax: self.xmax,
+            ymax: self.ymax,
+        }
+    }
+
+    pub fn to_text_block(&self) -> TextBlock {
+        TextBlock {
+            xmin: self.xmin,
+            ymin: self.ymin,
+            xmax: self.xmax,
+            ymax: self.ymax,
+            translated_text: self.translated_text.clone(),
+            font_size: self.font_size,
+            text_color: self.text_color.clone(),
+            background_color: self.background_color.clone(),
+            manual_bg_color: None,
+            manual_text_color: None,
+            font_family: None,
+            font_weight: self.font_weight.clone(),
+            font_stretch: None,
+            letter_spacing: None,
+            line_height: None,
+            appearance: None,
+        }
+    }
+}
+
+/// Decoded results of a full pipeline run
+pub struct PageResult {
+    pub rendered: DynamicImage,
+    pub textless: Option<DynamicImage>,
+    pub mask: GrayImage,
+    pub blocks: Vec<PageBlock>,
+}
+
+/// process_page response: final PNG plus every intermediate artifact
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageOutput {
+    pub image_png: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    pub textless_png: Option<Vec<u8>>,
+    pub mask_png: Vec<u8>,
+    pub mask_width: u32,
+    pub mask_height: u32,
+    pub blocks: Vec<PageBlock>,
+}
+
+impl PageOutput {
+    pub fn from_result(result: PageResult) -> anyhow::Result<Self> {
+        let (width, height) = result.rendered.dimensions();
+        let textless_png = match &result.textless {
+            Some(textless) => Some(encode_png(textless)?),
+            None => None,
+        };
+        let mask_width = result.mask.width();
+        let mask_height = result.mask.height();
+        let mask_png = encode_png(&DynamicImage::ImageLuma8(result.mask))?;
+
+        Ok(Self {
+            image_png: encode_png(&result.rendered)?,
+            width,
+            height,
+            textless_png,
+            mask_png,
+            mask_width,
+            mask_height,
+            blocks: result.blocks,
+        })
+    }
+}
+
+pub fn encode_png(image: &DynamicImage) -> anyhow::Result<Vec<u8>> {
+    let mut png_bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png_bytes), image::ImageFormat::Png)
+        .context("Failed to encode PNG")?;
+    Ok(png_bytes)
+}
+
+/// Stage 1: detect text blocks and the segmentation mask
+pub async fn detect_blocks(
+    state: &AppState,
+    image: &DynamicImage,
+    confidence_threshold: f32,
+    nms_threshold: f32,
+) -> anyhow::Result<(Vec<PageBlock>, GrayImage)> {
+    let stage_start = Instant::now();
+    let output = state
+        .comic_text_detector
+        .lock()
+        .await
+        .inference(image, confidence_threshold, nms_threshold)
+        .context("Failed to perform inference")?;
+
+    let comic_text_detector::Output {
+        bboxes,
+        segment,
+        mask_width,
+        mask_height,
+    } = output;
+
+    let mask = GrayImage::from_vec(mask_width, mask_height, segment)
+        .context("Failed to reconstruct segmentation mask")?;
+    let blocks: Vec<PageBlock> = bboxes.iter().map(PageBlock::from_detection).collect();
+
+    tracing::info!(
+        "[pipeline] detection found {} block(s) in {}ms",
+        blocks.len(),
+        stage_start.elapsed().as_millis()
+    );
+
+    Ok((blocks, mask))
+}
+
+/// Stage 2: OCR on each detected block with the active engine
+pub async fn recognize_blocks(
+    state: &AppState,
+    image: &DynamicImage,
+    blocks: &mut [PageBlock],
+    progress: &(dyn Fn(StageProgress) + Send + Sync),
+) -> anyhow::Result<()> {
+    let stage_start = Instant::now();
+    let total = blocks.len();
+    let active_key = state.active_ocr.read().await.clone();
+    for (i, block) in blocks.iter_mut().enumerate() {
+        progress(StageProgress::new(PipelineStage::Ocr, i, total));
+
+        let crop = match crop_to_bbox(image, &block.bbox()) {
+            Ok(crop) => crop,
+            Err(err) => {
+                tracing::warn!("[pipeline] skipping OCR for block {}: {}", i, err);
+                continue;
+            }
+        };
+        let payload_bytes = (crop.width() as usize) * (crop.height() as usize) * 4;
+        let run_result = run_ocr_with_pipelines(state, &active_key, &crop, payload_bytes)
+            .await
+            .with_context(|| format!("OCR failed for block {}", i))?;
+
+        block.text = run_result.texts.into_iter().next().unwrap_or_default();
+    }
+    tracing::info!(
+        "[pipeline] OCR finished for {} block(s) in {}ms",
+        total,
+        stage_start.elapsed().as_millis()
+    );
+    progress(StageProgress::new(PipelineStage::Ocr, total, total));
+
+    Ok(())
+}
+
+/// OCR on an image that is already a single text region (e.g. a crop from `detect --export-crops`)
+pub async fn recognize_crop(state: &AppState, crop: &DynamicImage) -> anyhow::Result<String> {
+    let active_key = state.active_ocr.read().await.clone();
+    let payload_bytes = (crop.width() as usize) * (crop.height() as usize) * 4;
+    let run_result = run_ocr_with_pipelines(state, &active_key, crop, payload_bytes).await?;
+    Ok(run_result.texts.into_iter().next().unwrap_or_default())
+}
+
+/// Stage 3: translate every block that has source text
+pub async fn translate_blocks(
+    translator: &TranslatorConfig,
+    blocks: &mut [PageBlock],
+    progress: &(dyn Fn(StageProgress) + Send + Sync),
+) -> anyhow::Result<()> {
+    let stage_start = Instant::now();
+    let total = blocks.len();
+    for (i, block) in blocks.iter_mut().enumerate() {
+        progress(StageProgress::new(PipelineStage::Translation, i, total));
+
+        if block.text.trim().is_empty() {
+            continue;
+        }
+
+        let translated = translator
+            .translate(&block.text)
+            .await
+            .with_context(|| format!("Translation failed for block {}", i))?;
+        block.translated_text = Some(translated);
+    }
+    tracing::info!(
+        "[pipeline] translation finished in {}ms",
+        stage_start.elapsed().as_millis()
+    );
+    progress(StageProgress::new(PipelineStage::Translation, total, total));
+
+    Ok(())
+}
+
+/// Stage 4: inpaint every block and composite the results into a textless page
+pub async fn inpaint_blocks(
+    state: &AppState,
+    image: &DynamicImage,
+    mask: &GrayImage,
+    blocks: &[PageBlock],
+    config: &InpaintConfig,
+    debug_dir: Option<&Path>,
+    progress: &(dyn Fn(StageProgress) + Send + Sync),
+) -> anyhow::Result<DynamicImage> {
+    let stage_start = Instant::now();
+    let total = blocks.len();
+    let mut canvas = image.to_rgba8();
+    for (i, block) in blocks.iter().enumerate() {
+        progress(StageProgress::new(PipelineStage::Inpainting, i, total));
+
+        let region = run_inpainting_pipeline(debug_dir, state, image, mask, &block.bbox(), config)
+            .await
+            .with_context(|| format!("Inpainting failed for block {}", i))?;
+        composite_region(&mut canvas, &region);
+    }
+    tracing::info!(
+        "[pipeline] inpainting finished in {}ms",
+        stage_start.elapsed().as_millis()
+    );
+    progress(StageProgress::new(PipelineStage::Inpainting, total, total));
+
+    Ok(DynamicImage::ImageRgba8(canvas))
+}
+
+/// Stage 4 for "auto": blocks on a plain bubble have their text painted over in the bubble's
+/// color, and only blocks on screentone or art are inpainted
+async fn fill_or_inpaint_blocks(
+    state: &AppState,
+    image: &DynamicImage,
+    mask: &GrayImage,
+    blocks: &[PageBlock],
+    config: &InpaintConfig,
+    debug_dir: Option<&Path>,
+    progress: &(dyn Fn(StageProgress) + Send + Sync),
+) -> anyhow::Result<DynamicImage> {
+    let mut fills = Vec::new();
+    let mut textured = Vec::new();
+    for block in blocks {
+        match bubble::analyze(image, mask, &block.bbox()) {
+            Some(analysis) if analysis.background == Background::Plain => {
+                fills.push((block.bbox(), analysis.fill_color))
+            }
+            _ => textured.push(block.clone()),
+        }
+    }
+    tracing::info!(
+        "[pipeline] auto render: {} block(s) filled, {} inpainted",
+        fills.len(),
+        textured.len()
+    );
+
+    let inpainted = inpaint_blocks(state, image, mask, &textured, config, debug_dir, progress);
+    let mut canvas = inpainted.await?.to_rgba8();
+    for (bbox, color) in &fills {
+        bubble::fill_text(&mut canvas, mask, bbox, color);
+    }
+    Ok(DynamicImage::ImageRgba8(canvas))
+}
+
+/// "auto" onto a base that is already cleaned, as when exporting: blocks on a plain bubble have
+/// the text pixels in `mask` painted in the bubble's color, and the rest are drawn over the
+/// base as with "lama"
+pub fn fill_plain_blocks(base: DynamicImage, mask: &GrayImage, blocks: &[BBox]) -> DynamicImage {
+    let fills: Vec<_> = blocks
+        .iter()
+        .filter_map(|bbox| match bubble::analyze(&base, mask, bbox) {
+            Some(analysis) if analysis.background == Background::Plain => {
+                Some((bbox, analysis.fill_color))
+            }
+            _ => None,
+        })
+        .collect();
+    tracing::info!(
+        "[pipeline] auto render: {} of {} block(s) filled on the base",
+        fills.len(),
+        blocks.len()
+    );
+
+    let mut canvas = base.to_rgba8();
+    for (bbox, color) in &fills {
+        bubble::fill_text(&mut canvas, mask, bbox, color);
+    }
+    DynamicImage::ImageRgba8(canvas)
+}
+
+/// Size translations after the source lettering, shrunk to fit when the translation runs
+/// longer; blocks that already have a font size, or no text in the mask, are left alone
+fn match_source_lettering(
+    blocks: &mut [PageBlock],
+    mask: &GrayImage,
+    image: &DynamicImage,
+    default_font: &str,
+) -> anyhow::Result<()> {
+    let font_stack = FontStack::from_font_family(default_font)?;
+    for block in blocks.iter_mut() {
+        if block.font_size.is_some() && block.font_weight.is_some() {
+            continue;
+        }
+        let Some(translated) = &block.translated_text else {
+            continue;
+        };
+        let Some(source) = lettering::estimate(mask, &block.bbox(), image.width(), image.height())
+        else {
+            continue;
+        };
+        let fitted = match block.font_size {
+            Some(_) => None,
+            None => Some(fit_font_size(
+                translated,
+                &font_stack,
+                block.xmax - block.xmin,
+                block.ymax - block.ymin,
+                1.2,
+                0.0,
+            )),
+        };
+        apply_source_lettering(block, &source, fitted);
+    }
+    Ok(())
+}
+
+/// Fill the size and weight a block doesn't set yet from the source lettering; the size never
+/// exceeds `fitted`, the largest that fits the translation in the box
+fn apply_source_lettering(
+    block: &mut PageBlock,
+    source: &lettering::LetteringEstimate,
+    fitted: Option<f32>,
+) {
+    if let (None, Some(fitted)) = (block.font_size, fitted) {
+        block.font_size = Some(source.font_size.min(fitted));
+    }
+    if block.font_weight.is_none() {
+        block.font_weight = Some(source.font_weight.to_string());
+    }
+}
+
+/// Stage 5: fill in typesetting defaults and render translated text onto the base image
+pub fn typeset_and_render(
+    base_image: DynamicImage,
+    blocks: &mut [PageBlock],
+    render_method: &str,
+    default_font: &str,
+) -> anyhow::Result<DynamicImage> {
+    let stage_start = Instant::now();
+    let font_stack = FontStack::from_font_family(default_font)?;
+    for block in blocks.iter_mut() {
+        if let Some(translated) = &block.translated_text {
+            if block.font_size.is_none() {
+                block.font_size = Some(fit_font_size(
+                    translated,
+                    &font_stack,
+                    block.xmax - block.xmin,
+                    block.ymax - block.ymin,
+                    1.2,
+                    0.0,
+                ));
+            }
+        }
+        if block.text_color.is_none() {
+            block.text_color = Some(RgbColor { r: 0, g: 0, b: 0 });
+        }
+        if block.background_color.is_none() && render_method == "rectangle" {
+            block.background_color = Some(RgbColor {
+                r: 255,
+                g: 255,
+                b: 255,
+            });
+        }
+    }
+
+    let text_blocks = blocks.iter().map(PageBlock::to_text_block).collect();
+    let rendered = render_text_on_image(base_image, text_blocks, render_method, default_font)
+        .context("Rendering failed")?;
+    tracing::info!(
+        "[pipeline] rendering finished in {}ms",
+        stage_start.elapsed().as_millis()
+    );
+
+    Ok(rendered)
+}
+
+/// Run detection, OCR, translation, inpainting and rendering for one page
+pub async fn run_page(
+    state: &AppState,
+    image: &DynamicImage,
+    options: &ProcessOptions,
+    debug_dir: Option<&Path>,
+    progress: &(dyn Fn(StageProgress) + Send + Sync),
+) -> anyhow::Result<PageResult> {
+    validate_render_method(&options.render_method)?;
+
+    let total_start = Instant::now();
+
+    progress(StageProgress::new(PipelineStage::Detection, 0, 1));
+    let (mut blocks, mask) = detect_blocks(
+        state,
+        image,
+        options.confidence_threshold,
+        options.nms_threshold,
+    )
+    .await?;
+    progress(StageProgress::new(PipelineStage::Detection, 1, 1));
+
+    recognize_blocks(state, image, &mut blocks, progress).await?;
+    // The scripts lock is taken per hook so a reload never waits on translation or inpainting
+    state.scripts.read().await.run(Hook::PostOcr, &mut blocks)?;
+
+    if let Some(translator) = &options.translator {
+        state
+            .scripts
+            .read()
+            .await
+            .run(Hook::PreTranslate, &mut blocks)?;
+        // Blocks a pre-translate script already filled in are left alone
+        let pending: Vec<usize> = (0..blocks.len())
+            .filter(|&i| blocks[i].translated_text.is_none())
+            .collect();
+        let mut untranslated: Vec<PageBlock> = pending.iter().map(|&i| blocks[i].clone()).collect();
+        translate_blocks(translator, &mut untranslated, progress).await?;
+        for (i, block) in pending.into_iter().zip(untranslated) {
+            blocks[i] = block;
+        }
+        state
+            .scripts
+            .read()
+            .await
+            .run(Hook::PostTranslate, &mut blocks)?;
+    } else {
+        tracing::info!("[pipeline] no translator configured, skipping translation");
+    }
+
+    // Rectangle mode paints backgrounds instead of inpainting
+    let textless = match options.render_method.as_str() {
+        "rectangle" => None,
+        "auto" => Some(
+            fill_or_inpaint_blocks(
+                state,
+                image,
+                &mask,
+                &blocks,
+                &options.inpaint,
+                debug_dir,
+                progress,
+            )
+            .await?,
+        ),
+        _ => Some(
+            inpaint_blocks(
+                state,
+                image,
+                &mask,
+                &blocks,
+                &options.inpaint,
+                debug_dir,
+                progress,
+            )
+            .await?,
+        ),
+    };
+
+    state
+        .scripts
+        .read()
+        .await
+        .run(Hook::PreRender, &mut blocks)?;
+    match_source_lettering(&mut blocks, &mask, image, &options.default_font)?;
+
+    progress(StageProgress::new(PipelineStage::Rendering, 0, 1));
+    let base_image = textless.clone().unwrap_or_else(|| image.clone());
+    let rendered = typeset_and_render(
+        base_image,
+        &mut blocks,
+        &options.render_method,
+        &options.default_font,
+    )?;
+    progress(StageProgress::new(PipelineStage::Rendering, 1, 1));
+
+    tracing::info!(
+        "[pipeline] page processed in {}ms ({} block(s), method={})",
+        total_start.elapsed().as_millis(),
+        blocks.len(),
+        options.render_method
+    );
+    progress(StageProgress::new(PipelineStage::Complete, 1, 1));
+
+    Ok(PageResult {
+        rendered,
+        textless,
+        mask,
+        blocks,
+    })
+}
+
+/// Paste the masked pixels of an inpainted crop back onto the full page
+pub(crate) fn composite_region(canvas: &mut RgbaImage, region: &InpaintedRegion) {
+    for y in 0..region.height {
+        for x in 0..region.width {
+            let idx = (y * region.width + x) as usize;
+            if region.mask.get(idx).copied().unwrap_or(0) == 0 {
+                continue;
+            }
+
+            let target_x = region.x + x;
+            let target_y = region.y + y;
+            if target_x >= canvas.width() || target_y >= canvas.height() {
+                continue;
+            }
+
+            if let Some(px) = region.image.get(idx * 4..idx * 4 + 4) {
+                canvas.put_pixel(
+                    target_x,
+                    target_y,
+                    image::Rgba([px[0], px[1], px[2], px[3]]),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_lettering_fills_size_and_weight() {
+        let source = lettering::LetteringEstimate {
+            vertical: true,
+            glyph_size_px: 30.0,
+            stroke_width_px: 4.0,
+            font_size: 33.0,
+            font_weight: 600,
+        };
+        let mut block = PageBlock::from_detection(&comic_text_detector::ClassifiedBbox {
+            xmin: 0.0,
+            ymin: 0.0,
+            xmax: 120.0,
+            ymax: 160.0,
+            confidence: 0.9,
+            class: 0,
+        });
+
+        apply_source_lettering(&mut block, &source, Some(24.0));
+        assert_eq!(block.font_size, Some(24.0));
+        assert_eq!(block.font_weight.as_deref(), Some("600"));
+        assert_eq!(block.to_text_block().font_weight.as_deref(), Some("600"));
+
+        // Values already set, by the user or a script, are kept
+        block.font_weight = Some("300".to_string());
+        apply_source_lettering(&mut block, &source, Some(40.0));
+        assert_eq!(block.font_size, Some(24.0));
+        assert_eq!(block.font_weight.as_deref(), Some("300"));
+    }
+
+    #[test]
+    fn test_auto_on_a_cleaned_base_fills_only_plain_blocks() {
+        // White bubble on the left, gradient art on the right, a text bar in each
+        let text = |x: u32, y: u32| (x % 64 >= 24 && x % 64 < 40) && (16..48).contains(&y);
+        let base = DynamicImage::ImageRgb8(image::RgbImage::from_fn(128, 64, |x, y| {
+            let value = match (text(x, y), x < 64) {
+                (true, _) => 0,
+                (false, true) => 255,
+                (false, false) => ((x - 64 + y) * 2) as u8,
+            };
+            image::Rgb([value, value, value])
+        }));
+        let mask = GrayImage::from_fn(128, 64, |x, y| {
+            image::Luma([if text(x, y) { 255 } else { 0 }])
+        });
+        let bbox = |xmin: f32| BBox {
+            xmin,
+            ymin: 0.0,
+            xmax: xmin + 64.0,
+            ymax: 64.0,
+        };
+
+        let filled = fill_plain_blocks(base, &mask, &[bbox(0.0), bbox(64.0)]).to_rgba8();
+        assert_eq!(filled.get_pixel(30, 30).0, [255, 255, 255, 255]);
+        assert_eq!(filled.get_pixel(94, 30).0, [0, 0, 0, 255]);
+    }
+}
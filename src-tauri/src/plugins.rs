@@ -0,0 +1,548 @@
+// External OCR/translation providers run as subprocesses speaking JSON-RPC 2.0 over stdio
+// Each plugin lives in app_data_dir/plugins/<dir>/plugin.json and is spawned on first use.
+// Messages are one JSON object per line. Koharu calls `initialize`, then `ocr` and/or
+// `translate`; plugins may send `log` notifications, and get a `shutdown` notification on unload.
+
+use anyhow::{Context, anyhow};
+use async_trait::async_trait;
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use image::{DynamicImage, GenericImageView};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::{Mutex, RwLock};
+
+use crate::error::{ErrorCode, coded};
+use crate::ocr_pipeline::{OcrPipeline, TextRegion};
+use crate::pipeline::encode_png;
+
+pub const PLUGINS_DIR: &str = "plugins";
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Prefix for plugin keys in the OCR engine list and the translation provider setting
+pub const PLUGIN_PREFIX: &str = "plugin:";
+
+const MANIFEST_FILE: &str = "plugin.json";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+static REGISTRY: OnceLock<PluginRegistry> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PluginKind {
+    Ocr,
+    Translation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub provides: Vec<PluginKind>,
+    pub command: String, // Relative paths are resolved against the plugin's directory
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginInfo {
+    #[serde(flatten)]
+    pub manifest: PluginManifest,
+    pub dir: PathBuf,
+    pub running: bool,
+}
+
+// ============================================================================
+// JSON-RPC framing
+// ============================================================================
+
+fn request_line(id: u64, method: &str, params: &Value) -> String {
+    let mut line =
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }).to_string();
+    line.push('\n');
+    line
+}
+
+#[derive(Debug)]
+enum Incoming {
+    Response {
+        id: u64,
+        result: anyhow::Result<Value>,
+    },
+    Notification {
+        method: String,
+        params: Value,
+    },
+}
+
+fn parse_line(line: &str) -> anyhow::Result<Incoming> {
+    let message: Value = serde_json::from_str(line).context("Plugin sent invalid JSON")?;
+    if let Some(id) = message.get("id").and_then(Value::as_u64) {
+        let result = match message.get("error") {
+            Some(error) => Err(anyhow!(
+                "{} (code {})",
+                error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error"),
+                error.get("code").and_then(Value::as_i64).unwrap_or(0)
+            )),
+            None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+        };
+        return Ok(Incoming::Response { id, result });
+    }
+    match message.get("method").and_then(Value::as_str) {
+        Some(method) => Ok(Incoming::Notification {
+            method: method.to_string(),
+            params: message.get("params").cloned().unwrap_or(Value::Null),
+        }),
+        None => Err(anyhow!("Plugin sent a message without id or method")),
+    }
+}
+
+// ============================================================================
+// Plugin process
+// ============================================================================
+
+struct Channel {
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    next_id: u64,
+}
+
+/// A running plugin; calls are serialized, one request in flight at a time
+pub struct PluginProcess {
+    id: String,
+    child: Mutex<Child>,
+    channel: Mutex<Channel>,
+}
+
+impl PluginProcess {
+    async fn spawn(manifest: &PluginManifest, dir: &Path) -> anyhow::Result<Self> {
+        let command =
+            if Path::new(&manifest.command).is_relative() && dir.join(&manifest.command).exists() {
+                dir.join(&manifest.command)
+            } else {
+                PathBuf::from(&manifest.command) // Looked up on PATH, e.g. "python"
+            };
+
+        let mut child = Command::new(&command)
+            .args(&manifest.args)
+            .current_dir(dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start plugin '{}' ({:?})", manifest.id, command))?;
+
+        let stdin = child.stdin.take().context("Plugin stdin unavailable")?;
+        let stdout = child.stdout.take().context("Plugin stdout unavailable")?;
+        if let Some(stderr) = child.stderr.take() {
+            let id = manifest.id.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    tracing::debug!("[plugin:{}] {}", id, line);
+                }
+            });
+        }
+
+        let process = PluginProcess {
+            id: manifest.id.clone(),
+            child: Mutex::new(child),
+            channel: Mutex::new(Channel {
+                stdin,
+                stdout: BufReader::new(stdout).lines(),
+                next_id: 1,
+            }),
+        };
+
+        let info = process
+            .call(
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "appVersion": env!("CARGO_PKG_VERSION"),
+                }),
+            )
+            .await?;
+        let version = info.get("protocolVersion").and_then(Value::as_u64);
+        if version != Some(PROTOCOL_VERSION as u64) {
+            return Err(anyhow!(
+                "Plugin '{}' speaks protocol {:?}, expected {}",
+                manifest.id,
+                version,
+                PROTOCOL_VERSION
+            ));
+        }
+        tracing::info!("[plugins] started '{}' ({:?})", manifest.id, command);
+        Ok(process)
+    }
+
+    /// Send a request and wait for its response, handling notifications meanwhile
+    pub async fn call(&self, method: &str, params: Value) -> anyhow::Result<Value> {
+        let mut channel = self.channel.lock().await;
+        let id = channel.next_id;
+        channel.next_id += 1;
+
+        let line = request_line(id, method, &params);
+        channel
+            .stdin
+            .write_all(line.as_bytes())
+            .await
+            .with_context(|| format!("Plugin '{}' is not accepting requests", self.id))?;
+        channel.stdin.flush().await?;
+
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, async {
+            loop {
+                let line = channel
+                    .stdout
+                    .next_line()
+                    .await?
+                    .ok_or_else(|| anyhow!("Plugin '{}' exited", self.id))?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match parse_line(&line)? {
+                    Incoming::Response { id: got, result } if got == id => return result,
+                    Incoming::Response { id: got, .. } => {
+                        tracing::warn!("[plugin:{}] ignoring response to {}", self.id, got)
+                    }
+                    Incoming::Notification { method, params } => {
+                        self.notification(&method, &params)
+                    }
+                }
+            }
+        })
+        .await
+        .map_err(|_| {
+            coded(
+                ErrorCode::PluginFailed,
+                format!("Plugin '{}' timed out on '{}'", self.id, method),
+            )
+        })?;
+
+        response.map_err(|err| {
+            coded(
+                ErrorCode::PluginFailed,
+                format!("Plugin '{}' failed on '{}': {:#}", self.id, method, err),
+            )
+        })
+    }
+
+    fn notification(&self, method: &str, params: &Value) {
+        let message = params.get("message").and_then(Value::as_str).unwrap_or("");
+        match (method, params.get("level").and_then(Value::as_str)) {
+            ("log", Some("error")) => tracing::error!("[plugin:{}] {}", self.id, message),
+            ("log", Some("warn")) => tracing::warn!("[plugin:{}] {}", self.id, message),
+            ("log", _) => tracing::info!("[plugin:{}] {}", self.id, message),
+            _ => tracing::debug!("[plugin:{}] ignoring notification '{}'", self.id, method),
+        }
+    }
+
+    async fn shutdown(&self) {
+        {
+            let mut channel = self.channel.lock().await;
+            let line = json!({ "jsonrpc": "2.0", "method": "shutdown" }).to_string() + "\n";
+            let _ = channel.stdin.write_all(line.as_bytes()).await;
+            let _ = channel.stdin.flush().await;
+        }
+        let mut child = self.child.lock().await;
+        if tokio::time::timeout(Duration::from_secs(2), child.wait())
+            .await
+            .is_err()
+        {
+            let _ = child.kill().await;
+        }
+        tracing::info!("[plugins] stopped '{}'", self.id);
+    }
+}
+
+// ============================================================================
+// Registry
+// ============================================================================
+
+pub struct PluginRegistry {
+    dir: PathBuf,
+    manifests: RwLock<Vec<(PathBuf, PluginManifest)>>,
+    running: Mutex<HashMap<String, Arc<PluginProcess>>>,
+}
+
+/// Read every `<dir>/*/plugin.json`, skipping (and logging) invalid manifests
+fn scan(dir: &Path) -> Vec<(PathBuf, PluginManifest)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut found: Vec<(PathBuf, PluginManifest)> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.join(MANIFEST_FILE).is_file())
+        .filter_map(|plugin_dir| {
+            let path = plugin_dir.join(MANIFEST_FILE);
+            let manifest = fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|json| serde_json::from_str::<PluginManifest>(&json).map_err(Into::into));
+            match manifest {
+                Ok(manifest) => Some((plugin_dir, manifest)),
+                Err(err) => {
+                    tracing::warn!("[plugins] skipping {:?}: {:#}", path, err);
+                    None
+                }
+            }
+        })
+        .collect();
+    found.sort_by(|a, b| a.1.id.cmp(&b.1.id));
+    found.dedup_by(|a, b| a.1.id == b.1.id);
+    found
+}
+
+impl PluginRegistry {
+    fn new(dir: PathBuf) -> Self {
+        let manifests = scan(&dir);
+        PluginRegistry {
+            dir,
+            manifests: RwLock::new(manifests),
+            running: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub async fn list(&self) -> Vec<PluginInfo> {
+        let running = self.running.lock().await;
+        self.manifests
+            .read()
+            .await
+            .iter()
+            .map(|(dir, manifest)| PluginInfo {
+                manifest: manifest.clone(),
+                dir: dir.clone(),
+                running: running.contains_key(&manifest.id),
+            })
+            .collect()
+    }
+
+    /// Stop running plugins and rescan the plugins directory
+    pub async fn reload(&self) -> Vec<PluginInfo> {
+        let stopped: Vec<_> = self.running.lock().await.drain().map(|(_, p)| p).collect();
+        for process in stopped {
+            process.shutdown().await;
+        }
+        *self.manifests.write().await = scan(&self.dir);
+        self.list().await
+    }
+
+    /// The running process for `id`, starting it if needed
+    pub async fn process(&self, id: &str) -> anyhow::Result<Arc<PluginProcess>> {
+        let mut running = self.running.lock().await;
+        if let Some(process) = running.get(id) {
+            return Ok(process.clone());
+        }
+
+        let (dir, manifest) = self
+            .manifests
+            .read()
+            .await
+            .iter()
+            .find(|(_, manifest)| manifest.id == id)
+            .cloned()
+            .ok_or_else(|| anyhow!("Plugin '{}' is not installed", id))?;
+        let process = PluginProcess::spawn(&manifest, &dir)
+            .await
+            .map_err(|err| coded(ErrorCode::PluginFailed, format!("{:#}", err)))?;
+        let process = Arc::new(process);
+        running.insert(id.to_string(), process.clone());
+        Ok(process)
+    }
+
+    /// OCR engines backed by plugins, keyed as `plugin:<id>`
+    pub async fn ocr_pipelines(&self) -> Vec<(String, Arc<dyn OcrPipeline + Send + Sync>)> {
+        self.manifests
+            .read()
+            .await
+            .iter()
+            .filter(|(_, manifest)| manifest.provides.contains(&PluginKind::Ocr))
+            .map(|(_, manifest)| {
+                let pipeline = Arc::new(PluginOcr {
+                    id: manifest.id.clone(),
+                }) as Arc<dyn OcrPipeline + Send + Sync>;
+                (format!("{}{}", PLUGIN_PREFIX, manifest.id), pipeline)
+            })
+            .collect()
+    }
+}
+
+/// Set up the registry for `dir`; later calls keep the first directory
+pub fn init(dir: PathBuf) -> &'static PluginRegistry {
+    REGISTRY.get_or_init(|| PluginRegistry::new(dir))
+}
+
+pub fn registry() -> anyhow::Result<&'static PluginRegistry> {
+    REGISTRY
+        .get()
+        .ok_or_else(|| anyhow!("Plugins are not initialized"))
+}
+
+/// Translate through the plugin `id`
+pub async fn translate(
+    id: &str,
+    text: &str,
+    source_lang: Option<&str>,
+    target_lang: Option<&str>,
+) -> anyhow::Result<String> {
+    let result = registry()?
+        .process(id)
+        .await?
+        .call(
+            "translate",
+            json!({ "text": text, "sourceLang": source_lang, "targetLang": target_lang }),
+        )
+        .await?;
+    result
+        .get("text")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("Plugin '{}' returned no text", id))
+}
+
+// ============================================================================
+// OCR provider
+// ============================================================================
+
+/// OCR engine that sends each (already cropped) block image to a plugin as base64 PNG
+#[derive(Debug)]
+struct PluginOcr {
+    id: String,
+}
+
+#[async_trait]
+impl OcrPipeline for PluginOcr {
+    async fn detect_text_regions(&self, image: &DynamicImage) -> anyhow::Result<Vec<TextRegion>> {
+        let (width, height) = image.dimensions();
+        Ok(vec![TextRegion {
+            bbox: [0.0, 0.0, width as f32, height as f32],
+            confidence: 1.0,
+            text: String::new(),
+            angle: None,
+        }])
+    }
+
+    async fn recognize_text(
+        &self,
+        image: &DynamicImage,
+        regions: &[TextRegion],
+    ) -> anyhow::Result<Vec<String>> {
+        let png = BASE64.encode(encode_png(image)?);
+        let result = registry()?
+            .process(&self.id)
+            .await?
+            .call("ocr", json!({ "image": png, "format": "png" }))
+            .await?;
+        let text = result
+            .get("text")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("Plugin '{}' returned no text", self.id))?
+            .to_string();
+        Ok(regions.iter().map(|_| text.clone()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_responses_and_notifications() {
+        let line = request_line(3, "translate", &json!({ "text": "a" }));
+        assert!(line.ends_with('\n') && !line.trim_end().contains('\n'));
+        let request: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(request["id"], 3);
+        assert_eq!(request["method"], "translate");
+        assert_eq!(request["params"]["text"], "a");
+
+        let Incoming::Response { id, result } =
+            parse_line(r#"{"jsonrpc":"2.0","id":3,"result":{"text":"hi"}}"#).unwrap()
+        else {
+            panic!("expected a response");
+        };
+        assert_eq!(id, 3);
+        assert_eq!(result.unwrap(), json!({ "text": "hi" }));
+
+        let Incoming::Response { result, .. } =
+            parse_line(r#"{"jsonrpc":"2.0","id":4,"error":{"code":-32000,"message":"quota"}}"#)
+                .unwrap()
+        else {
+            panic!("expected a response");
+        };
+        assert_eq!(result.unwrap_err().to_string(), "quota (code -32000)");
+
+        let log = parse_line(r#"{"jsonrpc":"2.0","method":"log","params":{"message":"x"}}"#);
+        assert!(matches!(log, Ok(Incoming::Notification { ref method, .. }) if method == "log"));
+        assert!(parse_line("{}").is_err());
+    }
+
+    #[test]
+    fn test_scan_reads_valid_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = dir.path().join("echo");
+        fs::create_dir_all(&good).unwrap();
+        fs::write(
+            good.join(MANIFEST_FILE),
+            r#"{"id":"echo","name":"Echo","provides":["translation"],"command":"python","args":["echo.py"]}"#,
+        )
+        .unwrap();
+        let bad = dir.path().join("broken");
+        fs::create_dir_all(&bad).unwrap();
+        fs::write(bad.join(MANIFEST_FILE), "{").unwrap();
+
+        let found = scan(dir.path());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].1.provides, vec![PluginKind::Translation]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_call_round_trip_with_shell_plugin() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = r#"
+read init
+echo '{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":1}}'
+read req
+echo '{"jsonrpc":"2.0","method":"log","params":{"message":"working"}}'
+echo '{"jsonrpc":"2.0","id":2,"result":{"text":"Hello"}}'
+"#;
+        fs::write(dir.path().join("plugin.sh"), script).unwrap();
+        let manifest = PluginManifest {
+            id: "shell".to_string(),
+            name: "Shell".to_string(),
+            version: None,
+            description: None,
+            provides: vec![PluginKind::Translation],
+            command: "sh".to_string(),
+            args: vec!["plugin.sh".to_string()],
+        };
+
+        let process = PluginProcess::spawn(&manifest, dir.path()).await.unwrap();
+        let result = process
+            .call("translate", json!({ "text": "こんにちは" }))
+            .await
+            .unwrap();
+        assert_eq!(result["text"], "Hello");
+        assert!(process.call("translate", json!({})).await.is_err()); // Plugin exited
+    }
+}
@@ -0,0 +1,216 @@
+// Unified progress reporting for long-running commands
+// Every command emits the same `task://progress` payload so the UI can drive one progress panel.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "gui")]
+use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(feature = "gui")]
+use tauri::{AppHandle, Emitter, Manager};
+
+#[cfg(feature = "gui")]
+use crate::state::AppState;
+
+/// Event emitted for every progress step of every task
+pub const TASK_PROGRESS_EVENT: &str = "task://progress";
+
+/// Terminal stages; a task reporting one of these has been removed from the registry
+pub const STAGE_DONE: &str = "done";
+pub const STAGE_FAILED: &str = "failed";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskProgress {
+    pub task_id: String,
+    pub kind: String, // Command that owns the task, e.g. "detection" or "process_folder"
+    pub stage: String,
+    pub current: usize,
+    pub total: usize,
+    pub message: Option<String>,
+    pub started_at: u64, // Unix millis
+}
+
+/// Latest progress of every running task, used by list_active_tasks
+#[derive(Debug, Default)]
+pub struct TaskRegistry {
+    tasks: Mutex<HashMap<String, TaskProgress>>,
+    next_id: AtomicU64,
+}
+
+impl TaskRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn next_task_id(&self, kind: &str) -> String {
+        let seq = self.next_id.fetch_add(1, Ordering::Relaxed);
+        format!("{}-{}", kind, seq)
+    }
+
+    fn insert(&self, progress: TaskProgress) {
+        self.tasks
+            .lock()
+            .unwrap()
+            .insert(progress.task_id.clone(), progress);
+    }
+
+    fn remove(&self, task_id: &str) {
+        self.tasks.lock().unwrap().remove(task_id);
+    }
+
+    /// Snapshot of every running task, oldest first
+    pub fn list(&self) -> Vec<TaskProgress> {
+        let mut tasks: Vec<_> = self.tasks.lock().unwrap().values().cloned().collect();
+        tasks.sort_by(|a, b| {
+            a.started_at
+                .cmp(&b.started_at)
+                .then_with(|| a.task_id.cmp(&b.task_id))
+        });
+        tasks
+    }
+}
+
+/// Handle for one running task, deregistered once it completes
+///
+/// A handle dropped without `finish` or `complete` (e.g. by an early `?` return)
+/// reports the task as failed.
+#[cfg(feature = "gui")]
+pub struct TaskHandle {
+    app: AppHandle,
+    task_id: String,
+    kind: String,
+    started_at: u64,
+    finished: bool,
+}
+
+#[cfg(feature = "gui")]
+impl TaskHandle {
+    /// Register a task, using `task_id` from the caller when given so the UI can correlate events
+    pub fn start(app: &AppHandle, kind: &str, task_id: Option<String>) -> Self {
+        let state = app.state::<AppState>();
+        let task_id = task_id.unwrap_or_else(|| state.tasks.next_task_id(kind));
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        let handle = Self {
+            app: app.clone(),
+            task_id,
+            kind: kind.to_string(),
+            started_at,
+            finished: false,
+        };
+        handle.report("started", 0, 0, None);
+        handle
+    }
+
+    fn progress(
+        &self,
+        stage: &str,
+        current: usize,
+        total: usize,
+        message: Option<String>,
+    ) -> TaskProgress {
+        TaskProgress {
+            task_id: self.task_id.clone(),
+            kind: self.kind.clone(),
+            stage: stage.to_string(),
+            current,
+            total,
+            message,
+            started_at: self.started_at,
+        }
+    }
+
+    fn emit(&self, progress: &TaskProgress) {
+        if let Err(err) = self.app.emit(TASK_PROGRESS_EVENT, progress) {
+            tracing::warn!("[progress] failed to emit progress event: {}", err);
+        }
+    }
+
+    /// Record and emit a progress step
+    pub fn report(&self, stage: &str, current: usize, total: usize, message: Option<String>) {
+        let progress = self.progress(stage, current, total, message);
+        self.app.state::<AppState>().tasks.insert(progress.clone());
+        self.emit(&progress);
+    }
+
+    fn end(&mut self, stage: &str, message: Option<String>) {
+        self.finished = true;
+        self.app.state::<AppState>().tasks.remove(&self.task_id);
+        let (current, total) = if stage == STAGE_DONE { (1, 1) } else { (0, 0) };
+        self.emit(&self.progress(stage, current, total, message));
+    }
+
+    pub fn finish(mut self) {
+        self.end(STAGE_DONE, None);
+    }
+
+    pub fn fail(mut self, error: &anyhow::Error) {
+        self.end(STAGE_FAILED, Some(format!("{:#}", error)));
+    }
+
+    /// Finish or fail the task depending on `result`, passing the result through
+    pub fn complete<T>(self, result: anyhow::Result<T>) -> anyhow::Result<T> {
+        match &result {
+            Ok(_) => self.finish(),
+            Err(err) => self.fail(err),
+        }
+        result
+    }
+}
+
+#[cfg(feature = "gui")]
+impl Drop for TaskHandle {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.end(STAGE_FAILED, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(task_id: &str, started_at: u64) -> TaskProgress {
+        TaskProgress {
+            task_id: task_id.to_string(),
+            kind: "detection".to_string(),
+            stage: "inference".to_string(),
+            current: 0,
+            total: 1,
+            message: None,
+            started_at,
+        }
+    }
+
+    #[test]
+    fn test_registry_lists_oldest_first_and_replaces_updates() {
+        let registry = TaskRegistry::new();
+        registry.insert(progress("b", 20));
+        registry.insert(progress("a", 10));
+
+        let mut update = progress("b", 20);
+        update.current = 1;
+        registry.insert(update);
+
+        let tasks = registry.list();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].task_id, "a");
+        assert_eq!(tasks[1].current, 1);
+
+        registry.remove("a");
+        assert_eq!(registry.list().len(), 1);
+    }
+
+    #[test]
+    fn test_task_ids_are_unique_per_kind() {
+        let registry = TaskRegistry::new();
+        assert_eq!(registry.next_task_id("ocr"), "ocr-0");
+        assert_eq!(registry.next_task_id("ocr"), "ocr-1");
+    }
+}
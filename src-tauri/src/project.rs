@@ -0,0 +1,233 @@
+// Project files: per-series decisions (glossary, tone, fonts, provider, inpaint defaults)
+// koharu-project.json is looked up from a chapter folder upwards, so one file in the
+// series folder covers every chapter. While a project is open it overrides the saved settings.
+
+use anyhow::{Context, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::metadata::ExportMetadata;
+use crate::settings::Settings;
+use crate::stages::InpaintConfig;
+use crate::translation::GlossaryEntry;
+
+pub const PROJECT_FILE: &str = "koharu-project.json";
+pub const PROJECT_VERSION: u32 = 1;
+
+/// Unset fields fall back to the user's saved settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Project {
+    pub version: u32,
+    pub name: Option<String>,
+    pub glossary: Vec<GlossaryEntry>,
+    pub tone: Option<String>,
+    pub translation_provider: Option<String>, // Same values as the settings provider
+    pub default_font: Option<String>,
+    pub render_method: Option<String>,
+    pub inpaint: Option<InpaintConfig>,
+    pub metadata: Option<ProjectMetadata>,
+}
+
+/// Tags embedded in exported pages; each set field wins over the export settings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProjectMetadata {
+    pub title: Option<String>,
+    pub chapter: Option<String>,
+    pub translator: Option<String>,
+}
+
+impl ProjectMetadata {
+    pub fn apply(&self, metadata: &mut ExportMetadata) {
+        if self.title.is_some() {
+            metadata.title = self.title.clone();
+        }
+        if self.chapter.is_some() {
+            metadata.chapter = self.chapter.clone();
+        }
+        if self.translator.is_some() {
+            metadata.translator = self.translator.clone();
+        }
+    }
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Project {
+            version: PROJECT_VERSION,
+            name: None,
+            glossary: Vec::new(),
+            tone: None,
+            translation_provider: None,
+            default_font: None,
+            render_method: None,
+            inpaint: None,
+            metadata: None,
+        }
+    }
+}
+
+/// The project currently applied, and where it was loaded from
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenProject {
+    pub path: PathBuf,
+    pub project: Project,
+}
+
+impl Project {
+    /// Snapshot the current style decisions so later chapters start from them
+    pub fn from_settings(settings: &Settings, name: Option<String>) -> Self {
+        Project {
+            name,
+            glossary: settings.translation.style.glossary.clone(),
+            tone: settings.translation.style.tone.clone(),
+            translation_provider: Some(settings.translation.provider.clone()),
+            default_font: Some(settings.render.default_font.clone()),
+            render_method: Some(settings.render.method.clone()),
+            inpaint: Some(settings.inpaint.clone()),
+            metadata: Some(ProjectMetadata {
+                title: settings.export.metadata.title.clone(),
+                chapter: settings.export.metadata.chapter.clone(),
+                translator: settings.export.metadata.translator.clone(),
+            }),
+            ..Project::default()
+        }
+    }
+
+    /// Nearest project file in `start` or one of its parents
+    pub fn find(start: &Path) -> Option<PathBuf> {
+        start
+            .ancestors()
+            .map(|dir| dir.join(PROJECT_FILE))
+            .find(|path| path.is_file())
+    }
+
+    /// Load `path`, or the nearest project file when `path` is a directory
+    pub fn open(path: &Path) -> anyhow::Result<OpenProject> {
+        let path = if path.is_dir() {
+            Self::find(path)
+                .ok_or_else(|| anyhow!("No {} found in {:?} or its parents", PROJECT_FILE, path))?
+        } else {
+            path.to_path_buf()
+        };
+        let contents =
+            fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+        let project: Project = serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid project file {:?}", path))?;
+        if project.version > PROJECT_VERSION {
+            return Err(anyhow!(
+                "Project file {:?} is version {}, newer than this Koharu supports ({})",
+                path,
+                project.version,
+                PROJECT_VERSION
+            ));
+        }
+        Ok(OpenProject { path, project })
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json).with_context(|| format!("Failed to write {:?}", path))
+    }
+
+    /// Overlay the project's decisions on top of the saved settings
+    pub fn apply(&self, settings: &mut Settings) {
+        let style = &mut settings.translation.style;
+        if !self.glossary.is_empty() {
+            style.glossary = self.glossary.clone();
+        }
+        if self.tone.is_some() {
+            style.tone = self.tone.clone();
+        }
+        if let Some(provider) = &self.translation_provider {
+            settings.translation.provider = provider.clone();
+        }
+        if let Some(font) = &self.default_font {
+            settings.render.default_font = font.clone();
+        }
+        if let Some(method) = &self.render_method {
+            settings.render.method = method.clone();
+        }
+        if let Some(inpaint) = &self.inpaint {
+            settings.inpaint = inpaint.clone();
+        }
+        if let Some(metadata) = &self.metadata {
+            metadata.apply(&mut settings.export.metadata);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_finds_series_project_from_chapter_folder() {
+        let series = tempfile::tempdir().unwrap();
+        let chapter = series.path().join("ch02");
+        fs::create_dir_all(&chapter).unwrap();
+
+        let project = Project {
+            name: Some("Series".to_string()),
+            default_font: Some("Wild Words".to_string()),
+            ..Project::default()
+        };
+        project.save(&series.path().join(PROJECT_FILE)).unwrap();
+
+        let open = Project::open(&chapter).unwrap();
+        assert_eq!(open.path, series.path().join(PROJECT_FILE));
+        assert_eq!(open.project, project);
+
+        let empty = tempfile::tempdir().unwrap();
+        assert!(Project::open(empty.path()).is_err());
+    }
+
+    #[test]
+    fn test_apply_only_overrides_set_fields() {
+        let mut settings = Settings::default();
+        settings.render.method = "lama".to_string();
+        settings.export.metadata.title = Some("Saved title".to_string());
+        settings.export.metadata.translator = Some("Saved credit".to_string());
+
+        let project = Project {
+            glossary: vec![GlossaryEntry {
+                source: "先輩".to_string(),
+                target: "senpai".to_string(),
+                note: None,
+            }],
+            tone: Some("casual".to_string()),
+            default_font: Some("Wild Words".to_string()),
+            metadata: Some(ProjectMetadata {
+                title: Some("Series".to_string()),
+                chapter: Some("12".to_string()),
+                translator: None,
+            }),
+            ..Project::default()
+        };
+        project.apply(&mut settings);
+
+        assert_eq!(settings.render.default_font, "Wild Words");
+        assert_eq!(settings.render.method, "lama");
+        assert_eq!(settings.translation.style.glossary.len(), 1);
+        assert_eq!(settings.translation.style.tone.as_deref(), Some("casual"));
+        let metadata = &settings.export.metadata;
+        assert_eq!(metadata.title.as_deref(), Some("Series"));
+        assert_eq!(metadata.chapter.as_deref(), Some("12"));
+        assert_eq!(metadata.translator.as_deref(), Some("Saved credit"));
+        assert_eq!(
+            Project::from_settings(&settings, None).glossary,
+            project.glossary
+        );
+    }
+
+    #[test]
+    fn test_rejects_newer_project_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PROJECT_FILE);
+        fs::write(&path, r#"{"version": 99}"#).unwrap();
+        assert!(Project::open(&path).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+// User scripts (rhai) that transform text blocks at fixed pipeline hook points
+// Every *.rhai file in app_config_dir/scripts is loaded in name order when scripting is enabled.
+// A script defines any of `fn post_ocr(block)`, `fn pre_translate(block)`, `fn post_translate(block)`
+// or `fn pre_render(block)`; returning the (modified) block map replaces it, returning () keeps it.
+
+use anyhow::{Context, anyhow};
+use rhai::{AST, Dynamic, Engine, Scope};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::pipeline::PageBlock;
+
+pub const SCRIPTS_DIR: &str = "scripts";
+
+// Keep a runaway script from hanging the pipeline
+const MAX_OPERATIONS: u64 = 1_000_000;
+const MAX_CALL_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Hook {
+    PostOcr,
+    PreTranslate, // Setting translatedText here skips the translator for that block
+    PostTranslate,
+    PreRender,
+}
+
+impl Hook {
+    pub const ALL: [Hook; 4] = [
+        Hook::PostOcr,
+        Hook::PreTranslate,
+        Hook::PostTranslate,
+        Hook::PreRender,
+    ];
+
+    /// Script function implementing the hook
+    pub fn function_name(self) -> &'static str {
+        match self {
+            Hook::PostOcr => "post_ocr",
+            Hook::PreTranslate => "pre_translate",
+            Hook::PostTranslate => "post_translate",
+            Hook::PreRender => "pre_render",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScriptSettings {
+    pub enabled: bool, // Off by default: scripts run with the app's permissions
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptInfo {
+    pub name: String,
+    pub hooks: Vec<Hook>,
+}
+
+struct Script {
+    name: String,
+    ast: AST,
+    hooks: Vec<Hook>,
+}
+
+pub struct ScriptHooks {
+    dir: PathBuf,
+    engine: Engine,
+    scripts: Vec<Script>,
+}
+
+impl std::fmt::Debug for ScriptHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScriptHooks")
+            .field("dir", &self.dir)
+            .field("scripts", &self.info())
+            .finish()
+    }
+}
+
+impl ScriptHooks {
+    /// Hooks for the scripts in `dir`; nothing is loaded until `reload(true)`
+    pub fn new(dir: PathBuf) -> Self {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(MAX_CALL_DEPTH);
+        engine.on_print(|text| tracing::info!("[script] {}", text));
+        engine.on_debug(|text, source, pos| {
+            tracing::debug!("[script] {} @ {:?} {:?}", text, source, pos)
+        });
+
+        ScriptHooks {
+            dir,
+            engine,
+            scripts: Vec::new(),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Recompile every script; with `enabled` false all scripts are unloaded
+    ///
+    /// A script that fails to compile aborts the reload and keeps the previous set.
+    pub fn reload(&mut self, enabled: bool) -> anyhow::Result<Vec<ScriptInfo>> {
+        if !enabled {
+            self.scripts.clear();
+            return Ok(Vec::new());
+        }
+
+        let mut paths: Vec<PathBuf> = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "rhai"))
+                .collect(),
+            Err(_) => Vec::new(), // No scripts directory yet
+        };
+        paths.sort();
+
+        let mut scripts = Vec::with_capacity(paths.len());
+        for path in paths {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let source =
+                fs::read_to_string(&path).with_context(|| format!("Failed to read {:?}", path))?;
+            scripts.push(self.compile(&name, &source)?);
+        }
+
+        self.scripts = scripts;
+        let info = self.info();
+        tracing::info!(
+            "[script] loaded {} script(s) from {:?}",
+            info.len(),
+            self.dir
+        );
+        Ok(info)
+    }
+
+    fn compile(&self, name: &str, source: &str) -> anyhow::Result<Script> {
+        let ast = self
+            .engine
+            .compile(source)
+            .map_err(|err| anyhow!("Script '{}' failed to compile: {}", name, err))?;
+        let hooks = Hook::ALL
+            .into_iter()
+            .filter(|hook| {
+                ast.iter_functions()
+                    .any(|f| f.name == hook.function_name() && f.params.len() == 1)
+            })
+            .collect();
+        Ok(Script {
+            name: name.to_string(),
+            ast,
+            hooks,
+        })
+    }
+
+    pub fn info(&self) -> Vec<ScriptInfo> {
+        self.scripts
+            .iter()
+            .map(|script| ScriptInfo {
+                name: script.name.clone(),
+                hooks: script.hooks.clone(),
+            })
+            .collect()
+    }
+
+    /// Run `hook` from every script, in script order, over each block
+    pub fn run(&self, hook: Hook, blocks: &mut [PageBlock]) -> anyhow::Result<()> {
+        for script in self.scripts.iter().filter(|s| s.hooks.contains(&hook)) {
+            for (index, block) in blocks.iter_mut().enumerate() {
+                let input = rhai::serde::to_dynamic(&*block)
+                    .map_err(|err| anyhow!("Failed to convert block {}: {}", index, err))?;
+                let output: Dynamic = self
+                    .engine
+                    .call_fn(
+                        &mut Scope::new(),
+                        &script.ast,
+                        hook.function_name(),
+                        (input,),
+                    )
+                    .map_err(|err| {
+                        anyhow!(
+                            "Script '{}' {} failed on block {}: {}",
+                            script.name,
+                            hook.function_name(),
+                            index,
+                            err
+                        )
+                    })?;
+
+                if !output.is_unit() {
+                    *block = rhai::serde::from_dynamic(&output).map_err(|err| {
+                        anyhow!(
+                            "Script '{}' {} returned an invalid block: {}",
+                            script.name,
+                            hook.function_name(),
+                            err
+                        )
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(text: &str) -> PageBlock {
+        PageBlock {
+            xmin: 0.0,
+            ymin: 0.0,
+            xmax: 10.0,
+            ymax: 10.0,
+            confidence: 0.9,
+            class: 0,
+            text: text.to_string(),
+            translated_text: None,
+            font_size: None,
+            font_weight: None,
+            text_color: None,
+            background_color: None,
+        }
+    }
+
+    fn hooks_with(scripts: &[(&str, &str)]) -> (tempfile::TempDir, ScriptHooks) {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, source) in scripts {
+            fs::write(dir.path().join(name), source).unwrap();
+        }
+        let mut hooks = ScriptHooks::new(dir.path().to_path_buf());
+        hooks.reload(true).unwrap();
+        (dir, hooks)
+    }
+
+    #[test]
+    fn test_hooks_transform_blocks_in_script_order() {
+        let (_dir, hooks) = hooks_with(&[
+            (
+                "10-sfx.rhai",
+                r#"
+                fn pre_translate(block) {
+                    if block.text == "ドン" { block.translatedText = "BOOM"; return block; }
+                }
+                "#,
+            ),
+            (
+                "20-names.rhai",
+                r#"fn post_translate(block) { block.translatedText.replace("Senpai", "senpai"); block }"#,
+            ),
+        ]);
+        assert_eq!(hooks.info().len(), 2);
+        assert_eq!(hooks.info()[0].hooks, vec![Hook::PreTranslate]);
+
+        let mut blocks = vec![block("ドン"), block("先輩")];
+        hooks.run(Hook::PreTranslate, &mut blocks).unwrap();
+        assert_eq!(blocks[0].translated_text.as_deref(), Some("BOOM"));
+        assert_eq!(blocks[1].translated_text, None);
+
+        blocks[1].translated_text = Some("Senpai!".to_string());
+        hooks.run(Hook::PostTranslate, &mut blocks).unwrap();
+        assert_eq!(blocks[1].translated_text.as_deref(), Some("senpai!"));
+    }
+
+    #[test]
+    fn test_runaway_and_broken_scripts_fail() {
+        let (_dir, hooks) = hooks_with(&[("loop.rhai", "fn post_ocr(block) { loop {} }")]);
+        assert!(hooks.run(Hook::PostOcr, &mut [block("a")]).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("bad.rhai"), "fn post_ocr(block) {").unwrap();
+        let mut hooks = ScriptHooks::new(dir.path().to_path_buf());
+        assert!(hooks.reload(true).is_err());
+        assert!(hooks.reload(false).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+// Last-session state (open project, page, zoom, engine choices) restored on launch
+// Saved to app_config_dir/session.json whenever the frontend reports a change; the open
+// project is taken from AppState rather than from the frontend.
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+pub const SESSION_FILE: &str = "session.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Session {
+    pub project: Option<PathBuf>,
+    pub workspace: Option<String>, // Imported archive under app_data_dir/workspace
+    pub page_index: Option<usize>,
+    pub zoom: Option<f32>,
+    pub ocr_engine: Option<String>,
+    pub translation_provider: Option<String>,
+    pub render_method: Option<String>,
+}
+
+impl Session {
+    /// Overwrite the fields `update` sets; unset fields keep their saved value
+    pub fn merge(&mut self, update: Session) {
+        let Session {
+            project,
+            workspace,
+            page_index,
+            zoom,
+            ocr_engine,
+            translation_provider,
+            render_method,
+        } = update;
+        if project.is_some() {
+            self.project = project;
+        }
+        if workspace.is_some() {
+            self.workspace = workspace;
+        }
+        if page_index.is_some() {
+            self.page_index = page_index;
+        }
+        if zoom.is_some() {
+            self.zoom = zoom;
+        }
+        if ocr_engine.is_some() {
+            self.ocr_engine = ocr_engine;
+        }
+        if translation_provider.is_some() {
+            self.translation_provider = translation_provider;
+        }
+        if render_method.is_some() {
+            self.render_method = render_method;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SessionStore {
+    path: PathBuf,
+    session: Mutex<Session>,
+}
+
+impl SessionStore {
+    /// A missing or unreadable file starts an empty session
+    pub fn load(config_dir: &Path) -> Self {
+        let path = config_dir.join(SESSION_FILE);
+        let session = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                tracing::warn!("[session] ignoring invalid {:?}: {}", path, err);
+                Session::default()
+            }),
+            Err(_) => Session::default(),
+        };
+        SessionStore {
+            path,
+            session: Mutex::new(session),
+        }
+    }
+
+    pub async fn get(&self) -> Session {
+        self.session.lock().await.clone()
+    }
+
+    /// Apply `change` and write the result; returns the saved session
+    pub async fn update(&self, change: impl FnOnce(&mut Session)) -> anyhow::Result<Session> {
+        let mut session = self.session.lock().await;
+        let mut updated = session.clone();
+        change(&mut updated);
+        if updated != *session {
+            let json = serde_json::to_string_pretty(&updated)?;
+            fs::write(&self.path, json)
+                .with_context(|| format!("Failed to write {:?}", self.path))?;
+            *session = updated;
+        }
+        Ok(session.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_update_merges_and_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SessionStore::load(dir.path());
+        assert_eq!(store.get().await, Session::default());
+
+        store
+            .update(|session| {
+                session.merge(Session {
+                    zoom: Some(1.5),
+                    ocr_engine: Some("manga-ocr".to_string()),
+                    ..Session::default()
+                })
+            })
+            .await
+            .unwrap();
+        let saved = store
+            .update(|session| {
+                session.merge(Session {
+                    page_index: Some(3),
+                    ..Session::default()
+                })
+            })
+            .await
+            .unwrap();
+        assert_eq!(saved.zoom, Some(1.5));
+        assert_eq!(saved.page_index, Some(3));
+
+        assert_eq!(SessionStore::load(dir.path()).get().await, saved);
+    }
+}
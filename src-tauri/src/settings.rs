@@ -0,0 +1,492 @@
+// Typed application settings stored as versioned JSON in app_config_dir
+// Replaces gpu_preference.txt; older files are migrated on first load.
+
+use anyhow::{Context, anyhow};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::RwLock;
+
+use crate::backup::BackupSettings;
+use crate::image_io::ExportOptions;
+use crate::pipeline::ProcessOptions;
+use crate::plugins::PLUGIN_PREFIX;
+use crate::scripting::ScriptSettings;
+use crate::stages::InpaintConfig;
+use crate::translation::{TranslationStyle, TranslatorConfig};
+
+pub const SETTINGS_VERSION: u32 = 2;
+
+const SETTINGS_FILE: &str = "settings.json";
+const BUNDLE_FORMAT: &str = "koharu-settings";
+const LEGACY_GPU_PREFERENCE_FILE: &str = "gpu_preference.txt";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GpuSettings {
+    pub preference: String, // "cuda", "directml", or "cpu"
+    pub device_id: u32,
+}
+
+impl Default for GpuSettings {
+    fn default() -> Self {
+        GpuSettings {
+            preference: "cuda".to_string(),
+            device_id: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OcrSettings {
+    pub engine: Option<String>, // None = pick the best available engine at startup
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TranslationSettings {
+    pub provider: String, // "google", "deepl-free", "deepl-pro", or "ollama"
+    pub google_api_key: Option<String>,
+    pub deepl_api_key: Option<String>,
+    pub ollama_model: String,
+    pub ollama_system_prompt: Option<String>,
+    pub source_lang: Option<String>,
+    pub target_lang: Option<String>,
+    pub style: TranslationStyle, // Usually set by the open project
+}
+
+impl Default for TranslationSettings {
+    fn default() -> Self {
+        TranslationSettings {
+            provider: "google".to_string(),
+            google_api_key: None,
+            deepl_api_key: None,
+            ollama_model: "gemma2:2b".to_string(),
+            ollama_system_prompt: None,
+            source_lang: None,
+            target_lang: None,
+            style: TranslationStyle::default(),
+        }
+    }
+}
+
+impl TranslationSettings {
+    /// Backend translator for the selected provider, if it can run without the frontend
+    pub fn translator(&self) -> Option<TranslatorConfig> {
+        match self.provider.as_str() {
+            "deepl-free" | "deepl-pro" => {
+                self.deepl_api_key
+                    .clone()
+                    .map(|api_key| TranslatorConfig::Deepl {
+                        api_key,
+                        use_pro: self.provider == "deepl-pro",
+                        source_lang: self.source_lang.clone(),
+                        target_lang: self.target_lang.clone(),
+                        style: self.style.clone(),
+                    })
+            }
+            "ollama" => Some(TranslatorConfig::Ollama {
+                model: self.ollama_model.clone(),
+                system_prompt: self.ollama_system_prompt.clone(),
+                style: self.style.clone(),
+            }),
+            // Google translation runs in the frontend; "plugin:<id>" selects an installed plugin
+            provider => provider
+                .strip_prefix(PLUGIN_PREFIX)
+                .map(|id| TranslatorConfig::Plugin {
+                    id: id.to_string(),
+                    source_lang: self.source_lang.clone(),
+                    target_lang: self.target_lang.clone(),
+                    style: self.style.clone(),
+                }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RenderSettings {
+    pub method: String, // "rectangle", "lama", "newlama", or "auto"
+    pub default_font: String,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            method: "rectangle".to_string(),
+            default_font: "Arial".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiagnosticsSettings {
+    pub crash_reports: bool, // Opt-in: write a report to app_data_dir/crashes on panic
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationSettings {
+    pub job_finished: bool, // OS notification when a queued job completes or fails
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings { job_finished: true }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    pub version: u32,
+    pub gpu: GpuSettings,
+    pub ocr: OcrSettings,
+    pub inpaint: InpaintConfig,
+    pub translation: TranslationSettings,
+    pub render: RenderSettings,
+    pub export: ExportOptions,
+    pub diagnostics: DiagnosticsSettings,
+    pub notifications: NotificationSettings,
+    pub backup: BackupSettings,
+    pub scripting: ScriptSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            version: SETTINGS_VERSION,
+            gpu: GpuSettings::default(),
+            ocr: OcrSettings::default(),
+            inpaint: InpaintConfig::default(),
+            translation: TranslationSettings::default(),
+            render: RenderSettings::default(),
+            export: ExportOptions::default(),
+            diagnostics: DiagnosticsSettings::default(),
+            notifications: NotificationSettings::default(),
+            backup: BackupSettings::default(),
+            scripting: ScriptSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Pipeline options seeded from the saved defaults
+    pub fn process_options(&self) -> ProcessOptions {
+        ProcessOptions {
+            translator: self.translation.translator(),
+            render_method: self.render.method.clone(),
+            default_font: self.render.default_font.clone(),
+            inpaint: self.inpaint.clone(),
+            export: self.export.clone(),
+            ..ProcessOptions::default()
+        }
+    }
+}
+
+/// Portable settings file for moving between machines or sharing a team setup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsBundle {
+    pub format: String,
+    pub app_version: String,
+    pub settings: Value, // Raw so bundles from other versions go through `migrate`
+    #[serde(default)]
+    pub frontend: Option<Value>, // UI preferences kept in the webview's localStorage
+}
+
+impl SettingsBundle {
+    pub fn new(settings: &Settings, include_secrets: bool, frontend: Option<Value>) -> Self {
+        let mut settings = settings.clone();
+        if !include_secrets {
+            settings.translation.google_api_key = None;
+            settings.translation.deepl_api_key = None;
+        }
+        SettingsBundle {
+            format: BUNDLE_FORMAT.to_string(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            settings: serde_json::to_value(settings).unwrap_or_default(),
+            frontend,
+        }
+    }
+
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let contents =
+            fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+        let bundle: SettingsBundle = serde_json::from_str(&contents)
+            .with_context(|| format!("{:?} is not a Koharu settings file", path))?;
+        if bundle.format != BUNDLE_FORMAT {
+            return Err(anyhow!("Unknown settings file format '{}'", bundle.format));
+        }
+        Ok(bundle)
+    }
+
+    pub fn write(&self, path: &Path) -> anyhow::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json).with_context(|| format!("Failed to write {:?}", path))
+    }
+
+    /// Bundled settings applied over `current`; API keys left out of the bundle are kept
+    pub fn merge_into(&self, current: &Settings) -> anyhow::Result<Settings> {
+        let mut settings: Settings = serde_json::from_value(migrate(self.settings.clone())?)
+            .context("Invalid settings in bundle")?;
+        let translation = &mut settings.translation;
+        if translation.google_api_key.is_none() {
+            translation.google_api_key = current.translation.google_api_key.clone();
+        }
+        if translation.deepl_api_key.is_none() {
+            translation.deepl_api_key = current.translation.deepl_api_key.clone();
+        }
+        Ok(settings)
+    }
+}
+
+/// Upgrade a raw settings document to SETTINGS_VERSION, one version at a time
+fn migrate(mut value: Value) -> anyhow::Result<Value> {
+    let mut version = value.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+    if version > SETTINGS_VERSION {
+        return Err(anyhow!(
+            "Settings version {} is newer than supported version {}",
+            version,
+            SETTINGS_VERSION
+        ));
+    }
+
+    while version < SETTINGS_VERSION {
+        match version {
+            // v0: unversioned document written before the schema existed
+            0 => {}
+            // v1: every format stored a quality; only JPEG and AVIF take one now
+            1 => {
+                if let Some(export) = value.get_mut("export").and_then(Value::as_object_mut) {
+                    let format = export.get("format").and_then(Value::as_str);
+                    if !matches!(format, Some("jpeg" | "avif")) {
+                        export.remove("quality");
+                    }
+                }
+            }
+            _ => unreachable!("no migration from settings version {}", version),
+        }
+        version += 1;
+        value["version"] = Value::from(version);
+    }
+
+    Ok(value)
+}
+
+/// Settings loaded from disk plus the path they are saved to
+#[derive(Debug)]
+pub struct SettingsStore {
+    path: PathBuf,
+    settings: RwLock<Settings>,
+}
+
+impl SettingsStore {
+    /// Load settings from `config_dir`, migrating gpu_preference.txt if no settings file exists
+    pub fn load(config_dir: &Path) -> anyhow::Result<Self> {
+        fs::create_dir_all(config_dir).context("Failed to create app config directory")?;
+        let path = config_dir.join(SETTINGS_FILE);
+
+        let settings = if path.exists() {
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read settings at {:?}", path))?;
+            match serde_json::from_str::<Value>(&contents)
+                .context("Settings file is not valid JSON")
+                .and_then(migrate)
+                .and_then(|value| serde_json::from_value(value).context("Invalid settings"))
+            {
+                Ok(settings) => settings,
+                Err(err) => {
+                    // Keep the unreadable file around instead of silently overwriting it
+                    let backup = path.with_extension("json.bak");
+                    tracing::warn!(
+                        "[settings] {:#}; using defaults (previous file kept at {:?})",
+                        err,
+                        backup
+                    );
+                    fs::rename(&path, &backup).ok();
+                    Settings::default()
+                }
+            }
+        } else {
+            Self::from_legacy_files(config_dir)
+        };
+
+        let store = Self {
+            path,
+            settings: RwLock::new(settings.clone()),
+        };
+        store.write(&settings)?;
+
+        let legacy = config_dir.join(LEGACY_GPU_PREFERENCE_FILE);
+        if legacy.exists() {
+            fs::remove_file(&legacy).ok();
+            tracing::info!("[settings] migrated {:?} into {:?}", legacy, store.path);
+        }
+
+        Ok(store)
+    }
+
+    fn from_legacy_files(config_dir: &Path) -> Settings {
+        let mut settings = Settings::default();
+        if let Ok(preference) = fs::read_to_string(config_dir.join(LEGACY_GPU_PREFERENCE_FILE)) {
+            let preference = preference.trim();
+            if !preference.is_empty() {
+                settings.gpu.preference = preference.to_string();
+            }
+        }
+        settings
+    }
+
+    fn write(&self, settings: &Settings) -> anyhow::Result<()> {
+        let json =
+            serde_json::to_string_pretty(settings).context("Failed to serialize settings")?;
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, json).context("Failed to write settings")?;
+        fs::rename(&tmp, &self.path).context("Failed to replace settings file")
+    }
+
+    pub async fn get(&self) -> Settings {
+        self.settings.read().await.clone()
+    }
+
+    /// Replace all settings and persist them
+    pub async fn set(&self, mut settings: Settings) -> anyhow::Result<Settings> {
+        settings.version = SETTINGS_VERSION;
+        let mut current = self.settings.write().await;
+        self.write(&settings)?;
+        *current = settings.clone();
+        Ok(settings)
+    }
+
+    /// Apply `f` to the current settings and persist the result
+    pub async fn update(&self, f: impl FnOnce(&mut Settings)) -> anyhow::Result<Settings> {
+        let mut settings = self.get().await;
+        f(&mut settings);
+        self.set(settings).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_load_migrates_legacy_gpu_preference() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(LEGACY_GPU_PREFERENCE_FILE), "cpu\n").unwrap();
+
+        let store = SettingsStore::load(dir.path()).unwrap();
+        let settings = store.get().await;
+
+        assert_eq!(settings.gpu.preference, "cpu");
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert!(dir.path().join(SETTINGS_FILE).exists());
+        assert!(!dir.path().join(LEGACY_GPU_PREFERENCE_FILE).exists());
+    }
+
+    #[tokio::test]
+    async fn test_update_persists_and_fills_missing_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(SETTINGS_FILE),
+            r#"{"render":{"method":"lama"}}"#,
+        )
+        .unwrap();
+
+        let store = SettingsStore::load(dir.path()).unwrap();
+        let settings = store.get().await;
+        assert_eq!(settings.render.method, "lama");
+        assert_eq!(settings.render.default_font, "Arial");
+        assert_eq!(settings.inpaint.padding, 50);
+        assert_eq!(settings.export, ExportOptions::default());
+
+        store
+            .update(|s| s.ocr.engine = Some("manga-ocr".to_string()))
+            .await
+            .unwrap();
+        let reloaded = SettingsStore::load(dir.path()).unwrap().get().await;
+        assert_eq!(reloaded.ocr.engine.as_deref(), Some("manga-ocr"));
+        assert_eq!(reloaded.render.method, "lama");
+    }
+
+    #[test]
+    fn test_bundle_round_trip_keeps_local_secrets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("team.json");
+
+        let mut shared = Settings::default();
+        shared.render.default_font = "Wild Words".to_string();
+        shared.translation.deepl_api_key = Some("shared-key".to_string());
+        let frontend = serde_json::json!({ "theme": "dark" });
+        SettingsBundle::new(&shared, false, Some(frontend.clone()))
+            .write(&path)
+            .unwrap();
+
+        let bundle = SettingsBundle::read(&path).unwrap();
+        assert_eq!(bundle.frontend, Some(frontend));
+
+        let mut current = Settings::default();
+        current.translation.deepl_api_key = Some("local-key".to_string());
+        let merged = bundle.merge_into(&current).unwrap();
+        assert_eq!(merged.render.default_font, "Wild Words");
+        assert_eq!(
+            merged.translation.deepl_api_key.as_deref(),
+            Some("local-key")
+        );
+
+        fs::write(
+            &path,
+            r#"{"format":"other","appVersion":"1","settings":{}}"#,
+        )
+        .unwrap();
+        assert!(SettingsBundle::read(&path).is_err());
+    }
+
+    #[test]
+    fn test_migrate_drops_quality_of_lossless_formats() {
+        let value = serde_json::json!({
+            "version": 1,
+            "export": { "format": "webp", "quality": 90 }
+        });
+        let settings: Settings = serde_json::from_value(migrate(value).unwrap()).unwrap();
+        assert_eq!(settings.export.quality, None);
+
+        let value = serde_json::json!({
+            "version": 1,
+            "export": { "format": "jpeg", "quality": 80 }
+        });
+        let settings: Settings = serde_json::from_value(migrate(value).unwrap()).unwrap();
+        assert_eq!(settings.export.quality, Some(80));
+    }
+
+    #[test]
+    fn test_migrate_rejects_newer_version() {
+        let value = serde_json::json!({ "version": SETTINGS_VERSION + 1 });
+        assert!(migrate(value).is_err());
+    }
+
+    #[test]
+    fn test_translator_requires_deepl_key() {
+        let mut translation = TranslationSettings {
+            provider: "deepl-pro".to_string(),
+            ..TranslationSettings::default()
+        };
+        assert!(translation.translator().is_none());
+
+        translation.deepl_api_key = Some("key".to_string());
+        assert!(matches!(
+            translation.translator(),
+            Some(TranslatorConfig::Deepl { use_pro: true, .. })
+        ));
+
+        translation.provider = "plugin:echo".to_string();
+        assert!(matches!(
+            translation.translator(),
+            Some(TranslatorConfig::Plugin { ref id, .. }) if id == "echo"
+        ));
+    }
+}
@@ -0,0 +1,654 @@
+// Model stages shared by the Tauri commands, the page pipeline and the headless CLI
+// Kept free of Tauri so `koharu-cli` builds without the webview stack
+
+use anyhow::{Context, anyhow};
+use image::{DynamicImage, GenericImageView, GrayImage};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::error::{ErrorCode, coded};
+use crate::ocr_eval::{self, OcrEvaluation};
+use crate::ocr_pipeline::{MANGA_OCR_KEY, OcrPipeline};
+use crate::state::AppState;
+
+#[derive(Serialize)]
+pub(crate) struct OcrRunResult {
+    pub texts: Vec<String>,
+    pub engine: String,
+    pub region_count: usize,
+}
+
+async fn execute_ocr_pipeline(
+    pipeline: Arc<dyn OcrPipeline + Send + Sync>,
+    key: &str,
+    image: &DynamicImage,
+    payload_bytes: usize,
+) -> anyhow::Result<OcrRunResult> {
+    let detect_start = Instant::now();
+    let regions = pipeline.detect_text_regions(image).await?;
+    let detect_elapsed = detect_start.elapsed();
+    tracing::info!(
+        "[ocr:{}] detect_text_regions took {}ms ({} region(s), payload={} bytes)",
+        key,
+        detect_elapsed.as_millis(),
+        regions.len(),
+        payload_bytes
+    );
+
+    let recognize_start = Instant::now();
+    let recognized = pipeline.recognize_text(image, &regions).await?;
+    let recognize_elapsed = recognize_start.elapsed();
+    tracing::info!(
+        "[ocr:{}] recognize_text took {}ms",
+        key,
+        recognize_elapsed.as_millis()
+    );
+
+    Ok(OcrRunResult {
+        texts: recognized,
+        engine: key.to_string(),
+        region_count: regions.len(),
+    })
+}
+
+pub(crate) async fn run_ocr_with_pipelines(
+    state: &AppState,
+    active_key: &str,
+    image: &DynamicImage,
+    payload_bytes: usize,
+) -> anyhow::Result<OcrRunResult> {
+    let pipeline = {
+        let guard = state.ocr_pipelines.read().await;
+        guard.get(active_key).cloned()
+    };
+
+    let pipeline = match pipeline {
+        Some(p) => p,
+        None => {
+            let available: Vec<String> = {
+                let guard = state.ocr_pipelines.read().await;
+                guard.keys().cloned().collect()
+            };
+            return Err(coded(
+                ErrorCode::OcrEngineUnavailable,
+                format!(
+                    "OCR pipeline '{}' not found. Available engines: {:?}",
+                    active_key, available
+                ),
+            ));
+        }
+    };
+
+    match execute_ocr_pipeline(pipeline, active_key, image, payload_bytes).await {
+        Ok(result) => Ok(result),
+        Err(err) => {
+            tracing::warn!("OCR pipeline '{}' failed: {}", active_key, err);
+
+            if active_key != MANGA_OCR_KEY {
+                if let Some(fallback) = {
+                    let guard = state.ocr_pipelines.read().await;
+                    guard.get(MANGA_OCR_KEY).cloned()
+                } {
+                    tracing::warn!("Falling back to '{}' pipeline", MANGA_OCR_KEY);
+                    execute_ocr_pipeline(fallback, MANGA_OCR_KEY, image, payload_bytes).await
+                } else {
+                    Err(err)
+                }
+            } else {
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Crop an image to a bounding box, clamping the box to the image bounds
+pub(crate) fn crop_to_bbox(image: &DynamicImage, bbox: &BBox) -> anyhow::Result<DynamicImage> {
+    let (image_width, image_height) = image.dimensions();
+
+    let xmin_f = bbox.xmin.floor().max(0.0);
+    let ymin_f = bbox.ymin.floor().max(0.0);
+    let xmax_f = bbox.xmax.ceil().min(image_width as f32);
+    let ymax_f = bbox.ymax.ceil().min(image_height as f32);
+
+    if xmax_f <= xmin_f || ymax_f <= ymin_f {
+        return Err(anyhow!(
+            "Invalid bounding box after clamping: [{:.2},{:.2}->{:.2},{:.2}]",
+            xmin_f,
+            ymin_f,
+            xmax_f,
+            ymax_f
+        ));
+    }
+
+    let mut width = (xmax_f - xmin_f).ceil().max(1.0) as u32;
+    let mut height = (ymax_f - ymin_f).ceil().max(1.0) as u32;
+
+    let xmin = xmin_f as u32;
+    let ymin = ymin_f as u32;
+
+    if xmin >= image_width || ymin >= image_height {
+        return Err(anyhow!(
+            "Bounding box origin outside image bounds after clamping: ({}, {}) >= ({} ,{})",
+            xmin,
+            ymin,
+            image_width,
+            image_height
+        ));
+    }
+
+    let max_width = image_width - xmin;
+    let max_height = image_height - ymin;
+
+    if max_width == 0 || max_height == 0 {
+        return Err(anyhow!(
+            "Bounding box collapses to zero area after clamping"
+        ));
+    }
+
+    if width > max_width {
+        width = max_width;
+    }
+    if height > max_height {
+        height = max_height;
+    }
+
+    if width == 0 || height == 0 {
+        return Err(anyhow!("Computed crop dimensions are zero after clamping"));
+    }
+
+    Ok(image.crop_imm(xmin, ymin, width, height))
+}
+
+/// Loaded pipeline for an OCR engine key, the active engine when `None`
+pub(crate) async fn ocr_pipeline_for(
+    state: &AppState,
+    engine: Option<String>,
+) -> anyhow::Result<(String, Arc<dyn OcrPipeline + Send + Sync>)> {
+    let engine = match engine {
+        Some(engine) => engine,
+        None => state.active_ocr.read().await.clone(),
+    };
+    let pipeline = state.ocr_pipelines.read().await.get(&engine).cloned();
+    let Some(pipeline) = pipeline else {
+        let available: Vec<String> = state.ocr_pipelines.read().await.keys().cloned().collect();
+        return Err(coded(
+            ErrorCode::OcrEngineUnavailable,
+            format!(
+                "OCR model '{}' not found. Available engines: {:?}",
+                engine, available
+            ),
+        ));
+    };
+    Ok((engine, pipeline))
+}
+
+/// Score an OCR engine (the active one by default) on a folder of image + `.txt` pairs
+pub(crate) async fn evaluate_ocr_dataset(
+    state: &AppState,
+    dataset_dir: &Path,
+    engine: Option<String>,
+) -> anyhow::Result<OcrEvaluation> {
+    let (engine, pipeline) = ocr_pipeline_for(state, engine).await?;
+    let evaluation = ocr_eval::evaluate(pipeline, &engine, dataset_dir).await?;
+    *state.last_ocr_evaluation.lock().await = Some(evaluation.clone());
+    Ok(evaluation)
+}
+
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct BBox {
+    pub xmin: f32,
+    pub ymin: f32,
+    pub xmax: f32,
+    pub ymax: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InpaintConfig {
+    pub padding: i32,        // Context padding (15-100px)
+    pub target_size: u32,    // Inference resolution (256/384/512/768/1024)
+    pub mask_threshold: u8,  // Binary threshold (0-50)
+    pub mask_erosion: u32,   // Erosion radius (0-10px)
+    pub mask_dilation: u32,  // Optional dilation before erosion (0-5px)
+    pub feather_radius: u32, // Alpha compositing feather (used by frontend)
+    pub debug_mode: bool,    // Export triptychs
+}
+
+impl Default for InpaintConfig {
+    fn default() -> Self {
+        InpaintConfig {
+            padding: 50,
+            target_size: 512,
+            mask_threshold: 30,
+            mask_erosion: 3,
+            mask_dilation: 0,
+            feather_radius: 5,
+            debug_mode: false,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InpaintedRegion {
+    pub image: Vec<u8>,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub mask: Vec<u8>,
+    pub mask_width: u32,
+    pub mask_height: u32,
+    pub padded_bbox: BBox,
+}
+
+pub(crate) async fn run_inpainting_pipeline(
+    debug_dir: Option<&Path>,
+    state: &AppState,
+    full_image: &DynamicImage,
+    full_mask: &GrayImage,
+    bbox: &BBox,
+    cfg: &InpaintConfig,
+) -> anyhow::Result<InpaintedRegion> {
+    let (image_width, image_height) = full_image.dimensions();
+    let mask_width = full_mask.width();
+    let mask_height = full_mask.height();
+
+    tracing::info!(
+        "inpaint pipeline start: config={:?}, image={}x{}, mask={}x{}",
+        cfg,
+        image_width,
+        image_height,
+        mask_width,
+        mask_height
+    );
+
+    let padded_min_x = (bbox.xmin - cfg.padding as f32)
+        .floor()
+        .clamp(0.0, image_width.saturating_sub(1) as f32);
+    let padded_min_y = (bbox.ymin - cfg.padding as f32)
+        .floor()
+        .clamp(0.0, image_height.saturating_sub(1) as f32);
+    let padded_max_x = (bbox.xmax + cfg.padding as f32)
+        .ceil()
+        .clamp(0.0, image_width as f32);
+    let padded_max_y = (bbox.ymax + cfg.padding as f32)
+        .ceil()
+        .clamp(0.0, image_height as f32);
+
+    let crop_x = padded_min_x as u32;
+    let crop_y = padded_min_y as u32;
+    let crop_x2 = padded_max_x as u32;
+    let crop_y2 = padded_max_y as u32;
+
+    if !(crop_x2 > crop_x && crop_y2 > crop_y) {
+        anyhow::bail!(
+            "Invalid padded bbox after clamping: [{},{} -> {},{}]",
+            crop_x,
+            crop_y,
+            crop_x2,
+            crop_y2
+        );
+    }
+
+    let crop_width = crop_x2 - crop_x;
+    let crop_height = crop_y2 - crop_y;
+
+    let padded_bbox = BBox {
+        xmin: crop_x as f32,
+        ymin: crop_y as f32,
+        xmax: crop_x2 as f32,
+        ymax: crop_y2 as f32,
+    };
+
+    tracing::debug!(
+        "Padded bbox: [{},{} -> {},{}] = {}x{}px",
+        padded_bbox.xmin,
+        padded_bbox.ymin,
+        padded_bbox.xmax,
+        padded_bbox.ymax,
+        crop_width,
+        crop_height
+    );
+
+    let cropped_image = full_image.crop_imm(crop_x, crop_y, crop_width, crop_height);
+
+    fn extract_and_resize_mask(
+        full_mask: &GrayImage,
+        bbox: &BBox,
+        orig_width: u32,
+        orig_height: u32,
+        target_width: u32,
+        target_height: u32,
+        config: &InpaintConfig,
+    ) -> anyhow::Result<GrayImage> {
+        let mask_width = full_mask.width();
+        let mask_height = full_mask.height();
+        let scale_x = mask_width as f32 / orig_width as f32;
+        let scale_y = mask_height as f32 / orig_height as f32;
+
+        let mask_xmin = (bbox.xmin * scale_x).floor().max(0.0) as u32;
+        let mask_ymin = (bbox.ymin * scale_y).floor().max(0.0) as u32;
+        let mask_xmax = (bbox.xmax * scale_x).ceil().min(mask_width as f32) as u32;
+        let mask_ymax = (bbox.ymax * scale_y).ceil().min(mask_height as f32) as u32;
+
+        let mask_crop_width = mask_xmax.saturating_sub(mask_xmin);
+        let mask_crop_height = mask_ymax.saturating_sub(mask_ymin);
+
+        tracing::debug!(
+            "Mask extraction: scale=({:.3},{:.3}), mask_bbox=[{},{} -> {},{}], crop={}x{}",
+            scale_x,
+            scale_y,
+            mask_xmin,
+            mask_ymin,
+            mask_xmax,
+            mask_ymax,
+            mask_crop_width,
+            mask_crop_height
+        );
+
+        if mask_crop_width == 0 || mask_crop_height == 0 {
+            return Err(anyhow!(
+                "Invalid mask crop dimensions: {}x{}",
+                mask_crop_width,
+                mask_crop_height
+            ));
+        }
+
+        let mut cropped_mask = GrayImage::new(mask_crop_width, mask_crop_height);
+        for y in 0..mask_crop_height {
+            for x in 0..mask_crop_width {
+                let px = (mask_xmin + x).min(mask_width - 1);
+                let py = (mask_ymin + y).min(mask_height - 1);
+                let pixel = full_mask.get_pixel(px, py);
+                cropped_mask.put_pixel(x, y, *pixel);
+            }
+        }
+
+        let mut thresholded = cropped_mask.clone();
+        for pixel in thresholded.pixels_mut() {
+            if pixel[0] < config.mask_threshold {
+                pixel[0] = 0;
+            }
+        }
+
+        let mut morphed = thresholded;
+        if config.mask_dilation > 0 {
+            morphed = dilate_mask(&morphed, config.mask_dilation);
+            tracing::debug!("Applied {}px mask dilation", config.mask_dilation);
+        }
+
+        let mut resized_mask = image::imageops::resize(
+            &morphed,
+            target_width,
+            target_height,
+            image::imageops::FilterType::Nearest,
+        );
+
+        if config.mask_erosion > 0 {
+            resized_mask = erode_mask(&resized_mask, config.mask_erosion);
+            tracing::debug!("Applied {}px mask erosion", config.mask_erosion);
+        }
+
+        tracing::debug!(
+            "Mask resized: {}x{} -> {}x{} (threshold={}, erosion={}px, dilation={}px)",
+            mask_crop_width,
+            mask_crop_height,
+            target_width,
+            target_height,
+            config.mask_threshold,
+            config.mask_erosion,
+            config.mask_dilation
+        );
+
+        Ok(resized_mask)
+    }
+
+    fn dilate_mask(mask: &GrayImage, kernel_size: u32) -> GrayImage {
+        use imageproc::distance_transform::Norm;
+        use imageproc::morphology::dilate;
+
+        dilate(mask, Norm::LInf, kernel_size as u8)
+    }
+
+    fn erode_mask(mask: &GrayImage, kernel_size: u32) -> GrayImage {
+        use imageproc::distance_transform::Norm;
+        use imageproc::morphology::dilate_mut;
+
+        let mut result = mask.clone();
+
+        for pixel in result.pixels_mut() {
+            pixel[0] = 255 - pixel[0];
+        }
+
+        dilate_mut(&mut result, Norm::LInf, kernel_size as u8);
+
+        for pixel in result.pixels_mut() {
+            pixel[0] = 255 - pixel[0];
+        }
+
+        result
+    }
+
+    let cropped_mask = extract_and_resize_mask(
+        full_mask,
+        &padded_bbox,
+        image_width,
+        image_height,
+        crop_width,
+        crop_height,
+        cfg,
+    )?;
+
+    if let Some(debug_dir) = debug_dir {
+        save_debug_triptych(debug_dir, &cropped_image, &cropped_mask, bbox, &padded_bbox)?;
+    }
+
+    tracing::info!(
+        "Running LaMa inference with target_size={}",
+        cfg.target_size
+    );
+
+    let mask_dynamic = image::DynamicImage::ImageLuma8(cropped_mask.clone());
+
+    let inpainted_crop = state
+        .lama
+        .lock()
+        .await
+        .inference_with_size(&cropped_image, &mask_dynamic, cfg.target_size)
+        .context("Failed to perform inpainting")?;
+
+    tracing::info!("LaMa inference completed successfully");
+
+    if let Some(debug_dir) = debug_dir {
+        save_debug_output(
+            debug_dir,
+            &cropped_image,
+            &cropped_mask,
+            &inpainted_crop,
+            bbox,
+        )?;
+    }
+
+    let mut output_rgba = inpainted_crop.to_rgba8();
+    let actual_width = output_rgba.width();
+    let actual_height = output_rgba.height();
+
+    tracing::debug!(
+        "[inpaint] raw LaMa output dimensions: {}x{} (target {}x{})",
+        actual_width,
+        actual_height,
+        crop_width,
+        crop_height
+    );
+
+    if actual_width != crop_width || actual_height != crop_height {
+        tracing::warn!(
+            "[inpaint] correcting LaMa output from {}x{} to {}x{}",
+            actual_width,
+            actual_height,
+            crop_width,
+            crop_height
+        );
+
+        let resized = image::DynamicImage::ImageRgba8(output_rgba)
+            .resize_exact(
+                crop_width,
+                crop_height,
+                image::imageops::FilterType::CatmullRom,
+            )
+            .to_rgba8();
+
+        tracing::info!(
+            "[inpaint] resampled buffer: {} bytes for {}x{} region",
+            resized.len(),
+            crop_width,
+            crop_height
+        );
+
+        output_rgba = resized;
+    }
+
+    let mut output_pixels = output_rgba.into_raw();
+    let expected_pixel_bytes = (crop_width as usize)
+        .saturating_mul(crop_height as usize)
+        .saturating_mul(4);
+
+    if output_pixels.len() != expected_pixel_bytes {
+        tracing::error!(
+            "[inpaint] pixel buffer mismatch after correction: expected={} actual={} bbox=[{},{} -> {},{}]",
+            expected_pixel_bytes,
+            output_pixels.len(),
+            padded_bbox.xmin,
+            padded_bbox.ymin,
+            padded_bbox.xmax,
+            padded_bbox.ymax
+        );
+
+        if output_pixels.len() < expected_pixel_bytes {
+            tracing::warn!(
+                "[inpaint] padding output buffer from {} to {} bytes",
+                output_pixels.len(),
+                expected_pixel_bytes
+            );
+            output_pixels.resize(expected_pixel_bytes, 0);
+        } else {
+            tracing::warn!(
+                "[inpaint] truncating output buffer from {} to {} bytes",
+                output_pixels.len(),
+                expected_pixel_bytes
+            );
+            output_pixels.truncate(expected_pixel_bytes);
+        }
+    } else {
+        tracing::debug!(
+            "[inpaint] pixel buffer ok: {} bytes for {}x{} region",
+            output_pixels.len(),
+            crop_width,
+            crop_height
+        );
+    }
+    let mask_bytes = cropped_mask.into_raw();
+
+    Ok(InpaintedRegion {
+        image: output_pixels,
+        x: crop_x,
+        y: crop_y,
+        width: crop_width,
+        height: crop_height,
+        mask: mask_bytes,
+        mask_width: crop_width,
+        mask_height: crop_height,
+        padded_bbox,
+    })
+}
+
+/// Save debug triptych: original crop, mask, and red overlay
+fn save_debug_triptych(
+    debug_dir: &Path,
+    crop: &image::DynamicImage,
+    mask: &image::GrayImage,
+    bbox: &BBox,
+    _padded_bbox: &BBox,
+) -> anyhow::Result<()> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+
+    let bbox_str = format!("{:.0}_{:.0}", bbox.xmin, bbox.ymin);
+
+    // Save crop
+    crop.save(debug_dir.join(format!("{}_{}_crop.png", timestamp, bbox_str)))?;
+
+    // Save mask
+    image::DynamicImage::ImageLuma8(mask.clone())
+        .save(debug_dir.join(format!("{}_{}_mask.png", timestamp, bbox_str)))?;
+
+    // Create red overlay
+    let mut overlay = crop.to_rgb8();
+    for y in 0..mask.height() {
+        for x in 0..mask.width() {
+            if mask.get_pixel(x, y)[0] > 128 {
+                // Red overlay on white mask regions
+                overlay.put_pixel(x, y, image::Rgb([255, 0, 0]));
+            }
+        }
+    }
+    image::DynamicImage::ImageRgb8(overlay)
+        .save(debug_dir.join(format!("{}_{}_overlay.png", timestamp, bbox_str)))?;
+
+    tracing::info!("Saved debug triptych to {:?}", debug_dir);
+    Ok(())
+}
+
+/// Save debug output after inpainting
+fn save_debug_output(
+    debug_dir: &Path,
+    crop: &image::DynamicImage,
+    mask: &image::GrayImage,
+    output: &image::DynamicImage,
+    bbox: &BBox,
+) -> anyhow::Result<()> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+
+    let bbox_str = format!("{:.0}_{:.0}", bbox.xmin, bbox.ymin);
+
+    // Create side-by-side triptych
+    let w = crop.width();
+    let h = crop.height();
+    let mut triptych = image::RgbImage::new(w * 3, h);
+
+    // Panel 1: Original crop
+    let crop_rgb = crop.to_rgb8();
+    for y in 0..h {
+        for x in 0..w {
+            triptych.put_pixel(x, y, *crop_rgb.get_pixel(x, y));
+        }
+    }
+
+    // Panel 2: Mask (white = hole to fill)
+    for y in 0..h {
+        for x in 0..w {
+            let v = mask.get_pixel(x, y)[0];
+            triptych.put_pixel(w + x, y, image::Rgb([v, v, v]));
+        }
+    }
+
+    // Panel 3: LaMa output
+    let output_rgb = output.to_rgb8();
+    for y in 0..h {
+        for x in 0..w {
+            triptych.put_pixel(w * 2 + x, y, *output_rgb.get_pixel(x, y));
+        }
+    }
+
+    image::DynamicImage::ImageRgb8(triptych)
+        .save(debug_dir.join(format!("{}_{}_triptych.png", timestamp, bbox_str)))?;
+
+    tracing::info!("Saved inpaint triptych to {:?}", debug_dir);
+    Ok(())
+}
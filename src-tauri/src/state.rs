@@ -1,13 +1,23 @@
+use crate::export_scope::ExportScope;
+use crate::history::PageHistories;
+use crate::image_io::ExportOptions;
+use crate::jobs::JobManager;
+use crate::ocr_eval::OcrEvaluation;
 use crate::ocr_pipeline::OcrPipeline;
+use crate::page_store::PageStore;
+use crate::progress::TaskRegistry;
+use crate::project::OpenProject;
+use crate::scripting::ScriptHooks;
+use crate::session::SessionStore;
+use crate::settings::{Settings, SettingsStore};
 use comic_text_detector::ComicTextDetector;
-use image::{DynamicImage, GrayImage};
 use lama::Lama;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::{Mutex, RwLock};
 
-#[derive(Clone, Serialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct GpuInitResult {
     pub requested_provider: String,
     pub available_providers: Vec<String>,
@@ -25,7 +35,37 @@ pub struct AppState {
     pub gpu_init_result: Mutex<GpuInitResult>,
     pub ocr_pipelines: RwLock<HashMap<String, Arc<dyn OcrPipeline + Send + Sync>>>,
     pub active_ocr: RwLock<String>,
-    pub inpaint_image_cache: RwLock<Option<Arc<DynamicImage>>>,
-    pub inpaint_mask_cache: RwLock<Option<Arc<GrayImage>>>,
-    pub ocr_image_cache: RwLock<Option<Arc<DynamicImage>>>,
+    pub pages: Mutex<PageStore>,
+    pub jobs: JobManager,
+    pub tasks: TaskRegistry,
+    pub settings: SettingsStore,
+    pub export_scope: ExportScope,
+    pub project: RwLock<Option<OpenProject>>,
+    pub scripts: RwLock<ScriptHooks>,
+    pub session: SessionStore,
+    pub history: Mutex<PageHistories>,
+    pub last_ocr_evaluation: Mutex<Option<OcrEvaluation>>, // For export_accuracy_report
+}
+
+impl AppState {
+    /// Saved settings with the open project's overrides applied
+    pub async fn effective_settings(&self) -> Settings {
+        let mut settings = self.settings.get().await;
+        if let Some(open) = self.project.read().await.as_ref() {
+            open.project.apply(&mut settings);
+        }
+        settings
+    }
+
+    /// Export options sent by the frontend, with the open project's tags taking precedence
+    pub async fn project_export(&self, mut export: ExportOptions) -> ExportOptions {
+        let project = self.project.read().await;
+        if let Some(metadata) = project
+            .as_ref()
+            .and_then(|open| open.project.metadata.as_ref())
+        {
+            metadata.apply(&mut export.metadata);
+        }
+        export
+    }
 }
@@ -9,7 +9,7 @@ use glyph_brush_layout::{FontId, GlyphPositioner, Layout, SectionGeometry, Secti
 use image::{DynamicImage, Rgba, RgbaImage};
 use imageproc::drawing::{draw_filled_rect_mut, draw_text_mut};
 use imageproc::rect::Rect as IpRect;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 // Font stack for Unicode fallback support
 #[derive(Clone)]
@@ -148,7 +148,7 @@ impl FontStack {
 }
 
 // RGB color type matching frontend
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RgbColor {
     pub r: u8,
     pub g: u8,
@@ -156,7 +156,7 @@ pub struct RgbColor {
 }
 
 // Text block structure matching frontend TextBlock type
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TextBlock {
     pub xmin: f32,
@@ -178,11 +178,13 @@ pub struct TextBlock {
     pub appearance: Option<AppearanceData>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppearanceData {
+    pub source_text_color: Option<RgbColor>,
     pub source_outline_color: Option<RgbColor>,
     pub outline_width_px: Option<f32>,
+    pub source_background_color: Option<RgbColor>,
 }
 
 /// Load a font by family name from system fonts, with fallback to embedded font
@@ -430,31 +432,7 @@ fn draw_text_block(
     let center_x = (block.xmin + block.xmax) / 2.0;
     let center_y = (block.ymin + block.ymax) / 2.0;
 
-    // Word wrap logic (matches JS)
-    let words: Vec<&str> = text.split(' ').collect();
-    let mut lines: Vec<String> = Vec::new();
-    let mut current_line = String::new();
-
-    for word in words {
-        let test_line = if current_line.is_empty() {
-            word.to_string()
-        } else {
-            format!("{} {}", current_line, word)
-        };
-
-        let test_width =
-            measure_text_width_mixed_fonts(&test_line, font_stack, scale, letter_spacing);
-
-        if test_width > max_width && !current_line.is_empty() {
-            lines.push(current_line.clone());
-            current_line = word.to_string();
-        } else {
-            current_line = test_line;
-        }
-    }
-    if !current_line.is_empty() {
-        lines.push(current_line);
-    }
+    let lines = wrap_text_lines(text, font_stack, scale, letter_spacing, max_width);
 
     // Calculate vertical positioning (matches JS)
     let line_height = font_size * line_height_multiplier;
@@ -527,6 +505,88 @@ fn draw_text_block(
     Ok(())
 }
 
+/// Word wrap logic (matches JS)
+fn wrap_text_lines(
+    text: &str,
+    font_stack: &FontStack,
+    scale: PxScale,
+    letter_spacing: f32,
+    max_width: f32,
+) -> Vec<String> {
+    let words: Vec<&str> = text.split(' ').collect();
+    let mut lines: Vec<String> = Vec::new();
+    let mut current_line = String::new();
+
+    for word in words {
+        let test_line = if current_line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", current_line, word)
+        };
+
+        let test_width =
+            measure_text_width_mixed_fonts(&test_line, font_stack, scale, letter_spacing);
+
+        if test_width > max_width && !current_line.is_empty() {
+            lines.push(current_line.clone());
+            current_line = word.to_string();
+        } else {
+            current_line = test_line;
+        }
+    }
+    if !current_line.is_empty() {
+        lines.push(current_line);
+    }
+
+    lines
+}
+
+/// Find the largest font size whose wrapped text fits inside the given box
+/// Uses the same 90% padding and wrapping rules as draw_text_block
+pub fn fit_font_size(
+    text: &str,
+    font_stack: &FontStack,
+    box_width: f32,
+    box_height: f32,
+    line_height_multiplier: f32,
+    letter_spacing: f32,
+) -> f32 {
+    const MIN_FONT_SIZE: f32 = 8.0;
+    const MAX_FONT_SIZE: f32 = 96.0;
+
+    let max_width = box_width * 0.9;
+    let max_height = box_height * 0.9;
+
+    let fits = |size: f32| {
+        let scale = PxScale::from(size);
+        let lines = wrap_text_lines(text, font_stack, scale, letter_spacing, max_width);
+        let widest = lines
+            .iter()
+            .map(|line| measure_text_width_mixed_fonts(line, font_stack, scale, letter_spacing))
+            .fold(0.0f32, f32::max);
+        let total_height = lines.len() as f32 * size * line_height_multiplier;
+        widest <= max_width && total_height <= max_height
+    };
+
+    if !fits(MIN_FONT_SIZE) {
+        return MIN_FONT_SIZE;
+    }
+
+    // Binary search to 0.5px precision
+    let mut low = MIN_FONT_SIZE;
+    let mut high = MAX_FONT_SIZE;
+    while high - low > 0.5 {
+        let mid = (low + high) / 2.0;
+        if fits(mid) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    low.floor()
+}
+
 /// Measure text width without letter spacing (using glyph_brush_layout for proper kerning)
 fn measure_text_width(text: &str, font: &FontArc, scale: PxScale) -> f32 {
     if text.is_empty() {
@@ -650,7 +710,7 @@ fn draw_text_with_outline(
 }
 
 /// Measure text width with mixed fonts and optional letter spacing
-fn measure_text_width_mixed_fonts(
+pub(crate) fn measure_text_width_mixed_fonts(
     text: &str,
     font_stack: &FontStack,
     scale: PxScale,
@@ -672,7 +732,7 @@ fn measure_text_width_mixed_fonts(
 }
 
 /// Draw text with mixed fonts (handles Unicode characters properly)
-fn draw_text_with_mixed_fonts(
+pub(crate) fn draw_text_with_mixed_fonts(
     img: &mut RgbaImage,
     center_x: f32,
     y: f32,
@@ -0,0 +1,479 @@
+// Translation provider clients shared by the Tauri commands and the backend pipeline
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::error::{ErrorCode, coded};
+use crate::plugins;
+
+// DeepL Translation API types
+#[derive(Debug, Serialize, Deserialize)]
+struct DeepLRequest {
+    text: Vec<String>,
+    target_lang: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_lang: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DeepLTranslation {
+    detected_source_language: Option<String>,
+    text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DeepLResponse {
+    translations: Vec<DeepLTranslation>,
+}
+
+// Ollama Translation API types
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaChatMessage {
+    role: String,
+    content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaChatRequest {
+    model: String,
+    messages: Vec<OllamaChatMessage>,
+    stream: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaChatResponse {
+    message: OllamaChatMessage,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GlossaryEntry {
+    pub source: String,
+    pub target: String,
+    #[serde(default)]
+    pub note: Option<String>, // Context for the translator, e.g. "main character, male"
+}
+
+/// Tone and glossary applied on top of whichever provider is selected
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TranslationStyle {
+    pub tone: Option<String>, // Free text for LLM providers, e.g. "casual, keep honorifics"
+    pub glossary: Vec<GlossaryEntry>,
+}
+
+impl TranslationStyle {
+    /// System prompt for LLM providers: the user's prompt followed by tone and glossary rules
+    pub fn system_prompt(&self, base: Option<&str>) -> Option<String> {
+        let mut parts: Vec<String> = base
+            .filter(|prompt| !prompt.trim().is_empty())
+            .map(|prompt| vec![prompt.to_string()])
+            .unwrap_or_default();
+
+        if let Some(tone) = self.tone.as_deref().filter(|t| !t.trim().is_empty()) {
+            parts.push(format!("Use this tone: {}.", tone.trim()));
+        }
+        if !self.glossary.is_empty() {
+            let mut rules = "Always translate these terms as given:".to_string();
+            for entry in &self.glossary {
+                rules.push_str(&format!("\n- {} => {}", entry.source, entry.target));
+                if let Some(note) = entry.note.as_deref().filter(|n| !n.trim().is_empty()) {
+                    rules.push_str(&format!(" ({})", note.trim()));
+                }
+            }
+            parts.push(rules);
+        }
+
+        (!parts.is_empty()).then(|| parts.join("\n\n"))
+    }
+
+    /// Replace glossary terms in the source text before sending it to providers that can't
+    /// take instructions, preferring the longest match at each position
+    pub fn substitute(&self, text: &str) -> String {
+        if self.glossary.is_empty() {
+            return text.to_string();
+        }
+        let mut entries: Vec<&GlossaryEntry> = self
+            .glossary
+            .iter()
+            .filter(|entry| !entry.source.is_empty())
+            .collect();
+        entries.sort_by(|a, b| b.source.len().cmp(&a.source.len()));
+
+        let mut output = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            match entries.iter().find(|entry| rest.starts_with(&entry.source)) {
+                Some(entry) => {
+                    output.push_str(&entry.target);
+                    rest = &rest[entry.source.len()..];
+                }
+                None => {
+                    output.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+        output
+    }
+}
+
+/// Translation provider selection used by backend-driven pipelines
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "provider", rename_all = "camelCase")]
+pub enum TranslatorConfig {
+    #[serde(rename_all = "camelCase")]
+    Deepl {
+        // Empty once forgotten for storage; `with_saved_key` fills it back in
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        api_key: String,
+        #[serde(default)]
+        use_pro: bool,
+        source_lang: Option<String>,
+        target_lang: Option<String>,
+        #[serde(default)]
+        style: TranslationStyle,
+    },
+    #[serde(rename_all = "camelCase")]
+    Ollama {
+        model: String,
+        system_prompt: Option<String>,
+        #[serde(default)]
+        style: TranslationStyle,
+    },
+    /// External provider from the plugins directory, see `plugins`
+    #[serde(rename_all = "camelCase")]
+    Plugin {
+        id: String,
+        source_lang: Option<String>,
+        target_lang: Option<String>,
+        #[serde(default)]
+        style: TranslationStyle,
+    },
+}
+
+// Hand-written so the DeepL key never reaches logs or crash reports
+impl fmt::Debug for TranslatorConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranslatorConfig::Deepl {
+                api_key,
+                use_pro,
+                source_lang,
+                target_lang,
+                style,
+            } => f
+                .debug_struct("Deepl")
+                .field(
+                    "api_key",
+                    &if api_key.is_empty() { "" } else { "<redacted>" },
+                )
+                .field("use_pro", use_pro)
+                .field("source_lang", source_lang)
+                .field("target_lang", target_lang)
+                .field("style", style)
+                .finish(),
+            TranslatorConfig::Ollama {
+                model,
+                system_prompt,
+                style,
+            } => f
+                .debug_struct("Ollama")
+                .field("model", model)
+                .field("system_prompt", system_prompt)
+                .field("style", style)
+                .finish(),
+            TranslatorConfig::Plugin {
+                id,
+                source_lang,
+                target_lang,
+                style,
+            } => f
+                .debug_struct("Plugin")
+                .field("id", id)
+                .field("source_lang", source_lang)
+                .field("target_lang", target_lang)
+                .field("style", style)
+                .finish(),
+        }
+    }
+}
+
+impl TranslatorConfig {
+    /// Drop the DeepL key so the config can be stored without it
+    pub fn forget_key(&mut self) {
+        if let TranslatorConfig::Deepl { api_key, .. } = self {
+            api_key.clear();
+        }
+    }
+
+    /// Fill a forgotten DeepL key with the one saved in settings
+    pub fn with_saved_key(mut self, saved: Option<&str>) -> anyhow::Result<Self> {
+        if let TranslatorConfig::Deepl { api_key, .. } = &mut self {
+            if api_key.is_empty() {
+                *api_key = saved
+                    .filter(|key| !key.trim().is_empty())
+                    .ok_or_else(|| coded(ErrorCode::ApiKeyMissing, "No DeepL API key is saved"))?
+                    .to_string();
+            }
+        }
+        Ok(self)
+    }
+
+    /// Language the provider translates into, when it is configured explicitly
+    pub fn target_lang(&self) -> Option<&str> {
+        match self {
+            TranslatorConfig::Deepl { target_lang, .. } => target_lang.as_deref(),
+            TranslatorConfig::Ollama { .. } => None,
+            TranslatorConfig::Plugin { target_lang, .. } => target_lang.as_deref(),
+        }
+    }
+
+    /// Translate a single text with the configured provider
+    pub async fn translate(&self, text: &str) -> anyhow::Result<String> {
+        match self {
+            TranslatorConfig::Deepl {
+                api_key,
+                use_pro,
+                source_lang,
+                target_lang,
+                style,
+            } => {
+                translate_deepl(
+                    api_key,
+                    &style.substitute(text),
+                    *use_pro,
+                    source_lang.as_deref(),
+                    target_lang.as_deref(),
+                )
+                .await
+            }
+            TranslatorConfig::Ollama {
+                model,
+                system_prompt,
+                style,
+            } => {
+                let prompt = style.system_prompt(system_prompt.as_deref());
+                translate_ollama(text, model, prompt.as_deref()).await
+            }
+            TranslatorConfig::Plugin {
+                id,
+                source_lang,
+                target_lang,
+                style,
+            } => {
+                plugins::translate(
+                    id,
+                    &style.substitute(text),
+                    source_lang.as_deref(),
+                    target_lang.as_deref(),
+                )
+                .await
+            }
+        }
+    }
+}
+
+pub async fn translate_deepl(
+    api_key: &str,
+    text: &str,
+    use_pro: bool,
+    source_lang: Option<&str>,
+    target_lang: Option<&str>,
+) -> anyhow::Result<String> {
+    if api_key.trim().is_empty() {
+        return Err(coded(ErrorCode::ApiKeyMissing, "DeepL API key is not set"));
+    }
+
+    let base_url = if use_pro {
+        "https://api.deepl.com"
+    } else {
+        "https://api-free.deepl.com"
+    };
+
+    let url = format!("{}/v2/translate", base_url);
+
+    // Default to EN-US as recommended by DeepL docs
+    let target = target_lang.unwrap_or("EN-US").to_uppercase();
+
+    let request_body = DeepLRequest {
+        text: vec![text.to_string()],
+        target_lang: target,
+        source_lang: source_lang.map(|s| s.to_uppercase()),
+    };
+
+    tracing::debug!(
+        "DeepL request: endpoint={}, use_pro={}, body={:?}",
+        url,
+        use_pro,
+        request_body
+    );
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(&url)
+        .header("Authorization", format!("DeepL-Auth-Key {}", api_key))
+        .header("User-Agent", "Koharu/1.0")
+        .header("Content-Type", "application/json")
+        .json(&request_body)
+        .send()
+        .await
+        .context("Failed to send DeepL API request")?;
+
+    let status = response.status();
+
+    if !status.is_success() {
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+
+        // Handle specific error codes
+        let (code, error_msg) = match status.as_u16() {
+            401 | 403 => (
+                ErrorCode::ApiKeyInvalid,
+                "Invalid API key or insufficient permissions".to_string(),
+            ),
+            429 => (
+                ErrorCode::RateLimited,
+                "Rate limit exceeded. Please wait and try again.".to_string(),
+            ),
+            456 => (
+                ErrorCode::QuotaExceeded,
+                "Quota exceeded. For DeepL Free, you've used your 500,000 character/month limit."
+                    .to_string(),
+            ),
+            _ => (
+                ErrorCode::Unknown,
+                format!("DeepL API error ({}): {}", status.as_u16(), error_text),
+            ),
+        };
+
+        return Err(coded(code, error_msg));
+    }
+
+    let deepl_response: DeepLResponse = response
+        .json()
+        .await
+        .context("Failed to parse DeepL API response")?;
+
+    deepl_response
+        .translations
+        .first()
+        .map(|t| t.text.clone())
+        .ok_or_else(|| anyhow::anyhow!("DeepL returned no translations"))
+}
+
+pub async fn translate_ollama(
+    text: &str,
+    model: &str,
+    system_prompt: Option<&str>,
+) -> anyhow::Result<String> {
+    let url = "http://localhost:11434/api/chat";
+
+    // Build messages array
+    let mut messages = Vec::new();
+
+    // Add system prompt if provided
+    if let Some(prompt) = system_prompt {
+        if !prompt.trim().is_empty() {
+            messages.push(OllamaChatMessage {
+                role: "system".to_string(),
+                content: prompt.to_string(),
+            });
+        }
+    }
+
+    // Add user message with the OCR'd text
+    messages.push(OllamaChatMessage {
+        role: "user".to_string(),
+        content: text.to_string(),
+    });
+
+    let request_body = OllamaChatRequest {
+        model: model.to_string(),
+        messages,
+        stream: false,
+    };
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .json(&request_body)
+        .send()
+        .await
+        .map_err(|err| {
+            coded(
+                ErrorCode::ProviderUnavailable,
+                format!(
+                    "Failed to connect to Ollama. Make sure Ollama is running on http://localhost:11434: {}",
+                    err
+                ),
+            )
+        })?;
+
+    let status = response.status();
+
+    if !status.is_success() {
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        let error_msg = format!("Ollama API error ({}): {}", status.as_u16(), error_text);
+        return Err(anyhow::anyhow!(error_msg));
+    }
+
+    let ollama_response: OllamaChatResponse = response
+        .json()
+        .await
+        .context("Failed to parse Ollama API response")?;
+
+    Ok(ollama_response.message.content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(source: &str, target: &str) -> GlossaryEntry {
+        GlossaryEntry {
+            source: source.to_string(),
+            target: target.to_string(),
+            note: None,
+        }
+    }
+
+    #[test]
+    fn test_substitute_prefers_longest_term() {
+        let style = TranslationStyle {
+            tone: None,
+            glossary: vec![
+                entry("炭治郎", "Tanjiro"),
+                entry("竈門炭治郎", "Tanjiro Kamado"),
+            ],
+        };
+        assert_eq!(
+            style.substitute("竈門炭治郎と炭治郎"),
+            "Tanjiro KamadoとTanjiro"
+        );
+        assert_eq!(style.substitute("こんにちは"), "こんにちは");
+    }
+
+    #[test]
+    fn test_system_prompt_appends_tone_and_glossary() {
+        let style = TranslationStyle {
+            tone: Some("casual".to_string()),
+            glossary: vec![GlossaryEntry {
+                note: Some("male".to_string()),
+                ..entry("炭治郎", "Tanjiro")
+            }],
+        };
+        let prompt = style.system_prompt(Some("Translate to English.")).unwrap();
+        assert!(prompt.starts_with("Translate to English.\n\nUse this tone: casual."));
+        assert!(prompt.ends_with("- 炭治郎 => Tanjiro (male)"));
+
+        assert_eq!(TranslationStyle::default().system_prompt(Some("  ")), None);
+    }
+}
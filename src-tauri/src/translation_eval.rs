@@ -0,0 +1,182 @@
+// Translation quality on the user's own content: each provider translates the source lines of a
+// reference set and is scored against the user's reference translations with BLEU and chrF.
+// A reference set is a JSON array of `{ "source", "reference" }` or a TSV of `source<TAB>reference`.
+
+use anyhow::{Context, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::accuracy::TranslationScore;
+use crate::settings::TranslationSettings;
+use crate::translation::TranslatorConfig;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferencePair {
+    pub source: String,
+    pub reference: String,
+}
+
+/// Source line left out of the scores, with why
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedTranslation {
+    pub source: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderScore {
+    pub provider: String,
+    #[serde(flatten)]
+    pub score: TranslationScore,
+    pub failed: Vec<FailedTranslation>,
+    pub elapsed_ms: u64,
+}
+
+/// Reference pairs from a `.json` array or a tab-separated file; blank TSV lines are skipped
+pub fn load_references(path: &Path) -> anyhow::Result<Vec<ReferencePair>> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let pairs: Vec<ReferencePair> = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid reference set {:?}", path))?
+    } else {
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| match line.split_once('\t') {
+                Some((source, reference)) => Ok(ReferencePair {
+                    source: source.trim().to_string(),
+                    reference: reference.trim().to_string(),
+                }),
+                None => Err(anyhow!(
+                    "{:?} line {}: expected source<TAB>reference",
+                    path,
+                    index + 1
+                )),
+            })
+            .collect::<anyhow::Result<_>>()?
+    };
+    if pairs.is_empty() {
+        return Err(anyhow!("No reference pairs in {:?}", path));
+    }
+    Ok(pairs)
+}
+
+/// Backend translator for `provider` ("deepl-free", "ollama", "plugin:<id>", ...),
+/// reusing the keys, model, and languages from `saved`
+pub fn translator_for(
+    saved: &TranslationSettings,
+    provider: &str,
+) -> anyhow::Result<TranslatorConfig> {
+    let mut settings = saved.clone();
+    settings.provider = provider.to_string();
+    settings.translator().ok_or_else(|| match provider {
+        "deepl-free" | "deepl-pro" => anyhow!("No DeepL API key configured"),
+        _ => anyhow!("Provider '{}' cannot be scored from the backend", provider),
+    })
+}
+
+/// Translate every source with `translator` and score it; failed lines are left out
+pub async fn evaluate(
+    provider: &str,
+    translator: &TranslatorConfig,
+    pairs: &[ReferencePair],
+) -> ProviderScore {
+    let start = Instant::now();
+    let mut samples = Vec::with_capacity(pairs.len());
+    let mut failed = Vec::new();
+    for pair in pairs {
+        match translator.translate(&pair.source).await {
+            Ok(translation) => samples.push((
+                pair.source.clone(),
+                pair.reference.clone(),
+                translation.trim().to_string(),
+            )),
+            Err(err) => failed.push(FailedTranslation {
+                source: pair.source.clone(),
+                reason: format!("{:#}", err),
+            }),
+        }
+    }
+
+    let score = TranslationScore::calculate(samples);
+    tracing::info!(
+        "[translation-eval] '{}': BLEU {:.1}, chrF {:.1} over {} line(s), {} failed",
+        provider,
+        score.bleu,
+        score.chrf,
+        score.samples.len(),
+        failed.len()
+    );
+    ProviderScore {
+        provider: provider.to_string(),
+        score,
+        failed,
+        elapsed_ms: start.elapsed().as_millis() as u64,
+    }
+}
+
+/// Score each provider in turn on the same references; an unusable provider fails the whole run
+pub async fn compare(
+    saved: &TranslationSettings,
+    providers: &[String],
+    pairs: &[ReferencePair],
+) -> anyhow::Result<Vec<ProviderScore>> {
+    let translators = providers
+        .iter()
+        .map(|provider| translator_for(saved, provider))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let mut scores = Vec::with_capacity(providers.len());
+    for (provider, translator) in providers.iter().zip(&translators) {
+        scores.push(evaluate(provider, translator, pairs).await);
+    }
+    Ok(scores)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_references_from_tsv_and_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let tsv = dir.path().join("refs.tsv");
+        fs::write(&tsv, "こんにちは\tHello\n\n元気?\tHow are you?\n").unwrap();
+        let pairs = load_references(&tsv).unwrap();
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[1].reference, "How are you?");
+
+        let json = dir.path().join("refs.json");
+        fs::write(
+            &json,
+            r#"[{ "source": "また明日", "reference": "See you tomorrow" }]"#,
+        )
+        .unwrap();
+        assert_eq!(load_references(&json).unwrap()[0].source, "また明日");
+
+        fs::write(&tsv, "no tab here\n").unwrap();
+        assert!(load_references(&tsv).is_err());
+    }
+
+    #[test]
+    fn test_translator_for_needs_a_backend_provider() {
+        let mut saved = TranslationSettings::default();
+        assert!(matches!(
+            translator_for(&saved, "ollama").unwrap(),
+            TranslatorConfig::Ollama { .. }
+        ));
+        assert!(translator_for(&saved, "google").is_err());
+        assert!(translator_for(&saved, "deepl-free").is_err());
+        saved.deepl_api_key = Some("key".into());
+        assert!(matches!(
+            translator_for(&saved, "deepl-pro").unwrap(),
+            TranslatorConfig::Deepl { use_pro: true, .. }
+        ));
+    }
+}
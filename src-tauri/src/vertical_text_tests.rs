@@ -1,9 +1,26 @@
 use crate::accuracy::{AccuracyMetrics, BatchAccuracy};
 use crate::model_package::ModelPackage;
 use crate::ocr_pipeline::{DeviceConfig, PaddleOcrPipeline};
+use crate::text_renderer::{FontStack, draw_text_with_mixed_fonts, measure_text_width_mixed_fonts};
+use ab_glyph::{Font, PxScale, ScaleFont};
 use anyhow::Result;
-use image::{DynamicImage, RgbaImage};
+use image::{DynamicImage, Rgba, RgbaImage, imageops};
+use imageproc::geometric_transformations::{Interpolation, rotate_about_center};
 use std::path::Path;
+use std::sync::OnceLock;
+
+const FIXTURE_FONT: &str = "Noto Sans";
+const FIXTURE_FONT_SIZE: f32 = 32.0;
+const FIXTURE_PADDING: f32 = 8.0;
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// Loaded once; falls back to the embedded CJK font when no system font is found
+fn fixture_fonts() -> &'static FontStack {
+    static FONTS: OnceLock<FontStack> = OnceLock::new();
+    FONTS.get_or_init(|| {
+        FontStack::from_font_family(FIXTURE_FONT).expect("embedded fallback font should load")
+    })
+}
 
 /// Test fixture for vertical text OCR validation
 pub struct VerticalTextFixture {
@@ -54,12 +71,48 @@ impl VerticalTextFixture {
         }
     }
 
-    /// Create a simple text image (placeholder - in real implementation, use font rendering)
-    fn create_text_image(_text: &str, _rotation: f32) -> DynamicImage {
-        // Placeholder: create a simple colored rectangle
-        // In real implementation, this would render actual text with rotation
-        let img = RgbaImage::from_pixel(100, 32, image::Rgba([255, 255, 255, 255]));
-        DynamicImage::ImageRgba8(img)
+    /// Render `text` as one black line on white, then rotate it `rotation` degrees clockwise
+    ///
+    /// Right angles rotate losslessly; other angles are rotated onto a canvas large enough
+    /// to keep the corners.
+    fn create_text_image(text: &str, rotation: f32) -> DynamicImage {
+        let fonts = fixture_fonts();
+        let scale = PxScale::from(FIXTURE_FONT_SIZE);
+        let text_width = measure_text_width_mixed_fonts(text, fonts, scale, 0.0);
+        let line_height = fonts.primary().as_scaled(scale).height();
+        let width = (text_width + FIXTURE_PADDING * 2.0).ceil() as u32;
+        let height = (line_height + FIXTURE_PADDING * 2.0).ceil() as u32;
+
+        let mut line = RgbaImage::from_pixel(width, height, WHITE);
+        draw_text_with_mixed_fonts(
+            &mut line,
+            width as f32 / 2.0,
+            FIXTURE_PADDING,
+            scale,
+            fonts,
+            text,
+            Rgba([0, 0, 0, 255]),
+            0.0,
+        );
+
+        let image = match rotation.rem_euclid(360.0) {
+            r if r == 0.0 => line,
+            r if r == 90.0 => imageops::rotate90(&line),
+            r if r == 180.0 => imageops::rotate180(&line),
+            r if r == 270.0 => imageops::rotate270(&line),
+            r => {
+                let side = (width as f32).hypot(height as f32).ceil() as u32;
+                let mut canvas = RgbaImage::from_pixel(side, side, WHITE);
+                imageops::overlay(
+                    &mut canvas,
+                    &line,
+                    ((side - width) / 2) as i64,
+                    ((side - height) / 2) as i64,
+                );
+                rotate_about_center(&canvas, r.to_radians(), Interpolation::Bilinear, WHITE)
+            }
+        };
+        DynamicImage::ImageRgba8(image)
     }
 }
 
@@ -159,7 +212,7 @@ mod tests {
     #[tokio::test]
     async fn test_vertical_text_fixtures() {
         let suite = VerticalTextTestSuite::new();
-        assert_eq!(suite.fixtures.len(), 5);
+        assert_eq!(suite.fixtures.len(), 6);
 
         // Test that fixtures are created correctly
         assert_eq!(suite.fixtures[0].ground_truth, "Hello World");
@@ -168,6 +221,43 @@ mod tests {
         assert_eq!(suite.fixtures[3].expected_angle, Some(180.0));
     }
 
+    fn dark_pixels(image: &DynamicImage) -> usize {
+        image.to_luma8().pixels().filter(|p| p[0] < 128).count()
+    }
+
+    #[test]
+    fn test_fixtures_contain_rendered_text() {
+        let horizontal = VerticalTextFixture::horizontal("Hello World", "horizontal");
+        let (width, height) = (horizontal.image.width(), horizontal.image.height());
+        assert!(width > height);
+        assert!(dark_pixels(&horizontal.image) > 100);
+
+        // Right-angle rotations keep every glyph pixel and swap the sides
+        let vertical = VerticalTextFixture::vertical_90("Hello World", "vertical");
+        assert_eq!(
+            (vertical.image.width(), vertical.image.height()),
+            (height, width)
+        );
+        assert_eq!(dark_pixels(&vertical.image), dark_pixels(&horizontal.image));
+
+        let japanese = VerticalTextFixture::vertical_270("こんにちは", "japanese");
+        assert!(japanese.image.height() > japanese.image.width());
+        assert!(dark_pixels(&japanese.image) > 100);
+
+        let flipped = VerticalTextFixture::upside_down("Hello World", "flipped");
+        assert_eq!(
+            flipped.image.to_rgba8(),
+            imageops::rotate180(&horizontal.image.to_rgba8())
+        );
+    }
+
+    #[test]
+    fn test_arbitrary_rotation_keeps_the_whole_line() {
+        let tilted = VerticalTextFixture::create_text_image("Hello", 30.0);
+        assert_eq!(tilted.width(), tilted.height());
+        assert!(dark_pixels(&tilted) > 50);
+    }
+
     #[tokio::test]
     #[ignore] // Requires actual model files
     async fn test_accuracy_calculation() {
@@ -0,0 +1,250 @@
+// Detection QA images: boxes colored by class, confidence labels, and the segmentation mask
+// Written by `detect --draw`. With `ordered`, overlapping boxes are merged and numbered in
+// manga reading order (rows top to bottom, right to left within a row).
+
+use ab_glyph::{FontArc, PxScale};
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, GrayImage, Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_rect_mut, draw_hollow_rect_mut, draw_text_mut, text_size};
+use imageproc::rect::Rect;
+
+use crate::pipeline::PageBlock;
+
+/// Box colors by detector class (0 = dark text, 1 = light text)
+const CLASS_COLORS: [Rgba<u8>; 2] = [Rgba([230, 40, 40, 255]), Rgba([30, 120, 240, 255])];
+const MASK_COLOR: [u8; 3] = [255, 0, 200];
+const LABEL_SCALE: f32 = 18.0;
+
+#[derive(Debug, Clone)]
+pub struct DrawOptions {
+    pub class_colors: bool, // Otherwise every box uses the class 0 color
+    pub labels: bool,       // Index, class, and confidence above each box
+    pub mask_alpha: f32,    // 0 hides the mask
+    pub ordered: bool,      // Merge overlapping boxes and number them in reading order
+}
+
+impl Default for DrawOptions {
+    fn default() -> Self {
+        DrawOptions {
+            class_colors: true,
+            labels: false,
+            mask_alpha: 0.0,
+            ordered: false,
+        }
+    }
+}
+
+fn overlaps(a: &PageBlock, b: &PageBlock) -> bool {
+    a.xmin < b.xmax && b.xmin < a.xmax && a.ymin < b.ymax && b.ymin < a.ymax
+}
+
+/// Union of every group of overlapping boxes, keeping the most confident box's class
+pub fn merge_overlapping(blocks: &[PageBlock]) -> Vec<PageBlock> {
+    let mut merged: Vec<PageBlock> = Vec::new();
+    for block in blocks {
+        let mut current = block.clone();
+        // Absorbing a box can make the union overlap earlier ones, so repeat until stable
+        while let Some(pos) = merged.iter().position(|other| overlaps(&current, other)) {
+            let other = merged.remove(pos);
+            if other.confidence > current.confidence {
+                current.class = other.class;
+                current.confidence = other.confidence;
+            }
+            current.xmin = current.xmin.min(other.xmin);
+            current.ymin = current.ymin.min(other.ymin);
+            current.xmax = current.xmax.max(other.xmax);
+            current.ymax = current.ymax.max(other.ymax);
+        }
+        merged.push(current);
+    }
+    merged
+}
+
+/// Indices of `blocks` in manga reading order
+///
+/// Boxes whose vertical center falls inside the current row join it; rows are read top to
+/// bottom and each row right to left.
+pub fn reading_order(blocks: &[PageBlock]) -> Vec<usize> {
+    let mut by_top: Vec<usize> = (0..blocks.len()).collect();
+    by_top.sort_by(|&a, &b| blocks[a].ymin.total_cmp(&blocks[b].ymin));
+
+    let mut rows: Vec<(f32, Vec<usize>)> = Vec::new(); // (bottom of the row, members)
+    for index in by_top {
+        let block = &blocks[index];
+        let center = (block.ymin + block.ymax) / 2.0;
+        match rows.last_mut() {
+            Some((bottom, members)) if center < *bottom => {
+                *bottom = bottom.max(block.ymax);
+                members.push(index);
+            }
+            _ => rows.push((block.ymax, vec![index])),
+        }
+    }
+
+    rows.into_iter()
+        .flat_map(|(_, mut members)| {
+            members.sort_by(|&a, &b| blocks[b].xmax.total_cmp(&blocks[a].xmax));
+            members
+        })
+        .collect()
+}
+
+fn blend_mask(image: &mut RgbaImage, mask: &GrayImage, alpha: f32) {
+    if alpha <= 0.0 {
+        return;
+    }
+    // The detector mask is model-sized (1024x1024), not page-sized
+    let resized;
+    let mask = if mask.dimensions() == image.dimensions() {
+        mask
+    } else {
+        resized = imageops::resize(mask, image.width(), image.height(), FilterType::Triangle);
+        &resized
+    };
+    let alpha = alpha.min(1.0);
+    for (pixel, value) in image.pixels_mut().zip(mask.pixels()) {
+        let weight = alpha * value[0] as f32 / 255.0;
+        for channel in 0..3 {
+            let base = pixel[channel] as f32;
+            pixel[channel] = (base + (MASK_COLOR[channel] as f32 - base) * weight).round() as u8;
+        }
+    }
+}
+
+fn draw_label(image: &mut RgbaImage, font: &FontArc, text: &str, x: i32, y: i32, color: Rgba<u8>) {
+    let scale = PxScale::from(LABEL_SCALE);
+    let (width, height) = text_size(scale, font, text);
+    // Above the box, or just inside it when the box touches the top edge
+    let top = if y >= height as i32 + 2 {
+        y - height as i32 - 2
+    } else {
+        y
+    };
+    draw_filled_rect_mut(
+        image,
+        Rect::at(x, top).of_size(width + 4, height + 2),
+        color,
+    );
+    draw_text_mut(
+        image,
+        Rgba([255, 255, 255, 255]),
+        x + 2,
+        top,
+        scale,
+        font,
+        text,
+    );
+}
+
+/// Page with the detections drawn on it; labels are skipped when no font is given
+pub fn draw(
+    image: &DynamicImage,
+    blocks: &[PageBlock],
+    mask: Option<&GrayImage>,
+    options: &DrawOptions,
+    font: Option<&FontArc>,
+) -> RgbaImage {
+    let mut canvas = image.to_rgba8();
+    if let Some(mask) = mask {
+        blend_mask(&mut canvas, mask, options.mask_alpha);
+    }
+
+    let merged;
+    let (blocks, order) = if options.ordered {
+        merged = merge_overlapping(blocks);
+        let order = reading_order(&merged);
+        (merged.as_slice(), order)
+    } else {
+        (blocks, (0..blocks.len()).collect())
+    };
+
+    for (position, &index) in order.iter().enumerate() {
+        let block = &blocks[index];
+        let color = if options.class_colors {
+            CLASS_COLORS[block.class.min(CLASS_COLORS.len() - 1)]
+        } else {
+            CLASS_COLORS[0]
+        };
+        let x = block.xmin.max(0.0) as i32;
+        let y = block.ymin.max(0.0) as i32;
+        let width = (block.xmax - block.xmin).max(1.0) as u32;
+        let height = (block.ymax - block.ymin).max(1.0) as u32;
+        // Two pixels wide so thin boxes stay visible on busy art
+        draw_hollow_rect_mut(&mut canvas, Rect::at(x, y).of_size(width, height), color);
+        if width > 2 && height > 2 {
+            draw_hollow_rect_mut(
+                &mut canvas,
+                Rect::at(x + 1, y + 1).of_size(width - 2, height - 2),
+                color,
+            );
+        }
+
+        let label = match (options.ordered, options.labels) {
+            (true, true) => Some(format!("#{} {:.2}", position + 1, block.confidence)),
+            (true, false) => Some(format!("#{}", position + 1)),
+            (false, true) => Some(format!(
+                "{} c{} {:.2}",
+                index, block.class, block.confidence
+            )),
+            (false, false) => None,
+        };
+        if let (Some(label), Some(font)) = (label, font) {
+            draw_label(&mut canvas, font, &label, x, y, color);
+        }
+    }
+    canvas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(xmin: f32, ymin: f32, xmax: f32, ymax: f32, confidence: f32) -> PageBlock {
+        PageBlock::from_detection(&comic_text_detector::ClassifiedBbox {
+            xmin,
+            ymin,
+            xmax,
+            ymax,
+            confidence,
+            class: 0,
+        })
+    }
+
+    #[test]
+    fn test_merge_overlapping_unions_chains() {
+        let blocks = [
+            block(0.0, 0.0, 10.0, 10.0, 0.9),
+            block(20.0, 0.0, 30.0, 10.0, 0.8),
+            block(8.0, 0.0, 22.0, 5.0, 0.5), // Bridges the first two
+            block(50.0, 50.0, 60.0, 60.0, 0.7),
+        ];
+        let merged = merge_overlapping(&blocks);
+        assert_eq!(merged.len(), 2);
+        let wide = merged.iter().find(|b| b.xmin == 0.0).unwrap();
+        assert_eq!((wide.xmax, wide.confidence), (30.0, 0.9));
+    }
+
+    #[test]
+    fn test_reading_order_is_right_to_left_then_down() {
+        let blocks = [
+            block(0.0, 5.0, 10.0, 40.0, 0.9),   // Top row, left
+            block(50.0, 0.0, 60.0, 30.0, 0.9),  // Top row, right
+            block(30.0, 60.0, 40.0, 90.0, 0.9), // Second row
+        ];
+        assert_eq!(reading_order(&blocks), vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn test_mask_overlay_respects_alpha() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 1, Rgba([0, 0, 0, 255])));
+        let mut mask = GrayImage::new(2, 1);
+        mask.put_pixel(0, 0, image::Luma([255]));
+        let options = DrawOptions {
+            mask_alpha: 0.5,
+            ..DrawOptions::default()
+        };
+        let canvas = draw(&image, &[], Some(&mask), &options, None);
+        assert_eq!(canvas.get_pixel(0, 0), &Rgba([128, 0, 100, 255]));
+        assert_eq!(canvas.get_pixel(1, 0), &Rgba([0, 0, 0, 255]));
+    }
+}
@@ -0,0 +1,154 @@
+// Folder watching for `process-folder --watch`: hands over images as they land in a directory
+// Scanners and sync tools write files in pieces, so a file is only handed over once it has
+// gone a settle period without new events.
+
+use anyhow::Context;
+use notify::event::{CreateKind, ModifyKind};
+use notify::{Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use tokio::time::{Duration, Instant};
+
+use crate::image_io;
+
+/// Quiet time after the last event before a file counts as fully written
+pub const SETTLE_TIME: Duration = Duration::from_millis(1000);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+pub struct FolderWatcher {
+    _watcher: RecommendedWatcher, // Dropping it stops the events
+    events: mpsc::UnboundedReceiver<PathBuf>,
+    pending: HashMap<PathBuf, Instant>,
+    seen: HashSet<PathBuf>,
+    settle: Duration,
+}
+
+/// Image paths an event creates or writes to; everything else is ignored
+fn touched_images(event: &Event) -> Vec<PathBuf> {
+    let relevant = matches!(
+        event.kind,
+        EventKind::Create(CreateKind::File | CreateKind::Any)
+            | EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Name(_) | ModifyKind::Any)
+    );
+    if !relevant {
+        return Vec::new();
+    }
+    event
+        .paths
+        .iter()
+        .filter(|path| image_io::is_supported_input(path))
+        .cloned()
+        .collect()
+}
+
+/// Remove and return the pending files that have been quiet for `settle`, oldest first
+fn take_settled(
+    pending: &mut HashMap<PathBuf, Instant>,
+    now: Instant,
+    settle: Duration,
+) -> Vec<PathBuf> {
+    let mut settled: Vec<(PathBuf, Instant)> = pending
+        .iter()
+        .filter(|(_, last)| now.duration_since(**last) >= settle)
+        .map(|(path, last)| (path.clone(), *last))
+        .collect();
+    settled.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+    for (path, _) in &settled {
+        pending.remove(path);
+    }
+    settled.into_iter().map(|(path, _)| path).collect()
+}
+
+impl FolderWatcher {
+    /// Watch `dir` (non-recursive); files in `existing` are never handed over
+    pub fn new(dir: &Path, existing: &[PathBuf], settle: Duration) -> anyhow::Result<Self> {
+        let (sender, events) = mpsc::unbounded_channel();
+        let mut watcher = RecommendedWatcher::new(
+            move |res: Result<Event, notify::Error>| match res {
+                Ok(event) => {
+                    for path in touched_images(&event) {
+                        let _ = sender.send(path);
+                    }
+                }
+                Err(err) => tracing::warn!("[watch] watch error: {:?}", err),
+            },
+            Config::default(),
+        )?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {:?}", dir))?;
+        tracing::info!("[watch] watching {:?}", dir);
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            pending: HashMap::new(),
+            seen: existing.iter().map(|path| normalize(path)).collect(),
+            settle,
+        })
+    }
+
+    /// Next new image that has finished writing, or None once the watcher shuts down
+    pub async fn next(&mut self) -> Option<PathBuf> {
+        loop {
+            for path in take_settled(&mut self.pending, Instant::now(), self.settle) {
+                // Deleted or renamed away while settling
+                if path.is_file() && self.seen.insert(normalize(&path)) {
+                    return Some(path);
+                }
+            }
+            tokio::select! {
+                path = self.events.recv() => {
+                    self.pending.insert(path?, Instant::now());
+                }
+                _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            }
+        }
+    }
+}
+
+/// Watcher events carry absolute paths, while listings may be relative
+fn normalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_written_images_are_picked_up() {
+        let created = Event::new(EventKind::Create(CreateKind::File))
+            .add_path("scan/001.png".into())
+            .add_path("scan/notes.txt".into());
+        assert_eq!(
+            touched_images(&created),
+            vec![PathBuf::from("scan/001.png")]
+        );
+
+        let removed = Event::new(EventKind::Remove(notify::event::RemoveKind::File))
+            .add_path("scan/002.png".into());
+        assert!(touched_images(&removed).is_empty());
+    }
+
+    #[test]
+    fn test_files_wait_for_the_settle_time() {
+        let start = Instant::now();
+        let settle = Duration::from_secs(1);
+        let mut pending = HashMap::new();
+        pending.insert(PathBuf::from("b.png"), start);
+        pending.insert(PathBuf::from("a.png"), start + Duration::from_millis(200));
+
+        assert!(take_settled(&mut pending, start + Duration::from_millis(900), settle).is_empty());
+        assert_eq!(
+            take_settled(&mut pending, start + Duration::from_millis(1100), settle),
+            vec![PathBuf::from("b.png")]
+        );
+        assert_eq!(
+            take_settled(&mut pending, start + Duration::from_secs(2), settle),
+            vec![PathBuf::from("a.png")]
+        );
+        assert!(pending.is_empty());
+    }
+}